@@ -57,6 +57,36 @@ pub enum Commands {
     Run,
     #[command(about = "Run UI server")]
     UI(UI),
+    #[command(
+        about = "List or replay dead letters",
+        long_about = "List or replay dead letters, the operations that processors and sinks with \
+            the `DeadLetter` error policy failed on"
+    )]
+    DeadLetters(DeadLetters),
+}
+
+#[derive(Debug, Args)]
+pub struct DeadLetters {
+    #[command(subcommand)]
+    pub command: DeadLetterCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DeadLetterCommands {
+    #[command(about = "List dead letters")]
+    List {
+        #[arg(help = "Only list the dead letters of this node", long)]
+        node: Option<String>,
+    },
+    #[command(
+        about = "Replay dead letters on the next run",
+        long_about = "Queue dead letters to be processed again by their node on the next run. \
+            Operations that fail again are dead-lettered again"
+    )]
+    Replay {
+        #[arg(help = "Only replay the dead letters of this node", long)]
+        node: Option<String>,
+    },
 }
 
 #[derive(Debug, Args)]
@@ -68,6 +68,11 @@ pub enum OrchestrationError {
     SourceValidationError(String),
     #[error("connection: {0:?} not found")]
     ConnectionNotFound(String),
+    #[error("connection: {connection:?} is not a {expected} connection")]
+    WrongConnectionType {
+        connection: String,
+        expected: &'static str,
+    },
     #[error("Pipeline validation failed")]
     PipelineValidationError,
     #[error("Output table {0} not used in any sink")]
@@ -23,19 +23,29 @@ impl HomeDir {
         Ok(build_path)
     }
 
-    fn get_build_path(&self, build_id: BuildId) -> BuildPath {
+    pub fn get_build_path(&self, build_id: BuildId) -> BuildPath {
         let build_dir = self.home_dir.join(&build_id.name);
 
         let contracts_dir = build_dir.join("contracts");
         let descriptor_path = contracts_dir.join("file_descriptor_set.bin");
 
         let data_dir = build_dir.join("data");
+        let checkpoint_dir = data_dir.join("checkpoints");
+        let state_dir = data_dir.join("state");
+        let dead_letter_dir = data_dir.join("dead_letters");
+        let operation_log_dir = data_dir.join("operation_log");
+        let sink_metadata_dir = data_dir.join("sinks");
 
         BuildPath {
             id: build_id,
             contracts_dir,
             descriptor_path,
             data_dir,
+            checkpoint_dir,
+            state_dir,
+            dead_letter_dir,
+            operation_log_dir,
+            sink_metadata_dir,
         }
     }
 }
@@ -63,4 +73,10 @@ pub struct BuildPath {
     pub contracts_dir: Utf8PathBuf,
     pub descriptor_path: Utf8PathBuf,
     pub data_dir: Utf8PathBuf,
+    pub checkpoint_dir: Utf8PathBuf,
+    pub state_dir: Utf8PathBuf,
+    pub dead_letter_dir: Utf8PathBuf,
+    pub operation_log_dir: Utf8PathBuf,
+    /// Where sinks whose destination can't store their position keep it.
+    pub sink_metadata_dir: Utf8PathBuf,
 }
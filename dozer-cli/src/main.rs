@@ -1,7 +1,7 @@
 use clap::Parser;
 use dozer_cli::cli::init_config;
 use dozer_cli::cli::init_dozer;
-use dozer_cli::cli::types::{Cli, Commands, UICommands};
+use dozer_cli::cli::types::{Cli, Commands, DeadLetterCommands, UICommands};
 use dozer_cli::errors::{CliError, CloudError, OrchestrationError};
 use dozer_cli::ui;
 use dozer_cli::ui::app::AppUIError;
@@ -96,6 +96,10 @@ fn run() -> Result<(), OrchestrationError> {
                 .block_on(dozer.build(force, shutdown_receiver, build.locked))
         }
         Commands::Clean => dozer.clean(),
+        Commands::DeadLetters(dead_letters) => match dead_letters.command {
+            DeadLetterCommands::List { node } => dozer.list_dead_letters(node.as_deref()),
+            DeadLetterCommands::Replay { node } => dozer.replay_dead_letters(node.as_deref()),
+        },
         Commands::UI(_) => {
             panic!("This should not happen as it is handled earlier");
         }
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use dozer_core::app::App;
 use dozer_core::app::AppPipeline;
 use dozer_core::app::PipelineEntryPoint;
+use dozer_core::app::PipelineFlags;
 use dozer_core::node::SinkFactory;
 use dozer_core::shutdown::ShutdownReceiver;
 use dozer_core::DEFAULT_PORT_HANDLE;
@@ -14,17 +15,24 @@ use dozer_tracing::DozerMonitorContext;
 use dozer_types::log::debug;
 use dozer_types::models::connection::Connection;
 use dozer_types::models::connection::ConnectionConfig;
-use dozer_types::models::flags::Flags;
 use dozer_types::models::sink::Sink;
 use dozer_types::models::sink::SinkConfig;
 use dozer_types::models::source::Source;
 use dozer_types::models::udf_config::UdfConfig;
 use dozer_types::types::PortHandle;
 use std::hash::Hash;
+use std::path::PathBuf;
 use tokio::runtime::Runtime;
 
 use crate::pipeline::dummy_sink::DummySinkFactory;
 use dozer_sink_clickhouse::ClickhouseSinkFactory;
+use dozer_sink_file::FileSinkFactory;
+use dozer_sink_kafka::KafkaSinkFactory;
+use dozer_sink_mysql::MySQLSinkFactory;
+use dozer_sink_postgres::PostgresSinkFactory;
+#[cfg(feature = "snowflake")]
+use dozer_sink_snowflake::SnowflakeSinkFactory;
+use dozer_sink_webhook::WebhookSinkFactory;
 
 use super::source_builder::SourceBuilder;
 use crate::errors::OrchestrationError;
@@ -53,8 +61,9 @@ pub struct PipelineBuilder<'a> {
     sql: Option<&'a str>,
     sinks: &'a [Sink],
     labels: DozerMonitorContext,
-    flags: Flags,
+    flags: PipelineFlags,
     udfs: &'a [UdfConfig],
+    sink_metadata_dir: Option<PathBuf>,
 }
 
 impl<'a> PipelineBuilder<'a> {
@@ -64,7 +73,7 @@ impl<'a> PipelineBuilder<'a> {
         sql: Option<&'a str>,
         sinks: &'a [Sink],
         labels: DozerMonitorContext,
-        flags: Flags,
+        flags: PipelineFlags,
         udfs: &'a [UdfConfig],
     ) -> Self {
         Self {
@@ -75,9 +84,16 @@ impl<'a> PipelineBuilder<'a> {
             labels,
             flags,
             udfs,
+            sink_metadata_dir: None,
         }
     }
 
+    /// Sinks that can't store their position in their destination keep it below `dir`, one file per sink.
+    pub fn with_sink_metadata_dir(mut self, dir: PathBuf) -> Self {
+        self.sink_metadata_dir = Some(dir);
+        self
+    }
+
     // Based on used_sources, map it to the connection name and create sources
     // For not breaking current functionality, current format is to be still supported.
     pub async fn get_grouped_tables(
@@ -134,7 +150,7 @@ impl<'a> PipelineBuilder<'a> {
         let mut original_sources = vec![];
 
         let mut query_ctx = None;
-        let mut pipeline = AppPipeline::new((&self.flags).into());
+        let mut pipeline = AppPipeline::new(self.flags.clone());
 
         let mut transformed_sources = vec![];
 
@@ -187,7 +203,7 @@ impl<'a> PipelineBuilder<'a> {
 
         let mut pipelines: Vec<AppPipeline> = vec![];
 
-        let mut pipeline = AppPipeline::new(self.flags.into());
+        let mut pipeline = AppPipeline::new(self.flags);
 
         let mut available_output_tables: HashMap<String, OutputTableInfo> = HashMap::new();
 
@@ -248,6 +264,54 @@ impl<'a> PipelineBuilder<'a> {
                 SinkConfig::Clickhouse(config) => {
                     let sink =
                         Box::new(ClickhouseSinkFactory::new(config.clone(), runtime.clone()));
+                    // Ports follow the order of the tables in the config.
+                    let table_infos = config
+                        .tables
+                        .iter()
+                        .enumerate()
+                        .map(|(port, table)| {
+                            get_table_info(&table.source_table_name)
+                                .map(|table_info| (table_info, port as PortHandle))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    add_sink_to_pipeline(&mut pipeline, sink, id, table_infos);
+                }
+                SinkConfig::Postgres(config) => {
+                    let ConnectionConfig::Postgres(connection) =
+                        self.find_connection(&config.connection)?
+                    else {
+                        return Err(OrchestrationError::WrongConnectionType {
+                            connection: config.connection.clone(),
+                            expected: "postgres",
+                        });
+                    };
+                    let sink = Box::new(PostgresSinkFactory::new(
+                        config.clone(),
+                        connection.clone(),
+                        runtime.clone(),
+                    ));
+                    let table_info = get_table_info(&config.source_table_name)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
+                }
+                SinkConfig::MySQL(config) => {
+                    let ConnectionConfig::MySQL(connection) =
+                        self.find_connection(&config.connection)?
+                    else {
+                        return Err(OrchestrationError::WrongConnectionType {
+                            connection: config.connection.clone(),
+                            expected: "mysql",
+                        });
+                    };
+                    let sink = Box::new(MySQLSinkFactory::new(
+                        config.clone(),
+                        connection.clone(),
+                        runtime.clone(),
+                    ));
                     let table_info = get_table_info(&config.source_table_name)?;
                     add_sink_to_pipeline(
                         &mut pipeline,
@@ -256,6 +320,94 @@ impl<'a> PipelineBuilder<'a> {
                         vec![(table_info, DEFAULT_PORT_HANDLE)],
                     );
                 }
+                SinkConfig::Kafka(config) => {
+                    let ConnectionConfig::Kafka(connection) =
+                        self.find_connection(&config.connection)?
+                    else {
+                        return Err(OrchestrationError::WrongConnectionType {
+                            connection: config.connection.clone(),
+                            expected: "kafka",
+                        });
+                    };
+                    let sink = Box::new(KafkaSinkFactory::new(
+                        config.clone(),
+                        id.clone(),
+                        config.connection.clone(),
+                        connection.clone(),
+                        runtime.clone(),
+                    ));
+                    // Ports follow the order of the tables in the config.
+                    let table_infos = config
+                        .tables
+                        .iter()
+                        .enumerate()
+                        .map(|(port, table)| {
+                            get_table_info(&table.source_table_name)
+                                .map(|table_info| (table_info, port as PortHandle))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    add_sink_to_pipeline(&mut pipeline, sink, id, table_infos);
+                }
+                SinkConfig::File(config) => {
+                    let connection = match &config.connection {
+                        Some(connection) => match self.find_connection(connection)? {
+                            config @ (ConnectionConfig::S3Storage(_)
+                            | ConnectionConfig::LocalStorage(_)) => Some(config.clone()),
+                            _ => {
+                                return Err(OrchestrationError::WrongConnectionType {
+                                    connection: connection.clone(),
+                                    expected: "s3storage or localstorage",
+                                })
+                            }
+                        },
+                        None => None,
+                    };
+                    let sink = Box::new(FileSinkFactory::new(
+                        config.clone(),
+                        connection,
+                        runtime.clone(),
+                    ));
+                    let table_info = get_table_info(&config.source_table_name)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
+                }
+                SinkConfig::Webhook(config) => {
+                    let metadata_path = self
+                        .sink_metadata_dir
+                        .as_ref()
+                        .map(|dir| dir.join(format!("{id}.json")));
+                    let sink = Box::new(WebhookSinkFactory::new(
+                        config.clone(),
+                        metadata_path,
+                        runtime.clone(),
+                    ));
+                    // Ports follow the order of the tables in the config.
+                    let table_infos = config
+                        .tables
+                        .iter()
+                        .enumerate()
+                        .map(|(port, table)| {
+                            get_table_info(&table.source_table_name)
+                                .map(|table_info| (table_info, port as PortHandle))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    add_sink_to_pipeline(&mut pipeline, sink, id, table_infos);
+                }
+                #[cfg(feature = "snowflake")]
+                SinkConfig::Snowflake(config) => {
+                    let sink = Box::new(SnowflakeSinkFactory::new(config.clone()));
+                    let table_info = get_table_info(&config.endpoint)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
+                }
                 x => {
                     return Err(OrchestrationError::UnsupportedFeature(x.name()));
                 }
@@ -278,6 +430,14 @@ impl<'a> PipelineBuilder<'a> {
 
         Ok(dag)
     }
+
+    fn find_connection(&self, name: &str) -> Result<&ConnectionConfig, OrchestrationError> {
+        self.connections
+            .iter()
+            .find(|connection| connection.name == name)
+            .map(|connection| &connection.config)
+            .ok_or_else(|| OrchestrationError::ConnectionNotFound(name.to_string()))
+    }
 }
 
 fn dedup<T: Eq + Hash + Clone>(v: &mut Vec<T>) {
@@ -293,8 +453,26 @@ fn table_names(sink: &Sink) -> Vec<&String> {
             .iter()
             .map(|table| &table.source_table_name)
             .collect(),
-        SinkConfig::Clickhouse(sink) => vec![&sink.source_table_name],
+        SinkConfig::Clickhouse(sink) => sink
+            .tables
+            .iter()
+            .map(|table| &table.source_table_name)
+            .collect(),
         SinkConfig::Oracle(sink) => vec![&sink.table_name],
+        SinkConfig::Postgres(sink) => vec![&sink.source_table_name],
+        SinkConfig::MySQL(sink) => vec![&sink.source_table_name],
+        SinkConfig::Kafka(sink) => sink
+            .tables
+            .iter()
+            .map(|table| &table.source_table_name)
+            .collect(),
+        SinkConfig::File(sink) => vec![&sink.source_table_name],
+        SinkConfig::Snowflake(sink) => vec![&sink.endpoint],
+        SinkConfig::Webhook(sink) => sink
+            .tables
+            .iter()
+            .map(|table| &table.source_table_name)
+            .collect(),
     }
 }
 
@@ -29,6 +29,7 @@ struct Table {
     schema_name: Option<String>,
     name: String,
     columns: Vec<String>,
+    explicit_columns: bool,
     schema: Schema,
     cdc_type: CdcType,
     port: PortHandle,
@@ -81,6 +82,8 @@ impl ConnectorSourceFactory {
         for ((table, _), columns) in table_and_ports.iter_mut().zip(all_columns) {
             if table.column_names.is_empty() {
                 table.column_names = columns.column_names;
+            } else {
+                table.explicit_columns = true;
             }
         }
 
@@ -97,6 +100,7 @@ impl ConnectorSourceFactory {
         for ((table, port), source_schema) in table_and_ports.into_iter().zip(source_schemas) {
             let name = table.name;
             let columns = table.column_names;
+            let explicit_columns = table.explicit_columns;
             let source_schema = source_schema.map_err(ConnectorSourceFactoryError::Connector)?;
             let schema = source_schema.schema;
             let cdc_type = source_schema.cdc_type;
@@ -105,6 +109,7 @@ impl ConnectorSourceFactory {
                 name,
                 schema_name: table.schema.clone(),
                 columns,
+                explicit_columns,
                 schema,
                 cdc_type,
                 port,
@@ -183,6 +188,7 @@ impl SourceFactory for ConnectorSourceFactory {
                 schema: table.schema_name.clone(),
                 name: table.name.clone(),
                 column_names: table.columns.clone(),
+                explicit_columns: table.explicit_columns,
             })
             .collect();
         let ports = self.tables.iter().map(|table| table.port).collect();
@@ -351,6 +357,7 @@ async fn forward_message_to_pipeline(
                     Operation::Delete { .. } => "delete",
                     Operation::Update { .. } => "update",
                     Operation::BatchInsert { .. } => "insert",
+                    Operation::Truncate => "truncate",
                 };
                 labels.push(dozer_tracing::KeyValue::new(OPERATION_TYPE_LABEL, op_str));
 
@@ -378,6 +385,35 @@ async fn forward_message_to_pipeline(
                     break;
                 }
             }
+            IngestionMessage::SchemaChanged {
+                table_index,
+                schema,
+            } => {
+                let table_index = *table_index;
+                let table_name = &tables[table_index].name;
+
+                // Add source information to the schema, like `get_output_schema` does.
+                let mut schema = schema.clone();
+                for field in &mut schema.fields {
+                    field.source = SourceDefinition::Table {
+                        connection: connection_name.clone(),
+                        name: table_name.clone(),
+                    };
+                }
+                info!(
+                    "Source: Schema of {} changed\n{}",
+                    table_name,
+                    schema.print()
+                );
+
+                let message = IngestionMessage::SchemaChanged {
+                    table_index,
+                    schema,
+                };
+                if sender.send((ports[table_index], message)).await.is_err() {
+                    break;
+                }
+            }
             IngestionMessage::TransactionInfo(_) => {
                 // For transaction level messages, we can send to any port.
                 if sender.send((ports[0], message)).await.is_err() {
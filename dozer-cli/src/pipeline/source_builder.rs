@@ -62,6 +62,7 @@ impl SourceBuilder {
                         schema: source.schema.clone(),
                         name: source.table_name.clone(),
                         column_names: source.columns.clone(),
+                        explicit_columns: false,
                     },
                     port,
                 ));
@@ -7,7 +7,6 @@ use dozer_types::models::config::Config;
 use dozer_types::models::ingestion_types::{ConfigSchemas, GrpcConfig};
 
 use dozer_types::models::connection::{Connection, ConnectionConfig};
-use dozer_types::models::flags::Flags;
 use dozer_types::models::source::Source;
 
 fn get_default_config() -> Config {
@@ -66,7 +65,7 @@ fn load_multi_sources() {
         config.sql.as_deref(),
         &config.sinks,
         Default::default(),
-        Flags::default(),
+        Default::default(),
         &config.udfs,
     );
 
@@ -1,9 +1,10 @@
+use dozer_core::app::PipelineFlags;
 use dozer_core::shutdown::ShutdownReceiver;
 use dozer_tracing::DozerMonitorContext;
-use dozer_types::models::flags::Flags;
 use dozer_types::models::sink::Sink;
 use tokio::runtime::Runtime;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use dozer_types::models::source::Source;
@@ -23,6 +24,7 @@ pub struct Executor<'a> {
     sinks: &'a [Sink],
     labels: DozerMonitorContext,
     udfs: &'a [UdfConfig],
+    sink_metadata_dir: PathBuf,
 }
 
 impl<'a> Executor<'a> {
@@ -36,6 +38,7 @@ impl<'a> Executor<'a> {
         sinks: &'a [Sink],
         labels: DozerMonitorContext,
         udfs: &'a [UdfConfig],
+        sink_metadata_dir: PathBuf,
     ) -> Result<Executor<'a>, OrchestrationError> {
         Ok(Executor {
             connections,
@@ -44,6 +47,7 @@ impl<'a> Executor<'a> {
             sinks,
             labels,
             udfs,
+            sink_metadata_dir,
         })
     }
 
@@ -52,7 +56,7 @@ impl<'a> Executor<'a> {
         runtime: &Arc<Runtime>,
         executor_options: ExecutorOptions,
         shutdown: ShutdownReceiver,
-        flags: Flags,
+        flags: PipelineFlags,
     ) -> Result<DagExecutor, OrchestrationError> {
         let builder = PipelineBuilder::new(
             self.connections,
@@ -62,7 +66,8 @@ impl<'a> Executor<'a> {
             self.labels.clone(),
             flags,
             self.udfs,
-        );
+        )
+        .with_sink_metadata_dir(self.sink_metadata_dir);
 
         let dag = builder.build(runtime, shutdown).await?;
         let exec = DagExecutor::new(dag, executor_options).await?;
@@ -6,12 +6,13 @@ use crate::pipeline::connector_source::ConnectorSourceFactoryError;
 use crate::pipeline::PipelineBuilder;
 use crate::simple::build;
 use crate::simple::helper::validate_config;
-use crate::utils::get_executor_options;
+use crate::utils::{get_executor_options, get_pipeline_flags};
 
 use crate::flatten_join_handle;
 use camino::Utf8PathBuf;
 use dozer_core::app::AppPipeline;
 use dozer_core::dag_schemas::DagSchemas;
+use dozer_core::dead_letter::DeadLetterDir;
 use dozer_core::event::EventHub;
 use dozer_core::shutdown::ShutdownReceiver;
 use dozer_tracing::DozerMonitorContext;
@@ -79,6 +80,7 @@ impl SimpleOrchestrator {
         shutdown: ShutdownReceiver,
         api_notifier: Option<oneshot::Sender<()>>,
     ) -> Result<(), OrchestrationError> {
+        let build_path = HomeDir::new(self.home_dir()).get_build_path(BuildId::first());
         let executor = Executor::new(
             &self.config.connections,
             &self.config.sources,
@@ -86,14 +88,15 @@ impl SimpleOrchestrator {
             &self.config.sinks,
             self.labels.clone(),
             &self.config.udfs,
+            build_path.sink_metadata_dir.clone().into_std_path_buf(),
         )
         .await?;
         let dag_executor = executor
             .create_dag_executor(
                 &self.runtime,
-                get_executor_options(&self.config),
+                get_executor_options(&self.config, &build_path),
                 shutdown.clone(),
-                self.config.flags.clone(),
+                get_pipeline_flags(&self.config),
             )
             .await?;
 
@@ -171,7 +174,7 @@ impl SimpleOrchestrator {
             self.config.sql.as_deref(),
             &self.config.sinks,
             self.labels.clone(),
-            self.config.flags.clone(),
+            get_pipeline_flags(&self.config),
             &self.config.udfs,
         );
         let dag = builder.build(&self.runtime, shutdown).await?;
@@ -216,6 +219,39 @@ impl SimpleOrchestrator {
         Ok(())
     }
 
+    fn dead_letter_dir(&self) -> DeadLetterDir {
+        let build_path = HomeDir::new(self.home_dir()).get_build_path(BuildId::first());
+        DeadLetterDir::new(build_path.dead_letter_dir.into_std_path_buf())
+    }
+
+    pub fn list_dead_letters(&self, node: Option<&str>) -> Result<(), OrchestrationError> {
+        let dead_letters = self.dead_letter_dir().list(node)?;
+        for dead_letter in &dead_letters {
+            let op_id = dead_letter
+                .op
+                .id
+                .map(|id| format!("{}:{}", id.txid, id.seq_in_tx))
+                .unwrap_or_else(|| "-".to_string());
+            info!(
+                "[{}] epoch {}, op {}, port {}: {}\n{:?}",
+                dead_letter.node,
+                dead_letter.epoch_id,
+                op_id,
+                dead_letter.op.port,
+                dead_letter.error,
+                dead_letter.op.op
+            );
+        }
+        info!("{} dead letters", dead_letters.len());
+        Ok(())
+    }
+
+    pub fn replay_dead_letters(&self, node: Option<&str>) -> Result<(), OrchestrationError> {
+        let count = self.dead_letter_dir().queue_replay(node)?;
+        info!("{count} dead letters will be replayed on the next run");
+        Ok(())
+    }
+
     pub async fn run_all(
         &self,
         shutdown: ShutdownReceiver,
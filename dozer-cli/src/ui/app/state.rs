@@ -15,7 +15,6 @@ use dozer_types::{
     models::{
         api_config::{ApiConfig, AppGrpcOptions, GrpcApiOptions, RestApiOptions},
         api_security::ApiSecurity,
-        flags::Flags,
     },
 };
 use tempfile::TempDir;
@@ -305,7 +304,7 @@ pub async fn create_dag(dozer: &SimpleOrchestrator) -> Result<Dag, Orchestration
         dozer.config.sql.as_deref(),
         &dozer.config.sinks,
         Default::default(),
-        Flags::default(),
+        Default::default(),
         &dozer.config.udfs,
     );
     let (_shutdown_sender, shutdown_receiver) = shutdown::new(&dozer.runtime);
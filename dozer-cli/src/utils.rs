@@ -1,8 +1,25 @@
-use dozer_core::executor::ExecutorOptions;
+use dozer_core::{
+    app::PipelineFlags,
+    executor::{ErrorPolicies, ExecutorOptions, SinkRetryOptions},
+    operation_log::OperationLogOptions,
+    state_store::StateStoreOptions,
+};
 use dozer_types::models::{
-    app_config::{default_app_buffer_size, default_error_threshold, default_event_hub_capacity},
+    app_config::{
+        default_app_buffer_size, default_error_threshold, default_event_hub_capacity,
+        default_max_cross_join_records, default_operation_log_max_segment_bytes,
+        default_operation_log_retention_bytes, default_parallelism,
+        default_sink_initial_backoff_ms, default_sink_max_backoff_ms, default_sink_max_buffered,
+        default_sink_max_open_duration_ms, default_sink_max_retries, default_sink_open_duration_ms,
+        default_state_store_cache_size, StateStoreConfig,
+    },
     config::Config,
+    flags::default_enable_app_checkpoints,
 };
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::home_dir::BuildPath;
 
 fn get_buffer_size(config: &Config) -> u32 {
     config
@@ -25,10 +42,115 @@ fn get_event_hub_capacity(config: &Config) -> usize {
         .unwrap_or_else(default_event_hub_capacity)
 }
 
-pub fn get_executor_options(config: &Config) -> ExecutorOptions {
+fn get_checkpoint_dir(config: &Config, build_path: &BuildPath) -> Option<PathBuf> {
+    config
+        .flags
+        .enable_app_checkpoints
+        .unwrap_or_else(default_enable_app_checkpoints)
+        .then(|| build_path.checkpoint_dir.clone().into_std_path_buf())
+}
+
+fn get_state_store(config: &Config, build_path: &BuildPath) -> StateStoreOptions {
+    match &config.app.state_store {
+        None | Some(StateStoreConfig::InMemory) => StateStoreOptions::InMemory,
+        Some(StateStoreConfig::OnDisk(on_disk)) => StateStoreOptions::OnDisk {
+            path: build_path.state_dir.clone().into_std_path_buf(),
+            cache_size: on_disk
+                .cache_size
+                .unwrap_or_else(default_state_store_cache_size),
+        },
+    }
+}
+
+fn get_error_policies(config: &Config, build_path: &BuildPath) -> ErrorPolicies {
+    ErrorPolicies {
+        default: config.app.error_policy.unwrap_or_default(),
+        nodes: config
+            .app
+            .node_error_policies
+            .iter()
+            .map(|node| (node.node.clone(), node.policy))
+            .collect(),
+        dead_letter_dir: Some(build_path.dead_letter_dir.clone().into_std_path_buf()),
+    }
+}
+
+fn get_sink_retry(config: &Config) -> SinkRetryOptions {
+    let sink_retry = config.app.sink_retry.clone().unwrap_or_default();
+    SinkRetryOptions {
+        max_retries: sink_retry
+            .max_retries
+            .unwrap_or_else(default_sink_max_retries),
+        initial_backoff: Duration::from_millis(
+            sink_retry
+                .initial_backoff_ms
+                .unwrap_or_else(default_sink_initial_backoff_ms),
+        ),
+        max_backoff: Duration::from_millis(
+            sink_retry
+                .max_backoff_ms
+                .unwrap_or_else(default_sink_max_backoff_ms),
+        ),
+        open_duration: Duration::from_millis(
+            sink_retry
+                .open_duration_ms
+                .unwrap_or_else(default_sink_open_duration_ms),
+        ),
+        max_open_duration: Duration::from_millis(
+            sink_retry
+                .max_open_duration_ms
+                .unwrap_or_else(default_sink_max_open_duration_ms),
+        ),
+        max_buffered: sink_retry
+            .max_buffered
+            .unwrap_or_else(default_sink_max_buffered),
+    }
+}
+
+fn get_operation_log(config: &Config, build_path: &BuildPath) -> Option<OperationLogOptions> {
+    let operation_log = config.app.operation_log.as_ref()?;
+    Some(OperationLogOptions {
+        dir: build_path.operation_log_dir.clone().into_std_path_buf(),
+        max_segment_bytes: operation_log
+            .max_segment_bytes
+            .unwrap_or_else(default_operation_log_max_segment_bytes),
+        retention_bytes: operation_log
+            .retention_bytes
+            .unwrap_or_else(default_operation_log_retention_bytes),
+        retention: operation_log.retention_secs.map(Duration::from_secs),
+    })
+}
+
+pub fn get_executor_options(config: &Config, build_path: &BuildPath) -> ExecutorOptions {
     ExecutorOptions {
         channel_buffer_sz: get_buffer_size(config) as usize,
         error_threshold: Some(get_error_threshold(config)),
         event_hub_capacity: get_event_hub_capacity(config),
+        checkpoint_dir: get_checkpoint_dir(config, build_path),
+        state_store: get_state_store(config, build_path),
+        error_policies: get_error_policies(config, build_path),
+        sink_retry: get_sink_retry(config),
+        operation_log: get_operation_log(config, build_path),
+    }
+}
+
+pub fn get_pipeline_flags(config: &Config) -> PipelineFlags {
+    PipelineFlags {
+        parallelism: config
+            .app
+            .parallelism
+            .unwrap_or_else(default_parallelism)
+            .get(),
+        query_parallelism: config
+            .app
+            .query_parallelism
+            .iter()
+            .map(|query| (query.table_name.clone(), query.parallelism.get()))
+            .collect(),
+        max_cross_join_records: config
+            .app
+            .max_cross_join_records
+            .unwrap_or_else(default_max_cross_join_records),
+        ..PipelineFlags::from(&config.flags)
     }
 }
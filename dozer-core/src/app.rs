@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
+use dozer_types::models::app_config::default_max_cross_join_records;
 use dozer_types::models::flags::{EnableProbabilisticOptimizations, Flags};
 use dozer_types::node::NodeHandle;
 
 use crate::appsource::{self, AppSourceManager};
 use crate::errors::ExecutionError;
 use crate::node::{PortHandle, ProcessorFactory, SinkFactory};
+use crate::partition::{self, add_partitioned_processor};
 use crate::{Dag, Edge, Endpoint};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -29,6 +33,8 @@ pub struct AppPipeline {
     processors: Vec<(NodeHandle, Box<dyn ProcessorFactory>)>,
     sinks: Vec<(NodeHandle, Box<dyn SinkFactory>)>,
     entry_points: Vec<(NodeHandle, PipelineEntryPoint)>,
+    /// Processors that run as several instances, by id.
+    parallelism: HashMap<String, usize>,
     flags: PipelineFlags,
 }
 
@@ -41,6 +47,11 @@ impl AppPipeline {
         self.processors.push((Self::create_handle(id), proc));
     }
 
+    /// Runs processor `id` as `parallelism` instances, sharded by its factory's [Partitioner](crate::node::Partitioner).
+    pub fn set_parallelism(&mut self, id: &str, parallelism: usize) {
+        self.parallelism.insert(id.to_string(), parallelism);
+    }
+
     pub fn add_sink(&mut self, sink: Box<dyn SinkFactory>, id: String) {
         self.sinks.push((Self::create_handle(id), sink));
     }
@@ -70,6 +81,7 @@ impl AppPipeline {
             sinks: Vec::new(),
             edges: Vec::new(),
             entry_points: Vec::new(),
+            parallelism: HashMap::new(),
             flags,
         }
     }
@@ -93,12 +105,31 @@ impl AppPipeline {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PipelineFlags {
     pub enable_probabilistic_optimizations: EnableProbabilisticOptimizations,
+    /// How many instances aggregations and joins run as.
+    pub parallelism: usize,
+    /// Overrides `parallelism` for the queries outputting these tables.
+    pub query_parallelism: HashMap<String, usize>,
+    /// How many records either side of a CROSS JOIN may keep, as every record joins with all records of the other side.
+    pub max_cross_join_records: usize,
+}
+
+impl PipelineFlags {
+    /// The parallelism of the query outputting `table_name`.
+    pub fn parallelism_of(&self, table_name: &str) -> usize {
+        self.query_parallelism
+            .get(table_name)
+            .copied()
+            .unwrap_or(self.parallelism)
+    }
 }
 
 impl From<&Flags> for PipelineFlags {
     fn from(flags: &Flags) -> Self {
         Self {
             enable_probabilistic_optimizations: flags.enable_probabilistic_optimizations.clone(),
+            parallelism: 1,
+            query_parallelism: HashMap::new(),
+            max_cross_join_records: default_max_cross_join_records(),
         }
     }
 }
@@ -134,8 +165,41 @@ impl App {
 
         // Add all processors and sinks while collecting the entry points.
         for (pipeline_id, pipeline) in self.pipelines {
+            // Partitioned processors are entered through their router and left through their merge.
+            let parallelism = |id: &String| {
+                pipeline
+                    .parallelism
+                    .get(id)
+                    .copied()
+                    .filter(|parallelism| *parallelism > 1)
+            };
+            let input_id = |id: String| {
+                if parallelism(&id).is_some() {
+                    partition::router_id(&id)
+                } else {
+                    id
+                }
+            };
+            let output_id = |id: String| {
+                if parallelism(&id).is_some() {
+                    partition::merge_id(&id)
+                } else {
+                    id
+                }
+            };
+
             for (handle, proc) in pipeline.processors {
-                dag.add_processor(NodeHandle::new(Some(pipeline_id), handle.id), proc);
+                if let Some(parallelism) = parallelism(&handle.id) {
+                    add_partitioned_processor(
+                        &mut dag,
+                        Some(pipeline_id),
+                        &handle.id,
+                        proc,
+                        parallelism,
+                    )?;
+                } else {
+                    dag.add_processor(NodeHandle::new(Some(pipeline_id), handle.id), proc);
+                }
             }
             for (handle, sink) in pipeline.sinks {
                 dag.add_sink(NodeHandle::new(Some(pipeline_id), handle.id), sink);
@@ -143,11 +207,11 @@ impl App {
             for edge in pipeline.edges {
                 dag.connect(
                     Endpoint::new(
-                        NodeHandle::new(Some(pipeline_id), edge.from.node.id),
+                        NodeHandle::new(Some(pipeline_id), output_id(edge.from.node.id)),
                         edge.from.port,
                     ),
                     Endpoint::new(
-                        NodeHandle::new(Some(pipeline_id), edge.to.node.id),
+                        NodeHandle::new(Some(pipeline_id), input_id(edge.to.node.id)),
                         edge.to.port,
                     ),
                 )?;
@@ -156,7 +220,10 @@ impl App {
             for (handle, entry) in pipeline.entry_points {
                 entry_points.push((
                     entry.source_name,
-                    Endpoint::new(NodeHandle::new(Some(pipeline_id), handle.id), entry.port),
+                    Endpoint::new(
+                        NodeHandle::new(Some(pipeline_id), input_id(handle.id)),
+                        entry.port,
+                    ),
                 ));
             }
         }
@@ -1,19 +1,21 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fmt::Debug,
 };
 
 use daggy::{petgraph::visit::IntoNodeIdentifiers, NodeIndex};
 use dozer_types::{
-    log::warn,
-    node::{NodeHandle, OpIdentifier},
+    log::{info, warn},
+    node::{NodeHandle, OpIdentifier, SourceState},
 };
 
 use crate::{
+    checkpoint::{CheckpointDir, ProcessorCheckpoint},
     dag_schemas::{DagHaveSchemas, DagSchemas, EdgeType},
     errors::ExecutionError,
     event::EventHub,
-    node::{Processor, Sink, SinkFactory, Source},
+    node::{Processor, Sink, SinkFactory, Source, SourceFactory},
+    state_store::StateStore,
     NodeKind as DagNodeKind,
 };
 
@@ -28,28 +30,44 @@ pub struct NodeType {
 
 #[derive(Debug)]
 /// Node kind, source, processor or sink. Source has a checkpoint to start from.
+/// Sink has the source it follows and the checkpoint it has caught up to in it.
 pub enum NodeKind {
     Source {
         source: Box<dyn Source>,
         last_checkpoint: Option<OpIdentifier>,
     },
-    Processor(Box<dyn Processor>),
-    Sink(Box<dyn Sink>),
+    Processor {
+        processor: Box<dyn Processor>,
+        /// The store the processor keeps its state in, checkpointed along with it.
+        state_store: StateStore,
+    },
+    Sink {
+        sink: Box<dyn Sink>,
+        source: NodeHandle,
+        /// Once the source node is created, it's only kept if the source replays operations up to it,
+        /// which the sink then skips.
+        last_checkpoint: Option<OpIdentifier>,
+    },
 }
 
 /// Builder DAG builds all the sources, processors and sinks.
 /// It also asks each source if its possible to start from the given checkpoint.
 /// If not possible, it resets metadata and updates the checkpoint.
+/// Processors are restored from `checkpoint_dir` if their checkpoint matches where the sources resume.
 #[derive(Debug)]
 pub struct BuilderDag {
     graph: daggy::Dag<NodeType, EdgeType>,
     event_hub: EventHub,
+    checkpoint_dir: Option<CheckpointDir>,
+    state_store: StateStore,
 }
 
 impl BuilderDag {
     pub async fn new(
         dag_schemas: DagSchemas,
         event_hub_capacity: usize,
+        checkpoint_dir: Option<CheckpointDir>,
+        state_store: StateStore,
     ) -> Result<Self, ExecutionError> {
         // Collect input output schemas.
         let mut input_schemas = HashMap::new();
@@ -98,6 +116,15 @@ impl BuilderDag {
                     )
                     .await
                     .map_err(ExecutionError::Factory)?;
+                sink.set_source(&source).map_err(ExecutionError::Sink)?;
+
+                // The DAG is built within the runtime, and sinks may block on it, so resolve from another thread.
+                std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| resolve_prepared_epochs(&handle, sink.as_mut()))
+                        .join()
+                        .expect("resolving prepared epochs panicked")
+                })?;
 
                 let state = sink.get_source_state().map_err(ExecutionError::Sink)?;
                 if let Some(state) = state {
@@ -113,21 +140,25 @@ impl BuilderDag {
                     }
                 }
 
+                // A source can only resume from where every sink connected to it has caught up to.
                 let op_id = sink.get_latest_op_id().map_err(ExecutionError::Sink)?;
-                if let Some(op_id) = op_id {
-                    match source_op_ids.entry(source.clone()) {
-                        Entry::Occupied(mut entry) => {
-                            *entry.get_mut() = op_id.min(*entry.get());
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(op_id);
-                        }
+                match source_op_ids.entry(source.clone()) {
+                    Entry::Occupied(mut entry) => {
+                        let min_op_id = entry.get().zip(op_id).map(|(a, b)| a.min(b));
+                        *entry.get_mut() = min_op_id;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(op_id);
                     }
                 }
 
                 let new_node_index = graph.add_node(NodeType {
                     handle,
-                    kind: NodeKind::Sink(sink),
+                    kind: NodeKind::Sink {
+                        sink,
+                        source: source.clone(),
+                        last_checkpoint: op_id,
+                    },
                 });
                 node_index_map.insert(node_index, new_node_index);
                 source_id_to_sinks
@@ -137,73 +168,100 @@ impl BuilderDag {
             }
         }
 
-        // Build sources, processors, and collect source states.
+        // Build sources, and collect the op ids they resume from.
+        let mut source_checkpoints = HashMap::new();
         for (node_index, node) in nodes.iter_mut().enumerate() {
-            let Some(node) = node.take() else {
+            let Some((handle, source)) = take_source(node) else {
                 continue;
             };
             let node_index = NodeIndex::new(node_index);
-            let node = match node.kind {
-                DagNodeKind::Source(source) => {
-                    let source = source
-                        .build(
-                            output_schemas
-                                .remove(&node_index)
-                                .expect("we collected all output schemas"),
-                            event_hub.clone(),
-                            source_states.remove(&node.handle),
-                        )
-                        .map_err(ExecutionError::Factory)?;
-
-                    // Write state to relevant sink.
-                    let state = source
-                        .serialize_state()
-                        .await
-                        .map_err(ExecutionError::Source)?;
-                    let mut checkpoint = None;
-                    for sink in source_id_to_sinks.remove(&node.handle).unwrap_or_default() {
-                        let sink = &mut graph[sink];
-                        let sink_handle = &sink.handle;
-                        let NodeKind::Sink(sink) = &mut sink.kind else {
-                            unreachable!()
-                        };
-                        sink.set_source_state(&state)
-                            .map_err(ExecutionError::Sink)?;
-                        if let Some(sink_checkpoint) = source_op_ids.remove(sink_handle) {
-                            checkpoint =
-                                Some(checkpoint.unwrap_or(sink_checkpoint).min(sink_checkpoint));
-                        }
-                    }
+            let source = source
+                .build(
+                    output_schemas
+                        .remove(&node_index)
+                        .expect("we collected all output schemas"),
+                    event_hub.clone(),
+                    source_states.remove(&handle),
+                )
+                .map_err(ExecutionError::Factory)?;
 
-                    NodeType {
-                        handle: node.handle,
-                        kind: NodeKind::Source {
-                            source,
-                            last_checkpoint: checkpoint,
-                        },
-                    }
-                }
-                DagNodeKind::Processor(processor) => {
-                    let processor = processor
-                        .build(
-                            input_schemas
-                                .remove(&node_index)
-                                .expect("we collected all input schemas"),
-                            output_schemas
-                                .remove(&node_index)
-                                .expect("we collected all output schemas"),
-                            event_hub.clone(),
-                        )
-                        .await
-                        .map_err(ExecutionError::Factory)?;
-                    NodeType {
-                        handle: node.handle,
-                        kind: NodeKind::Processor(processor),
-                    }
-                }
-                DagNodeKind::Sink(_) => unreachable!(),
+            // Write state to relevant sink.
+            let state = source
+                .serialize_state()
+                .await
+                .map_err(ExecutionError::Source)?;
+            for sink in source_id_to_sinks.remove(&handle).unwrap_or_default() {
+                let NodeKind::Sink { sink, .. } = &mut graph[sink].kind else {
+                    unreachable!()
+                };
+                sink.set_source_state(&state)
+                    .map_err(ExecutionError::Sink)?;
+            }
+
+            let checkpoint = source_op_ids.remove(&handle).flatten();
+            if let Some(checkpoint) = checkpoint {
+                source_checkpoints.insert(handle.clone(), checkpoint);
+            }
+
+            let new_node_index = graph.add_node(NodeType {
+                handle,
+                kind: NodeKind::Source {
+                    source,
+                    last_checkpoint: checkpoint,
+                },
+            });
+            node_index_map.insert(node_index, new_node_index);
+        }
+
+        // Build processors, restoring their state if it's consistent with the sources.
+        for (node_index, node) in nodes.iter_mut().enumerate() {
+            let Some(node) = node.take() else {
+                continue;
             };
-            let new_node_index = graph.add_node(node);
+            let DagNodeKind::Processor(processor) = node.kind else {
+                unreachable!("we built all sources and sinks")
+            };
+            let checkpoint = match &checkpoint_dir {
+                Some(checkpoint_dir) => load_processor_checkpoint(
+                    checkpoint_dir,
+                    &node.handle,
+                    &affecting_sources[node_index],
+                    &source_checkpoints,
+                    &state_store,
+                )?,
+                None => None,
+            };
+            let node_state_store = state_store
+                .for_node(
+                    &node.handle,
+                    checkpoint
+                        .as_ref()
+                        .and_then(|checkpoint| checkpoint.state_store_checkpoint),
+                )
+                .map_err(ExecutionError::OpenStateStore)?;
+
+            let node_index = NodeIndex::new(node_index);
+            let processor = processor
+                .build(
+                    input_schemas
+                        .remove(&node_index)
+                        .expect("we collected all input schemas"),
+                    output_schemas
+                        .remove(&node_index)
+                        .expect("we collected all output schemas"),
+                    event_hub.clone(),
+                    checkpoint.map(|checkpoint| checkpoint.state),
+                    node_state_store.clone(),
+                )
+                .await
+                .map_err(ExecutionError::Factory)?;
+            let new_node_index = graph.add_node(NodeType {
+                handle: node.handle,
+                kind: NodeKind::Processor {
+                    processor,
+                    state_store: node_state_store,
+                },
+            });
             node_index_map.insert(node_index, new_node_index);
         }
 
@@ -218,13 +276,26 @@ impl BuilderDag {
                 .expect("we know there's no loop");
         }
 
-        Ok(BuilderDag { graph, event_hub })
+        Ok(BuilderDag {
+            graph,
+            event_hub,
+            checkpoint_dir,
+            state_store,
+        })
     }
 
     pub fn graph(&self) -> &daggy::Dag<NodeType, EdgeType> {
         &self.graph
     }
 
+    pub fn checkpoint_dir(&self) -> Option<&CheckpointDir> {
+        self.checkpoint_dir.as_ref()
+    }
+
+    pub fn state_store(&self) -> &StateStore {
+        &self.state_store
+    }
+
     pub fn into_graph_and_event_hub(self) -> (daggy::Dag<NodeType, EdgeType>, EventHub) {
         (self.graph, self.event_hub)
     }
@@ -239,3 +310,64 @@ fn take_sink(node: &mut Option<super::NodeType>) -> Option<(NodeHandle, Box<dyn
         None
     }
 }
+
+fn take_source(node: &mut Option<super::NodeType>) -> Option<(NodeHandle, Box<dyn SourceFactory>)> {
+    let super::NodeType { handle, kind } = node.take()?;
+    if let super::NodeKind::Source(source) = kind {
+        Some((handle, source))
+    } else {
+        *node = Some(super::NodeType { handle, kind });
+        None
+    }
+}
+
+/// Commits the epochs the sink prepared but didn't get to commit before it last stopped, so the position it
+/// resumes from is known. A prepared epoch holds its operations together with the source states they were
+/// received at, so committing it never duplicates or loses operations.
+pub fn resolve_prepared_epochs(
+    handle: &NodeHandle,
+    sink: &mut dyn Sink,
+) -> Result<(), ExecutionError> {
+    let mut epoch_ids = sink.prepared_epochs().map_err(ExecutionError::Sink)?;
+    epoch_ids.sort();
+    for epoch_id in epoch_ids {
+        info!("[{handle}] Committing prepared epoch {epoch_id}");
+        sink.commit_prepared(epoch_id)
+            .map_err(ExecutionError::Sink)?;
+    }
+    Ok(())
+}
+
+/// Returns the newest checkpoint of the processor taken at the op ids its sources resume from.
+fn load_processor_checkpoint(
+    checkpoint_dir: &CheckpointDir,
+    handle: &NodeHandle,
+    sources: &HashSet<NodeHandle>,
+    source_checkpoints: &HashMap<NodeHandle, OpIdentifier>,
+    state_store: &StateStore,
+) -> Result<Option<ProcessorCheckpoint>, ExecutionError> {
+    let mut found = false;
+    let consistent = checkpoint_dir.find(handle, |checkpoint| {
+        found = true;
+        sources.iter().all(|source| {
+            source_checkpoints.get(source).is_some_and(|op_id| {
+                checkpoint.source_states.get(source) == Some(&SourceState::Restartable(*op_id))
+            })
+        })
+    })?;
+    match consistent {
+        // Disk backed state isn't in the checkpoint, but in the database checkpoint taken along.
+        Some(checkpoint)
+            if checkpoint.state_store_checkpoint.is_some() && !state_store.is_on_disk() =>
+        {
+            warn!("Checkpoint of {handle} has its state on disk, but the state store is in memory, starting it from empty state");
+            Ok(None)
+        }
+        Some(checkpoint) => Ok(Some(checkpoint)),
+        None if !found => Ok(None),
+        None => {
+            warn!("No checkpoint of {handle} matches where its sources resume from, starting it from empty state");
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use dozer_types::{
+    bincode,
+    node::{NodeHandle, SourceStates},
+};
+
+use crate::errors::ExecutionError;
+use crate::state_store::StateStore;
+
+/// A processor's serialized state, together with the source states of the epoch it was taken at.
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct ProcessorCheckpoint {
+    pub source_states: SourceStates,
+    pub state: Vec<u8>,
+    /// The checkpoint of the processor's state store database taken along, if its state is on disk.
+    pub state_store_checkpoint: Option<u64>,
+}
+
+/// How many checkpoints of every processor are kept. Sinks that batch commits resume from an epoch
+/// behind the latest one, so the processor is restored from the checkpoint taken at that epoch.
+pub(crate) const KEPT_CHECKPOINTS: usize = 16;
+
+/// Directory holding the latest checkpoints of every stateful processor, one directory per node
+/// and one file per checkpoint, named by a sequence number that keeps increasing across runs.
+#[derive(Debug, Clone)]
+pub struct CheckpointDir {
+    dir: PathBuf,
+}
+
+impl CheckpointDir {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn processor_dir(&self, node_handle: &NodeHandle) -> PathBuf {
+        self.dir.join(node_handle.to_string())
+    }
+
+    /// Sequence numbers of the processor's checkpoints, oldest first.
+    fn sequence_numbers(&self, node_handle: &NodeHandle) -> Result<Vec<u64>, ExecutionError> {
+        let dir = self.processor_dir(node_handle);
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(ExecutionError::FileSystemError(dir, e)),
+        };
+        let mut sequence_numbers = vec![];
+        for entry in entries {
+            let entry = entry.map_err(|e| ExecutionError::FileSystemError(dir.clone(), e))?;
+            let path = entry.path();
+            // Leftover temporary files are skipped.
+            if path.extension().and_then(|extension| extension.to_str()) != Some("bin") {
+                continue;
+            }
+            if let Some(seq) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            {
+                sequence_numbers.push(seq);
+            }
+        }
+        sequence_numbers.sort();
+        Ok(sequence_numbers)
+    }
+
+    fn checkpoint_path(&self, node_handle: &NodeHandle, seq: u64) -> PathBuf {
+        self.processor_dir(node_handle)
+            .join(format!("{seq:020}.bin"))
+    }
+
+    /// Adds a checkpoint of the processor, along with a checkpoint of its state store, and removes the ones beyond
+    /// the kept count. The file is written aside, synced and renamed, so a crash never leaves a partially written
+    /// checkpoint behind.
+    pub fn write(
+        &self,
+        node_handle: &NodeHandle,
+        source_states: SourceStates,
+        state: Vec<u8>,
+        state_store: &StateStore,
+    ) -> Result<(), ExecutionError> {
+        let dir = self.processor_dir(node_handle);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| ExecutionError::FileSystemError(dir.clone(), e))?;
+
+        let sequence_numbers = self.sequence_numbers(node_handle)?;
+        let seq = sequence_numbers.last().map_or(0, |seq| seq + 1);
+        let checkpoint = ProcessorCheckpoint {
+            source_states,
+            state,
+            state_store_checkpoint: state_store
+                .checkpoint(seq)
+                .map_err(ExecutionError::CheckpointStateStore)?,
+        };
+        let data = bincode::encode_to_vec(&checkpoint, bincode::config::legacy())
+            .map_err(ExecutionError::SerializeCheckpoint)?;
+        let path = self.checkpoint_path(node_handle, seq);
+        let temp_path = path.with_extension("bin.tmp");
+        let write = || {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(&data)?;
+            file.sync_all()
+        };
+        write().map_err(|e| ExecutionError::FileSystemError(temp_path.clone(), e))?;
+        std::fs::rename(&temp_path, &path).map_err(|e| ExecutionError::FileSystemError(path, e))?;
+
+        let outdated = (sequence_numbers.len() + 1).saturating_sub(KEPT_CHECKPOINTS);
+        for seq in &sequence_numbers[..outdated] {
+            let path = self.checkpoint_path(node_handle, *seq);
+            std::fs::remove_file(&path).map_err(|e| ExecutionError::FileSystemError(path, e))?;
+        }
+        let oldest = sequence_numbers.get(outdated).copied().unwrap_or(seq);
+        state_store
+            .remove_checkpoints_before(oldest)
+            .map_err(ExecutionError::CheckpointStateStore)
+    }
+
+    /// Returns the newest of the processor's kept checkpoints that `matches` accepts.
+    pub fn find(
+        &self,
+        node_handle: &NodeHandle,
+        mut matches: impl FnMut(&ProcessorCheckpoint) -> bool,
+    ) -> Result<Option<ProcessorCheckpoint>, ExecutionError> {
+        for seq in self.sequence_numbers(node_handle)?.into_iter().rev() {
+            let path = self.checkpoint_path(node_handle, seq);
+            let data =
+                std::fs::read(&path).map_err(|e| ExecutionError::FileSystemError(path, e))?;
+            let (checkpoint, _) = bincode::decode_from_slice(&data, bincode::config::legacy())
+                .map_err(ExecutionError::CorruptedCheckpoint)?;
+            if matches(&checkpoint) {
+                return Ok(Some(checkpoint));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use dozer_types::{
+    node::NodeHandle,
+    serde::{Deserialize, Serialize},
+    serde_json,
+    types::TableOperation,
+};
+
+use crate::errors::ExecutionError;
+
+/// An operation that a node failed on, kept so it can be replayed once the cause is fixed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct DeadLetter {
+    /// The node that failed, as shown in logs.
+    pub node: String,
+    pub error: String,
+    /// The epoch the operation was received in.
+    pub epoch_id: u64,
+    /// The operation, including its `OpIdentifier` and input port.
+    pub op: TableOperation,
+}
+
+/// Directory holding dead letters as JSON lines, one file per node.
+///
+/// Replaying moves a node's dead letters to a replay file, which the node processes when it starts
+/// and removes on its first commit. Operations that fail again are dead-lettered again.
+#[derive(Debug, Clone)]
+pub struct DeadLetterDir {
+    dir: PathBuf,
+}
+
+const DEAD_LETTER_EXTENSION: &str = "jsonl";
+const REPLAY_EXTENSION: &str = "replay";
+
+impl DeadLetterDir {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, node: &str) -> PathBuf {
+        self.dir.join(format!("{node}.{DEAD_LETTER_EXTENSION}"))
+    }
+
+    fn replay_path(&self, node: &str) -> PathBuf {
+        self.dir.join(format!("{node}.{REPLAY_EXTENSION}"))
+    }
+
+    pub fn push(&self, dead_letter: &DeadLetter) -> Result<(), ExecutionError> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| ExecutionError::FileSystemError(self.dir.clone(), e))?;
+
+        let mut line =
+            serde_json::to_vec(dead_letter).map_err(ExecutionError::SerializeDeadLetter)?;
+        line.push(b'\n');
+        let path = self.path(&dead_letter.node);
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&line))
+            .map_err(|e| ExecutionError::FileSystemError(path, e))
+    }
+
+    /// Returns the dead letters of `node`, or of all nodes, ordered by node.
+    pub fn list(&self, node: Option<&str>) -> Result<Vec<DeadLetter>, ExecutionError> {
+        let mut result = vec![];
+        for node in self.nodes(node)? {
+            result.extend(read(&self.path(&node))?);
+        }
+        Ok(result)
+    }
+
+    /// Queues the dead letters of `node`, or of all nodes, to be replayed on the next run. Returns how many were queued.
+    pub fn queue_replay(&self, node: Option<&str>) -> Result<usize, ExecutionError> {
+        let mut count = 0;
+        for node in self.nodes(node)? {
+            let path = self.path(&node);
+            let data = std::fs::read(&path)
+                .map_err(|e| ExecutionError::FileSystemError(path.clone(), e))?;
+            count += data.iter().filter(|byte| **byte == b'\n').count();
+
+            let replay_path = self.replay_path(&node);
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&replay_path)
+                .and_then(|mut file| file.write_all(&data))
+                .map_err(|e| ExecutionError::FileSystemError(replay_path, e))?;
+            std::fs::remove_file(&path).map_err(|e| ExecutionError::FileSystemError(path, e))?;
+        }
+        Ok(count)
+    }
+
+    /// Returns the dead letters queued for replay into `node_handle`.
+    pub fn replays(&self, node_handle: &NodeHandle) -> Result<Vec<DeadLetter>, ExecutionError> {
+        let path = self.replay_path(&node_handle.to_string());
+        if path.exists() {
+            read(&path)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Removes the replay queue of `node_handle`, once the replayed operations are committed.
+    pub fn finish_replay(&self, node_handle: &NodeHandle) -> Result<(), ExecutionError> {
+        let path = self.replay_path(&node_handle.to_string());
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(ExecutionError::FileSystemError(path, e)),
+        }
+    }
+
+    /// Returns the sorted names of nodes that have dead letters, only including `node` if given.
+    fn nodes(&self, node: Option<&str>) -> Result<Vec<String>, ExecutionError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(ExecutionError::FileSystemError(self.dir.clone(), e)),
+        };
+
+        let mut result = vec![];
+        for entry in entries {
+            let path = entry
+                .map_err(|e| ExecutionError::FileSystemError(self.dir.clone(), e))?
+                .path();
+            if path.extension().and_then(|extension| extension.to_str())
+                != Some(DEAD_LETTER_EXTENSION)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if node.map_or(true, |node| node == name) {
+                result.push(name.to_string());
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<DeadLetter>, ExecutionError> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))?;
+    data.lines()
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| ExecutionError::CorruptedDeadLetter(path.to_path_buf(), e))
+        })
+        .collect()
+}
@@ -1,8 +1,17 @@
 use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
 
+use dozer_types::models::app_config::ErrorPolicy;
+use dozer_types::node::NodeHandle;
 use dozer_types::tracing::error_span;
+use dozer_types::types::TableOperation;
 use dozer_types::{errors::internal::BoxedError, log::error};
 
+use crate::dead_letter::{DeadLetter, DeadLetterDir};
+use crate::errors::ExecutionError;
+use crate::executor::ErrorPolicies;
+use crate::partition::processor_id;
+
 /// `ErrorManager` records and counts the number of errors happened.
 ///
 /// It panics when an error threshold is set and reached.
@@ -10,21 +19,27 @@ use dozer_types::{errors::internal::BoxedError, log::error};
 pub struct ErrorManager {
     threshold: Option<u32>,
     count: AtomicU32,
+    policies: ErrorPolicies,
+    dead_letter_dir: Option<DeadLetterDir>,
 }
 
 impl ErrorManager {
-    pub fn new_threshold(threshold: u32) -> Self {
-        Self {
-            threshold: Some(threshold),
-            count: AtomicU32::new(0),
+    pub fn new(threshold: Option<u32>, policies: ErrorPolicies) -> Result<Self, ExecutionError> {
+        let dead_letters = policies.default == ErrorPolicy::DeadLetter
+            || policies
+                .nodes
+                .values()
+                .any(|policy| *policy == ErrorPolicy::DeadLetter);
+        if dead_letters && policies.dead_letter_dir.is_none() {
+            return Err(ExecutionError::MissingDeadLetterDir);
         }
-    }
 
-    pub fn new_unlimited() -> Self {
-        Self {
-            threshold: None,
+        Ok(Self {
+            threshold,
             count: AtomicU32::new(0),
-        }
+            dead_letter_dir: policies.dead_letter_dir.clone().map(DeadLetterDir::new),
+            policies,
+        })
     }
 
     pub fn report(&self, error: BoxedError) {
@@ -39,4 +54,73 @@ impl ErrorManager {
             }
         }
     }
+
+    pub fn dead_letter_dir(&self) -> Option<&DeadLetterDir> {
+        self.dead_letter_dir.as_ref()
+    }
+
+    /// Returns the handler for errors that `node_handle` hits on operations.
+    pub fn op_error_handler(self: &Arc<Self>, node_handle: &NodeHandle) -> OpErrorHandler {
+        let policy = self
+            .policies
+            .nodes
+            .get(processor_id(&node_handle.id))
+            .copied()
+            .unwrap_or(self.policies.default);
+        OpErrorHandler {
+            node_handle: node_handle.clone(),
+            policy,
+            error_manager: self.clone(),
+        }
+    }
+}
+
+/// Applies a node's [`ErrorPolicy`] to the errors it hits on operations.
+#[derive(Debug)]
+pub struct OpErrorHandler {
+    node_handle: NodeHandle,
+    policy: ErrorPolicy,
+    error_manager: Arc<ErrorManager>,
+}
+
+impl OpErrorHandler {
+    /// Returns if errors on operations fail the node, rather than skip or dead-letter the operations.
+    pub fn fails(&self) -> bool {
+        self.policy == ErrorPolicy::Fail
+    }
+
+    /// Returns a copy of `op` if the policy needs it, in case handling `op` fails.
+    pub fn keep(&self, op: &TableOperation) -> Option<TableOperation> {
+        (self.policy == ErrorPolicy::DeadLetter).then(|| op.clone())
+    }
+
+    /// Reports `error` on an operation received in `epoch_id`. `op` is the copy returned by [`Self::keep`].
+    pub fn report(
+        &self,
+        epoch_id: u64,
+        op: Option<TableOperation>,
+        error: BoxedError,
+    ) -> Result<(), ExecutionError> {
+        match (self.policy, op, self.error_manager.dead_letter_dir()) {
+            (ErrorPolicy::Fail, _, _) => self.error_manager.report(error),
+            (ErrorPolicy::Skip, _, _) => {
+                error!("[{}] Skipped operation: {}", self.node_handle, error);
+            }
+            (ErrorPolicy::DeadLetter, Some(op), Some(dead_letter_dir)) => {
+                error!("[{}] Dead-lettered operation: {}", self.node_handle, error);
+                dead_letter_dir.push(&DeadLetter {
+                    node: self.node_handle.to_string(),
+                    error: error.to_string(),
+                    epoch_id,
+                    op,
+                })?;
+            }
+            (ErrorPolicy::DeadLetter, _, _) => {
+                unreachable!(
+                    "Operations are kept and the dead letter directory is checked on creation"
+                )
+            }
+        }
+        Ok(())
+    }
 }
@@ -3,9 +3,10 @@ use std::path::PathBuf;
 use crate::node::PortHandle;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::errors::types::{DeserializationError, SerializationError};
-use dozer_types::node::NodeHandle;
+use dozer_types::node::{NodeHandle, OpIdentifier};
 use dozer_types::thiserror::Error;
-use dozer_types::{bincode, thiserror};
+use dozer_types::types::SchemaDiff;
+use dozer_types::{bincode, serde_json, thiserror};
 
 #[derive(Error, Debug)]
 pub enum ExecutionError {
@@ -49,8 +50,56 @@ pub enum ExecutionError {
     SourceCannotRestart(NodeHandle),
     #[error("Failed to create checkpoint: {0}")]
     FailedToCreateCheckpoint(BoxedError),
+    #[error("Failed to serialize checkpoint: {0}")]
+    SerializeCheckpoint(#[source] bincode::error::EncodeError),
+    #[error("Dead letters need a directory to be written to")]
+    MissingDeadLetterDir,
+    #[error("Failed to serialize dead letter: {0}")]
+    SerializeDeadLetter(#[source] serde_json::Error),
+    #[error("Cannot deserialize dead letter in {0:?}: {1}")]
+    CorruptedDeadLetter(PathBuf, #[source] serde_json::Error),
+    #[error("Failed to serialize operation log entry: {0}")]
+    SerializeOperationLog(#[source] bincode::error::EncodeError),
+    #[error("Cannot deserialize operation log entry in {0:?}: {1}")]
+    CorruptedOperationLog(PathBuf, #[source] bincode::error::DecodeError),
+    #[error("Operation log {0:?} doesn't go back to {1:?}, the entries were removed by retention or never logged")]
+    OperationLogIncomplete(PathBuf, Option<OpIdentifier>),
     #[error("Failed to serialize record writer: {0}")]
     SerializeRecordWriter(#[source] SerializationError),
+    #[error("Failed to open state store: {0}")]
+    OpenStateStore(#[source] StateStoreError),
+    #[error("Failed to checkpoint state store: {0}")]
+    CheckpointStateStore(#[source] StateStoreError),
+    #[error("Processor {0} cannot be partitioned")]
+    NotPartitionable(String),
+    #[error("{node} cannot apply the schema change on port {port} ({diff}): {source}")]
+    SchemaChange {
+        node: NodeHandle,
+        port: PortHandle,
+        diff: SchemaDiff,
+        #[source]
+        source: BoxedError,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum SchemaChangeError {
+    #[error("Schema changes are not supported")]
+    Unsupported,
+    #[error("Only appending nullable fields is supported")]
+    NotAdditive,
+}
+
+#[derive(Error, Debug)]
+pub enum StateStoreError {
+    #[error("RocksDB error: {0}")]
+    RocksDb(#[from] rocksdb::Error),
+    #[error("File system error {0:?}: {1}")]
+    FileSystem(PathBuf, #[source] std::io::Error),
+    #[error("Failed to encode state: {0}")]
+    Encode(#[source] bincode::error::EncodeError),
+    #[error("Failed to decode state: {0}")]
+    Decode(#[source] bincode::error::DecodeError),
 }
 
 impl<T> From<crossbeam::channel::SendError<T>> for ExecutionError {
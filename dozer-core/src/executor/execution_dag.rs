@@ -6,10 +6,12 @@ use std::{
 
 use crate::{
     builder_dag::{BuilderDag, NodeKind},
+    checkpoint::CheckpointDir,
     dag_schemas::EdgeKind,
     error_manager::ErrorManager,
     errors::ExecutionError,
     event::EventHub,
+    executor::ErrorPolicies,
     executor_operation::ExecutorOperation,
     forwarder::SenderWithPortMapping,
     hash_map_to_vec::insert_vec_element,
@@ -23,6 +25,7 @@ use daggy::petgraph::{
 };
 use dozer_tracing::DozerMonitorContext;
 use dozer_types::node::NodeHandle;
+use dozer_types::types::Schema;
 use tokio::sync::Mutex;
 
 #[derive(Debug)]
@@ -45,6 +48,8 @@ pub struct EdgeType {
     pub record_writer: SharedRecordWriter,
     /// Input port handle.
     pub input_port: PortHandle,
+    /// The schema of data flowing through this edge when the DAG was built.
+    pub schema: Schema,
     /// The receiver from receiving data from upstream. Edges that have same source and target node share the same receiver.
     pub receiver: Receiver<ExecutorOperation>,
 }
@@ -57,6 +62,7 @@ pub struct ExecutionDag {
     error_manager: Arc<ErrorManager>,
     labels: DozerMonitorContext,
     event_hub: EventHub,
+    checkpoint_dir: Option<CheckpointDir>,
 }
 
 impl ExecutionDag {
@@ -65,6 +71,7 @@ impl ExecutionDag {
         labels: DozerMonitorContext,
         channel_buffer_sz: usize,
         error_threshold: Option<u32>,
+        error_policies: ErrorPolicies,
     ) -> Result<Self, ExecutionError> {
         // We only create record writer once for every output port. Every `HashMap` in this `Vec` tracks if a node's output ports already have the record writer created.
         let mut all_record_writers = vec![
@@ -78,6 +85,7 @@ impl ExecutionDag {
         >::new();
 
         // Create new edges.
+        let state_store = builder_dag.state_store().clone();
         let mut edges = vec![];
         for builder_dag_edge in builder_dag.graph().raw_edges().iter() {
             let source_node_index = builder_dag_edge.source();
@@ -95,7 +103,7 @@ impl ExecutionDag {
                                 port_type: OutputPortType::StatefulWithPrimaryKeyLookup,
                                 ..
                             } => Some(
-                                create_record_writer(edge.schema.clone())
+                                create_record_writer(edge.schema.clone(), &state_store)
                                     .map_err(ExecutionError::RestoreRecordWriter)?,
                             ),
                             _ => None,
@@ -123,12 +131,14 @@ impl ExecutionDag {
                 sender,
                 record_writer,
                 input_port: edge.input_port,
+                schema: edge.schema.clone(),
                 receiver,
             };
             edges.push(Some(edge));
         }
 
         // Create new graph.
+        let checkpoint_dir = builder_dag.checkpoint_dir().cloned();
         let (graph, event_hub) = builder_dag.into_graph_and_event_hub();
         let graph = graph.map_owned(
             |_, node| NodeType {
@@ -144,13 +154,10 @@ impl ExecutionDag {
         Ok(ExecutionDag {
             graph,
             initial_epoch_id: 0,
-            error_manager: Arc::new(if let Some(threshold) = error_threshold {
-                ErrorManager::new_threshold(threshold)
-            } else {
-                ErrorManager::new_unlimited()
-            }),
+            error_manager: Arc::new(ErrorManager::new(error_threshold, error_policies)?),
             labels,
             event_hub,
+            checkpoint_dir,
         })
     }
 
@@ -178,6 +185,10 @@ impl ExecutionDag {
         &self.event_hub
     }
 
+    pub fn checkpoint_dir(&self) -> Option<&CheckpointDir> {
+        self.checkpoint_dir.as_ref()
+    }
+
     pub fn collect_senders(&self, node_index: daggy::NodeIndex) -> Vec<SenderWithPortMapping> {
         // Map from target node index to `SenderWithPortMapping`.
         let mut senders = HashMap::<daggy::NodeIndex, SenderWithPortMapping>::new();
@@ -190,6 +201,7 @@ impl ExecutionDag {
                             .collect();
                     entry.insert(SenderWithPortMapping {
                         sender: edge.weight().sender.clone(),
+                        target: self.graph[edge.target()].handle.clone(),
                         port_mapping,
                     });
                 }
@@ -233,6 +245,17 @@ impl ExecutionDag {
         record_writers
     }
 
+    /// Returns the schema of each input port of the node.
+    pub fn collect_input_schemas(
+        &self,
+        node_index: daggy::NodeIndex,
+    ) -> HashMap<PortHandle, Schema> {
+        self.graph
+            .edges_directed(node_index, Direction::Incoming)
+            .map(|edge| (edge.weight().input_port, edge.weight().schema.clone()))
+            .collect()
+    }
+
     pub fn collect_receivers(
         &self,
         node_index: daggy::NodeIndex,
@@ -1,13 +1,23 @@
 use crate::builder_dag::{BuilderDag, NodeKind};
+use crate::checkpoint::CheckpointDir;
 use crate::dag_schemas::DagSchemas;
 use crate::errors::ExecutionError;
+use crate::operation_log::OperationLogOptions;
+use crate::state_store::{StateStore, StateStoreOptions};
 use crate::Dag;
 
 use daggy::petgraph::visit::IntoNodeIdentifiers;
 
 use dozer_tracing::DozerMonitorContext;
+use dozer_types::models::app_config::{
+    default_sink_initial_backoff_ms, default_sink_max_backoff_ms, default_sink_max_buffered,
+    default_sink_max_open_duration_ms, default_sink_max_retries, default_sink_open_duration_ms,
+    ErrorPolicy,
+};
 use futures::Future;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::thread::{self, Builder};
@@ -19,6 +29,57 @@ pub struct ExecutorOptions {
     pub channel_buffer_sz: usize,
     pub event_hub_capacity: usize,
     pub error_threshold: Option<u32>,
+    /// Directory to checkpoint processor states to. Processors are not checkpointed if `None`.
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Where processors and record writers keep their state.
+    pub state_store: StateStoreOptions,
+    /// What processors and sinks do with operations they fail on.
+    pub error_policies: ErrorPolicies,
+    /// How failed sink calls are retried.
+    pub sink_retry: SinkRetryOptions,
+    /// Where sources log what they ingest, so downstream nodes can be rebuilt by replaying it. Disabled if `None`.
+    pub operation_log: Option<OperationLogOptions>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorPolicies {
+    pub default: ErrorPolicy,
+    /// Overrides `default` by node id. Partitioned processors are looked up by the id they were added with.
+    pub nodes: HashMap<String, ErrorPolicy>,
+    /// Where dead letters are written to. Required if any node dead-letters operations.
+    pub dead_letter_dir: Option<PathBuf>,
+}
+
+/// Failed sink calls are retried with exponential backoff. Once retries are exhausted, the sink's circuit opens:
+/// the sink is paused and its input buffered, while other sinks keep running.
+///
+/// Failed operations are only retried if the sink's error policy is `Fail`. Errors the sink knows won't go away are never retried.
+#[derive(Debug, Clone)]
+pub struct SinkRetryOptions {
+    /// Retries of a failed call before the circuit opens. Zero disables retries and the circuit breaker.
+    pub max_retries: u32,
+    /// The wait before the first retry, doubled on every retry up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How long an open circuit pauses the sink before the failed call is tried again.
+    pub open_duration: Duration,
+    /// How long calls may keep the circuit open before their errors are reported.
+    pub max_open_duration: Duration,
+    /// How many inputs are buffered while the circuit is open. Once full, the sink back-pressures its inputs.
+    pub max_buffered: usize,
+}
+
+impl Default for SinkRetryOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: default_sink_max_retries(),
+            initial_backoff: Duration::from_millis(default_sink_initial_backoff_ms()),
+            max_backoff: Duration::from_millis(default_sink_max_backoff_ms()),
+            open_duration: Duration::from_millis(default_sink_open_duration_ms()),
+            max_open_duration: Duration::from_millis(default_sink_max_open_duration_ms()),
+            max_buffered: default_sink_max_buffered(),
+        }
+    }
 }
 
 impl Default for ExecutorOptions {
@@ -27,6 +88,11 @@ impl Default for ExecutorOptions {
             channel_buffer_sz: 20_000,
             event_hub_capacity: 100,
             error_threshold: Some(0),
+            checkpoint_dir: None,
+            state_store: Default::default(),
+            error_policies: Default::default(),
+            sink_retry: Default::default(),
+            operation_log: None,
         }
     }
 }
@@ -59,7 +125,15 @@ impl DagExecutor {
     pub async fn new(dag: Dag, options: ExecutorOptions) -> Result<Self, ExecutionError> {
         let dag_schemas = DagSchemas::new(dag).await?;
 
-        let builder_dag = BuilderDag::new(dag_schemas, options.event_hub_capacity).await?;
+        let state_store =
+            StateStore::open(&options.state_store).map_err(ExecutionError::OpenStateStore)?;
+        let builder_dag = BuilderDag::new(
+            dag_schemas,
+            options.event_hub_capacity,
+            options.checkpoint_dir.clone().map(CheckpointDir::new),
+            state_store,
+        )
+        .await?;
 
         Ok(Self {
             builder_dag,
@@ -84,12 +158,14 @@ impl DagExecutor {
             labels,
             self.options.channel_buffer_sz,
             self.options.error_threshold,
+            self.options.error_policies,
         )?;
         let node_indexes = execution_dag.graph().node_identifiers().collect::<Vec<_>>();
 
         // Start the threads.
         let source_node =
-            create_source_node(&mut execution_dag, &self.options, shutdown, runtime.clone()).await;
+            create_source_node(&mut execution_dag, &self.options, shutdown, runtime.clone())
+                .await?;
         let mut join_handles = vec![start_source(source_node)?];
         for node_index in node_indexes {
             let Some(node) = execution_dag.graph()[node_index].kind.as_ref() else {
@@ -97,12 +173,16 @@ impl DagExecutor {
             };
             match node {
                 NodeKind::Source { .. } => unreachable!("We already started the source node"),
-                NodeKind::Processor(_) => {
+                NodeKind::Processor { .. } => {
                     let processor_node = ProcessorNode::new(&mut execution_dag, node_index).await;
                     join_handles.push(start_processor(processor_node)?);
                 }
-                NodeKind::Sink(_) => {
-                    let sink_node = SinkNode::new(&mut execution_dag, node_index);
+                NodeKind::Sink { .. } => {
+                    let sink_node = SinkNode::new(
+                        &mut execution_dag,
+                        node_index,
+                        self.options.sink_retry.clone(),
+                    );
                     join_handles.push(start_sink(sink_node)?);
                 }
             }
@@ -1,20 +1,32 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{borrow::Cow, mem::swap};
 
 use crossbeam::channel::Receiver;
 use daggy::NodeIndex;
+use dozer_types::log::info;
 use dozer_types::node::{NodeHandle, OpIdentifier};
-use dozer_types::types::TableOperation;
+use dozer_types::types::{Schema, SchemaDiff, TableOperation};
 
+use crate::checkpoint::CheckpointDir;
 use crate::epoch::Epoch;
-use crate::error_manager::ErrorManager;
+use crate::error_manager::{ErrorManager, OpErrorHandler};
 use crate::executor_operation::ExecutorOperation;
+use crate::state_store::StateStore;
 use crate::{
-    builder_dag::NodeKind, errors::ExecutionError, forwarder::ChannelManager, node::Processor,
+    builder_dag::NodeKind,
+    errors::ExecutionError,
+    forwarder::ChannelManager,
+    node::{PortHandle, Processor},
+    node_metrics::NodeMetrics,
 };
 
 use super::{execution_dag::ExecutionDag, name::Name, receiver_loop::ReceiverLoop};
 
+/// How often the state size is recorded.
+const STATE_SIZE_INTERVAL: Duration = Duration::from_secs(10);
+
 /// A processor in the execution DAG.
 #[derive(Debug)]
 pub struct ProcessorNode {
@@ -22,16 +34,30 @@ pub struct ProcessorNode {
     node_handle: NodeHandle,
     /// The epoch id the processor was constructed for.
     initial_epoch_id: u64,
+    /// The epoch id of the operations being received.
+    epoch_id: u64,
     /// Input node handles.
     node_handles: Vec<NodeHandle>,
     /// Input data channels.
     receivers: Vec<Receiver<ExecutorOperation>>,
+    /// The current schema of each input port.
+    input_schemas: HashMap<PortHandle, Schema>,
     /// The processor.
     processor: Box<dyn Processor>,
     /// This node's output channel manager, for forwarding data, writing metadata and writing port state.
     channel_manager: ChannelManager,
     /// The error manager, for reporting non-fatal errors.
     error_manager: Arc<ErrorManager>,
+    /// Handles errors on operations according to the node's error policy.
+    op_error_handler: OpErrorHandler,
+    /// If replayed dead letters are waiting to be committed.
+    replaying: bool,
+    /// Where the processor's state is checkpointed to on every commit, if checkpointing is enabled.
+    checkpoint_dir: Option<CheckpointDir>,
+    /// The store the processor keeps its state in, checkpointed along with it.
+    state_store: StateStore,
+    /// When the processor's state size was last recorded.
+    state_size_recorded_at: Option<Instant>,
 }
 
 impl ProcessorNode {
@@ -41,11 +67,16 @@ impl ProcessorNode {
             panic!("Must pass in a node")
         };
         let node_handle = node.handle.clone();
-        let NodeKind::Processor(processor) = kind else {
+        let NodeKind::Processor {
+            processor,
+            state_store,
+        } = kind
+        else {
             panic!("Must pass in a processor node");
         };
 
         let (node_handles, receivers) = dag.collect_receivers(node_index);
+        let input_schemas = dag.collect_input_schemas(node_index);
 
         let senders = dag.collect_senders(node_index);
         let record_writers = dag.collect_record_writers(node_index).await;
@@ -55,16 +86,26 @@ impl ProcessorNode {
             record_writers,
             senders,
             dag.error_manager().clone(),
+            dag.labels(),
         );
 
+        let op_error_handler = dag.error_manager().op_error_handler(&node_handle);
+
         Self {
             node_handle,
             initial_epoch_id: dag.initial_epoch_id(),
+            epoch_id: dag.initial_epoch_id(),
             node_handles,
             receivers,
+            input_schemas,
             processor,
             channel_manager,
+            op_error_handler,
             error_manager: dag.error_manager().clone(),
+            replaying: false,
+            checkpoint_dir: dag.checkpoint_dir().cloned(),
+            state_store,
+            state_size_recorded_at: None,
         }
     }
 
@@ -94,9 +135,66 @@ impl ReceiverLoop for ProcessorNode {
         Cow::Owned(self.node_handles[index].to_string())
     }
 
+    fn node_metrics(&self) -> Option<&NodeMetrics> {
+        Some(self.channel_manager.metrics())
+    }
+
+    fn replay_dead_letters(&mut self) -> Result<(), ExecutionError> {
+        let Some(dead_letter_dir) = self.error_manager.dead_letter_dir() else {
+            return Ok(());
+        };
+        let dead_letters = dead_letter_dir.replays(&self.node_handle)?;
+        if dead_letters.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "[{}] Replaying {} dead letters",
+            self.node_handle,
+            dead_letters.len()
+        );
+        self.replaying = true;
+        for dead_letter in dead_letters {
+            self.on_op(0, dead_letter.op)?;
+        }
+        Ok(())
+    }
+
     fn on_op(&mut self, _index: usize, op: TableOperation) -> Result<(), ExecutionError> {
+        let kept_op = self.op_error_handler.keep(&op);
         if let Err(e) = self.processor.process(op, &mut self.channel_manager) {
-            self.error_manager.report(e);
+            self.op_error_handler.report(self.epoch_id, kept_op, e)?;
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(
+        &mut self,
+        _index: usize,
+        port: PortHandle,
+        schema: Schema,
+    ) -> Result<(), ExecutionError> {
+        let old = self
+            .input_schemas
+            .get(&port)
+            .ok_or(ExecutionError::InvalidPortHandle(port))?;
+        let diff = SchemaDiff::new(old, &schema);
+        if diff.is_empty() {
+            return Ok(());
+        }
+
+        let output_schemas = self
+            .processor
+            .on_schema_change(port, old, &schema)
+            .map_err(|source| ExecutionError::SchemaChange {
+                node: self.node_handle.clone(),
+                port,
+                diff,
+                source,
+            })?;
+        self.input_schemas.insert(port, schema);
+        for (port, schema) in output_schemas {
+            self.channel_manager.send_schema_change(port, schema)?;
         }
         Ok(())
     }
@@ -106,6 +204,38 @@ impl ReceiverLoop for ProcessorNode {
             self.error_manager.report(e);
         }
 
+        if let Some(checkpoint_dir) = &self.checkpoint_dir {
+            match self.processor.serialize_state() {
+                Ok(Some(state)) => checkpoint_dir.write(
+                    &self.node_handle,
+                    epoch.common_info.source_states.as_ref().clone(),
+                    state,
+                    &self.state_store,
+                )?,
+                Ok(None) => {}
+                Err(e) => self.error_manager.report(e),
+            }
+        }
+
+        if self.state_size_recorded_at.map_or(true, |recorded_at| {
+            recorded_at.elapsed() >= STATE_SIZE_INTERVAL
+        }) {
+            match self.processor.state_size() {
+                Ok(Some(size)) => self.channel_manager.metrics().record_state_size(size),
+                Ok(None) => {}
+                Err(e) => self.error_manager.report(e),
+            }
+            self.state_size_recorded_at = Some(Instant::now());
+        }
+
+        if self.replaying {
+            if let Some(dead_letter_dir) = self.error_manager.dead_letter_dir() {
+                dead_letter_dir.finish_replay(&self.node_handle)?;
+            }
+            self.replaying = false;
+        }
+        self.epoch_id = epoch.common_info.id + 1;
+
         self.channel_manager.send_commit(epoch)
     }
 
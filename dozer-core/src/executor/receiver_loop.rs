@@ -1,9 +1,16 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, time::Instant};
 
 use crossbeam::channel::{Receiver, Select};
-use dozer_types::{log::debug, node::OpIdentifier, types::TableOperation};
+use dozer_types::{
+    log::debug,
+    node::OpIdentifier,
+    types::{Schema, TableOperation},
+};
 
-use crate::{epoch::Epoch, errors::ExecutionError, executor_operation::ExecutorOperation};
+use crate::{
+    epoch::Epoch, errors::ExecutionError, executor_operation::ExecutorOperation, node::PortHandle,
+    node_metrics::NodeMetrics,
+};
 
 use super::name::Name;
 
@@ -17,8 +24,21 @@ pub trait ReceiverLoop: Name {
     fn receivers(&mut self) -> Vec<Receiver<ExecutorOperation>>;
     /// Returns the name of the receiver at `index`. Used for logging.
     fn receiver_name(&self, index: usize) -> Cow<str>;
+    /// Returns the metrics that processed operations are recorded to, if any.
+    fn node_metrics(&self) -> Option<&NodeMetrics> {
+        None
+    }
+    /// Processes the operations queued for replay from this node's dead letters. Called before receiving anything.
+    fn replay_dead_letters(&mut self) -> Result<(), ExecutionError>;
     /// Responds to `op` from the receiver at `index`.
     fn on_op(&mut self, index: usize, op: TableOperation) -> Result<(), ExecutionError>;
+    /// Responds to the schema of input `port` changing to `schema`, sent by the receiver at `index`.
+    fn on_schema_change(
+        &mut self,
+        index: usize,
+        port: PortHandle,
+        schema: Schema,
+    ) -> Result<(), ExecutionError>;
     /// Responds to `commit` of `epoch`.
     fn on_commit(&mut self, epoch: Epoch) -> Result<(), ExecutionError>;
     /// Responds to `terminate`.
@@ -56,7 +76,14 @@ pub trait ReceiverLoop: Name {
 
             match op {
                 ExecutorOperation::Op { op } => {
+                    let start = Instant::now();
                     self.on_op(index, op)?;
+                    if let Some(metrics) = self.node_metrics() {
+                        metrics.record_op(start.elapsed());
+                    }
+                }
+                ExecutorOperation::SchemaChange { port, schema } => {
+                    self.on_schema_change(index, port, schema)?;
                 }
                 ExecutorOperation::Commit { epoch } => {
                     assert_eq!(epoch.common_info.id, epoch_id);
@@ -113,7 +140,7 @@ mod tests {
     use crossbeam::channel::{unbounded, Sender};
     use dozer_types::{
         node::{NodeHandle, SourceState, SourceStates},
-        types::{Field, Operation, Record},
+        types::{Field, FieldDefinition, FieldType, Operation, Record, SourceDefinition},
     };
 
     use crate::DEFAULT_PORT_HANDLE;
@@ -123,6 +150,7 @@ mod tests {
     #[derive(Clone)]
     struct TestReceiverLoopState {
         ops: Vec<(usize, TableOperation)>,
+        schema_changes: Vec<(usize, PortHandle, Schema)>,
         commits: Vec<Epoch>,
         snapshotting_started: Vec<String>,
         snapshotting_done: Vec<(String, Option<OpIdentifier>)>,
@@ -155,11 +183,28 @@ mod tests {
             Cow::Owned(format!("receiver_{index}"))
         }
 
+        fn replay_dead_letters(&mut self) -> Result<(), ExecutionError> {
+            Ok(())
+        }
+
         fn on_op(&mut self, index: usize, op: TableOperation) -> Result<(), ExecutionError> {
             self.state.borrow_mut().ops.push((index, op));
             Ok(())
         }
 
+        fn on_schema_change(
+            &mut self,
+            index: usize,
+            port: PortHandle,
+            schema: Schema,
+        ) -> Result<(), ExecutionError> {
+            self.state
+                .borrow_mut()
+                .schema_changes
+                .push((index, port, schema));
+            Ok(())
+        }
+
         fn on_commit(&mut self, epoch: Epoch) -> Result<(), ExecutionError> {
             self.state.borrow_mut().commits.push(epoch);
             Ok(())
@@ -205,6 +250,7 @@ mod tests {
             let (senders, receivers) = (0..num_receivers).map(|_| unbounded()).unzip();
             let state = Rc::new(RefCell::new(TestReceiverLoopState {
                 ops: vec![],
+                schema_changes: vec![],
                 commits: vec![],
                 snapshotting_started: vec![],
                 snapshotting_done: vec![],
@@ -274,6 +320,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn receiver_loop_forwards_schema_change() {
+        let (test_loop, senders, state) = TestReceiverLoop::new(2);
+        let mut schema = Schema::new();
+        schema.field(
+            FieldDefinition::new(
+                "id".to_string(),
+                FieldType::Int,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            true,
+        );
+        senders[1]
+            .send(ExecutorOperation::SchemaChange {
+                port: DEFAULT_PORT_HANDLE,
+                schema: schema.clone(),
+            })
+            .unwrap();
+        senders[0].send(ExecutorOperation::Terminate).unwrap();
+        senders[1].send(ExecutorOperation::Terminate).unwrap();
+        test_loop.receiver_loop(0).unwrap();
+        assert_eq!(
+            state.borrow().schema_changes,
+            vec![(1, DEFAULT_PORT_HANDLE, schema)]
+        );
+    }
+
     #[test]
     fn receiver_loop_increases_epoch_id() {
         let (test_loop, senders, state) = TestReceiverLoop::new(2);
@@ -1,23 +1,29 @@
-use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use crossbeam::channel::{Receiver, SelectTimeoutError, SelectedOperation, Sender, TryRecvError};
 use daggy::NodeIndex;
 use dozer_tracing::{
     constants::{
         ConnectorEntityType, DOZER_METER_NAME, OPERATION_TYPE_LABEL, PIPELINE_LATENCY_GAUGE_NAME,
-        SINK_OPERATION_COUNTER_NAME, TABLE_LABEL, TOTAL_LATENCY_HISTOGRAM_NAME,
+        SINK_CALL_LABEL, SINK_CIRCUIT_OPEN_GAUGE_NAME, SINK_OPERATION_COUNTER_NAME,
+        SINK_RETRY_COUNTER_NAME, TABLE_LABEL, TOTAL_LATENCY_HISTOGRAM_NAME,
     },
     emit_event,
     opentelemetry_metrics::{Counter, Gauge, Histogram},
     DozerMonitorContext,
 };
-use dozer_types::{epoch::SourceTime, log::warn};
 use dozer_types::{
+    epoch::SourceTime,
+    log::{info, warn},
+};
+use dozer_types::{
+    errors::internal::BoxedError,
     log::debug,
-    node::{NodeHandle, OpIdentifier},
+    node::{NodeHandle, OpIdentifier, SourceState},
     tracing::error,
-    types::{Operation, TableOperation},
+    types::{Operation, Schema, SchemaDiff, TableOperation},
 };
 use std::{
     borrow::Cow,
+    collections::{HashMap, VecDeque},
     mem::swap,
     sync::Arc,
     time::{Duration, Instant},
@@ -26,11 +32,18 @@ use std::{
 use tokio::sync::broadcast;
 
 use crate::{
-    builder_dag::NodeKind, epoch::Epoch, error_manager::ErrorManager, errors::ExecutionError,
-    event::Event, executor_operation::ExecutorOperation, node::Sink,
+    builder_dag::NodeKind,
+    epoch::Epoch,
+    error_manager::{ErrorManager, OpErrorHandler},
+    errors::ExecutionError,
+    event::Event,
+    executor_operation::ExecutorOperation,
+    node::{PortHandle, Sink},
+    node_metrics::NodeMetrics,
 };
 
 use super::execution_dag::ExecutionDag;
+use super::SinkRetryOptions;
 use super::{name::Name, receiver_loop::ReceiverLoop};
 
 const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_millis(20);
@@ -88,6 +101,53 @@ impl FlushScheduler {
     }
 }
 
+/// A call to the sink that is retried if it fails.
+#[derive(Debug)]
+enum SinkCall {
+    Process {
+        op: TableOperation,
+        epoch_id: u64,
+    },
+    /// With two-phase commit, `prepared` is set once the epoch is prepared, so retries only commit it.
+    Commit {
+        epoch: Epoch,
+        prepared: bool,
+    },
+    Flush(Epoch),
+}
+
+impl SinkCall {
+    fn name(&self) -> &'static str {
+        match self {
+            SinkCall::Process { .. } => "process",
+            SinkCall::Commit { .. } => "commit",
+            SinkCall::Flush(_) => "flush",
+        }
+    }
+}
+
+/// An input to the sink, buffered while its circuit is open.
+#[derive(Debug)]
+enum SinkInput {
+    Op(TableOperation),
+    SchemaChange { port: PortHandle, schema: Schema },
+    Commit(Epoch),
+    Flush,
+    SnapshottingStarted(String),
+    SnapshottingDone(String, Option<OpIdentifier>),
+    Terminate,
+}
+
+/// A sink call failed after all retries, so the sink is paused until it succeeds.
+#[derive(Debug)]
+struct OpenCircuit {
+    opened_at: Instant,
+    /// When the calls are tried again.
+    retry_at: Instant,
+    /// The failed call, followed by the calls made after it.
+    calls: VecDeque<SinkCall>,
+}
+
 /// A sink in the execution DAG.
 #[derive(Debug)]
 pub struct SinkNode {
@@ -95,14 +155,30 @@ pub struct SinkNode {
     node_handle: NodeHandle,
     /// The epoch id the sink was constructed for.
     initial_epoch_id: u64,
+    /// The epoch id of the operations being received.
+    epoch_id: u64,
     /// Input node handles.
     node_handles: Vec<NodeHandle>,
     /// Input data channels.
     receivers: Vec<Receiver<ExecutorOperation>>,
+    /// The current schema of each input port.
+    input_schemas: HashMap<PortHandle, Schema>,
     /// The sink.
     sink: Box<dyn Sink>,
     /// The error manager, for reporting non-fatal errors.
     error_manager: Arc<ErrorManager>,
+    /// Handles errors on operations according to the node's error policy.
+    op_error_handler: OpErrorHandler,
+    /// If replayed dead letters are waiting to be committed.
+    replaying: bool,
+    /// Set while the source replays operations the sink already has, with the op id of the commit they end at.
+    replayed_until: Option<(NodeHandle, OpIdentifier)>,
+    /// How failed sink calls are retried.
+    retry_options: SinkRetryOptions,
+    /// Set while the sink is paused because a call keeps failing.
+    circuit: Option<OpenCircuit>,
+    /// Inputs received while the circuit is open.
+    buffer: VecDeque<SinkInput>,
     /// The metrics labels.
     labels: DozerMonitorContext,
 
@@ -116,6 +192,7 @@ pub struct SinkNode {
 
     event_sender: broadcast::Sender<Event>,
     metrics: SinkMetrics,
+    node_metrics: NodeMetrics,
     source_times: Option<Vec<SourceTime>>,
 }
 
@@ -125,20 +202,32 @@ pub struct SinkMetrics {
     sink_counter: Counter<u64>,
     latency_gauge: Gauge<f64>,
     total_latency_hist: Histogram<u64>,
+    retry_counter: Counter<u64>,
+    circuit_open_gauge: Gauge<u64>,
 }
 
 impl SinkNode {
-    pub fn new(dag: &mut ExecutionDag, node_index: NodeIndex) -> Self {
+    pub fn new(
+        dag: &mut ExecutionDag,
+        node_index: NodeIndex,
+        retry_options: SinkRetryOptions,
+    ) -> Self {
         let node = dag.node_weight_mut(node_index);
         let Some(kind) = node.kind.take() else {
             panic!("Must pass in a node")
         };
         let node_handle = node.handle.clone();
-        let NodeKind::Sink(sink) = kind else {
+        let NodeKind::Sink {
+            sink,
+            source,
+            last_checkpoint,
+        } = kind
+        else {
             panic!("Must pass in a sink node");
         };
 
         let (node_handles, receivers) = dag.collect_receivers(node_index);
+        let input_schemas = dag.collect_input_schemas(node_index);
 
         let meter = dozer_tracing::global::meter(DOZER_METER_NAME);
         let sink_counter = meter
@@ -154,6 +243,14 @@ impl SinkNode {
             .u64_histogram(TOTAL_LATENCY_HISTOGRAM_NAME)
             .with_description("Measures total latency between commit on source and commit on sink")
             .init();
+        let retry_counter = meter
+            .u64_counter(SINK_RETRY_COUNTER_NAME)
+            .with_description("No of retried sink calls")
+            .init();
+        let circuit_open_gauge = meter
+            .u64_gauge(SINK_CIRCUIT_OPEN_GAUGE_NAME)
+            .with_description("1 while the sink is paused because a call keeps failing")
+            .init();
 
         let max_flush_interval = sink
             .max_batch_duration_ms()
@@ -170,14 +267,24 @@ impl SinkNode {
 
         std::thread::spawn(move || scheduler.run());
         let source_times = sink.supports_batching().then(Vec::new);
+        let op_error_handler = dag.error_manager().op_error_handler(&node_handle);
+        let node_metrics = NodeMetrics::new(dag.labels(), &node_handle);
 
         Self {
             node_handle,
             initial_epoch_id: dag.initial_epoch_id(),
+            epoch_id: dag.initial_epoch_id(),
             node_handles,
             receivers,
+            input_schemas,
             sink,
+            op_error_handler,
             error_manager: dag.error_manager().clone(),
+            replaying: false,
+            replayed_until: last_checkpoint.map(|checkpoint| (source, checkpoint)),
+            retry_options,
+            circuit: None,
+            buffer: VecDeque::new(),
             labels: dag.labels().clone(),
             last_op_if_commit: None,
             flush_scheduled_on_next_commit: false,
@@ -191,7 +298,10 @@ impl SinkNode {
                 sink_counter,
                 latency_gauge,
                 total_latency_hist,
+                retry_counter,
+                circuit_open_gauge,
             },
+            node_metrics,
         }
     }
 
@@ -200,13 +310,14 @@ impl SinkNode {
     }
 
     fn flush(&mut self, epoch: Epoch) -> Result<(), ExecutionError> {
-        if let Err(e) = self.sink.flush_batch() {
-            self.error_manager.report(e);
-        }
         self.ops_since_flush = 0;
         self.flush_scheduler_sender
             .send(self.max_flush_interval)
             .unwrap();
+        self.call(SinkCall::Flush(epoch))
+    }
+
+    fn on_flushed(&mut self, epoch: Epoch) {
         let _ = self.event_sender.send(Event::SinkFlushed {
             node: self.node_handle.clone(),
             epoch,
@@ -223,8 +334,258 @@ impl SinkNode {
                 }
             }
         }
+    }
+
+    /// Handles `input`, returning if the sink terminated.
+    fn handle_input(&mut self, input: SinkInput) -> Result<bool, ExecutionError> {
+        if self.skip_replayed(&input) {
+            return Ok(false);
+        }
+        match input {
+            SinkInput::Op(op) => self.on_op(0, op)?,
+            SinkInput::SchemaChange { port, schema } => self.on_schema_change(0, port, schema)?,
+            SinkInput::Commit(epoch) => self.on_commit(epoch)?,
+            SinkInput::Flush => {
+                if let Some(epoch) = self.last_op_if_commit.take() {
+                    self.flush(epoch)?;
+                } else {
+                    self.flush_scheduled_on_next_commit = true;
+                }
+            }
+            SinkInput::SnapshottingStarted(connection_name) => {
+                emit_event(
+                    &connection_name,
+                    &ConnectorEntityType::Connector,
+                    &self.labels,
+                    "snapshotting_started",
+                );
+                self.on_snapshotting_started(connection_name)?;
+            }
+            SinkInput::SnapshottingDone(connection_name, id) => {
+                emit_event(
+                    &connection_name,
+                    &ConnectorEntityType::Connector,
+                    &self.labels,
+                    "snapshotting_done",
+                );
+                self.on_snapshotting_done(connection_name, id)?;
+            }
+            SinkInput::Terminate => {
+                self.on_terminate()?;
+                debug!("[{}] Quit", self.name());
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns if `input` was replayed by the source although the sink already has it, so it's skipped.
+    fn skip_replayed(&mut self, input: &SinkInput) -> bool {
+        let Some((source, checkpoint)) = &self.replayed_until else {
+            return false;
+        };
+        match input {
+            SinkInput::Op(_) | SinkInput::SchemaChange { .. } => true,
+            SinkInput::Commit(epoch) => {
+                if epoch
+                    .common_info
+                    .source_states
+                    .get(source)
+                    .and_then(SourceState::op_id)
+                    .is_some_and(|op_id| op_id >= checkpoint)
+                {
+                    info!(
+                        "[{}] Skipped replayed operations up to {:?}",
+                        self.node_handle, checkpoint
+                    );
+                    self.replayed_until = None;
+                }
+                self.epoch_id = epoch.common_info.id + 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Calls the sink, retrying with exponential backoff. Once retries are exhausted, the circuit opens,
+    /// pausing the sink until the call succeeds. Calls made while the circuit is open are queued after it.
+    ///
+    /// Errors the sink doesn't consider retryable are reported right away, and so are failed operations
+    /// unless the error policy fails on them.
+    fn call(&mut self, call: SinkCall) -> Result<(), ExecutionError> {
+        if let Some(circuit) = &mut self.circuit {
+            circuit.calls.push_back(call);
+            return Ok(());
+        }
+
+        let mut call = match call {
+            SinkCall::Process { op, epoch_id }
+                if self.retry_options.max_retries == 0 || !self.op_error_handler.fails() =>
+            {
+                // Operations that aren't retried don't need to be copied.
+                let kept_op = self.op_error_handler.keep(&op);
+                if let Err(e) = self.sink.process(op) {
+                    self.op_error_handler.report(epoch_id, kept_op, e)?;
+                }
+                return Ok(());
+            }
+            call => call,
+        };
+
+        if self.retry_options.max_retries == 0 {
+            return match self.invoke(&mut call) {
+                Ok(()) => {
+                    self.on_called(call);
+                    Ok(())
+                }
+                Err(e) => self.report(call, e),
+            };
+        }
+
+        let mut backoff = self.retry_options.initial_backoff;
+        let mut retries = 0;
+        while let Err(e) = self.invoke(&mut call) {
+            if !self.is_retryable(&call, &e) {
+                return self.report(call, e);
+            }
+            if retries == self.retry_options.max_retries {
+                warn!(
+                    "[{}] Sink {} failed after {} retries, pausing the sink: {}",
+                    self.node_handle,
+                    call.name(),
+                    retries,
+                    e
+                );
+                let now = Instant::now();
+                self.circuit = Some(OpenCircuit {
+                    opened_at: now,
+                    retry_at: now + self.retry_options.open_duration,
+                    calls: VecDeque::from([call]),
+                });
+                self.metrics
+                    .circuit_open_gauge
+                    .record(1, &self.metric_labels());
+                return Ok(());
+            }
+
+            warn!(
+                "[{}] Sink {} failed, retrying in {:?}: {}",
+                self.node_handle,
+                call.name(),
+                backoff,
+                e
+            );
+            let mut labels = self.metric_labels();
+            labels.push(dozer_tracing::KeyValue::new(SINK_CALL_LABEL, call.name()));
+            self.metrics.retry_counter.add(1, &labels);
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(self.retry_options.max_backoff);
+            retries += 1;
+        }
+        self.on_called(call);
+        Ok(())
+    }
+
+    /// Tries the calls of the open circuit again, and closes it once they succeed.
+    ///
+    /// Calls that still fail once the circuit has been open for `max_open_duration` are reported as errors.
+    fn retry_open_circuit(&mut self) -> Result<(), ExecutionError> {
+        let Some(mut circuit) = self.circuit.take() else {
+            return Ok(());
+        };
+        while let Some(mut call) = circuit.calls.pop_front() {
+            match self.invoke(&mut call) {
+                Ok(()) => self.on_called(call),
+                Err(e)
+                    if !self.is_retryable(&call, &e)
+                        || circuit.opened_at.elapsed() >= self.retry_options.max_open_duration =>
+                {
+                    self.report(call, e)?;
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}] Sink {} still fails, keeping the sink paused: {}",
+                        self.node_handle,
+                        call.name(),
+                        e
+                    );
+                    circuit.calls.push_front(call);
+                    circuit.retry_at = Instant::now() + self.retry_options.open_duration;
+                    self.circuit = Some(circuit);
+                    return Ok(());
+                }
+            }
+        }
+
+        info!("[{}] Sink resumed", self.node_handle);
+        self.metrics
+            .circuit_open_gauge
+            .record(0, &self.metric_labels());
         Ok(())
     }
+
+    fn invoke(&mut self, call: &mut SinkCall) -> Result<(), BoxedError> {
+        match call {
+            SinkCall::Process { op, .. } => self.sink.process(op.clone()),
+            SinkCall::Commit { epoch, .. } if !self.sink.supports_two_phase_commit() => {
+                self.sink.commit(epoch)
+            }
+            SinkCall::Commit { epoch, prepared } => {
+                if !*prepared {
+                    self.sink.prepare(epoch)?;
+                    *prepared = true;
+                }
+                self.sink.commit_prepared(epoch.common_info.id)
+            }
+            SinkCall::Flush(_) => self.sink.flush_batch(),
+        }
+    }
+
+    /// Failed operations are only retried if the error policy fails on them, as they'd be skipped or dead-lettered otherwise.
+    fn is_retryable(&self, call: &SinkCall, error: &BoxedError) -> bool {
+        match call {
+            SinkCall::Process { .. } if !self.op_error_handler.fails() => false,
+            _ => self.sink.is_retryable(error),
+        }
+    }
+
+    fn on_called(&mut self, call: SinkCall) {
+        if let SinkCall::Flush(epoch) = call {
+            self.on_flushed(epoch);
+        }
+    }
+
+    /// Reports the error of a call that is given up on.
+    fn report(&mut self, call: SinkCall, error: BoxedError) -> Result<(), ExecutionError> {
+        match call {
+            SinkCall::Process { op, epoch_id } => {
+                self.op_error_handler.report(epoch_id, Some(op), error)?
+            }
+            SinkCall::Commit { epoch, prepared } => {
+                // An epoch that can't be prepared is aborted. One that is prepared is left for the next start to commit.
+                if self.sink.supports_two_phase_commit() && !prepared {
+                    if let Err(e) = self.sink.abort_prepared(epoch.common_info.id) {
+                        self.error_manager.report(e);
+                    }
+                }
+                self.error_manager.report(error);
+            }
+            SinkCall::Flush(epoch) => {
+                self.error_manager.report(error);
+                self.on_flushed(epoch);
+            }
+        }
+        Ok(())
+    }
+
+    fn metric_labels(&self) -> Vec<dozer_tracing::KeyValue> {
+        let mut labels = self.labels.attrs().clone();
+        labels.push(dozer_tracing::KeyValue::new(
+            TABLE_LABEL,
+            self.node_handle.id.clone(),
+        ));
+        labels
+    }
 }
 
 impl Name for SinkNode {
@@ -277,6 +638,18 @@ impl<'a> Select<'a> {
 
     fn recv(&mut self) -> Result<ReceiverMsg, ExecutionError> {
         let msg = self.inner.select();
+        self.read(msg)
+    }
+
+    /// Like [`Self::recv`], but returns `None` if nothing is ready before `deadline`.
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<ReceiverMsg>, ExecutionError> {
+        match self.inner.select_deadline(deadline) {
+            Ok(msg) => self.read(msg).map(Some),
+            Err(SelectTimeoutError) => Ok(None),
+        }
+    }
+
+    fn read(&self, msg: SelectedOperation<'a>) -> Result<ReceiverMsg, ExecutionError> {
         let index = msg.index();
         let res = if index == self.flush_idx {
             msg.recv(self.flush_receiver).map(|_| ReceiverMsg::Flush)
@@ -327,70 +700,109 @@ impl ReceiverLoop for SinkNode {
             .unwrap();
         let mut sel = Select::new(&receivers, &should_flush_receiver);
         loop {
-            let ReceiverMsg::Op(index, op) = sel.recv()? else {
-                if let Some(epoch) = self.last_op_if_commit.take() {
-                    self.flush(epoch)?;
-                } else {
-                    self.flush_scheduled_on_next_commit = true;
+            // Inputs buffered while the circuit was open are handled once it closes.
+            if self.circuit.is_none() {
+                if let Some(input) = self.buffer.pop_front() {
+                    if self.handle_input(input)? {
+                        return Ok(());
+                    }
+                    continue;
                 }
-                continue;
-            };
+            }
 
-            match op {
-                ExecutorOperation::Op { op } => {
-                    self.on_op(index, op)?;
+            let msg = match self.circuit.as_ref().map(|circuit| circuit.retry_at) {
+                None => sel.recv()?,
+                Some(retry_at) => {
+                    // Keep receiving while the sink is paused, so other sinks of the same sources keep running.
+                    let msg = if self.buffer.len() < self.retry_options.max_buffered {
+                        sel.recv_deadline(retry_at)?
+                    } else {
+                        std::thread::sleep(retry_at.saturating_duration_since(Instant::now()));
+                        None
+                    };
+                    let Some(msg) = msg else {
+                        self.retry_open_circuit()?;
+                        continue;
+                    };
+                    msg
                 }
-                ExecutorOperation::Commit { epoch } => {
-                    assert_eq!(epoch.common_info.id, epoch_id);
-                    commits_received += 1;
-                    sel.remove(index);
-
-                    if commits_received == receivers.len() {
-                        self.on_commit(epoch)?;
-                        epoch_id += 1;
-                        commits_received = 0;
-                        sel.reinit();
+            };
+
+            let input = match msg {
+                ReceiverMsg::Flush => Some(SinkInput::Flush),
+                ReceiverMsg::Op(index, op) => match op {
+                    ExecutorOperation::Op { op } => Some(SinkInput::Op(op)),
+                    ExecutorOperation::SchemaChange { port, schema } => {
+                        Some(SinkInput::SchemaChange { port, schema })
                     }
-                }
-                ExecutorOperation::Terminate => {
-                    is_terminated[index] = true;
-                    sel.remove(index);
-                    debug!(
-                        "[{}] Received Terminate request from {}",
-                        self.name(),
-                        self.receiver_name(index)
-                    );
-                    if is_terminated.iter().all(|value| *value) {
-                        self.on_terminate()?;
-                        debug!("[{}] Quit", self.name());
-                        return Ok(());
+                    ExecutorOperation::Commit { epoch } => {
+                        assert_eq!(epoch.common_info.id, epoch_id);
+                        commits_received += 1;
+                        sel.remove(index);
+
+                        if commits_received == receivers.len() {
+                            epoch_id += 1;
+                            commits_received = 0;
+                            sel.reinit();
+                            Some(SinkInput::Commit(epoch))
+                        } else {
+                            None
+                        }
                     }
-                }
-                ExecutorOperation::SnapshottingStarted { connection_name } => {
-                    emit_event(
-                        &connection_name,
-                        &ConnectorEntityType::Connector,
-                        &self.labels,
-                        "snapshotting_started",
-                    );
-                    self.on_snapshotting_started(connection_name)?;
-                }
-                ExecutorOperation::SnapshottingDone {
-                    connection_name,
-                    id,
-                } => {
-                    emit_event(
-                        &connection_name,
-                        &ConnectorEntityType::Connector,
-                        &self.labels,
-                        "snapshotting_done",
-                    );
-                    self.on_snapshotting_done(connection_name, id)?;
-                }
+                    ExecutorOperation::Terminate => {
+                        is_terminated[index] = true;
+                        sel.remove(index);
+                        debug!(
+                            "[{}] Received Terminate request from {}",
+                            self.name(),
+                            self.receiver_name(index)
+                        );
+                        is_terminated
+                            .iter()
+                            .all(|value| *value)
+                            .then_some(SinkInput::Terminate)
+                    }
+                    ExecutorOperation::SnapshottingStarted { connection_name } => {
+                        Some(SinkInput::SnapshottingStarted(connection_name))
+                    }
+                    ExecutorOperation::SnapshottingDone {
+                        connection_name,
+                        id,
+                    } => Some(SinkInput::SnapshottingDone(connection_name, id)),
+                },
+            };
+            let Some(input) = input else {
+                continue;
+            };
+            if self.circuit.is_some() {
+                self.buffer.push_back(input);
+            } else if self.handle_input(input)? {
+                return Ok(());
             }
         }
     }
 
+    fn replay_dead_letters(&mut self) -> Result<(), ExecutionError> {
+        let Some(dead_letter_dir) = self.error_manager.dead_letter_dir() else {
+            return Ok(());
+        };
+        let dead_letters = dead_letter_dir.replays(&self.node_handle)?;
+        if dead_letters.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "[{}] Replaying {} dead letters",
+            self.node_handle,
+            dead_letters.len()
+        );
+        self.replaying = true;
+        for dead_letter in dead_letters {
+            self.on_op(0, dead_letter.op)?;
+        }
+        Ok(())
+    }
+
     fn on_op(&mut self, _index: usize, op: TableOperation) -> Result<(), ExecutionError> {
         self.last_op_if_commit = None;
         let mut labels = self.labels.attrs();
@@ -405,6 +817,7 @@ impl ReceiverLoop for SinkNode {
             Operation::Delete { .. } => "delete",
             Operation::Update { .. } => "update",
             Operation::BatchInsert { .. } => "insert",
+            Operation::Truncate => "truncate",
         };
         labels.push(dozer_tracing::KeyValue::new(OPERATION_TYPE_LABEL, op_str));
         let counter_number: u64 = match &op.op {
@@ -413,19 +826,58 @@ impl ReceiverLoop for SinkNode {
         };
         self.ops_since_flush += counter_number;
 
-        if let Err(e) = self.sink.process(op) {
-            self.error_manager.report(e);
-        }
+        let start = Instant::now();
+        self.call(SinkCall::Process {
+            op,
+            epoch_id: self.epoch_id,
+        })?;
+        self.node_metrics.record_op(start.elapsed());
 
         self.metrics.sink_counter.add(counter_number, &labels);
         Ok(())
     }
 
+    fn on_schema_change(
+        &mut self,
+        _index: usize,
+        port: PortHandle,
+        schema: Schema,
+    ) -> Result<(), ExecutionError> {
+        let old = self
+            .input_schemas
+            .get(&port)
+            .ok_or(ExecutionError::InvalidPortHandle(port))?;
+        let diff = SchemaDiff::new(old, &schema);
+        if diff.is_empty() {
+            return Ok(());
+        }
+
+        self.sink
+            .on_schema_change(port, old, &schema)
+            .map_err(|source| ExecutionError::SchemaChange {
+                node: self.node_handle.clone(),
+                port,
+                diff,
+                source,
+            })?;
+        self.input_schemas.insert(port, schema);
+        Ok(())
+    }
+
     fn on_commit(&mut self, epoch: Epoch) -> Result<(), ExecutionError> {
         // debug!("[{}] Checkpointing - {}", self.node_handle, epoch);
-        if let Err(e) = self.sink.commit(&epoch) {
-            self.error_manager.report(e);
+        self.call(SinkCall::Commit {
+            epoch: epoch.clone(),
+            prepared: false,
+        })?;
+        // Replayed operations are only done once they are committed.
+        if self.replaying && self.circuit.is_none() {
+            if let Some(dead_letter_dir) = self.error_manager.dead_letter_dir() {
+                dead_letter_dir.finish_replay(&self.node_handle)?;
+            }
+            self.replaying = false;
         }
+        self.epoch_id = epoch.common_info.id + 1;
         self.last_op_if_commit = Some(epoch.clone());
 
         match epoch.decision_instant.elapsed() {
@@ -2,10 +2,13 @@ use std::{fmt::Debug, future::Future, pin::pin, sync::Arc, time::SystemTime};
 
 use daggy::petgraph::visit::IntoNodeIdentifiers;
 use dozer_types::{
-    log::debug, models::ingestion_types::TransactionInfo, node::OpIdentifier, types::TableOperation,
+    log::{debug, info},
+    models::ingestion_types::TransactionInfo,
+    node::{NodeHandle, OpIdentifier},
+    types::TableOperation,
 };
 use dozer_types::{models::ingestion_types::IngestionMessage, node::SourceState};
-use futures::{future::Either, StreamExt};
+use futures::{future::Either, FutureExt, StreamExt};
 use tokio::{
     runtime::Runtime,
     sync::mpsc::{channel, Receiver, Sender},
@@ -18,6 +21,7 @@ use crate::{
     executor_operation::ExecutorOperation,
     forwarder::ChannelManager,
     node::{PortHandle, Source},
+    operation_log::{LogReader, OperationLog},
 };
 
 use super::{execution_dag::ExecutionDag, node::Node, ExecutorOptions};
@@ -41,8 +45,27 @@ pub struct SourceNode<F> {
 
 impl<F: Future + Unpin> Node for SourceNode<F> {
     fn run(mut self) -> Result<(), ExecutionError> {
+        // Replay the operation logs before the sources add to them.
+        for index in 0..self.sources.len() {
+            let Some(replay) = self.sources[index].replay.take() else {
+                continue;
+            };
+            info!(
+                "[{}] Replaying operation log",
+                self.sources[index].channel_manager.owner().id
+            );
+            for entry in replay {
+                if (&mut self.shutdown).now_or_never().is_some() {
+                    send_to_all_nodes(&self.sources, ExecutorOperation::Terminate)?;
+                    return Ok(());
+                }
+                let (_, port, message) = entry?;
+                handle_message(&mut self.sources, &mut self.epoch_id, index, port, message)?;
+            }
+        }
+
         let mut handles = vec![];
-        for mut source_runner in self.source_runners {
+        for mut source_runner in std::mem::take(&mut self.source_runners) {
             handles.push(Some(self.runtime.spawn(async move {
                 source_runner
                     .source
@@ -52,21 +75,22 @@ impl<F: Future + Unpin> Node for SourceNode<F> {
         }
         let mut num_running_sources = handles.len();
 
-        let mut stream = pin!(stream::receivers_stream(self.receivers));
+        let mut stream = pin!(stream::receivers_stream(std::mem::take(
+            &mut self.receivers
+        )));
         loop {
             let next = stream.next();
             let next = pin!(next);
             match self
                 .runtime
-                .block_on(futures::future::select(self.shutdown, next))
+                .block_on(futures::future::select(&mut self.shutdown, next))
             {
                 Either::Left((_, _)) => {
                     send_to_all_nodes(&self.sources, ExecutorOperation::Terminate)?;
                     return Ok(());
                 }
-                Either::Right((next, shutdown)) => {
+                Either::Right((next, _)) => {
                     let next = next.expect("We return just when the stream ends");
-                    self.shutdown = shutdown;
                     let index = next.0;
                     let Some((port, message)) = next.1 else {
                         debug!("[{}] quit", self.sources[index].channel_manager.owner().id);
@@ -89,67 +113,83 @@ impl<F: Future + Unpin> Node for SourceNode<F> {
                             }
                         }
                     };
-                    let source = &mut self.sources[index];
-                    match message {
-                        IngestionMessage::OperationEvent { op, id, .. } => {
-                            source.state = SourceState::NonRestartable;
-                            source
-                                .channel_manager
-                                .send_op(TableOperation { op, id, port })?;
+                    if let Some(log) = &mut self.sources[index].log {
+                        log.append(port, &message)?;
+                        if matches!(
+                            message,
+                            IngestionMessage::TransactionInfo(TransactionInfo::Commit { .. })
+                        ) {
+                            log.sync()?;
                         }
-                        IngestionMessage::TransactionInfo(info) => match info {
-                            TransactionInfo::Commit { id, source_time } => {
-                                if let Some(id) = id {
-                                    source.state = SourceState::Restartable(id);
-                                } else {
-                                    source.state = SourceState::NonRestartable;
-                                }
-
-                                let source_states = Arc::new(
-                                    self.sources
-                                        .iter()
-                                        .map(|source| {
-                                            (
-                                                source.channel_manager.owner().clone(),
-                                                source.state.clone(),
-                                            )
-                                        })
-                                        .collect(),
-                                );
-                                let mut epoch =
-                                    Epoch::new(self.epoch_id, source_states, SystemTime::now());
-                                if let Some(st) = source_time {
-                                    epoch = epoch.with_source_time(st);
-                                }
-                                send_to_all_nodes(
-                                    &self.sources,
-                                    ExecutorOperation::Commit { epoch },
-                                )?;
-                                self.epoch_id += 1;
-                            }
-                            TransactionInfo::SnapshottingStarted => {
-                                source.channel_manager.send_snapshotting_started(
-                                    source.channel_manager.owner().id.clone(),
-                                )?;
-                            }
-                            TransactionInfo::SnapshottingDone { id } => {
-                                source.channel_manager.send_snapshotting_done(
-                                    source.channel_manager.owner().id.clone(),
-                                    id,
-                                )?;
-                            }
-                        },
                     }
+                    handle_message(&mut self.sources, &mut self.epoch_id, index, port, message)?;
                 }
             }
         }
     }
 }
 
-#[derive(Debug)]
-struct RunningSource {
-    channel_manager: ChannelManager,
-    state: SourceState,
+/// Sends what the source at `index` ingested to the downstream nodes.
+fn handle_message(
+    sources: &mut [RunningSource],
+    epoch_id: &mut u64,
+    index: usize,
+    port: PortHandle,
+    message: IngestionMessage,
+) -> Result<(), ExecutionError> {
+    let source = &mut sources[index];
+    match message {
+        IngestionMessage::OperationEvent { op, id, .. } => {
+            source.state = SourceState::NonRestartable;
+            source
+                .channel_manager
+                .send_op(TableOperation { op, id, port })?;
+        }
+        IngestionMessage::SchemaChanged { schema, .. } => {
+            source.channel_manager.send_schema_change(port, schema)?;
+        }
+        IngestionMessage::TransactionInfo(info) => match info {
+            TransactionInfo::Commit { id, source_time } => {
+                if let Some(id) = id {
+                    source.state = SourceState::Restartable(id);
+                } else {
+                    source.state = SourceState::NonRestartable;
+                }
+                if let Some(source_time) = &source_time {
+                    source
+                        .channel_manager
+                        .metrics()
+                        .record_source_lag(source_time);
+                }
+
+                let source_states = Arc::new(
+                    sources
+                        .iter()
+                        .map(|source| {
+                            (source.channel_manager.owner().clone(), source.state.clone())
+                        })
+                        .collect(),
+                );
+                let mut epoch = Epoch::new(*epoch_id, source_states, SystemTime::now());
+                if let Some(st) = source_time {
+                    epoch = epoch.with_source_time(st);
+                }
+                send_to_all_nodes(sources, ExecutorOperation::Commit { epoch })?;
+                *epoch_id += 1;
+            }
+            TransactionInfo::SnapshottingStarted => {
+                source
+                    .channel_manager
+                    .send_snapshotting_started(source.channel_manager.owner().id.clone())?;
+            }
+            TransactionInfo::SnapshottingDone { id } => {
+                source
+                    .channel_manager
+                    .send_snapshotting_done(source.channel_manager.owner().id.clone(), id)?;
+            }
+        },
+    }
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -175,7 +215,7 @@ pub async fn create_source_node<F>(
     options: &ExecutorOptions,
     shutdown: F,
     runtime: Arc<Runtime>,
-) -> SourceNode<F> {
+) -> Result<SourceNode<F>, ExecutionError> {
     let mut sources = vec![];
     let mut source_runners = vec![];
     let mut receivers = vec![];
@@ -199,6 +239,37 @@ pub async fn create_source_node<F>(
             continue;
         };
 
+        // With the operation log, the source resumes from the log, and downstream nodes catch up by replaying it.
+        let (last_checkpoint, log, replay) = match &options.operation_log {
+            Some(log_options) => {
+                let mut log = OperationLog::open(log_options, &node_handle)?;
+                match log.last_commit()? {
+                    Some((seq, id)) => {
+                        // The source sends messages after its last commit again.
+                        log.truncate(seq + 1)?;
+                        let replay_start = log.replay_start(last_checkpoint)?;
+                        keep_replayed_sink_checkpoints(
+                            dag,
+                            &node_handle,
+                            Some((&log, replay_start)),
+                        )?;
+                        let replay = log.read_from(replay_start);
+                        (Some(id), Some(log), Some(replay))
+                    }
+                    None => {
+                        // The source sends what follows its checkpoint again, so what's logged would be duplicated.
+                        log.clear(last_checkpoint)?;
+                        keep_replayed_sink_checkpoints(dag, &node_handle, None)?;
+                        (last_checkpoint, Some(log), None)
+                    }
+                }
+            }
+            None => {
+                keep_replayed_sink_checkpoints(dag, &node_handle, None)?;
+                (last_checkpoint, None, None)
+            }
+        };
+
         let senders = dag.collect_senders(node_index);
         let record_writers = dag.collect_record_writers(node_index).await;
         let channel_manager = ChannelManager::new(
@@ -206,10 +277,13 @@ pub async fn create_source_node<F>(
             record_writers,
             senders,
             dag.error_manager().clone(),
+            dag.labels(),
         );
         sources.push(RunningSource {
             channel_manager,
             state: SourceState::NotStarted,
+            log,
+            replay,
         });
 
         let (sender, receiver) = channel(options.channel_buffer_sz);
@@ -222,14 +296,50 @@ pub async fn create_source_node<F>(
         receivers.push(receiver);
     }
 
-    SourceNode {
+    Ok(SourceNode {
         sources,
         source_runners,
         receivers,
         epoch_id: dag.initial_epoch_id(),
         shutdown,
         runtime,
+    })
+}
+
+/// Keeps the checkpoints of the sinks of `source` that the replay from `replay` passes, so they skip the replayed
+/// operations they already have. Other sinks get all operations, as there's no commit to tell where they caught up.
+fn keep_replayed_sink_checkpoints(
+    dag: &mut ExecutionDag,
+    source: &NodeHandle,
+    replay: Option<(&OperationLog, u64)>,
+) -> Result<(), ExecutionError> {
+    let node_indices = dag.graph().node_identifiers().collect::<Vec<_>>();
+    for node_index in node_indices {
+        let Some(NodeKind::Sink {
+            source: sink_source,
+            last_checkpoint,
+            ..
+        }) = &mut dag.node_weight_mut(node_index).kind
+        else {
+            continue;
+        };
+        let Some(checkpoint) = *last_checkpoint else {
+            continue;
+        };
+        if *sink_source != *source {
+            continue;
+        }
+        let replayed = match replay {
+            Some((log, replay_start)) => log
+                .commit_seq(checkpoint)?
+                .is_some_and(|seq| seq >= replay_start),
+            None => false,
+        };
+        if !replayed {
+            *last_checkpoint = None;
+        }
     }
+    Ok(())
 }
 
 mod stream;
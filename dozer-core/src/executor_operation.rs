@@ -1,12 +1,20 @@
-use dozer_types::{node::OpIdentifier, types::TableOperation};
+use dozer_types::{
+    node::OpIdentifier,
+    types::{Schema, TableOperation},
+};
 
-use crate::epoch::Epoch;
+use crate::{epoch::Epoch, node::PortHandle};
 
 #[derive(Clone, Debug)]
 pub enum ExecutorOperation {
     Op {
         op: TableOperation,
     },
+    /// The schema of `port` changed. Following ops on the port use `schema`.
+    SchemaChange {
+        port: PortHandle,
+        schema: Schema,
+    },
     Commit {
         epoch: Epoch,
     },
@@ -4,19 +4,27 @@ use crate::error_manager::ErrorManager;
 use crate::errors::ExecutionError;
 use crate::executor_operation::ExecutorOperation;
 use crate::node::PortHandle;
+use crate::node_metrics::NodeMetrics;
 use crate::record_store::RecordWriter;
 
 use crossbeam::channel::Sender;
+use dozer_tracing::DozerMonitorContext;
 use dozer_types::log::debug;
 use dozer_types::node::{NodeHandle, OpIdentifier};
-use dozer_types::types::TableOperation;
+use dozer_types::types::{Schema, TableOperation};
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often the fill of the output channels is sampled while sending operations. It's also sampled on every commit.
+const CHANNEL_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 pub struct SenderWithPortMapping {
     pub sender: Sender<ExecutorOperation>,
+    /// The node receiving from `sender`.
+    pub target: NodeHandle,
     /// From output port to input port.
     pub port_mapping: HashMap<PortHandle, Vec<PortHandle>>,
 }
@@ -39,6 +47,24 @@ impl SenderWithPortMapping {
         }
         Ok(())
     }
+
+    pub fn send_schema_change(
+        &self,
+        port: PortHandle,
+        schema: &Schema,
+    ) -> Result<(), ExecutionError> {
+        let Some(ports) = self.port_mapping.get(&port) else {
+            return Ok(());
+        };
+
+        for port in ports {
+            self.sender.send(ExecutorOperation::SchemaChange {
+                port: *port,
+                schema: schema.clone(),
+            })?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -47,6 +73,8 @@ pub struct ChannelManager {
     record_writers: HashMap<PortHandle, Box<dyn RecordWriter>>,
     senders: Vec<SenderWithPortMapping>,
     error_manager: Arc<ErrorManager>,
+    metrics: NodeMetrics,
+    channels_sampled_at: Instant,
 }
 
 impl ChannelManager {
@@ -69,12 +97,34 @@ impl ChannelManager {
             last_sender.send_op(op)?;
         }
 
+        if self.channels_sampled_at.elapsed() >= CHANNEL_SAMPLE_INTERVAL {
+            self.sample_channels();
+        }
         Ok(())
     }
 
-    /// Send anything that's not an `ExecutorOperation::Op`.
+    /// Sends the new schema of output `port` to the nodes connected to it.
+    pub fn send_schema_change(
+        &mut self,
+        port: PortHandle,
+        schema: Schema,
+    ) -> Result<(), ExecutionError> {
+        if let Some(writer) = self.record_writers.get_mut(&port) {
+            writer.set_schema(schema.clone());
+        }
+
+        for sender in &self.senders {
+            sender.send_schema_change(port, &schema)?;
+        }
+        Ok(())
+    }
+
+    /// Send anything that's not an `ExecutorOperation::Op` or `ExecutorOperation::SchemaChange`.
     pub fn send_non_op(&self, op: ExecutorOperation) -> Result<(), ExecutionError> {
-        assert!(!matches!(op, ExecutorOperation::Op { .. }));
+        assert!(!matches!(
+            op,
+            ExecutorOperation::Op { .. } | ExecutorOperation::SchemaChange { .. }
+        ));
         if let Some((last_sender, senders)) = self.senders.split_last() {
             for sender in senders {
                 sender.sender.send(op.clone())?;
@@ -112,24 +162,40 @@ impl ChannelManager {
             epoch.common_info.source_states.deref()
         );
 
+        self.sample_channels();
         self.send_non_op(ExecutorOperation::Commit { epoch })
     }
 
+    fn sample_channels(&mut self) {
+        for sender in &self.senders {
+            self.metrics.record_channel(&sender.target, &sender.sender);
+        }
+        self.channels_sampled_at = Instant::now();
+    }
+
     pub fn owner(&self) -> &NodeHandle {
         &self.owner
     }
 
+    pub(crate) fn metrics(&self) -> &NodeMetrics {
+        &self.metrics
+    }
+
     pub fn new(
         owner: NodeHandle,
         record_writers: HashMap<PortHandle, Box<dyn RecordWriter>>,
         senders: Vec<SenderWithPortMapping>,
         error_manager: Arc<ErrorManager>,
+        labels: &DozerMonitorContext,
     ) -> Self {
+        let metrics = NodeMetrics::new(labels, &owner);
         Self {
             owner,
             record_writers,
             senders,
             error_manager,
+            metrics,
+            channels_sampled_at: Instant::now(),
         }
     }
 }
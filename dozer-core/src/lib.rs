@@ -1,10 +1,13 @@
 pub mod app;
 pub mod appsource;
 mod builder_dag;
+pub use builder_dag::resolve_prepared_epochs;
 pub mod channels;
+pub mod checkpoint;
 mod dag_impl;
 pub use dag_impl::*;
 pub mod dag_schemas;
+pub mod dead_letter;
 mod error_manager;
 pub mod errors;
 pub mod executor;
@@ -12,8 +15,12 @@ pub mod executor_operation;
 pub mod forwarder;
 mod hash_map_to_vec;
 pub mod node;
+mod node_metrics;
+pub mod operation_log;
+mod partition;
 pub mod record_store;
 pub mod shutdown;
+pub mod state_store;
 pub use tokio;
 
 #[cfg(test)]
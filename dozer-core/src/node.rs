@@ -1,13 +1,15 @@
 use crate::channels::ProcessorChannelForwarder;
 use crate::epoch::Epoch;
+use crate::errors::SchemaChangeError;
 use crate::event::EventHub;
+use crate::state_store::StateStore;
 
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::models::ingestion_types::IngestionMessage;
-use dozer_types::node::OpIdentifier;
+use dozer_types::node::{NodeHandle, OpIdentifier};
 use dozer_types::serde::{Deserialize, Serialize};
 use dozer_types::tonic::async_trait;
-use dozer_types::types::{Schema, TableOperation};
+use dozer_types::types::{Record, Schema, TableOperation};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use tokio::sync::mpsc::Sender;
@@ -76,14 +78,35 @@ pub trait ProcessorFactory: Send + Sync + Debug {
     ) -> Result<Schema, BoxedError>;
     fn get_input_ports(&self) -> Vec<PortHandle>;
     fn get_output_ports(&self) -> Vec<PortHandle>;
+    /// `checkpoint_data` is the state last returned by [Processor::serialize_state],
+    /// if it was taken at the epoch the sources resume from.
+    /// Stateful processors should keep their state in maps created from `state_store`.
     async fn build(
         &self,
         input_schemas: HashMap<PortHandle, Schema>,
         output_schemas: HashMap<PortHandle, Schema>,
         event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError>;
     fn type_name(&self) -> String;
     fn id(&self) -> String;
+
+    /// Returns how to shard the processor's input by key, if it can run as several instances that each
+    /// see the operations of a subset of the keys. Processors that can't be sharded return `None`.
+    async fn partitioner(
+        &self,
+        _input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        Ok(None)
+    }
+}
+
+/// Maps records to the key a partitioned processor is sharded by.
+pub trait Partitioner: Send + Sync + Debug {
+    /// Hashes the partition key of `record`, which arrived on input `port`.
+    /// Records that an instance must see together, such as the two sides of a join match, must hash equal.
+    fn partition_hash(&mut self, port: PortHandle, record: &Record) -> Result<u64, BoxedError>;
 }
 
 pub trait Processor: Send + Sync + Debug {
@@ -93,6 +116,32 @@ pub trait Processor: Send + Sync + Debug {
         op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError>;
+
+    /// Serializes the processor's state at an epoch boundary, after [Processor::commit].
+    /// Stateless processors return `None` and are not checkpointed.
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    /// Returns how many entries the processor keeps in its state, such as join or aggregation entries.
+    /// It's exported as a metric, so it's called periodically and should be cheap, like keeping a running count.
+    /// Stateless processors return `None`.
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(None)
+    }
+
+    /// Called when the schema of input `port` changes from `old` to `new`. Following operations on the port use `new`.
+    ///
+    /// Returns the new schemas of the output ports whose schema changes with it.
+    /// An error stops the pipeline, so processors that can't apply the change should return one.
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        _old: &Schema,
+        _new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        Err(SchemaChangeError::Unsupported.into())
+    }
 }
 
 #[async_trait]
@@ -121,6 +170,11 @@ pub trait Sink: Send + Debug {
     ) -> Result<(), BoxedError>;
 
     // Pipeline state management.
+    /// Called once the sink is built, before [Sink::get_latest_op_id], with the source its operations come from.
+    /// Sinks that store a resume position take it from this source's state in committed [Epoch]s.
+    fn set_source(&mut self, _source: &NodeHandle) -> Result<(), BoxedError> {
+        Ok(())
+    }
     fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError>;
     fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError>;
     fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError>;
@@ -144,4 +198,53 @@ pub trait Sink: Send + Debug {
     fn supports_batching(&self) -> bool {
         false
     }
+
+    /// Returns if a call that failed with `error` may succeed when retried, like after a dropped connection.
+    /// Errors that aren't retryable, like a rejected operation, are reported right away.
+    fn is_retryable(&self, _error: &BoxedError) -> bool {
+        true
+    }
+
+    /// If this returns `true`, epochs are committed in two phases instead of with [Sink::commit]:
+    /// [Sink::prepare] and then [Sink::commit_prepared], or [Sink::abort_prepared] if preparing fails.
+    fn supports_two_phase_commit(&self) -> bool {
+        false
+    }
+
+    /// Durably stages what was written since the last prepared epoch, together with the epoch's source states,
+    /// without making it visible. A prepared epoch must survive a crash, so it can be committed after a restart.
+    ///
+    /// If preparing fails, what was written must stay staged, so preparing can be retried.
+    fn prepare(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    /// Makes the prepared epoch `epoch_id` visible, and moves the position [Sink::get_latest_op_id] returns to it.
+    /// Committing an epoch that is already committed, or has nothing prepared, does nothing.
+    fn commit_prepared(&mut self, _epoch_id: u64) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    /// Discards what was staged for epoch `epoch_id`, whether it was fully prepared or not.
+    fn abort_prepared(&mut self, _epoch_id: u64) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    /// Returns the ids of epochs that were prepared but neither committed nor aborted when the sink last stopped.
+    /// They are committed before the pipeline starts, as epoch ids start over on every run.
+    fn prepared_epochs(&mut self) -> Result<Vec<u64>, BoxedError> {
+        Ok(vec![])
+    }
+
+    /// Called when the schema of input `port` changes from `old` to `new`. Following operations on the port use `new`.
+    ///
+    /// An error stops the pipeline, so sinks that can't apply the change should return one.
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        _old: &Schema,
+        _new: &Schema,
+    ) -> Result<(), BoxedError> {
+        Err(SchemaChangeError::Unsupported.into())
+    }
 }
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam::channel::Sender;
+use dozer_tracing::{
+    constants::{
+        CHANNEL_FILL_GAUGE_NAME, CHANNEL_QUEUE_DEPTH_GAUGE_NAME, DOZER_METER_NAME, NODE_LABEL,
+        NODE_OPERATION_COUNTER_NAME, NODE_PROCESSING_TIME_HISTOGRAM_NAME,
+        NODE_STATE_SIZE_GAUGE_NAME, SOURCE_LAG_GAUGE_NAME, TARGET_NODE_LABEL,
+    },
+    opentelemetry_metrics::{Counter, Gauge, Histogram},
+    DozerMonitorContext, KeyValue,
+};
+use dozer_types::{epoch::SourceTime, log::warn, node::NodeHandle};
+
+use crate::executor_operation::ExecutorOperation;
+
+/// Metrics of a node in the running DAG, labelled with the node.
+#[derive(Debug, Clone)]
+pub struct NodeMetrics {
+    labels: Vec<KeyValue>,
+    operation_counter: Counter<u64>,
+    processing_time_hist: Histogram<u64>,
+    state_size_gauge: Gauge<u64>,
+    queue_depth_gauge: Gauge<u64>,
+    channel_fill_gauge: Gauge<f64>,
+    source_lag_gauge: Gauge<u64>,
+    /// Set while the source clock is ahead of the system clock, so that's only warned about when it starts.
+    source_clock_ahead: Arc<AtomicBool>,
+}
+
+impl NodeMetrics {
+    pub fn new(labels: &DozerMonitorContext, node: &NodeHandle) -> Self {
+        let mut labels = labels.attrs();
+        labels.push(KeyValue::new(NODE_LABEL, node.to_string()));
+
+        let meter = dozer_tracing::global::meter(DOZER_METER_NAME);
+        let operation_counter = meter
+            .u64_counter(NODE_OPERATION_COUNTER_NAME)
+            .with_description("No of operations processed by the node")
+            .init();
+        let processing_time_hist = meter
+            .u64_histogram(NODE_PROCESSING_TIME_HISTOGRAM_NAME)
+            .with_description("Microseconds the node takes to process an operation")
+            .init();
+        let state_size_gauge = meter
+            .u64_gauge(NODE_STATE_SIZE_GAUGE_NAME)
+            .with_description("No of entries the node keeps in its state")
+            .init();
+        let queue_depth_gauge = meter
+            .u64_gauge(CHANNEL_QUEUE_DEPTH_GAUGE_NAME)
+            .with_description(
+                "No of messages waiting in the channel from the node to the target node",
+            )
+            .init();
+        let channel_fill_gauge = meter
+            .f64_gauge(CHANNEL_FILL_GAUGE_NAME)
+            .with_description(
+                "Fraction of the capacity of the channel from the node to the target node in use",
+            )
+            .init();
+        let source_lag_gauge = meter
+            .u64_gauge(SOURCE_LAG_GAUGE_NAME)
+            .with_description(
+                "Milliseconds between a commit on the source and the source node sending it",
+            )
+            .init();
+
+        Self {
+            labels,
+            operation_counter,
+            processing_time_hist,
+            state_size_gauge,
+            queue_depth_gauge,
+            channel_fill_gauge,
+            source_lag_gauge,
+            source_clock_ahead: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Records an operation the node took `elapsed` to process.
+    pub fn record_op(&self, elapsed: Duration) {
+        self.operation_counter.add(1, &self.labels);
+        self.processing_time_hist
+            .record(elapsed.as_micros() as u64, &self.labels);
+    }
+
+    pub fn record_state_size(&self, size: usize) {
+        self.state_size_gauge.record(size as u64, &self.labels);
+    }
+
+    /// Records how full the channel to `target` is.
+    pub fn record_channel(&self, target: &NodeHandle, sender: &Sender<ExecutorOperation>) {
+        let mut labels = self.labels.clone();
+        labels.push(KeyValue::new(TARGET_NODE_LABEL, target.to_string()));
+        let len = sender.len();
+        self.queue_depth_gauge.record(len as u64, &labels);
+        if let Some(capacity) = sender.capacity().filter(|capacity| *capacity > 0) {
+            self.channel_fill_gauge
+                .record(len as f64 / capacity as f64, &labels);
+        }
+    }
+
+    /// Records how far behind the source the source node is, from the time of a commit on the source.
+    pub fn record_source_lag(&self, source_time: &SourceTime) {
+        if let Some(lag) = source_time.elapsed_millis() {
+            self.source_lag_gauge.record(lag, &self.labels);
+            self.source_clock_ahead.store(false, Ordering::Relaxed);
+        } else if !self.source_clock_ahead.swap(true, Ordering::Relaxed) {
+            warn!("Source clock is ahead of the system clock. Source lag isn't recorded until they're in sync.");
+        }
+    }
+}
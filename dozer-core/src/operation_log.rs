@@ -0,0 +1,472 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use dozer_types::{
+    bincode,
+    log::warn,
+    models::ingestion_types::{IngestionMessage, TransactionInfo},
+    node::{NodeHandle, OpIdentifier},
+};
+
+use crate::{errors::ExecutionError, node::PortHandle};
+
+#[derive(Debug, Clone)]
+pub struct OperationLogOptions {
+    /// The log of every source is kept in a subdirectory named after the source.
+    pub dir: PathBuf,
+    /// Bytes a segment grows to before a new one is started.
+    pub max_segment_bytes: u64,
+    /// Bytes of segments kept per source. The oldest segments are removed first.
+    pub retention_bytes: u64,
+    /// How long a segment is kept after it's last written to. Segments are kept regardless of age if `None`.
+    pub retention: Option<Duration>,
+}
+
+/// A message as the source sent it, together with its sequence number in the log.
+pub type LogEntry = (u64, PortHandle, IngestionMessage);
+
+/// Where the log starts without gaps, as of when it was last cleared.
+#[derive(Debug, Clone, Copy, bincode::Encode, bincode::Decode)]
+struct LogStart {
+    /// The sequence number of the first entry the source sent after it started.
+    seq: u64,
+    /// The commit the source resumed after, or `None` if it started over.
+    resumed_after: Option<OpIdentifier>,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    first_seq: u64,
+    path: PathBuf,
+    len: u64,
+}
+
+/// An append-only log of the messages a source ingests.
+///
+/// The log is split into segment files named after the sequence number of their first entry. Entries are
+/// length-prefixed bincode. An entry left partially written by a crash is truncated when the log is opened.
+/// Where the log starts is kept in a separate file, so it's known even when all segments are removed.
+#[derive(Debug)]
+pub struct OperationLog {
+    dir: PathBuf,
+    options: OperationLogOptions,
+    start: Option<LogStart>,
+    segments: Vec<Segment>,
+    next_seq: u64,
+    writer: Option<BufWriter<File>>,
+}
+
+const SEGMENT_EXTENSION: &str = "log";
+const START_FILE_NAME: &str = "start";
+
+impl OperationLog {
+    pub fn open(
+        options: &OperationLogOptions,
+        source: &NodeHandle,
+    ) -> Result<Self, ExecutionError> {
+        let dir = options.dir.join(source.to_string());
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| ExecutionError::FileSystemError(dir.clone(), e))?;
+
+        let mut segments = vec![];
+        for entry in
+            std::fs::read_dir(&dir).map_err(|e| ExecutionError::FileSystemError(dir.clone(), e))?
+        {
+            let path = entry
+                .map_err(|e| ExecutionError::FileSystemError(dir.clone(), e))?
+                .path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(SEGMENT_EXTENSION)
+            {
+                continue;
+            }
+            let Some(first_seq) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            else {
+                continue;
+            };
+            let len = std::fs::metadata(&path)
+                .map_err(|e| ExecutionError::FileSystemError(path.clone(), e))?
+                .len();
+            segments.push(Segment {
+                first_seq,
+                path,
+                len,
+            });
+        }
+        segments.sort_by_key(|segment| segment.first_seq);
+
+        let start = read_start(&dir.join(START_FILE_NAME))?;
+        let mut next_seq = start.map_or(0, |start| start.seq);
+        if let Some(last) = segments.last_mut() {
+            let offsets = entry_offsets(&last.path)?;
+            let valid_len = offsets.last().copied().unwrap_or(0);
+            if valid_len < last.len {
+                warn!(
+                    "[{source}] Truncating partially written entry at the end of {:?}",
+                    last.path
+                );
+                truncate_segment(&last.path, valid_len)?;
+                last.len = valid_len;
+            }
+            next_seq = last.first_seq + offsets.len() as u64 - 1;
+        }
+
+        let mut log = Self {
+            dir,
+            options: options.clone(),
+            start,
+            segments,
+            next_seq,
+            writer: None,
+        };
+        log.remove_expired_segments()?;
+        Ok(log)
+    }
+
+    /// The sequence number of the oldest entry kept.
+    pub fn first_seq(&self) -> u64 {
+        self.segments
+            .first()
+            .map_or(self.next_seq, |segment| segment.first_seq)
+    }
+
+    /// The sequence number the next appended entry gets.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Appends a message and returns its sequence number. The message is durable once the log is synced.
+    pub fn append(
+        &mut self,
+        port: PortHandle,
+        message: &IngestionMessage,
+    ) -> Result<u64, ExecutionError> {
+        let data = bincode::encode_to_vec((port, message), bincode::config::legacy())
+            .map_err(ExecutionError::SerializeOperationLog)?;
+
+        if self.segments.last().map_or(true, |segment| {
+            segment.len >= self.options.max_segment_bytes
+        }) {
+            self.roll()?;
+        }
+        let segment = self.segments.last_mut().expect("we just rolled");
+        if self.writer.is_none() {
+            let file = OpenOptions::new()
+                .append(true)
+                .open(&segment.path)
+                .map_err(|e| ExecutionError::FileSystemError(segment.path.clone(), e))?;
+            self.writer = Some(BufWriter::new(file));
+        }
+        let writer = self.writer.as_mut().expect("we just opened it");
+        writer
+            .write_all(&(data.len() as u32).to_le_bytes())
+            .and_then(|()| writer.write_all(&data))
+            .map_err(|e| ExecutionError::FileSystemError(segment.path.clone(), e))?;
+        segment.len += 4 + data.len() as u64;
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        Ok(seq)
+    }
+
+    /// Flushes appended entries to disk, and removes the oldest segments that exceed the retention limits,
+    /// so segments age out even while no new one is started.
+    pub fn sync(&mut self) -> Result<(), ExecutionError> {
+        self.flush()?;
+        self.remove_expired_segments()
+    }
+
+    /// Returns the sequence number and id of the last commit the source can restart from.
+    pub fn last_commit(&self) -> Result<Option<(u64, OpIdentifier)>, ExecutionError> {
+        self.find_last_commit(|_| true)
+    }
+
+    /// Returns the sequence number to replay from, for consumers that have caught up to `checkpoint`.
+    ///
+    /// Returns an error if the log doesn't go back far enough for them, because retention removed the entries
+    /// or because the source resumed from a later commit when the log was cleared.
+    pub fn replay_start(&self, checkpoint: Option<OpIdentifier>) -> Result<u64, ExecutionError> {
+        if let Some(checkpoint) = checkpoint {
+            if let Some((seq, _)) = self.find_last_commit(|id| id <= checkpoint)? {
+                return Ok(seq + 1);
+            }
+        }
+        // Without a logged commit to replay after, consumers need everything since the source started.
+        match self.start {
+            Some(start) if start.seq >= self.first_seq() && start.resumed_after <= checkpoint => {
+                Ok(start.seq)
+            }
+            _ => Err(ExecutionError::OperationLogIncomplete(
+                self.dir.clone(),
+                checkpoint,
+            )),
+        }
+    }
+
+    /// Returns the sequence number of the commit with `id`, if it's logged.
+    pub fn commit_seq(&self, id: OpIdentifier) -> Result<Option<u64>, ExecutionError> {
+        Ok(self
+            .find_last_commit(|commit| commit <= id)?
+            .filter(|(_, commit)| *commit == id)
+            .map(|(seq, _)| seq))
+    }
+
+    /// Reads the entries from sequence number `from` on.
+    pub fn read_from(&self, from: u64) -> LogReader {
+        let start = self
+            .segments
+            .partition_point(|segment| segment.first_seq <= from)
+            .saturating_sub(1);
+        LogReader {
+            segments: self.segments[start..].iter().cloned().collect(),
+            current: None,
+            from,
+            end: self.next_seq,
+        }
+    }
+
+    /// Removes the entries from sequence number `from` on.
+    pub fn truncate(&mut self, from: u64) -> Result<(), ExecutionError> {
+        self.writer = None;
+        while let Some(segment) = self.segments.last_mut() {
+            if segment.first_seq >= from {
+                remove_segment(&segment.path)?;
+                self.segments.pop();
+                continue;
+            }
+            let offsets = entry_offsets(&segment.path)?;
+            let len = offsets[((from - segment.first_seq) as usize).min(offsets.len() - 1)];
+            truncate_segment(&segment.path, len)?;
+            segment.len = len;
+            break;
+        }
+        self.next_seq = self.next_seq.min(from);
+        Ok(())
+    }
+
+    /// Removes all entries, as the source sends everything after `resumed_after` again, or everything if `None`.
+    /// Sequence numbers keep increasing.
+    pub fn clear(&mut self, resumed_after: Option<OpIdentifier>) -> Result<(), ExecutionError> {
+        // The start is stored first, so sequence numbers continue from it even if removing segments is interrupted.
+        let start = LogStart {
+            seq: self.next_seq,
+            resumed_after,
+        };
+        write_start(&self.dir.join(START_FILE_NAME), &start)?;
+        self.start = Some(start);
+
+        self.writer = None;
+        for segment in self.segments.drain(..) {
+            remove_segment(&segment.path)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes appended entries to disk.
+    fn flush(&mut self) -> Result<(), ExecutionError> {
+        if let Some(writer) = &mut self.writer {
+            writer
+                .flush()
+                .and_then(|()| writer.get_ref().sync_data())
+                .map_err(|e| ExecutionError::FileSystemError(self.dir.clone(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Starts a new segment and removes the oldest ones that exceed the retention limits.
+    fn roll(&mut self) -> Result<(), ExecutionError> {
+        self.flush()?;
+        let path = self
+            .dir
+            .join(format!("{:020}.{SEGMENT_EXTENSION}", self.next_seq));
+        let file =
+            File::create(&path).map_err(|e| ExecutionError::FileSystemError(path.clone(), e))?;
+        self.writer = Some(BufWriter::new(file));
+        self.segments.push(Segment {
+            first_seq: self.next_seq,
+            path,
+            len: 0,
+        });
+        self.remove_expired_segments()
+    }
+
+    /// Removes the oldest segments while the log exceeds `retention_bytes` or they're older than `retention`.
+    /// The newest segment is always kept.
+    fn remove_expired_segments(&mut self) -> Result<(), ExecutionError> {
+        let mut total_len = self.segments.iter().map(|segment| segment.len).sum::<u64>();
+        while self.segments.len() > 1 {
+            let oldest = &self.segments[0];
+            let expired = match self.options.retention {
+                Some(retention) => std::fs::metadata(&oldest.path)
+                    .and_then(|metadata| metadata.modified())
+                    .map_err(|e| ExecutionError::FileSystemError(oldest.path.clone(), e))?
+                    .elapsed()
+                    .is_ok_and(|age| age > retention),
+                None => false,
+            };
+            if total_len <= self.options.retention_bytes && !expired {
+                break;
+            }
+            remove_segment(&oldest.path)?;
+            total_len -= oldest.len;
+            self.segments.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Searches segments from the newest for the last commit whose id matches `predicate`.
+    fn find_last_commit(
+        &self,
+        predicate: impl Fn(OpIdentifier) -> bool,
+    ) -> Result<Option<(u64, OpIdentifier)>, ExecutionError> {
+        for segment in self.segments.iter().rev() {
+            let mut reader = LogReader {
+                segments: VecDeque::from([segment.clone()]),
+                current: None,
+                from: segment.first_seq,
+                end: self.next_seq,
+            };
+            let mut last_commit = None;
+            for entry in &mut reader {
+                if let (
+                    seq,
+                    _,
+                    IngestionMessage::TransactionInfo(TransactionInfo::Commit {
+                        id: Some(id), ..
+                    }),
+                ) = entry?
+                {
+                    if predicate(id) {
+                        last_commit = Some((seq, id));
+                    }
+                }
+            }
+            if last_commit.is_some() {
+                return Ok(last_commit);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads log entries in order. Appending to the log doesn't affect a reader that's already created.
+#[derive(Debug)]
+pub struct LogReader {
+    segments: VecDeque<Segment>,
+    current: Option<(BufReader<File>, PathBuf, u64)>,
+    from: u64,
+    end: u64,
+}
+
+impl LogReader {
+    fn next_entry(&mut self) -> Result<Option<LogEntry>, ExecutionError> {
+        loop {
+            if self.current.is_none() {
+                let Some(segment) = self.segments.pop_front() else {
+                    return Ok(None);
+                };
+                let file = File::open(&segment.path)
+                    .map_err(|e| ExecutionError::FileSystemError(segment.path.clone(), e))?;
+                self.current = Some((BufReader::new(file), segment.path, segment.first_seq));
+            }
+            let (reader, path, seq) = self.current.as_mut().expect("we just opened it");
+            if *seq >= self.end {
+                return Ok(None);
+            }
+
+            let Some(data) =
+                read_entry(reader).map_err(|e| ExecutionError::FileSystemError(path.clone(), e))?
+            else {
+                self.current = None;
+                continue;
+            };
+            let entry_seq = *seq;
+            *seq += 1;
+            if entry_seq < self.from {
+                continue;
+            }
+            let ((port, message), _) = bincode::decode_from_slice(&data, bincode::config::legacy())
+                .map_err(|e| ExecutionError::CorruptedOperationLog(path.clone(), e))?;
+            return Ok(Some((entry_seq, port, message)));
+        }
+    }
+}
+
+impl Iterator for LogReader {
+    type Item = Result<LogEntry, ExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+/// Reads the next entry's data, or `None` at the end of the segment, including at a partially written entry.
+fn read_entry(reader: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    let mut data = vec![];
+    let result = reader.read_exact(&mut len).and_then(|()| {
+        data.resize(u32::from_le_bytes(len) as usize, 0);
+        reader.read_exact(&mut data)
+    });
+    match result {
+        Ok(()) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the byte offsets of the segment's complete entries, followed by the offset where they end.
+fn entry_offsets(path: &Path) -> Result<Vec<u64>, ExecutionError> {
+    let file =
+        File::open(path).map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut offsets = vec![0];
+    while let Some(data) = read_entry(&mut reader)
+        .map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))?
+    {
+        offsets.push(offsets.last().unwrap() + 4 + data.len() as u64);
+    }
+    Ok(offsets)
+}
+
+fn read_start(path: &Path) -> Result<Option<LogStart>, ExecutionError> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(ExecutionError::FileSystemError(path.to_path_buf(), e)),
+    };
+    let (start, _) = bincode::decode_from_slice(&data, bincode::config::legacy())
+        .map_err(|e| ExecutionError::CorruptedOperationLog(path.to_path_buf(), e))?;
+    Ok(Some(start))
+}
+
+/// Replaces the start file, so a crash leaves either the old or the new start.
+fn write_start(path: &Path, start: &LogStart) -> Result<(), ExecutionError> {
+    let data = bincode::encode_to_vec(start, bincode::config::legacy())
+        .map_err(ExecutionError::SerializeOperationLog)?;
+    let temp_path = path.with_extension("tmp");
+    File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(&data)?;
+            file.sync_data()
+        })
+        .and_then(|()| std::fs::rename(&temp_path, path))
+        .map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))
+}
+
+fn truncate_segment(path: &Path, len: u64) -> Result<(), ExecutionError> {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_len(len))
+        .map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))
+}
+
+fn remove_segment(path: &Path) -> Result<(), ExecutionError> {
+    std::fs::remove_file(path).map_err(|e| ExecutionError::FileSystemError(path.to_path_buf(), e))
+}
@@ -0,0 +1,502 @@
+//! Runs a processor as several instances, each owning the keys that hash to it.
+//!
+//! A partitioned processor is replaced by a router, the instances and a merge. The router sends every
+//! operation to the instance owning its key, so operations on a key keep their order. The merge forwards
+//! the instances' output, and only forwards a commit once all instances have committed the epoch.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::node::NodeHandle;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Record, Schema, SchemaDiff, TableOperation};
+
+use crate::channels::ProcessorChannelForwarder;
+use crate::epoch::Epoch;
+use crate::errors::ExecutionError;
+use crate::event::EventHub;
+use crate::node::{Partitioner, PortHandle, Processor, ProcessorFactory};
+use crate::state_store::StateStore;
+use crate::Dag;
+
+pub fn router_id(id: &str) -> String {
+    format!("{id}--router")
+}
+
+/// Instance handles include the parallelism, so a checkpoint is only restored into the same sharding.
+pub fn instance_id(id: &str, index: usize, parallelism: usize) -> String {
+    format!("{id}--{index}-of-{parallelism}")
+}
+
+pub fn merge_id(id: &str) -> String {
+    format!("{id}--merge")
+}
+
+/// Returns the id of the processor that a router, instance or merge was added for, or `id` itself for other nodes.
+pub fn processor_id(id: &str) -> &str {
+    id.split_once("--").map_or(id, |(id, _)| id)
+}
+
+/// Router output ports and merge input ports are numbered by instance, then by the index of the processor's port.
+pub fn instance_port(instance: usize, port_index: usize, num_ports: usize) -> PortHandle {
+    (instance * num_ports + port_index) as PortHandle
+}
+
+/// Adds `processor` as `parallelism` instances between a router and a merge, under namespace `ns`.
+///
+/// Edges into the processor must be connected to the router, and edges out of it to the merge.
+/// Their ports are the same as the processor's.
+pub fn add_partitioned_processor(
+    dag: &mut Dag,
+    ns: Option<u16>,
+    id: &str,
+    processor: Box<dyn ProcessorFactory>,
+    parallelism: usize,
+) -> Result<(), ExecutionError> {
+    let processor: Arc<dyn ProcessorFactory> = processor.into();
+    let input_ports = processor.get_input_ports();
+    let output_ports = processor.get_output_ports();
+
+    let router = dag.add_processor(
+        NodeHandle::new(ns, router_id(id)),
+        Box::new(RouterFactory::new(processor.clone(), parallelism)),
+    );
+    let merge = dag.add_processor(
+        NodeHandle::new(ns, merge_id(id)),
+        Box::new(MergeFactory::new(&*processor, parallelism)),
+    );
+    for index in 0..parallelism {
+        let instance = dag.add_processor(
+            NodeHandle::new(ns, instance_id(id, index, parallelism)),
+            Box::new(InstanceFactory::new(processor.clone(), index, parallelism)),
+        );
+        for (port_index, port) in input_ports.iter().enumerate() {
+            dag.connect_with_index(
+                router,
+                instance_port(index, port_index, input_ports.len()),
+                instance,
+                *port,
+            )?;
+        }
+        for (port_index, port) in output_ports.iter().enumerate() {
+            dag.connect_with_index(
+                instance,
+                *port,
+                merge,
+                instance_port(index, port_index, output_ports.len()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct RouterFactory {
+    processor: Arc<dyn ProcessorFactory>,
+    parallelism: usize,
+}
+
+impl RouterFactory {
+    pub fn new(processor: Arc<dyn ProcessorFactory>, parallelism: usize) -> Self {
+        Self {
+            processor,
+            parallelism,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessorFactory for RouterFactory {
+    async fn get_output_schema(
+        &self,
+        output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        let input_ports = self.processor.get_input_ports();
+        let input_port = input_ports[*output_port as usize % input_ports.len()];
+        Ok(input_schemas
+            .get(&input_port)
+            .ok_or(ExecutionError::InvalidPortHandle(input_port))?
+            .clone())
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        self.processor.get_input_ports()
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        let num_ports = self.processor.get_input_ports().len();
+        (0..self.parallelism)
+            .flat_map(|instance| {
+                (0..num_ports).map(move |port_index| instance_port(instance, port_index, num_ports))
+            })
+            .collect()
+    }
+
+    async fn build(
+        &self,
+        input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        let partitioner = self
+            .processor
+            .partitioner(&input_schemas)
+            .await?
+            .ok_or_else(|| ExecutionError::NotPartitionable(self.processor.id()))?;
+        Ok(Box::new(Router {
+            input_ports: self.processor.get_input_ports(),
+            parallelism: self.parallelism,
+            partitioner,
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "Router".to_string()
+    }
+
+    fn id(&self) -> String {
+        router_id(&self.processor.id())
+    }
+}
+
+#[derive(Debug)]
+struct Router {
+    input_ports: Vec<PortHandle>,
+    parallelism: usize,
+    partitioner: Box<dyn Partitioner>,
+}
+
+impl Router {
+    fn instance(&mut self, port: PortHandle, record: &Record) -> Result<usize, BoxedError> {
+        let hash = self.partitioner.partition_hash(port, record)?;
+        Ok((hash % self.parallelism as u64) as usize)
+    }
+}
+
+impl Processor for Router {
+    fn commit(&self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        let num_ports = self.input_ports.len();
+        let port_index = self
+            .input_ports
+            .iter()
+            .position(|port| *port == op.port)
+            .ok_or(ExecutionError::InvalidPortHandle(op.port))?;
+        let mut send = |instance: usize, operation: Operation| {
+            fw.send(TableOperation {
+                id: op.id,
+                op: operation,
+                port: instance_port(instance, port_index, num_ports),
+            })
+        };
+
+        match op.op {
+            Operation::Insert { new } => {
+                send(self.instance(op.port, &new)?, Operation::Insert { new })
+            }
+            Operation::Delete { old } => {
+                send(self.instance(op.port, &old)?, Operation::Delete { old })
+            }
+            Operation::Update { old, new } => {
+                let old_instance = self.instance(op.port, &old)?;
+                let new_instance = self.instance(op.port, &new)?;
+                if old_instance == new_instance {
+                    send(old_instance, Operation::Update { old, new });
+                } else {
+                    // The key moved to another instance, which can't see the old record.
+                    send(old_instance, Operation::Delete { old });
+                    send(new_instance, Operation::Insert { new });
+                }
+            }
+            Operation::BatchInsert { new } => {
+                let mut batches = vec![vec![]; self.parallelism];
+                for record in new {
+                    batches[self.instance(op.port, &record)?].push(record);
+                }
+                for (instance, new) in batches.into_iter().enumerate() {
+                    if !new.is_empty() {
+                        send(instance, Operation::BatchInsert { new });
+                    }
+                }
+            }
+            Operation::Truncate => {
+                for instance in 0..self.parallelism {
+                    send(instance, Operation::Truncate);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(
+        &mut self,
+        port: PortHandle,
+        _old: &Schema,
+        new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        let num_ports = self.input_ports.len();
+        let port_index = self
+            .input_ports
+            .iter()
+            .position(|input_port| *input_port == port)
+            .ok_or(ExecutionError::InvalidPortHandle(port))?;
+        Ok((0..self.parallelism)
+            .map(|instance| (instance_port(instance, port_index, num_ports), new.clone()))
+            .collect())
+    }
+}
+
+/// Builds instance `index` of the processor. Instances keep separate state and checkpoints.
+#[derive(Debug)]
+pub struct InstanceFactory {
+    processor: Arc<dyn ProcessorFactory>,
+    index: usize,
+    parallelism: usize,
+}
+
+impl InstanceFactory {
+    pub fn new(processor: Arc<dyn ProcessorFactory>, index: usize, parallelism: usize) -> Self {
+        Self {
+            processor,
+            index,
+            parallelism,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessorFactory for InstanceFactory {
+    async fn get_output_schema(
+        &self,
+        output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        self.processor
+            .get_output_schema(output_port, input_schemas)
+            .await
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        self.processor.get_input_ports()
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        self.processor.get_output_ports()
+    }
+
+    async fn build(
+        &self,
+        input_schemas: HashMap<PortHandle, Schema>,
+        output_schemas: HashMap<PortHandle, Schema>,
+        event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        self.processor
+            .build(
+                input_schemas,
+                output_schemas,
+                event_hub,
+                checkpoint_data,
+                state_store,
+            )
+            .await
+    }
+
+    fn type_name(&self) -> String {
+        self.processor.type_name()
+    }
+
+    fn id(&self) -> String {
+        instance_id(&self.processor.id(), self.index, self.parallelism)
+    }
+}
+
+#[derive(Debug)]
+pub struct MergeFactory {
+    id: String,
+    output_ports: Vec<PortHandle>,
+    parallelism: usize,
+}
+
+impl MergeFactory {
+    pub fn new(processor: &dyn ProcessorFactory, parallelism: usize) -> Self {
+        Self {
+            id: merge_id(&processor.id()),
+            output_ports: processor.get_output_ports(),
+            parallelism,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessorFactory for MergeFactory {
+    async fn get_output_schema(
+        &self,
+        output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        let port_index = self
+            .output_ports
+            .iter()
+            .position(|port| port == output_port)
+            .ok_or(ExecutionError::InvalidPortHandle(*output_port))?;
+        // All instances have the same output schema.
+        let input_port = instance_port(0, port_index, self.output_ports.len());
+        Ok(input_schemas
+            .get(&input_port)
+            .ok_or(ExecutionError::InvalidPortHandle(input_port))?
+            .clone())
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        let num_ports = self.output_ports.len();
+        (0..self.parallelism)
+            .flat_map(|instance| {
+                (0..num_ports).map(move |port_index| instance_port(instance, port_index, num_ports))
+            })
+            .collect()
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        self.output_ports.clone()
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        let output_schemas = self
+            .output_ports
+            .iter()
+            .map(|port| {
+                output_schemas
+                    .get(port)
+                    .cloned()
+                    .ok_or(ExecutionError::InvalidPortHandle(*port))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Box::new(Merge {
+            output_ports: self.output_ports.clone(),
+            output_schemas,
+            truncated: vec![vec![None; self.parallelism]; self.output_ports.len()],
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "Merge".to_string()
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+#[derive(Debug)]
+struct Merge {
+    output_ports: Vec<PortHandle>,
+    /// The current schema of each output port.
+    output_schemas: Vec<Schema>,
+    /// For each output port and instance, the operations the instance sent since it truncated the port,
+    /// or `None` if it didn't. A truncate is forwarded once all instances sent it, so records other
+    /// instances emit before their truncate can't survive it.
+    truncated: Vec<Vec<Option<Vec<TableOperation>>>>,
+}
+
+impl Merge {
+    fn merge(
+        &mut self,
+        instance: usize,
+        port_index: usize,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) {
+        let truncated = &mut self.truncated[port_index];
+        if let Some(held) = &mut truncated[instance] {
+            held.push(op);
+            return;
+        }
+        if !matches!(op.op, Operation::Truncate) {
+            fw.send(op);
+            return;
+        }
+
+        truncated[instance] = Some(vec![]);
+        if truncated.iter().all(Option::is_some) {
+            let parallelism = truncated.len();
+            let held = std::mem::replace(truncated, vec![None; parallelism]);
+            fw.send(op);
+            for (instance, ops) in held.into_iter().enumerate() {
+                for op in ops.into_iter().flatten() {
+                    self.merge(instance, port_index, op, fw);
+                }
+            }
+        }
+    }
+}
+
+impl Processor for Merge {
+    fn commit(&self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        mut op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        let instance = op.port as usize / self.output_ports.len();
+        let port_index = op.port as usize % self.output_ports.len();
+        op.port = self.output_ports[port_index];
+        // Instances apply a schema change at different times, so records of instances that haven't applied
+        // the change yet get nulls for the appended fields.
+        let num_fields = self.output_schemas[port_index].fields.len();
+        let pad = |record: &mut Record| {
+            if record.values.len() < num_fields {
+                record.values.resize(num_fields, Field::Null);
+            }
+        };
+        match &mut op.op {
+            Operation::Insert { new } => pad(new),
+            Operation::Delete { old } => pad(old),
+            Operation::Update { old, new } => {
+                pad(old);
+                pad(new);
+            }
+            Operation::BatchInsert { new } => new.iter_mut().for_each(pad),
+            Operation::Truncate => (),
+        }
+        self.merge(instance, port_index, op, fw);
+        Ok(())
+    }
+
+    fn on_schema_change(
+        &mut self,
+        port: PortHandle,
+        _old: &Schema,
+        new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // Every instance reports the change, the first one forwards it.
+        let port_index = port as usize % self.output_ports.len();
+        if SchemaDiff::new(&self.output_schemas[port_index], new).is_empty() {
+            return Ok(vec![]);
+        }
+        self.output_schemas[port_index] = new.clone();
+        Ok(vec![(self.output_ports[port_index], new.clone())])
+    }
+}
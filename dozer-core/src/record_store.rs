@@ -1,17 +1,22 @@
+use crate::errors::StateStoreError;
+use crate::state_store::{StateMap, StateStore};
 use dozer_types::errors::types::DeserializationError;
-use dozer_types::thiserror::Error;
-use dozer_types::types::{Operation, Record, Schema};
-use std::collections::HashMap;
+use dozer_types::thiserror::{self, Error};
+use dozer_types::types::{Field, Operation, Record, Schema};
 use std::fmt::{Debug, Formatter};
 
 #[derive(Debug, Error)]
 pub enum RecordWriterError {
     #[error("Record not found")]
     RecordNotFound,
+    #[error("State store error: {0}")]
+    StateStore(#[from] StateStoreError),
 }
 
 pub trait RecordWriter: Send + Sync {
     fn write(&mut self, op: Operation) -> Result<Operation, RecordWriterError>;
+    /// Called when the schema of written records changes.
+    fn set_schema(&mut self, schema: Schema);
 }
 
 impl Debug for dyn RecordWriter {
@@ -20,19 +25,25 @@ impl Debug for dyn RecordWriter {
     }
 }
 
-pub fn create_record_writer(schema: Schema) -> Result<Box<dyn RecordWriter>, DeserializationError> {
-    let writer = Box::new(PrimaryKeyLookupRecordWriter::new(schema)?);
+pub fn create_record_writer(
+    schema: Schema,
+    state_store: &StateStore,
+) -> Result<Box<dyn RecordWriter>, DeserializationError> {
+    let writer = Box::new(PrimaryKeyLookupRecordWriter::new(schema, state_store)?);
     Ok(writer)
 }
 
 #[derive(Debug)]
 pub(crate) struct PrimaryKeyLookupRecordWriter {
     schema: Schema,
-    index: HashMap<Vec<u8>, Record>,
+    index: StateMap<Vec<u8>, Record>,
 }
 
 impl PrimaryKeyLookupRecordWriter {
-    pub(crate) fn new(schema: Schema) -> Result<Self, DeserializationError> {
+    pub(crate) fn new(
+        schema: Schema,
+        state_store: &StateStore,
+    ) -> Result<Self, DeserializationError> {
         debug_assert!(
             !schema.primary_index.is_empty(),
             "PrimaryKeyLookupRecordWriter can only be used with a schema that has a primary key."
@@ -40,7 +51,7 @@ impl PrimaryKeyLookupRecordWriter {
 
         Ok(Self {
             schema,
-            index: Default::default(),
+            index: state_store.create_map(),
         })
     }
 }
@@ -50,38 +61,52 @@ impl RecordWriter for PrimaryKeyLookupRecordWriter {
         match op {
             Operation::Insert { new } => {
                 let new_key = new.get_key(&self.schema.primary_index);
-                self.index.insert(new_key, new.clone());
+                self.index.insert(new_key, new.clone())?;
                 Ok(Operation::Insert { new })
             }
             Operation::Delete { mut old } => {
                 let old_key = old.get_key(&self.schema.primary_index);
-                old = self
-                    .index
-                    .remove_entry(&old_key)
-                    .ok_or(RecordWriterError::RecordNotFound)?
-                    .1;
+                old = self.remove(old_key)?;
                 Ok(Operation::Delete { old })
             }
             Operation::Update { mut old, new } => {
                 let old_key = old.get_key(&self.schema.primary_index);
-                old = self
-                    .index
-                    .remove_entry(&old_key)
-                    .ok_or(RecordWriterError::RecordNotFound)?
-                    .1;
+                old = self.remove(old_key)?;
                 let new_key = new.get_key(&self.schema.primary_index);
-                self.index.insert(new_key, new.clone());
+                self.index.insert(new_key, new.clone())?;
                 Ok(Operation::Update { old, new })
             }
             Operation::BatchInsert { new } => {
                 let mut new_records = Vec::with_capacity(new.len());
                 for record in new {
                     let new_key = record.get_key(&self.schema.primary_index);
-                    self.index.insert(new_key, record.clone());
+                    self.index.insert(new_key, record.clone())?;
                     new_records.push(record);
                 }
                 Ok(Operation::BatchInsert { new: new_records })
             }
+            Operation::Truncate => {
+                self.index.clear()?;
+                Ok(Operation::Truncate)
+            }
+        }
+    }
+
+    fn set_schema(&mut self, schema: Schema) {
+        self.schema = schema;
+    }
+}
+
+impl PrimaryKeyLookupRecordWriter {
+    /// Removes the record of `key`. Records written before fields were appended to the schema get nulls for them.
+    fn remove(&mut self, key: Vec<u8>) -> Result<Record, RecordWriterError> {
+        let mut record = self
+            .index
+            .remove(&key)?
+            .ok_or(RecordWriterError::RecordNotFound)?;
+        if record.values.len() < self.schema.fields.len() {
+            record.values.resize(self.schema.fields.len(), Field::Null);
         }
+        Ok(record)
     }
 }
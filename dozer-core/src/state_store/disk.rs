@@ -0,0 +1,259 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use dozer_types::bincode::{Decode, Encode};
+use rocksdb::{
+    checkpoint::Checkpoint, BlockBasedOptions, Cache, Direction, IteratorMode, Options, WriteBatch,
+    DB,
+};
+
+use crate::errors::StateStoreError;
+
+use super::{decode, encode};
+
+/// The RocksDB database backing all disk maps of a [StateStore](super::StateStore).
+pub struct StateDb {
+    db: DB,
+    next_map_id: AtomicU32,
+}
+
+impl Debug for StateDb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateDb")
+            .field("path", &self.db.path())
+            .finish()
+    }
+}
+
+impl StateDb {
+    /// Opens the database at `path` with the contents of the database checkpoint at `checkpoint`,
+    /// or empty if there's none.
+    ///
+    /// What's in the database when it's opened may be ahead of where the sources resume from,
+    /// so it's always replaced.
+    pub fn open(
+        path: &Path,
+        cache: &Cache,
+        checkpoint: Option<&Path>,
+    ) -> Result<Self, StateStoreError> {
+        if path.exists() {
+            std::fs::remove_dir_all(path)
+                .map_err(|e| StateStoreError::FileSystem(path.to_path_buf(), e))?;
+        }
+        match checkpoint {
+            Some(checkpoint) => copy_checkpoint(checkpoint, path)?,
+            None => std::fs::create_dir_all(path)
+                .map_err(|e| StateStoreError::FileSystem(path.to_path_buf(), e))?,
+        }
+
+        let mut table_options = BlockBasedOptions::default();
+        table_options.set_block_cache(cache);
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.set_block_based_table_factory(&table_options);
+        let db = DB::open(&options, path)?;
+
+        Ok(Self {
+            db,
+            next_map_id: AtomicU32::new(0),
+        })
+    }
+
+    /// Creates a checkpoint of the database at `path`, which must not exist.
+    /// Table files are hard linked, so it's cheap while they're shared with the database.
+    pub fn checkpoint(&self, path: &Path) -> Result<(), StateStoreError> {
+        Checkpoint::new(&self.db)?.create_checkpoint(path)?;
+        Ok(())
+    }
+}
+
+/// Copies the database checkpoint at `from` to `to`. Table files are immutable, so they're hard linked,
+/// while the files the database appends to are copied, so the checkpoint stays intact.
+fn copy_checkpoint(from: &Path, to: &Path) -> Result<(), StateStoreError> {
+    std::fs::create_dir_all(to).map_err(|e| StateStoreError::FileSystem(to.to_path_buf(), e))?;
+    let entries =
+        std::fs::read_dir(from).map_err(|e| StateStoreError::FileSystem(from.to_path_buf(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| StateStoreError::FileSystem(from.to_path_buf(), e))?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let result = if source.extension().and_then(|extension| extension.to_str()) == Some("sst") {
+            std::fs::hard_link(&source, &target)
+        } else {
+            std::fs::copy(&source, &target).map(|_| ())
+        };
+        result.map_err(|e| StateStoreError::FileSystem(source, e))?;
+    }
+    Ok(())
+}
+
+/// A map stored in [StateDb]. Its keys are prefixed with the map id, so maps don't collide.
+pub struct DiskMap<K, V> {
+    db: Arc<StateDb>,
+    prefix: [u8; 4],
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> Debug for DiskMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskMap")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl<K, V> DiskMap<K, V> {
+    pub fn new(db: Arc<StateDb>) -> Self {
+        let prefix = db.next_map_id.fetch_add(1, Ordering::Relaxed).to_be_bytes();
+        Self {
+            db,
+            prefix,
+            _marker: PhantomData,
+        }
+    }
+
+    fn raw_key(&self, key: &[u8]) -> Vec<u8> {
+        let mut result = self.prefix.to_vec();
+        result.extend(key);
+        result
+    }
+
+    fn raw_iter(
+        &self,
+    ) -> impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), StateStoreError>> + '_ {
+        self.db
+            .db
+            .iterator(IteratorMode::From(&self.prefix, Direction::Forward))
+            .map(|entry| entry.map_err(StateStoreError::from))
+            .take_while(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |(key, _)| key.starts_with(&self.prefix))
+            })
+    }
+
+    pub fn count(&self) -> Result<usize, StateStoreError> {
+        let mut count = 0;
+        for entry in self.raw_iter() {
+            entry?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn clear(&mut self) -> Result<(), StateStoreError> {
+        let mut batch = WriteBatch::default();
+        for entry in self.raw_iter() {
+            let (key, _) = entry?;
+            batch.delete(key);
+        }
+        self.db.db.write(batch)?;
+        Ok(())
+    }
+}
+
+impl<K: Encode, V> DiskMap<K, V> {
+    fn key(&self, key: &K) -> Result<Vec<u8>, StateStoreError> {
+        Ok(self.raw_key(&encode(key)?))
+    }
+}
+
+impl<K: Encode, V: Encode> DiskMap<K, V> {
+    pub fn insert(&mut self, key: &K, value: &V) -> Result<(), StateStoreError> {
+        let key = self.key(key)?;
+        self.insert_raw(key, value)
+    }
+}
+
+impl<K, V: Encode> DiskMap<K, V> {
+    /// Inserts with a key that is already encoded, so the map sorts by its bytes.
+    pub fn insert_bytes(&mut self, key: &[u8], value: &V) -> Result<(), StateStoreError> {
+        self.insert_raw(self.raw_key(key), value)
+    }
+
+    fn insert_raw(&mut self, key: Vec<u8>, value: &V) -> Result<(), StateStoreError> {
+        self.db.db.put(key, encode(value)?)?;
+        Ok(())
+    }
+}
+
+impl<K: Encode, V: Decode> DiskMap<K, V> {
+    pub fn get(&self, key: &K) -> Result<Option<V>, StateStoreError> {
+        self.get_raw(&self.key(key)?)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, StateStoreError> {
+        let key = self.key(key)?;
+        self.remove_raw(key)
+    }
+}
+
+impl<K, V: Decode> DiskMap<K, V> {
+    pub fn get_bytes(&self, key: &[u8]) -> Result<Option<V>, StateStoreError> {
+        self.get_raw(&self.raw_key(key))
+    }
+
+    pub fn remove_bytes(&mut self, key: &[u8]) -> Result<Option<V>, StateStoreError> {
+        self.remove_raw(self.raw_key(key))
+    }
+
+    /// Iterates over the entries from `key` in `direction`, with their keys as they were inserted with
+    /// [DiskMap::insert_bytes]. Going forward starts at the first key not less than `key`, going in
+    /// reverse at the last key not greater than it.
+    pub fn iter_bytes(
+        &self,
+        key: &[u8],
+        direction: Direction,
+    ) -> impl Iterator<Item = Result<(Vec<u8>, V), StateStoreError>> + '_ {
+        self.db
+            .db
+            .iterator(IteratorMode::From(&self.raw_key(key), direction))
+            .map(|entry| entry.map_err(StateStoreError::from))
+            .take_while(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |(key, _)| key.starts_with(&self.prefix))
+            })
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((key[self.prefix.len()..].to_vec(), decode(&value)?))
+            })
+    }
+
+    fn get_raw(&self, key: &[u8]) -> Result<Option<V>, StateStoreError> {
+        self.db
+            .db
+            .get_pinned(key)?
+            .map(|value| decode(&value))
+            .transpose()
+    }
+
+    fn remove_raw(&mut self, key: Vec<u8>) -> Result<Option<V>, StateStoreError> {
+        let value = self
+            .db
+            .db
+            .get_pinned(&key)?
+            .map(|value| decode(&value))
+            .transpose()?;
+        if value.is_some() {
+            self.db.db.delete(key)?;
+        }
+        Ok(value)
+    }
+}
+
+impl<K: Decode, V: Decode> DiskMap<K, V> {
+    pub fn iter(&self) -> impl Iterator<Item = Result<(K, V), StateStoreError>> + '_ {
+        self.raw_iter().map(|entry| {
+            let (key, value) = entry?;
+            Ok((decode(&key[self.prefix.len()..])?, decode(&value)?))
+        })
+    }
+}
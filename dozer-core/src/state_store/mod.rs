@@ -0,0 +1,451 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug, Formatter},
+    hash::Hash,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use dozer_types::bincode::{self, enc::Encoder, error::EncodeError, Decode, Encode};
+use dozer_types::node::NodeHandle;
+use rocksdb::{Cache, Direction};
+
+use crate::errors::StateStoreError;
+
+use self::disk::{DiskMap, StateDb};
+
+mod disk;
+mod sort_key;
+
+pub use sort_key::SortKey;
+
+/// Where stateful operators (joins, aggregations, primary key lookups) keep their state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StateStoreOptions {
+    /// Keep all state in memory.
+    #[default]
+    InMemory,
+    /// Keep state in embedded RocksDB databases under `path`, caching up to `cache_size` bytes of it in memory.
+    ///
+    /// Every processor keeps its state in a database of its own, which is checkpointed with the processor
+    /// and restored from that checkpoint.
+    OnDisk { path: PathBuf, cache_size: usize },
+}
+
+/// Creates the maps operators keep their state in. Cloning a store shares the underlying database.
+#[derive(Debug, Clone, Default)]
+pub struct StateStore {
+    disk: Option<DiskStore>,
+}
+
+/// A database and the directory it's kept in, along with its checkpoints.
+#[derive(Clone)]
+struct DiskStore {
+    dir: PathBuf,
+    cache: Cache,
+    db: Arc<StateDb>,
+}
+
+impl Debug for DiskStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskStore")
+            .field("dir", &self.dir)
+            .field("db", &self.db)
+            .finish()
+    }
+}
+
+impl StateStore {
+    /// Opens the store shared by the nodes that aren't checkpointed. Its database starts empty.
+    pub fn open(options: &StateStoreOptions) -> Result<Self, StateStoreError> {
+        match options {
+            StateStoreOptions::InMemory => Ok(Self::default()),
+            StateStoreOptions::OnDisk { path, cache_size } => {
+                let cache = Cache::new_lru_cache(*cache_size);
+                let db = StateDb::open(&path.join("shared"), &cache, None)?;
+                Ok(Self {
+                    disk: Some(DiskStore {
+                        dir: path.clone(),
+                        cache,
+                        db: Arc::new(db),
+                    }),
+                })
+            }
+        }
+    }
+
+    /// Opens the store of processor `node_handle`, with the contents of its checkpoint `checkpoint`,
+    /// or empty if there's none. It shares the cache of this store, but has a database of its own.
+    pub fn for_node(
+        &self,
+        node_handle: &NodeHandle,
+        checkpoint: Option<u64>,
+    ) -> Result<Self, StateStoreError> {
+        let Some(disk) = &self.disk else {
+            return Ok(Self::default());
+        };
+        let dir = disk.dir.join("nodes").join(node_handle.to_string());
+        let checkpoint = checkpoint.map(|seq| checkpoint_path(&dir, seq));
+        let db = StateDb::open(&dir.join("db"), &disk.cache, checkpoint.as_deref())?;
+        Ok(Self {
+            disk: Some(DiskStore {
+                dir,
+                cache: disk.cache.clone(),
+                db: Arc::new(db),
+            }),
+        })
+    }
+
+    pub fn is_on_disk(&self) -> bool {
+        self.disk.is_some()
+    }
+
+    /// Checkpoints the database as checkpoint `seq`, replacing what a failed checkpoint may have left behind.
+    /// Returns `None` if the state is in memory, as it's then encoded by the maps.
+    pub fn checkpoint(&self, seq: u64) -> Result<Option<u64>, StateStoreError> {
+        let Some(disk) = &self.disk else {
+            return Ok(None);
+        };
+        let path = checkpoint_path(&disk.dir, seq);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)
+                .map_err(|e| StateStoreError::FileSystem(path.clone(), e))?;
+        }
+        disk.db.checkpoint(&path)?;
+        Ok(Some(seq))
+    }
+
+    /// Removes the database checkpoints before checkpoint `seq`.
+    pub fn remove_checkpoints_before(&self, seq: u64) -> Result<(), StateStoreError> {
+        let Some(disk) = &self.disk else {
+            return Ok(());
+        };
+        let dir = disk.dir.join("checkpoints");
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(StateStoreError::FileSystem(dir, e)),
+        };
+        for entry in entries {
+            let path = entry
+                .map_err(|e| StateStoreError::FileSystem(dir.clone(), e))?
+                .path();
+            let outdated = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<u64>().ok())
+                .is_some_and(|checkpoint| checkpoint < seq);
+            if outdated {
+                std::fs::remove_dir_all(&path).map_err(|e| StateStoreError::FileSystem(path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates an empty map. Maps of a disk backed store are told apart by the order they're created in,
+    /// so a processor restored from a database checkpoint finds its entries by creating its maps in the same order.
+    pub fn create_map<K, V>(&self) -> StateMap<K, V> {
+        let inner = match &self.disk {
+            Some(disk) => StateMapInner::OnDisk(DiskMap::new(disk.db.clone())),
+            None => StateMapInner::InMemory(HashMap::new()),
+        };
+        StateMap { inner }
+    }
+
+    /// Creates a map holding `entries`, for restoring a map that was encoded in a checkpoint.
+    pub fn create_map_from<K: Hash + Eq + Encode, V: Encode>(
+        &self,
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<StateMap<K, V>, StateStoreError> {
+        let mut map = self.create_map();
+        for (key, value) in entries {
+            map.insert(key, value)?;
+        }
+        Ok(map)
+    }
+
+    /// Creates an empty sorted map, told apart from other maps like in [StateStore::create_map].
+    pub fn create_sorted_map<V>(&self) -> SortedStateMap<V> {
+        let inner = match &self.disk {
+            Some(disk) => SortedStateMapInner::OnDisk(DiskMap::new(disk.db.clone())),
+            None => SortedStateMapInner::InMemory(BTreeMap::new()),
+        };
+        SortedStateMap { inner }
+    }
+
+    /// Creates a sorted map holding `entries`, for restoring a map that was encoded in a checkpoint.
+    pub fn create_sorted_map_from<V: Encode>(
+        &self,
+        entries: impl IntoIterator<Item = (SortKey, V)>,
+    ) -> Result<SortedStateMap<V>, StateStoreError> {
+        let mut map = self.create_sorted_map();
+        for (key, value) in entries {
+            map.insert(key, value)?;
+        }
+        Ok(map)
+    }
+}
+
+/// A map whose entries live either in memory or in the [StateStore]'s database.
+///
+/// Values are returned by [Cow] because disk backed maps decode them on every read.
+/// Disk backed maps compare keys by their encoding, which agrees with `Eq` for the key types operators use.
+///
+/// An in-memory map encodes the same way as a `HashMap`, so it can be decoded as a `Vec<(K, V)>`
+/// and restored with [StateStore::create_map_from]. A disk backed map encodes as an empty map,
+/// as it's restored with the database checkpoint of its store.
+#[derive(Debug)]
+pub struct StateMap<K, V> {
+    inner: StateMapInner<K, V>,
+}
+
+#[derive(Debug)]
+enum StateMapInner<K, V> {
+    InMemory(HashMap<K, V>),
+    OnDisk(DiskMap<K, V>),
+}
+
+impl<K: Hash + Eq + Encode, V: Encode> StateMap<K, V> {
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), StateStoreError> {
+        match &mut self.inner {
+            StateMapInner::InMemory(map) => {
+                map.insert(key, value);
+                Ok(())
+            }
+            StateMapInner::OnDisk(map) => map.insert(&key, &value),
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<(), StateStoreError> {
+        match &mut self.inner {
+            StateMapInner::InMemory(map) => {
+                map.clear();
+                Ok(())
+            }
+            StateMapInner::OnDisk(map) => map.clear(),
+        }
+    }
+
+    /// Returns the number of entries. Disk backed maps count them by scanning.
+    pub fn count(&self) -> Result<usize, StateStoreError> {
+        match &self.inner {
+            StateMapInner::InMemory(map) => Ok(map.len()),
+            StateMapInner::OnDisk(map) => map.count(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Encode, V: Decode> StateMap<K, V> {
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, StateStoreError> {
+        match &mut self.inner {
+            StateMapInner::InMemory(map) => Ok(map.remove(key)),
+            StateMapInner::OnDisk(map) => map.remove(key),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Encode, V: Encode + Decode> StateMap<K, V> {
+    /// Calls `f` with the value of `key`, or `None` if it's absent. The key is removed if `f` leaves `None`.
+    pub fn update<R>(
+        &mut self,
+        key: K,
+        f: impl FnOnce(&mut Option<V>) -> R,
+    ) -> Result<R, StateStoreError> {
+        match &mut self.inner {
+            StateMapInner::InMemory(map) => {
+                let mut value = map.remove(&key);
+                let result = f(&mut value);
+                if let Some(value) = value {
+                    map.insert(key, value);
+                }
+                Ok(result)
+            }
+            StateMapInner::OnDisk(map) => {
+                let mut value = map.get(&key)?;
+                let existed = value.is_some();
+                let result = f(&mut value);
+                match value {
+                    Some(value) => map.insert(&key, &value)?,
+                    None if existed => {
+                        map.remove(&key)?;
+                    }
+                    None => (),
+                }
+                Ok(result)
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq + Encode, V: Clone + Decode> StateMap<K, V> {
+    pub fn get(&self, key: &K) -> Result<Option<Cow<'_, V>>, StateStoreError> {
+        match &self.inner {
+            StateMapInner::InMemory(map) => Ok(map.get(key).map(Cow::Borrowed)),
+            StateMapInner::OnDisk(map) => Ok(map.get(key)?.map(Cow::Owned)),
+        }
+    }
+}
+
+impl<K: Clone + Decode, V: Clone + Decode> StateMap<K, V> {
+    /// Returns all entries, in no particular order.
+    pub fn entries(&self) -> Result<Vec<(K, V)>, StateStoreError> {
+        match &self.inner {
+            StateMapInner::InMemory(map) => Ok(map
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()),
+            StateMapInner::OnDisk(map) => map.iter().collect(),
+        }
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for StateMap<K, V> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        match &self.inner {
+            StateMapInner::InMemory(map) => map.encode(encoder),
+            // The entries are in the database checkpoint taken along.
+            StateMapInner::OnDisk(_) => 0u64.encode(encoder),
+        }
+    }
+}
+
+/// A map like [StateMap] that keeps its entries sorted by [SortKey], for operators that need ordered state.
+///
+/// An in-memory map encodes the same way as a `BTreeMap`, so it can be decoded as a `Vec<(SortKey, V)>`
+/// and restored with [StateStore::create_sorted_map_from]. A disk backed map encodes as an empty map,
+/// like [StateMap].
+#[derive(Debug)]
+pub struct SortedStateMap<V> {
+    inner: SortedStateMapInner<V>,
+}
+
+#[derive(Debug)]
+enum SortedStateMapInner<V> {
+    InMemory(BTreeMap<SortKey, V>),
+    OnDisk(DiskMap<SortKey, V>),
+}
+
+/// Iterates over the entries of a [SortedStateMap].
+pub type SortedStateMapIter<'a, V> =
+    Box<dyn Iterator<Item = Result<(SortKey, V), StateStoreError>> + 'a>;
+
+impl<V: Encode> SortedStateMap<V> {
+    pub fn insert(&mut self, key: SortKey, value: V) -> Result<(), StateStoreError> {
+        match &mut self.inner {
+            SortedStateMapInner::InMemory(map) => {
+                map.insert(key, value);
+                Ok(())
+            }
+            SortedStateMapInner::OnDisk(map) => map.insert_bytes(key.as_bytes(), &value),
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<(), StateStoreError> {
+        match &mut self.inner {
+            SortedStateMapInner::InMemory(map) => {
+                map.clear();
+                Ok(())
+            }
+            SortedStateMapInner::OnDisk(map) => map.clear(),
+        }
+    }
+
+    /// Returns the number of entries. Disk backed maps count them by scanning.
+    pub fn count(&self) -> Result<usize, StateStoreError> {
+        match &self.inner {
+            SortedStateMapInner::InMemory(map) => Ok(map.len()),
+            SortedStateMapInner::OnDisk(map) => map.count(),
+        }
+    }
+}
+
+impl<V: Decode> SortedStateMap<V> {
+    pub fn remove(&mut self, key: &SortKey) -> Result<Option<V>, StateStoreError> {
+        match &mut self.inner {
+            SortedStateMapInner::InMemory(map) => Ok(map.remove(key)),
+            SortedStateMapInner::OnDisk(map) => map.remove_bytes(key.as_bytes()),
+        }
+    }
+}
+
+impl<V: Clone + Decode> SortedStateMap<V> {
+    pub fn get(&self, key: &SortKey) -> Result<Option<Cow<'_, V>>, StateStoreError> {
+        match &self.inner {
+            SortedStateMapInner::InMemory(map) => Ok(map.get(key).map(Cow::Borrowed)),
+            SortedStateMapInner::OnDisk(map) => Ok(map.get_bytes(key.as_bytes())?.map(Cow::Owned)),
+        }
+    }
+
+    /// Iterates in ascending order over the entries whose keys are not less than `start`.
+    pub fn iter_from(&self, start: &SortKey) -> SortedStateMapIter<'_, V> {
+        match &self.inner {
+            SortedStateMapInner::InMemory(map) => Box::new(
+                map.range(start..)
+                    .map(|(key, value)| Ok((key.clone(), value.clone()))),
+            ),
+            SortedStateMapInner::OnDisk(map) => Box::new(
+                map.iter_bytes(start.as_bytes(), Direction::Forward)
+                    .map(|entry| entry.map(|(key, value)| (SortKey::from_bytes(key), value))),
+            ),
+        }
+    }
+
+    /// Iterates in descending order over the entries whose keys are less than `end`.
+    pub fn iter_rev_before(&self, end: &SortKey) -> SortedStateMapIter<'_, V> {
+        match &self.inner {
+            SortedStateMapInner::InMemory(map) => Box::new(
+                map.range((Bound::Unbounded, Bound::Excluded(end)))
+                    .rev()
+                    .map(|(key, value)| Ok((key.clone(), value.clone()))),
+            ),
+            SortedStateMapInner::OnDisk(map) => {
+                // The iterator outlives `end`.
+                let end = end.clone();
+                Box::new(
+                    map.iter_bytes(end.as_bytes(), Direction::Reverse)
+                        .filter(move |entry| {
+                            entry
+                                .as_ref()
+                                .map_or(true, |(key, _)| key.as_slice() != end.as_bytes())
+                        })
+                        .map(|entry| entry.map(|(key, value)| (SortKey::from_bytes(key), value))),
+                )
+            }
+        }
+    }
+
+    /// Iterates in ascending order over the entries whose keys start with `prefix`.
+    pub fn iter_prefix<'a>(&'a self, prefix: &'a SortKey) -> SortedStateMapIter<'a, V> {
+        Box::new(self.iter_from(prefix).take_while(|entry| {
+            entry
+                .as_ref()
+                .map_or(true, |(key, _)| key.starts_with(prefix))
+        }))
+    }
+}
+
+impl<V: Encode> Encode for SortedStateMap<V> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        match &self.inner {
+            SortedStateMapInner::InMemory(map) => map.encode(encoder),
+            // The entries are in the database checkpoint taken along.
+            SortedStateMapInner::OnDisk(_) => 0u64.encode(encoder),
+        }
+    }
+}
+
+fn checkpoint_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join("checkpoints").join(format!("{seq:020}"))
+}
+
+fn encode<T: Encode>(value: &T) -> Result<Vec<u8>, StateStoreError> {
+    bincode::encode_to_vec(value, bincode::config::legacy()).map_err(StateStoreError::Encode)
+}
+
+fn decode<T: Decode>(data: &[u8]) -> Result<T, StateStoreError> {
+    Ok(bincode::decode_from_slice(data, bincode::config::legacy())
+        .map_err(StateStoreError::Decode)?
+        .0)
+}
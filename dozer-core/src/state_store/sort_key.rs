@@ -0,0 +1,202 @@
+use dozer_types::bincode;
+use dozer_types::chrono::Datelike;
+use dozer_types::json_types::{DestructuredJsonRef, JsonValue};
+use dozer_types::ordered_float::OrderedFloat;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::types::Field;
+
+const NULL_FIRST: u8 = 0x00;
+const NULL_LAST: u8 = 0xFF;
+/// Ends strings, arrays and objects, so no encoding is a prefix of another.
+const END: u8 = 0x00;
+/// Precedes every element of an array or entry of an object, so it sorts after [END].
+const NEXT: u8 = 0x01;
+/// The largest scale of a [Decimal].
+const DECIMAL_MAX_SCALE: u32 = 28;
+
+/// A key of a [SortedStateMap](super::SortedStateMap), built from fields so that keys sort by their bytes
+/// in the order of the fields.
+///
+/// Every field is encoded so that no encoding is a prefix of another, so keys built from the same leading
+/// fields share a byte prefix, and [SortKey::starts_with] selects them.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(crate = "dozer_types::serde")]
+pub struct SortKey(Vec<u8>);
+
+impl SortKey {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn starts_with(&self, prefix: &SortKey) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// Returns the smallest key that sorts after every key starting with this one.
+    pub fn prefix_end(&self) -> SortKey {
+        let mut bytes = self.0.clone();
+        while let Some(last) = bytes.pop() {
+            if last != u8::MAX {
+                bytes.push(last + 1);
+                return SortKey(bytes);
+            }
+        }
+        // Only keys of `0xFF` bytes have no end, and encoded fields never are.
+        SortKey(vec![u8::MAX; self.0.len() + 1])
+    }
+
+    /// Appends `field` in the order of [Field]'s `Ord`, where nulls come last.
+    pub fn push(&mut self, field: &Field) {
+        self.push_ordered(field, false, false);
+    }
+
+    /// Appends `field`, in descending order if `descending`, and with nulls before all values if `nulls_first`.
+    pub fn push_ordered(&mut self, field: &Field, descending: bool, nulls_first: bool) {
+        let Some(typ) = field.ty() else {
+            self.0
+                .push(if nulls_first { NULL_FIRST } else { NULL_LAST });
+            return;
+        };
+        let start = self.0.len();
+        // Values of different types sort by type, like they do in `Field`'s `Ord`.
+        self.0.push(typ as u8 + 1);
+        self.push_value(field);
+        if descending {
+            for byte in &mut self.0[start..] {
+                *byte = !*byte;
+            }
+        }
+    }
+
+    fn push_value(&mut self, field: &Field) {
+        match field {
+            Field::UInt(value) => self.0.extend(value.to_be_bytes()),
+            Field::U128(value) => self.0.extend(value.to_be_bytes()),
+            Field::Int(value) => self.push_i64(*value),
+            // `Int8`s have the type of `Int`s.
+            Field::Int8(value) => self.push_i64((*value).into()),
+            Field::I128(value) => self.push_i128(*value),
+            Field::Float(value) => self.push_float(*value),
+            Field::Boolean(value) => self.0.push(*value as u8),
+            Field::String(value) | Field::Text(value) => self.push_bytes(value.as_bytes()),
+            Field::Binary(value) => self.push_bytes(value),
+            Field::Decimal(value) => self.push_decimal(*value),
+            Field::Timestamp(value) => {
+                self.push_i64(value.timestamp());
+                self.0.extend(value.timestamp_subsec_nanos().to_be_bytes());
+            }
+            Field::Date(value) => self.push_i64(value.num_days_from_ce().into()),
+            Field::Json(value) => self.push_json(value),
+            Field::Point(value) => {
+                self.push_float(value.0.x());
+                self.push_float(value.0.y());
+            }
+            Field::Duration(value) => {
+                self.0.extend(value.0.as_secs().to_be_bytes());
+                self.0.extend(value.0.subsec_nanos().to_be_bytes());
+            }
+            Field::Null => unreachable!("nulls have no type"),
+        }
+    }
+
+    fn push_i64(&mut self, value: i64) {
+        self.0.extend(((value as u64) ^ (1 << 63)).to_be_bytes());
+    }
+
+    fn push_i128(&mut self, value: i128) {
+        self.0.extend(((value as u128) ^ (1 << 127)).to_be_bytes());
+    }
+
+    fn push_float(&mut self, value: OrderedFloat<f64>) {
+        // `OrderedFloat` has one NaN, greater than all numbers, and one zero.
+        let value = if value.is_nan() {
+            f64::NAN
+        } else if value.0 == 0.0 {
+            0.0
+        } else {
+            value.0
+        };
+        let bits = value.to_bits();
+        let bits = if bits >> 63 == 1 {
+            !bits
+        } else {
+            bits | (1 << 63)
+        };
+        self.0.extend(bits.to_be_bytes());
+    }
+
+    /// Escapes `0x00` as `0x00 0xFF`, and ends with `0x00 0x00`, so shorter strings sort first.
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0.push(*byte);
+            if *byte == 0x00 {
+                self.0.push(0xFF);
+            }
+        }
+        self.0.extend([0x00, 0x00]);
+    }
+
+    /// Splits the decimal into its integral part and its fraction scaled to the maximum scale,
+    /// which both fit in an `i128` and have the sign of the decimal.
+    fn push_decimal(&mut self, value: Decimal) {
+        let mut integral = value.trunc();
+        integral.rescale(0);
+        let mut fraction = value.fract();
+        fraction.rescale(DECIMAL_MAX_SCALE);
+        self.push_i128(integral.mantissa());
+        self.push_i128(fraction.mantissa());
+    }
+
+    /// Follows the order of JSON values in `Field`'s `Ord`: by type, then by value.
+    /// Numbers are compared as floats.
+    fn push_json(&mut self, value: &JsonValue) {
+        self.0.push(value.type_() as u8);
+        match value.destructure_ref() {
+            DestructuredJsonRef::Null => (),
+            DestructuredJsonRef::Bool(value) => self.0.push(value as u8),
+            DestructuredJsonRef::Number(value) => {
+                self.push_float(OrderedFloat(value.to_f64_lossy()))
+            }
+            DestructuredJsonRef::String(value) => self.push_bytes(value.as_bytes()),
+            DestructuredJsonRef::Array(array) => {
+                for element in array.iter() {
+                    self.0.push(NEXT);
+                    self.push_json(element);
+                }
+                self.0.push(END);
+            }
+            DestructuredJsonRef::Object(object) => {
+                let mut entries = object.iter().collect::<Vec<_>>();
+                entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+                for (key, value) in entries {
+                    self.0.push(NEXT);
+                    self.push_bytes(key.as_bytes());
+                    self.push_json(value);
+                }
+                self.0.push(END);
+            }
+        }
+    }
+}
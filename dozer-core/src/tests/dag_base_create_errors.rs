@@ -2,6 +2,7 @@ use crate::event::EventHub;
 use crate::node::{
     OutputPortDef, OutputPortType, PortHandle, Processor, ProcessorFactory, Source, SourceFactory,
 };
+use crate::state_store::StateStore;
 use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
 
 use crate::tests::dag_base_run::NoopProcessorFactory;
@@ -192,6 +193,8 @@ impl ProcessorFactory for CreateErrProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         if self.panic {
             panic!("Generated error");
@@ -3,6 +3,7 @@ use crate::epoch::Epoch;
 use crate::event::EventHub;
 use crate::executor::DagExecutor;
 use crate::node::{PortHandle, Processor, ProcessorFactory};
+use crate::state_store::StateStore;
 use crate::tests::sinks::{CountingSinkFactory, COUNTING_SINK_INPUT_PORT};
 use crate::tests::sources::{
     DualPortGeneratorSourceFactory, GeneratorSourceFactory,
@@ -54,6 +55,8 @@ impl ProcessorFactory for NoopProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         Ok(Box::new(NoopProcessor {}))
     }
@@ -202,6 +205,8 @@ impl ProcessorFactory for NoopJoinProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         Ok(Box::new(NoopJoinProcessor {}))
     }
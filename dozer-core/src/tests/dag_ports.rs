@@ -2,6 +2,7 @@ use crate::event::EventHub;
 use crate::node::{
     OutputPortDef, OutputPortType, PortHandle, Processor, ProcessorFactory, Source, SourceFactory,
 };
+use crate::state_store::StateStore;
 use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::tonic::async_trait;
@@ -83,6 +84,8 @@ impl ProcessorFactory for DynPortsProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         todo!()
     }
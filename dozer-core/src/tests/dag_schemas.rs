@@ -4,6 +4,7 @@ use crate::node::{
     OutputPortDef, OutputPortType, PortHandle, Processor, ProcessorFactory, SinkFactory, Source,
     SourceFactory,
 };
+use crate::state_store::StateStore;
 use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
 
 use dozer_types::errors::internal::BoxedError;
@@ -149,6 +150,8 @@ impl ProcessorFactory for TestJoinProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         todo!()
     }
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_types::models::app_config::ErrorPolicy;
+use dozer_types::node::{NodeHandle, OpIdentifier};
+use dozer_types::types::{Field, Operation, Record, TableOperation};
+
+use crate::dead_letter::DeadLetterDir;
+use crate::error_manager::ErrorManager;
+use crate::errors::ExecutionError;
+use crate::executor::ErrorPolicies;
+
+fn insert(id: i64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(1, id as u64)),
+        op: Operation::Insert {
+            new: Record::new(vec![Field::Int(id)]),
+        },
+        port: 0,
+    }
+}
+
+#[test]
+fn test_dead_letter_policy() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let policies = ErrorPolicies {
+        default: ErrorPolicy::Skip,
+        nodes: HashMap::from([("proc".to_string(), ErrorPolicy::DeadLetter)]),
+        dead_letter_dir: Some(temp_dir.path().to_path_buf()),
+    };
+    let error_manager = Arc::new(ErrorManager::new(Some(0), policies).unwrap());
+    let dead_letter_dir = DeadLetterDir::new(temp_dir.path().to_path_buf());
+
+    // Skipped operations are not kept.
+    let sink = error_manager.op_error_handler(&NodeHandle::new(Some(1), "sink".to_string()));
+    assert_eq!(sink.keep(&insert(1)), None);
+    sink.report(0, None, "failed".into()).unwrap();
+
+    // Partitioned instances follow the policy of their processor.
+    let instance = NodeHandle::new(Some(1), "proc--0-of-2".to_string());
+    let proc = error_manager.op_error_handler(&instance);
+    for id in [1, 2] {
+        let op = insert(id);
+        let kept_op = proc.keep(&op);
+        assert_eq!(kept_op.as_ref(), Some(&op));
+        proc.report(3, kept_op, format!("failed on {id}").into())
+            .unwrap();
+    }
+
+    let dead_letters = dead_letter_dir.list(None).unwrap();
+    assert_eq!(dead_letters.len(), 2);
+    assert_eq!(dead_letters[0].node, instance.to_string());
+    assert_eq!(dead_letters[0].error, "failed on 1");
+    assert_eq!(dead_letters[0].epoch_id, 3);
+    assert_eq!(dead_letters[0].op, insert(1));
+    assert_eq!(
+        dead_letter_dir.list(Some("1_sink")).unwrap(),
+        vec![],
+        "nothing was dead-lettered for the sink"
+    );
+
+    // Replaying moves the dead letters to the node's replay queue, until it commits them.
+    assert_eq!(dead_letter_dir.queue_replay(None).unwrap(), 2);
+    assert_eq!(dead_letter_dir.list(None).unwrap(), vec![]);
+    let replays = dead_letter_dir.replays(&instance).unwrap();
+    assert_eq!(replays, dead_letters);
+    dead_letter_dir.finish_replay(&instance).unwrap();
+    assert_eq!(dead_letter_dir.replays(&instance).unwrap(), vec![]);
+}
+
+#[test]
+fn test_dead_letter_policy_needs_dir() {
+    let policies = ErrorPolicies {
+        default: ErrorPolicy::DeadLetter,
+        ..Default::default()
+    };
+    assert!(matches!(
+        ErrorManager::new(None, policies),
+        Err(ExecutionError::MissingDeadLetterDir)
+    ));
+}
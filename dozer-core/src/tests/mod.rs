@@ -12,9 +12,16 @@ mod dag_base_errors;
 mod dag_base_run;
 mod dag_ports;
 mod dag_schemas;
+mod dead_letter;
+mod operation_log;
+mod partition;
+mod processor_checkpoint;
 pub mod processors;
+mod sink_retry;
 pub mod sinks;
 pub mod sources;
+mod state_store;
+mod two_phase_commit;
 
 fn create_test_runtime() -> Arc<Runtime> {
     Arc::new(
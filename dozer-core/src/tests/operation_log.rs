@@ -0,0 +1,181 @@
+use std::io::Write;
+use std::time::Duration;
+
+use dozer_types::models::ingestion_types::{IngestionMessage, TransactionInfo};
+use dozer_types::node::{NodeHandle, OpIdentifier};
+use dozer_types::types::{Field, Operation, Record};
+
+use crate::errors::ExecutionError;
+use crate::operation_log::{OperationLog, OperationLogOptions};
+
+fn insert(value: i64) -> IngestionMessage {
+    IngestionMessage::OperationEvent {
+        table_index: 0,
+        op: Operation::Insert {
+            new: Record::new(vec![Field::Int(value)]),
+        },
+        id: None,
+    }
+}
+
+fn commit(txid: u64) -> IngestionMessage {
+    IngestionMessage::TransactionInfo(TransactionInfo::Commit {
+        id: Some(OpIdentifier::new(txid, 0)),
+        source_time: None,
+    })
+}
+
+fn read_all(log: &OperationLog, from: u64) -> Vec<(u64, IngestionMessage)> {
+    log.read_from(from)
+        .map(|entry| entry.map(|(seq, _, message)| (seq, message)).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_operation_log() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OperationLogOptions {
+        dir: temp_dir.path().to_path_buf(),
+        // Every entry gets its own segment.
+        max_segment_bytes: 1,
+        retention_bytes: u64::MAX,
+        retention: None,
+    };
+    let source = NodeHandle::new(None, "source".to_string());
+
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    // The source starts over.
+    log.clear(None).unwrap();
+    let messages = [insert(1), commit(1), insert(2), commit(2), insert(3)];
+    for (seq, message) in messages.iter().enumerate() {
+        assert_eq!(log.append(0, message).unwrap(), seq as u64);
+    }
+    log.sync().unwrap();
+    drop(log);
+
+    // A partially written entry is dropped on open.
+    let segment = temp_dir
+        .path()
+        .join("source")
+        .join(format!("{:020}.log", 4));
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&segment)
+        .unwrap()
+        .write_all(&[100, 0])
+        .unwrap();
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    assert_eq!(log.next_seq(), 5);
+    assert_eq!(
+        read_all(&log, 2),
+        vec![(2, insert(2)), (3, commit(2)), (4, insert(3))]
+    );
+
+    // Consumers replay from after the commit they caught up to, and the source resumes from the last commit.
+    assert_eq!(log.replay_start(Some(OpIdentifier::new(1, 0))).unwrap(), 2);
+    assert_eq!(log.replay_start(None).unwrap(), 0);
+    assert_eq!(log.commit_seq(OpIdentifier::new(1, 0)).unwrap(), Some(1));
+    assert_eq!(log.commit_seq(OpIdentifier::new(1, 1)).unwrap(), None);
+    assert_eq!(
+        log.last_commit().unwrap(),
+        Some((3, OpIdentifier::new(2, 0)))
+    );
+    log.truncate(4).unwrap();
+    assert_eq!(log.next_seq(), 4);
+    assert_eq!(read_all(&log, 3), vec![(3, commit(2))]);
+
+    // Once the log exceeds its retention, the oldest segments are removed.
+    drop(log);
+    let options = OperationLogOptions {
+        retention_bytes: 1,
+        ..options
+    };
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    for value in 4..8 {
+        log.append(0, &insert(value)).unwrap();
+    }
+    assert_eq!(log.first_seq(), 7);
+    assert_eq!(read_all(&log, 0), vec![(7, insert(7))]);
+    // Consumers that need the removed entries can't replay.
+    assert!(matches!(
+        log.replay_start(Some(OpIdentifier::new(1, 0))),
+        Err(ExecutionError::OperationLogIncomplete(..))
+    ));
+    assert!(matches!(
+        log.replay_start(None),
+        Err(ExecutionError::OperationLogIncomplete(..))
+    ));
+}
+
+#[test]
+fn test_operation_log_clear() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OperationLogOptions {
+        dir: temp_dir.path().to_path_buf(),
+        max_segment_bytes: u64::MAX,
+        retention_bytes: u64::MAX,
+        retention: None,
+    };
+    let source = NodeHandle::new(None, "source".to_string());
+
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    log.clear(None).unwrap();
+    for message in [insert(1), commit(1), insert(2)] {
+        log.append(0, &message).unwrap();
+    }
+    log.sync().unwrap();
+
+    // The source resumes after commit 1, so the log starts over, keeping its sequence numbers.
+    log.clear(Some(OpIdentifier::new(1, 0))).unwrap();
+    drop(log);
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    assert_eq!(log.next_seq(), 3);
+    assert_eq!(log.append(0, &insert(2)).unwrap(), 3);
+
+    // Only consumers that caught up to where the source resumed can replay.
+    assert_eq!(log.replay_start(Some(OpIdentifier::new(1, 0))).unwrap(), 3);
+    assert!(matches!(
+        log.replay_start(None),
+        Err(ExecutionError::OperationLogIncomplete(..))
+    ));
+}
+
+#[test]
+fn test_operation_log_age_retention() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OperationLogOptions {
+        dir: temp_dir.path().to_path_buf(),
+        // Every entry gets its own segment.
+        max_segment_bytes: 1,
+        retention_bytes: u64::MAX,
+        retention: None,
+    };
+    let source = NodeHandle::new(None, "source".to_string());
+
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    log.clear(None).unwrap();
+    for value in 0..3 {
+        log.append(0, &insert(value)).unwrap();
+    }
+    log.sync().unwrap();
+    drop(log);
+
+    // Segments that aged out while the log was closed are removed when it's opened.
+    std::thread::sleep(Duration::from_millis(100));
+    let options = OperationLogOptions {
+        retention: Some(Duration::from_millis(50)),
+        ..options
+    };
+    let mut log = OperationLog::open(&options, &source).unwrap();
+    assert_eq!(log.first_seq(), 2);
+
+    // And on sync, although no new segment is started.
+    for value in 3..5 {
+        log.append(0, &insert(value)).unwrap();
+    }
+    assert_eq!(log.first_seq(), 3);
+    std::thread::sleep(Duration::from_millis(100));
+    log.sync().unwrap();
+    assert_eq!(log.first_seq(), 4);
+    assert_eq!(read_all(&log, 0), vec![(4, insert(4))]);
+}
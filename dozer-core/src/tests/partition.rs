@@ -0,0 +1,270 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::node::NodeHandle;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, TableOperation,
+};
+
+use crate::app::{App, AppPipeline, PipelineEntryPoint};
+use crate::appsource::{AppSourceManager, AppSourceMappings};
+use crate::channels::ProcessorChannelForwarder;
+use crate::epoch::Epoch;
+use crate::event::EventHub;
+use crate::node::{Partitioner, PortHandle, Processor, ProcessorFactory};
+use crate::partition::{instance_port, MergeFactory};
+use crate::state_store::StateStore;
+use crate::tests::sinks::{CountingSinkFactory, COUNTING_SINK_INPUT_PORT};
+use crate::tests::sources::{GeneratorSourceFactory, GENERATOR_SOURCE_OUTPUT_PORT};
+use crate::{Edge, Endpoint, DEFAULT_PORT_HANDLE};
+
+use super::run_dag;
+
+const COUNT: u64 = 1_000;
+const PARALLELISM: usize = 4;
+
+fn key_hash(record: &Record) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    record.values[0].hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Records the keys every instance it builds receives.
+#[derive(Debug)]
+struct KeyRecordingProcessorFactory {
+    instance_keys: Arc<Mutex<Vec<HashSet<Field>>>>,
+}
+
+#[async_trait]
+impl ProcessorFactory for KeyRecordingProcessorFactory {
+    fn type_name(&self) -> String {
+        "KeyRecording".to_owned()
+    }
+
+    async fn get_output_schema(
+        &self,
+        _output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        Ok(input_schemas.get(&DEFAULT_PORT_HANDLE).unwrap().clone())
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        let mut instance_keys = self.instance_keys.lock().unwrap();
+        instance_keys.push(HashSet::new());
+        Ok(Box::new(KeyRecordingProcessor {
+            instance: instance_keys.len() - 1,
+            instance_keys: self.instance_keys.clone(),
+        }))
+    }
+
+    fn id(&self) -> String {
+        "KeyRecording".to_owned()
+    }
+
+    async fn partitioner(
+        &self,
+        _input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        Ok(Some(Box::new(FirstFieldPartitioner)))
+    }
+}
+
+#[derive(Debug)]
+struct FirstFieldPartitioner;
+
+impl Partitioner for FirstFieldPartitioner {
+    fn partition_hash(&mut self, _port: PortHandle, record: &Record) -> Result<u64, BoxedError> {
+        Ok(key_hash(record))
+    }
+}
+
+#[derive(Debug)]
+struct KeyRecordingProcessor {
+    instance: usize,
+    instance_keys: Arc<Mutex<Vec<HashSet<Field>>>>,
+}
+
+impl Processor for KeyRecordingProcessor {
+    fn commit(&self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        mut op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        if let Operation::Insert { new } = &op.op {
+            self.instance_keys.lock().unwrap()[self.instance].insert(new.values[0].clone());
+        }
+        op.port = DEFAULT_PORT_HANDLE;
+        fw.send(op);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_partitioned_processor() {
+    let latch = Arc::new(AtomicBool::new(true));
+    let instance_keys = Arc::new(Mutex::new(vec![]));
+
+    let mut asm = AppSourceManager::new();
+    asm.add(
+        Box::new(GeneratorSourceFactory::new(COUNT, latch.clone(), false)),
+        AppSourceMappings::new(
+            "generator".to_string(),
+            [("users".to_string(), GENERATOR_SOURCE_OUTPUT_PORT)]
+                .into_iter()
+                .collect(),
+        ),
+    )
+    .unwrap();
+
+    let mut pipeline = AppPipeline::new_with_default_flags();
+    pipeline.add_processor(
+        Box::new(KeyRecordingProcessorFactory {
+            instance_keys: instance_keys.clone(),
+        }),
+        "proc".to_string(),
+    );
+    pipeline.set_parallelism("proc", PARALLELISM);
+    pipeline.add_entry_point(
+        "proc".to_string(),
+        PipelineEntryPoint::new("users".to_string(), DEFAULT_PORT_HANDLE),
+    );
+    pipeline.add_sink(
+        Box::new(CountingSinkFactory::new(COUNT, latch)),
+        "sink".to_string(),
+    );
+    pipeline.connect_nodes(
+        "proc".to_string(),
+        DEFAULT_PORT_HANDLE,
+        "sink".to_string(),
+        COUNTING_SINK_INPUT_PORT,
+    );
+
+    let mut app = App::new(asm);
+    app.add_pipeline(pipeline);
+    let dag = app.into_dag().unwrap();
+
+    // The source feeds the router, and the merge feeds the sink.
+    let handle = |id: &str| NodeHandle::new(Some(1), id.to_string());
+    let edges = dag.edge_handles();
+    assert!(edges.contains(&Edge::new(
+        Endpoint::new(
+            NodeHandle::new(None, "generator".to_string()),
+            GENERATOR_SOURCE_OUTPUT_PORT
+        ),
+        Endpoint::new(handle("proc--router"), DEFAULT_PORT_HANDLE),
+    )));
+    assert!(edges.contains(&Edge::new(
+        Endpoint::new(handle("proc--merge"), DEFAULT_PORT_HANDLE),
+        Endpoint::new(handle("sink"), COUNTING_SINK_INPUT_PORT),
+    )));
+    for index in 0..PARALLELISM {
+        let instance = handle(&format!("proc--{index}-of-{PARALLELISM}"));
+        assert!(edges.contains(&Edge::new(
+            Endpoint::new(handle("proc--router"), index as PortHandle),
+            Endpoint::new(instance.clone(), DEFAULT_PORT_HANDLE),
+        )));
+        assert!(edges.contains(&Edge::new(
+            Endpoint::new(instance, DEFAULT_PORT_HANDLE),
+            Endpoint::new(handle("proc--merge"), index as PortHandle),
+        )));
+    }
+    assert_eq!(edges.len(), 2 + 2 * PARALLELISM);
+
+    run_dag(dag).unwrap();
+
+    // Every key is processed by exactly one instance, which owns all keys with the same hash.
+    let instance_keys = instance_keys.lock().unwrap();
+    assert_eq!(instance_keys.len(), PARALLELISM);
+    assert_eq!(
+        instance_keys.iter().map(HashSet::len).sum::<usize>(),
+        COUNT as usize
+    );
+    for keys in instance_keys.iter() {
+        let instances = keys
+            .iter()
+            .map(|key| key_hash(&Record::new(vec![key.clone()])) % PARALLELISM as u64)
+            .collect::<HashSet<_>>();
+        assert_eq!(instances.len(), 1);
+    }
+}
+
+#[derive(Debug, Default)]
+struct CollectingForwarder {
+    ops: Vec<Operation>,
+}
+
+impl ProcessorChannelForwarder for CollectingForwarder {
+    fn send(&mut self, op: TableOperation) {
+        self.ops.push(op.op);
+    }
+}
+
+#[test]
+fn test_merge_truncate_barrier() {
+    let factory = KeyRecordingProcessorFactory {
+        instance_keys: Default::default(),
+    };
+    let merge_factory = MergeFactory::new(&factory, 2);
+    let mut schema = Schema::default();
+    schema.field(
+        FieldDefinition::new("id".to_string(), FieldType::Int, false, Default::default()),
+        true,
+    );
+    let mut merge = futures::executor::block_on(merge_factory.build(
+        HashMap::new(),
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
+        EventHub::new(1),
+        None,
+        StateStore::default(),
+    ))
+    .unwrap();
+
+    let insert = |id: i64| Operation::Insert {
+        new: Record::new(vec![Field::Int(id)]),
+    };
+    let mut fw = CollectingForwarder::default();
+    let mut process = |instance: usize, op: Operation| {
+        merge
+            .process(
+                TableOperation::without_id(op, instance_port(instance, 0, 1)),
+                &mut fw,
+            )
+            .unwrap();
+    };
+
+    // Instance 1 still emits records it had before the truncate, instance 0 emits records after it.
+    process(0, Operation::Truncate);
+    process(0, insert(1));
+    process(1, insert(2));
+    process(1, Operation::Truncate);
+    process(1, insert(3));
+    assert_eq!(
+        fw.ops,
+        vec![insert(2), Operation::Truncate, insert(1), insert(3)]
+    );
+}
@@ -0,0 +1,363 @@
+use crate::channels::ProcessorChannelForwarder;
+use crate::checkpoint::{CheckpointDir, KEPT_CHECKPOINTS};
+use crate::epoch::Epoch;
+use crate::event::EventHub;
+use crate::executor::{DagExecutor, ExecutorOptions};
+use crate::node::{PortHandle, Processor, ProcessorFactory, Sink, SinkFactory};
+use crate::state_store::StateStore;
+use crate::tests::sources::{GeneratorSourceFactory, GENERATOR_SOURCE_OUTPUT_PORT};
+use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Schema, TableOperation};
+use futures::future::pending;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::create_test_runtime;
+
+const COUNT: u64 = 100;
+
+/// Counts the operations it forwards, and restores the count from its checkpoint.
+#[derive(Debug)]
+struct CountingProcessorFactory {
+    restored: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+#[async_trait]
+impl ProcessorFactory for CountingProcessorFactory {
+    fn type_name(&self) -> String {
+        "Counting".to_owned()
+    }
+
+    async fn get_output_schema(
+        &self,
+        _output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        Ok(input_schemas.get(&DEFAULT_PORT_HANDLE).unwrap().clone())
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        *self.restored.lock().unwrap() = checkpoint_data.clone();
+        let count = checkpoint_data.map_or(0, |data| u64::from_le_bytes(data.try_into().unwrap()));
+        Ok(Box::new(CountingProcessor { count }))
+    }
+
+    fn id(&self) -> String {
+        "Counting".to_owned()
+    }
+}
+
+#[derive(Debug)]
+struct CountingProcessor {
+    count: u64,
+}
+
+impl Processor for CountingProcessor {
+    fn commit(&self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        mut op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        self.count += 1;
+        op.port = DEFAULT_PORT_HANDLE;
+        fw.send(op);
+        Ok(())
+    }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(Some(self.count.to_le_bytes().to_vec()))
+    }
+}
+
+/// Reports the op id of the last epoch it committed.
+#[derive(Debug)]
+struct OpIdSinkFactory {
+    op_id: Arc<Mutex<Option<OpIdentifier>>>,
+    running: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl SinkFactory for OpIdSinkFactory {
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        "op_id".to_string()
+    }
+
+    fn prepare(&self, _input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        Ok(Box::new(OpIdSink {
+            op_id: self.op_id.clone(),
+            running: self.running.clone(),
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "op_id".to_string()
+    }
+}
+
+#[derive(Debug)]
+struct OpIdSink {
+    op_id: Arc<Mutex<Option<OpIdentifier>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl Sink for OpIdSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        let op_id = epoch_details
+            .common_info
+            .source_states
+            .values()
+            .find_map(SourceState::op_id)
+            .copied();
+        if op_id == Some(OpIdentifier::new(0, COUNT - 1)) {
+            self.running.store(false, Ordering::Relaxed);
+        }
+        *self.op_id.lock().unwrap() = op_id;
+        Ok(())
+    }
+
+    fn process(&mut self, _op: TableOperation) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        _id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(*self.op_id.lock().unwrap())
+    }
+}
+
+fn create_dag(
+    restored: Arc<Mutex<Option<Vec<u8>>>>,
+    op_id: Arc<Mutex<Option<OpIdentifier>>>,
+) -> Dag {
+    let running = Arc::new(AtomicBool::new(true));
+    let source_handle = NodeHandle::new(None, "source".to_string());
+    let proc_handle = NodeHandle::new(None, "proc".to_string());
+    let sink_handle = NodeHandle::new(None, "sink".to_string());
+
+    let mut dag = Dag::new();
+    dag.add_source(
+        source_handle.clone(),
+        Box::new(GeneratorSourceFactory::new(COUNT, running.clone(), false)),
+    );
+    dag.add_processor(
+        proc_handle.clone(),
+        Box::new(CountingProcessorFactory { restored }),
+    );
+    dag.add_sink(
+        sink_handle.clone(),
+        Box::new(OpIdSinkFactory { op_id, running }),
+    );
+    dag.connect(
+        Endpoint::new(source_handle, GENERATOR_SOURCE_OUTPUT_PORT),
+        Endpoint::new(proc_handle.clone(), DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+    dag.connect(
+        Endpoint::new(proc_handle, DEFAULT_PORT_HANDLE),
+        Endpoint::new(sink_handle, DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+    dag
+}
+
+fn checkpoint_options(checkpoint_dir: PathBuf) -> ExecutorOptions {
+    ExecutorOptions {
+        checkpoint_dir: Some(checkpoint_dir),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_processor_restored_from_checkpoint() {
+    let checkpoint_dir =
+        std::env::temp_dir().join(format!("dozer-checkpoint-{}", uuid::Uuid::new_v4()));
+    let runtime = create_test_runtime();
+    let op_id = Arc::new(Mutex::new(None));
+
+    // Run the pipeline to the end, checkpointing the processor on every epoch.
+    let dag = create_dag(Arc::new(Mutex::new(None)), op_id.clone());
+    let runtime_clone = runtime.clone();
+    let options = checkpoint_options(checkpoint_dir.clone());
+    runtime
+        .block_on(async move {
+            DagExecutor::new(dag, options)
+                .await?
+                .start(pending::<()>(), Default::default(), runtime_clone)
+                .await
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let last_op_id = OpIdentifier::new(0, COUNT - 1);
+    assert_eq!(*op_id.lock().unwrap(), Some(last_op_id));
+    let proc_handle = NodeHandle::new(None, "proc".to_string());
+    let source_handle = NodeHandle::new(None, "source".to_string());
+    let checkpoint = CheckpointDir::new(checkpoint_dir.clone())
+        .find(&proc_handle, |_| true)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        checkpoint.source_states,
+        HashMap::from([(source_handle.clone(), SourceState::Restartable(last_op_id))])
+    );
+    assert_eq!(checkpoint.state, COUNT.to_le_bytes());
+
+    // The sink has caught up with the checkpoint, so the processor is restored.
+    let restored = Arc::new(Mutex::new(None));
+    let dag = create_dag(restored.clone(), op_id.clone());
+    runtime
+        .block_on(DagExecutor::new(
+            dag,
+            checkpoint_options(checkpoint_dir.clone()),
+        ))
+        .unwrap();
+    assert_eq!(
+        restored.lock().unwrap().as_deref(),
+        Some(COUNT.to_le_bytes().as_slice())
+    );
+
+    // The sink is behind the newest checkpoint, so the older one it has caught up with is restored.
+    let mut kept = vec![];
+    CheckpointDir::new(checkpoint_dir.clone())
+        .find(&proc_handle, |checkpoint| {
+            kept.push(checkpoint.clone());
+            false
+        })
+        .unwrap();
+    let oldest = kept.last().unwrap();
+    let SourceState::Restartable(oldest_op_id) = oldest.source_states[&source_handle] else {
+        panic!("source isn't restartable");
+    };
+    *op_id.lock().unwrap() = Some(oldest_op_id);
+    let restored = Arc::new(Mutex::new(None));
+    let dag = create_dag(restored.clone(), op_id.clone());
+    runtime
+        .block_on(DagExecutor::new(
+            dag,
+            checkpoint_options(checkpoint_dir.clone()),
+        ))
+        .unwrap();
+    assert_eq!(
+        restored.lock().unwrap().as_deref(),
+        Some(oldest.state.as_slice())
+    );
+
+    // No kept checkpoint was taken where the source resumes from, so the processor starts empty.
+    let unmatched_op_id = (0..COUNT)
+        .rev()
+        .map(|seq| OpIdentifier::new(0, seq))
+        .find(|op_id| {
+            kept.iter().all(|checkpoint| {
+                checkpoint.source_states[&source_handle] != SourceState::Restartable(*op_id)
+            })
+        })
+        .unwrap();
+    *op_id.lock().unwrap() = Some(unmatched_op_id);
+    let restored = Arc::new(Mutex::new(None));
+    let dag = create_dag(restored.clone(), op_id);
+    runtime
+        .block_on(DagExecutor::new(
+            dag,
+            checkpoint_options(checkpoint_dir.clone()),
+        ))
+        .unwrap();
+    assert_eq!(*restored.lock().unwrap(), None);
+
+    std::fs::remove_dir_all(checkpoint_dir).unwrap();
+}
+
+#[test]
+fn test_outdated_checkpoints_removed() {
+    let dir = std::env::temp_dir().join(format!("dozer-checkpoint-{}", uuid::Uuid::new_v4()));
+    let checkpoint_dir = CheckpointDir::new(dir.clone());
+    let handle = NodeHandle::new(None, "proc".to_string());
+    let count = KEPT_CHECKPOINTS as u64 + 4;
+    for seq in 0..count {
+        checkpoint_dir
+            .write(
+                &handle,
+                Default::default(),
+                seq.to_le_bytes().to_vec(),
+                &StateStore::default(),
+            )
+            .unwrap();
+    }
+
+    let mut kept = vec![];
+    checkpoint_dir
+        .find(&handle, |checkpoint| {
+            kept.push(u64::from_le_bytes(
+                checkpoint.state.clone().try_into().unwrap(),
+            ));
+            false
+        })
+        .unwrap();
+    assert_eq!(
+        kept,
+        (count - KEPT_CHECKPOINTS as u64..count)
+            .rev()
+            .collect::<Vec<_>>()
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -5,6 +5,7 @@ use dozer_types::{errors::internal::BoxedError, tonic::async_trait, types::Schem
 use crate::{
     event::EventHub,
     node::{PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 
@@ -39,6 +40,8 @@ impl ProcessorFactory for ConnectivityTestProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         unimplemented!(
             "This struct is for connectivity test, only input and output ports are defined"
@@ -78,6 +81,8 @@ impl ProcessorFactory for NoInputPortProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         unimplemented!(
             "This struct is for connectivity test, only input and output ports are defined"
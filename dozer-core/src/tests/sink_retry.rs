@@ -0,0 +1,237 @@
+use crate::epoch::Epoch;
+use crate::event::EventHub;
+use crate::executor::{DagExecutor, ErrorPolicies, ExecutorOptions, SinkRetryOptions};
+use crate::node::{PortHandle, Sink, SinkFactory};
+use crate::tests::sources::{GeneratorSourceFactory, GENERATOR_SOURCE_OUTPUT_PORT};
+use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::models::app_config::ErrorPolicy;
+use dozer_types::node::{NodeHandle, OpIdentifier};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Schema, TableOperation};
+use futures::future::pending;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::create_test_runtime;
+
+const COUNT: u64 = 20;
+
+/// Fails the first `failures` calls to `process`, and records the operations it processes.
+#[derive(Debug)]
+struct FlakySinkFactory {
+    failures: u32,
+    retryable: bool,
+    op_ids: Arc<Mutex<Vec<OpIdentifier>>>,
+    running: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl SinkFactory for FlakySinkFactory {
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        "flaky".to_string()
+    }
+
+    fn prepare(&self, _input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        Ok(Box::new(FlakySink {
+            failures: self.failures,
+            retryable: self.retryable,
+            op_ids: self.op_ids.clone(),
+            running: self.running.clone(),
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "flaky".to_string()
+    }
+}
+
+#[derive(Debug)]
+struct FlakySink {
+    failures: u32,
+    retryable: bool,
+    op_ids: Arc<Mutex<Vec<OpIdentifier>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl Sink for FlakySink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err("connection reset".into());
+        }
+        let op_id = op.id.unwrap();
+        self.op_ids.lock().unwrap().push(op_id);
+        if op_id == OpIdentifier::new(0, COUNT - 1) {
+            self.running.store(false, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn is_retryable(&self, _error: &BoxedError) -> bool {
+        self.retryable
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        _id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(None)
+    }
+}
+
+/// Runs a generator into a sink that fails `failures` times, and returns the operations the sink processed.
+fn run_flaky_sink(failures: u32, retryable: bool, options: ExecutorOptions) -> Vec<OpIdentifier> {
+    let running = Arc::new(AtomicBool::new(true));
+    let op_ids = Arc::new(Mutex::new(vec![]));
+    let source_handle = NodeHandle::new(None, "source".to_string());
+    let sink_handle = NodeHandle::new(None, "sink".to_string());
+
+    let mut dag = Dag::new();
+    dag.add_source(
+        source_handle.clone(),
+        Box::new(GeneratorSourceFactory::new(COUNT, running.clone(), false)),
+    );
+    dag.add_sink(
+        sink_handle.clone(),
+        Box::new(FlakySinkFactory {
+            failures,
+            retryable,
+            op_ids: op_ids.clone(),
+            running,
+        }),
+    );
+    dag.connect(
+        Endpoint::new(source_handle, GENERATOR_SOURCE_OUTPUT_PORT),
+        Endpoint::new(sink_handle, DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+
+    let runtime = create_test_runtime();
+    let runtime_clone = runtime.clone();
+    runtime
+        .block_on(async move {
+            DagExecutor::new(dag, options)
+                .await?
+                .start(pending::<()>(), Default::default(), runtime_clone)
+                .await
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let op_ids = op_ids.lock().unwrap().clone();
+    op_ids
+}
+
+fn expected_op_ids() -> Vec<OpIdentifier> {
+    (0..COUNT).map(|n| OpIdentifier::new(0, n)).collect()
+}
+
+/// Errors that are reported bring the test down, as the error threshold is 0.
+fn retry_options(sink_retry: SinkRetryOptions) -> ExecutorOptions {
+    ExecutorOptions {
+        sink_retry,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_sink_retries_failed_calls() {
+    let sink_retry = SinkRetryOptions {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        run_flaky_sink(3, true, retry_options(sink_retry)),
+        expected_op_ids()
+    );
+}
+
+#[test]
+fn test_sink_circuit_opens_and_resumes() {
+    // The circuit opens once the retry fails, and stays open until the sink succeeds again.
+    // Operations received while the sink is paused are processed in order.
+    let sink_retry = SinkRetryOptions {
+        max_retries: 1,
+        initial_backoff: Duration::from_millis(1),
+        open_duration: Duration::from_millis(50),
+        ..Default::default()
+    };
+    assert_eq!(
+        run_flaky_sink(4, true, retry_options(sink_retry)),
+        expected_op_ids()
+    );
+}
+
+#[test]
+fn test_sink_does_not_retry_errors_that_are_not_retryable() {
+    // The failed operations are reported and dropped instead.
+    let options = ExecutorOptions {
+        error_threshold: None,
+        sink_retry: SinkRetryOptions {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(run_flaky_sink(2, false, options), expected_op_ids()[2..]);
+}
+
+#[test]
+fn test_sink_skips_failed_operations_without_retrying() {
+    let options = ExecutorOptions {
+        error_policies: ErrorPolicies {
+            default: ErrorPolicy::Skip,
+            ..Default::default()
+        },
+        sink_retry: SinkRetryOptions {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(run_flaky_sink(2, true, options), expected_op_ids()[2..]);
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use dozer_types::bincode;
+use dozer_types::node::NodeHandle;
+use dozer_types::ordered_float::OrderedFloat;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::types::Field;
+
+use crate::state_store::{SortKey, StateStore, StateStoreOptions};
+
+fn test_state_map(state_store: &StateStore) {
+    let mut map = state_store.create_map::<String, u64>();
+    let mut other_map = state_store.create_map::<String, u64>();
+
+    map.insert("a".to_string(), 1).unwrap();
+    map.insert("b".to_string(), 2).unwrap();
+    other_map.insert("a".to_string(), 10).unwrap();
+    assert_eq!(map.get(&"a".to_string()).unwrap().as_deref(), Some(&1));
+    assert_eq!(map.get(&"c".to_string()).unwrap(), None);
+    assert_eq!(
+        other_map.get(&"a".to_string()).unwrap().as_deref(),
+        Some(&10)
+    );
+
+    // Updating modifies, inserts and removes entries.
+    let old = map
+        .update("a".to_string(), |value| value.replace(3))
+        .unwrap();
+    assert_eq!(old, Some(1));
+    map.update("c".to_string(), |value| *value = Some(4))
+        .unwrap();
+    map.update("b".to_string(), |value| *value = None).unwrap();
+    assert_eq!(map.get(&"a".to_string()).unwrap().as_deref(), Some(&3));
+    assert_eq!(map.get(&"b".to_string()).unwrap(), None);
+    assert_eq!(map.get(&"c".to_string()).unwrap().as_deref(), Some(&4));
+
+    assert_eq!(map.remove(&"c".to_string()).unwrap(), Some(4));
+    assert_eq!(map.remove(&"c".to_string()).unwrap(), None);
+
+    // An in-memory map encodes like a `HashMap`, and restores from its entries.
+    if !state_store.is_on_disk() {
+        let data = bincode::encode_to_vec(&map, bincode::config::legacy()).unwrap();
+        let (entries, _): (HashMap<String, u64>, _) =
+            bincode::decode_from_slice(&data, bincode::config::legacy()).unwrap();
+        assert_eq!(entries, HashMap::from([("a".to_string(), 3)]));
+        let restored = state_store.create_map_from(entries).unwrap();
+        assert_eq!(restored.get(&"a".to_string()).unwrap().as_deref(), Some(&3));
+    }
+
+    // Clearing a map leaves other maps alone.
+    map.insert("d".to_string(), 5).unwrap();
+    let mut entries = map.entries().unwrap();
+    entries.sort();
+    assert_eq!(entries, vec![("a".to_string(), 3), ("d".to_string(), 5)]);
+    assert_eq!(map.count().unwrap(), 2);
+    map.clear().unwrap();
+    assert_eq!(map.entries().unwrap(), vec![]);
+    assert_eq!(map.count().unwrap(), 0);
+    assert_eq!(
+        other_map.get(&"a".to_string()).unwrap().as_deref(),
+        Some(&10)
+    );
+}
+
+fn key(fields: &[Field]) -> SortKey {
+    let mut key = SortKey::new();
+    for field in fields {
+        key.push(field);
+    }
+    key
+}
+
+fn test_sorted_state_map(state_store: &StateStore) {
+    let mut map = state_store.create_sorted_map::<u64>();
+    let mut other_map = state_store.create_sorted_map::<u64>();
+
+    for (partition, value) in [(1, 30), (2, 10), (1, 10), (1, 20)] {
+        map.insert(
+            key(&[Field::Int(partition), Field::Int(value)]),
+            value as u64,
+        )
+        .unwrap();
+    }
+    other_map
+        .insert(key(&[Field::Int(1), Field::Int(5)]), 5)
+        .unwrap();
+    assert_eq!(
+        map.get(&key(&[Field::Int(1), Field::Int(20)]))
+            .unwrap()
+            .as_deref(),
+        Some(&20)
+    );
+    assert_eq!(
+        map.get(&key(&[Field::Int(1), Field::Int(5)])).unwrap(),
+        None
+    );
+
+    let values = |iter: crate::state_store::SortedStateMapIter<'_, u64>| {
+        iter.map(|entry| entry.unwrap().1).collect::<Vec<_>>()
+    };
+    // Iterating by prefix stays within the partition and the map.
+    let partition = key(&[Field::Int(1)]);
+    assert_eq!(values(map.iter_prefix(&partition)), vec![10, 20, 30]);
+    assert_eq!(
+        values(map.iter_from(&key(&[Field::Int(1), Field::Int(15)]))),
+        vec![20, 30, 10]
+    );
+    assert_eq!(
+        values(map.iter_rev_before(&key(&[Field::Int(1), Field::Int(20)]))),
+        vec![10]
+    );
+    assert_eq!(
+        values(map.iter_rev_before(&partition.prefix_end())),
+        vec![30, 20, 10]
+    );
+    assert_eq!(values(map.iter_rev_before(&partition)), vec![]);
+
+    assert_eq!(
+        map.remove(&key(&[Field::Int(1), Field::Int(20)])).unwrap(),
+        Some(20)
+    );
+    assert_eq!(map.count().unwrap(), 3);
+
+    // An in-memory map encodes like a `BTreeMap`, and restores from its entries.
+    if !state_store.is_on_disk() {
+        let data = bincode::encode_to_vec(&map, bincode::config::legacy()).unwrap();
+        let (entries, _): (Vec<(SortKey, u64)>, _) =
+            bincode::decode_from_slice(&data, bincode::config::legacy()).unwrap();
+        assert_eq!(
+            entries.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+            vec![10, 30, 10]
+        );
+        let restored = state_store.create_sorted_map_from(entries).unwrap();
+        assert_eq!(values(restored.iter_prefix(&partition)), vec![10, 30]);
+    }
+
+    map.clear().unwrap();
+    assert_eq!(map.count().unwrap(), 0);
+    assert_eq!(values(other_map.iter_from(&SortKey::new())), vec![5]);
+}
+
+#[test]
+fn test_sort_key_order() {
+    let ascending = [
+        vec![
+            Field::Int(i64::MIN),
+            Field::Int(-1),
+            Field::Int(0),
+            Field::Int(300),
+        ],
+        vec![
+            Field::Float(OrderedFloat(f64::NEG_INFINITY)),
+            Field::Float(OrderedFloat(-1.5)),
+            Field::Float(OrderedFloat(0.0)),
+            Field::Float(OrderedFloat(2.0)),
+            Field::Float(OrderedFloat(f64::NAN)),
+        ],
+        vec![
+            Field::String("".to_string()),
+            Field::String("a".to_string()),
+            Field::String("a\0".to_string()),
+            Field::String("ab".to_string()),
+            Field::String("b".to_string()),
+        ],
+        vec![
+            Field::Decimal(Decimal::new(-15, 1)),
+            Field::Decimal(Decimal::new(-1, 0)),
+            Field::Decimal(Decimal::new(-5, 1)),
+            Field::Decimal(Decimal::new(1, 2)),
+            Field::Decimal(Decimal::new(11, 1)),
+            Field::Decimal(Decimal::new(2, 0)),
+        ],
+        // Values of different types sort by type, and nulls last.
+        vec![
+            Field::UInt(5),
+            Field::Int(1),
+            Field::String("a".to_string()),
+            Field::Null,
+        ],
+    ];
+    for fields in ascending {
+        for pair in fields.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+            assert!(key(&pair[..1]) < key(&pair[1..]), "{pair:?}");
+
+            // Descending order reverses values, and nulls go first if asked to.
+            let ordered = |field: &Field, nulls_first: bool| {
+                let mut key = SortKey::new();
+                key.push_ordered(field, true, nulls_first);
+                key
+            };
+            assert!(ordered(&pair[0], false) > ordered(&pair[1], false) || pair[1] == Field::Null);
+            assert!(ordered(&pair[0], true) > ordered(&pair[1], true));
+        }
+    }
+
+    // A shorter field sorts first even when followed by more fields.
+    let short = key(&[
+        Field::String("a".to_string()),
+        Field::String("z".to_string()),
+    ]);
+    let long = key(&[
+        Field::String("ab".to_string()),
+        Field::String("a".to_string()),
+    ]);
+    assert!(short < long);
+    assert!(!long.starts_with(&key(&[Field::String("a".to_string())])));
+}
+
+#[test]
+fn test_state_map_in_memory() {
+    test_state_map(&StateStore::default());
+    test_sorted_state_map(&StateStore::default());
+}
+
+#[test]
+fn test_state_map_on_disk() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = StateStoreOptions::OnDisk {
+        path: temp_dir.path().join("state"),
+        cache_size: 1024 * 1024,
+    };
+    test_state_map(&StateStore::open(&options).unwrap());
+    test_sorted_state_map(&StateStore::open(&options).unwrap());
+
+    // Opening the store again starts from empty state.
+    let state_store = StateStore::open(&options).unwrap();
+    let map = state_store.create_map::<String, u64>();
+    assert_eq!(map.get(&"a".to_string()).unwrap(), None);
+}
+
+#[test]
+fn test_node_state_store_restored_from_checkpoint() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let state_store = StateStore::open(&StateStoreOptions::OnDisk {
+        path: temp_dir.path().join("state"),
+        cache_size: 1024 * 1024,
+    })
+    .unwrap();
+    let handle = NodeHandle::new(None, "proc".to_string());
+
+    let node_state_store = state_store.for_node(&handle, None).unwrap();
+    let mut map = node_state_store.create_map::<String, u64>();
+    let mut sorted_map = node_state_store.create_sorted_map::<u64>();
+    map.insert("a".to_string(), 1).unwrap();
+    sorted_map.insert(key(&[Field::Int(1)]), 1).unwrap();
+    // Disk backed maps leave their entries to the database checkpoint.
+    let data = bincode::encode_to_vec(&map, bincode::config::legacy()).unwrap();
+    let (entries, _): (Vec<(String, u64)>, _) =
+        bincode::decode_from_slice(&data, bincode::config::legacy()).unwrap();
+    assert_eq!(entries, vec![]);
+    assert_eq!(node_state_store.checkpoint(0).unwrap(), Some(0));
+    map.insert("b".to_string(), 2).unwrap();
+    assert_eq!(node_state_store.checkpoint(1).unwrap(), Some(1));
+    drop((map, sorted_map, node_state_store));
+
+    // Maps created in the same order find the entries of the checkpoint, but not the ones after it.
+    let node_state_store = state_store.for_node(&handle, Some(0)).unwrap();
+    let map = node_state_store.create_map_from(entries).unwrap();
+    let sorted_map = node_state_store.create_sorted_map::<u64>();
+    assert_eq!(map.get(&"a".to_string()).unwrap().as_deref(), Some(&1));
+    assert_eq!(map.get(&"b".to_string()).unwrap(), None);
+    assert_eq!(
+        sorted_map.get(&key(&[Field::Int(1)])).unwrap().as_deref(),
+        Some(&1)
+    );
+    drop((map, sorted_map, node_state_store));
+
+    // Outdated checkpoints are removed, and the store starts empty without one.
+    let node_state_store = state_store.for_node(&handle, None).unwrap();
+    let map = node_state_store.create_map::<String, u64>();
+    assert_eq!(map.get(&"a".to_string()).unwrap(), None);
+    node_state_store.remove_checkpoints_before(1).unwrap();
+    drop((map, node_state_store));
+    assert!(state_store.for_node(&handle, Some(0)).is_err());
+    assert!(state_store.for_node(&handle, Some(1)).is_ok());
+}
@@ -0,0 +1,209 @@
+use crate::epoch::Epoch;
+use crate::event::EventHub;
+use crate::executor::{DagExecutor, ExecutorOptions, SinkRetryOptions};
+use crate::node::{PortHandle, Sink, SinkFactory};
+use crate::tests::sources::{GeneratorSourceFactory, GENERATOR_SOURCE_OUTPUT_PORT};
+use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::node::{NodeHandle, OpIdentifier};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Schema, TableOperation};
+use futures::future::pending;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::create_test_runtime;
+
+const COUNT: u64 = 10;
+
+/// Records the two-phase commit calls it gets, failing the first call to commit epoch `failing_commit`.
+#[derive(Debug)]
+struct TwoPhaseSinkFactory {
+    /// Epochs that are in doubt when the sink is built.
+    prepared: Vec<u64>,
+    failing_commit: Option<u64>,
+    calls: Arc<Mutex<Vec<String>>>,
+    running: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl SinkFactory for TwoPhaseSinkFactory {
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        "two_phase".to_string()
+    }
+
+    fn prepare(&self, _input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        Ok(Box::new(TwoPhaseSink {
+            prepared: self.prepared.clone(),
+            failing_commit: self.failing_commit,
+            calls: self.calls.clone(),
+            running: self.running.clone(),
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "two_phase".to_string()
+    }
+}
+
+#[derive(Debug)]
+struct TwoPhaseSink {
+    prepared: Vec<u64>,
+    failing_commit: Option<u64>,
+    calls: Arc<Mutex<Vec<String>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl Sink for TwoPhaseSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        self.calls.lock().unwrap().push("commit".to_string());
+        Ok(())
+    }
+
+    fn process(&mut self, _op: TableOperation) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn supports_two_phase_commit(&self) -> bool {
+        true
+    }
+
+    fn prepare(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        let epoch_id = epoch_details.common_info.id;
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("prepare {epoch_id}"));
+        self.prepared.push(epoch_id);
+        Ok(())
+    }
+
+    fn commit_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        if self.failing_commit == Some(epoch_id) {
+            self.failing_commit = None;
+            return Err("connection reset".into());
+        }
+        self.prepared.retain(|prepared| *prepared != epoch_id);
+        let mut calls = self.calls.lock().unwrap();
+        calls.push(format!("commit_prepared {epoch_id}"));
+        if calls.len() as u64 == 2 + 2 * COUNT {
+            self.running.store(false, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn abort_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("abort_prepared {epoch_id}"));
+        Ok(())
+    }
+
+    fn prepared_epochs(&mut self) -> Result<Vec<u64>, BoxedError> {
+        Ok(self.prepared.clone())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        _id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(None)
+    }
+}
+
+#[test]
+fn test_two_phase_commit() {
+    let running = Arc::new(AtomicBool::new(true));
+    let calls = Arc::new(Mutex::new(vec![]));
+    let source_handle = NodeHandle::new(None, "source".to_string());
+    let sink_handle = NodeHandle::new(None, "sink".to_string());
+
+    let mut dag = Dag::new();
+    dag.add_source(
+        source_handle.clone(),
+        Box::new(GeneratorSourceFactory::new(COUNT, running.clone(), false)),
+    );
+    dag.add_sink(
+        sink_handle.clone(),
+        Box::new(TwoPhaseSinkFactory {
+            prepared: vec![12, 11],
+            failing_commit: Some(3),
+            calls: calls.clone(),
+            running,
+        }),
+    );
+    dag.connect(
+        Endpoint::new(source_handle, GENERATOR_SOURCE_OUTPUT_PORT),
+        Endpoint::new(sink_handle, DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+
+    let options = ExecutorOptions {
+        sink_retry: SinkRetryOptions {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let runtime = create_test_runtime();
+    let runtime_clone = runtime.clone();
+    runtime
+        .block_on(async move {
+            DagExecutor::new(dag, options)
+                .await?
+                .start(pending::<()>(), Default::default(), runtime_clone)
+                .await
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    // Epochs left in doubt by the last run are committed first.
+    // Every epoch is then prepared and committed, and a failed commit doesn't prepare the epoch again.
+    let mut expected = vec![
+        "commit_prepared 11".to_string(),
+        "commit_prepared 12".to_string(),
+    ];
+    for epoch_id in 0..COUNT {
+        expected.push(format!("prepare {epoch_id}"));
+        expected.push(format!("commit_prepared {epoch_id}"));
+    }
+    assert_eq!(*calls.lock().unwrap(), expected);
+}
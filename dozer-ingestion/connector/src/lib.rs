@@ -142,4 +142,7 @@ pub struct TableInfo {
     pub name: String,
     /// The column names to be mapped.
     pub column_names: Vec<String>,
+    /// Whether `column_names` were configured, rather than filled with all columns of the table.
+    #[serde(default)]
+    pub explicit_columns: bool,
 }
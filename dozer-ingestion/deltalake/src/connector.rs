@@ -88,6 +88,7 @@ impl Connector for DeltaLakeConnector {
                 schema: None,
                 name: table_info.name,
                 column_names,
+                explicit_columns: false,
             })
         }
         Ok(result)
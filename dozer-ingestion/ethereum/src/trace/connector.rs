@@ -82,6 +82,7 @@ impl Connector for EthTraceConnector {
                 schema: table.schema,
                 name: table.name,
                 column_names,
+                explicit_columns: false,
             })
         }
         Ok(result)
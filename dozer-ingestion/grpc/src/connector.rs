@@ -152,6 +152,7 @@ where
                     schema: table.schema,
                     name: table.name,
                     column_names,
+                    explicit_columns: false,
                 })
             } else {
                 return Err(TableNotFound {
@@ -53,6 +53,7 @@ impl Connector for JavaScriptConnector {
             schema: None,
             name: "json_records".to_string(),
             column_names: vec!["value".to_string()],
+            explicit_columns: false,
         }])
     }
 
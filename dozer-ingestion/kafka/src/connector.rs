@@ -106,6 +106,7 @@ impl Connector for KafkaConnector {
                 schema: table.schema,
                 name: table.name,
                 column_names,
+                explicit_columns: false,
             });
         }
         Ok(result)
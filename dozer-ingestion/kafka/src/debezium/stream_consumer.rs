@@ -56,11 +56,17 @@ pub struct DebeziumSchemaParameters {
 #[serde(crate = "dozer_ingestion_connector::dozer_types::serde")]
 pub struct DebeziumSchemaStruct {
     pub r#type: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fields: Option<Vec<DebeziumSchemaStruct>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub optional: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<DebeziumSchemaParameters>,
 }
 
@@ -70,6 +76,10 @@ pub struct DebeziumPayload {
     pub before: Option<Value>,
     pub after: Option<Value>,
     pub op: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ts_ms: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
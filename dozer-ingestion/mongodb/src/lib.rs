@@ -481,6 +481,7 @@ impl Connector for MongodbConnector {
                 schema: None,
                 name: table.name,
                 column_names: vec!["data".to_owned()],
+                explicit_columns: false,
             })
             .collect())
     }
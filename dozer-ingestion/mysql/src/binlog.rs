@@ -16,7 +16,7 @@ use dozer_ingestion_connector::{
         log::{trace, warn},
         models::ingestion_types::IngestionMessage,
         types::Field,
-        types::{FieldType, Operation, Record},
+        types::{FieldType, Operation, Record, Schema},
     },
     futures::StreamExt,
     Ingestor,
@@ -322,6 +322,7 @@ impl BinlogIngestor<'_, '_, '_> {
                                             Statement::AlterTable {
                                                 name, operations, ..
                                             } => {
+                                                let mut added_columns = vec![];
                                                 if let Some(table) = table_cache
                                                     .find_table_by_object_name(&name, schema)
                                                 {
@@ -336,8 +337,12 @@ impl BinlogIngestor<'_, '_, '_> {
                                                     for operation in operations.iter() {
                                                         match operation {
                                                         AlterTableOperation::AddColumn {
+                                                            column_def,
                                                             ..
                                                         } => {
+                                                            if find_column(&column_def.name).is_none() {
+                                                                added_columns.push((table.table_index, added_column_definition(column_def)));
+                                                            }
                                                             schema_change_tracker.column_order_changed_in(table.table_index);
                                                         }
                                                         AlterTableOperation::DropColumn {
@@ -424,6 +429,9 @@ impl BinlogIngestor<'_, '_, '_> {
                                                     }
                                                     }
                                                 }
+                                                for (table_index, column) in added_columns {
+                                                    table_cache.add_column(table_index, column);
+                                                }
                                             }
                                             _ => (),
                                         }
@@ -431,6 +439,31 @@ impl BinlogIngestor<'_, '_, '_> {
                                 }
                             }
                         }
+                    } else if query.starts_with_case_insensitive(b"TRUNCATE") {
+                        let query = String::from_utf8_lossy(query);
+                        let dialect = &sqlparser::dialect::MySqlDialect {};
+                        let statements = match sqlparser::parser::Parser::parse_sql(dialect, &query)
+                        {
+                            Ok(statements) => statements,
+                            Err(err) => {
+                                warn!("Failed to parse MySQL query {query:?}: {err}");
+                                continue;
+                            }
+                        };
+                        for statement in statements {
+                            let sqlparser::ast::Statement::Truncate { table_name, .. } = statement
+                            else {
+                                continue;
+                            };
+                            let Some(table) = table_cache
+                                .find_table_by_object_name(&table_name, query_event.schema_raw())
+                            else {
+                                continue;
+                            };
+                            if !self.send_truncate(table.table_index).await {
+                                return Ok(());
+                            }
+                        }
                     }
                 }
 
@@ -465,8 +498,12 @@ impl BinlogIngestor<'_, '_, '_> {
                     let tme = self.get_tme(binlog_table_id)?;
 
                     if schema_change_tracker.unknown_schema_change_occured {
+                        let old_schemas = table_cache.schemas();
                         table_cache.refresh_full_schema(schema_helper).await?;
                         schema_change_tracker.clear();
+                        if !self.send_schema_changes(&table_cache, old_schemas).await {
+                            return Ok(());
+                        }
                     }
 
                     if let Some(table_index) = table_cache.get_corresponding_table_index(tme) {
@@ -474,11 +511,15 @@ impl BinlogIngestor<'_, '_, '_> {
                             .column_order_changed
                             .contains(&table_index)
                         {
+                            let old_schemas = table_cache.schemas();
                             let tables = &schema_change_tracker.column_order_changed;
                             table_cache
                                 .refresh_column_ordinals(schema_helper, tables)
                                 .await?;
                             schema_change_tracker.clear();
+                            if !self.send_schema_changes(&table_cache, old_schemas).await {
+                                return Ok(());
+                            }
                         }
 
                         let table = table_cache.get_table_details(table_index).unwrap();
@@ -496,6 +537,58 @@ impl BinlogIngestor<'_, '_, '_> {
         Ok(())
     }
 
+    /// Sends the schemas of the tables that changed since `old_schemas`.
+    ///
+    /// Returns `false` if the ingestion channel is closed.
+    async fn send_schema_changes(
+        &self,
+        table_cache: &TableManager<'_>,
+        old_schemas: Vec<Schema>,
+    ) -> bool {
+        for (table_index, (old_schema, schema)) in old_schemas
+            .into_iter()
+            .zip(table_cache.schemas())
+            .enumerate()
+        {
+            if old_schema != schema
+                && self
+                    .ingestor
+                    .handle_message(IngestionMessage::SchemaChanged {
+                        table_index,
+                        schema,
+                    })
+                    .await
+                    .is_err()
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Sends a truncate of the table, and commits it because `TRUNCATE` isn't part of a transaction.
+    ///
+    /// Returns `false` if the ingestion channel is closed.
+    async fn send_truncate(&self, table_index: usize) -> bool {
+        let id = Some(encode_state(&self.next_position));
+        self.ingestor
+            .handle_message(IngestionMessage::OperationEvent {
+                table_index,
+                op: Operation::Truncate,
+                id,
+            })
+            .await
+            .is_ok()
+            && self
+                .ingestor
+                .handle_message(IngestionMessage::TransactionInfo(TransactionInfo::Commit {
+                    id,
+                    source_time: None,
+                }))
+                .await
+                .is_ok()
+    }
+
     async fn handle_rows_event<'a>(
         &self,
         rows_event: &BinlogRowsEvent<'_>,
@@ -758,6 +851,31 @@ impl ColumnDefinitionsCache {
     }
 }
 
+/// The definition of a column added by `ALTER TABLE`. Its ordinal position is only known after the column ordinals are refreshed.
+fn added_column_definition(column_def: &sqlparser::ast::ColumnDef) -> ColumnDefinition {
+    use sqlparser::ast::ColumnOption;
+    let primary_key = column_def.options.iter().any(|option| {
+        matches!(
+            option.option,
+            ColumnOption::Unique {
+                is_primary: true,
+                ..
+            }
+        )
+    });
+    let not_null = column_def
+        .options
+        .iter()
+        .any(|option| matches!(option.option, ColumnOption::NotNull));
+    ColumnDefinition {
+        ordinal_position: u32::MAX,
+        name: column_def.name.value.clone(),
+        typ: get_field_type_for_sql_type(&column_def.data_type),
+        nullable: !(not_null || primary_key),
+        primary_key,
+    }
+}
+
 pub fn binlog_io_error(err: std::io::Error) -> MySQLConnectorError {
     MySQLConnectorError::BinlogReadError(mysql_async::Error::Io(mysql_async::IoError::Io(err)))
 }
@@ -922,6 +1040,15 @@ impl TableManager<'_> {
         Ok(())
     }
 
+    /// Adds a column added by `ALTER TABLE`. It's only read after the column ordinals are refreshed.
+    pub fn add_column(&mut self, table_index: usize, column: ColumnDefinition) {
+        self.tables[table_index].columns.push(column);
+    }
+
+    pub fn schemas(&self) -> Vec<Schema> {
+        self.tables.iter().map(TableDefinition::schema).collect()
+    }
+
     pub fn get_table_details(&self, table_index: usize) -> Option<TableDetails> {
         self.tables.get(table_index).map(|td| TableDetails {
             def: td,
@@ -16,7 +16,7 @@ use dozer_ingestion_connector::{
         models::ingestion_types::IngestionMessage,
         models::ingestion_types::TransactionInfo,
         node::OpIdentifier,
-        types::{FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition},
+        types::{FieldType, Operation, Record},
     },
     utils::TableNotFound,
     CdcType, Connector, Ingestor, SourceSchema, SourceSchemaResult, TableIdentifier, TableInfo,
@@ -153,37 +153,10 @@ impl Connector for MySQLConnector {
         };
 
         let schemas = table_definitions
-            .into_iter()
-            .map(|TableDefinition { columns, .. }| {
-                let primary_index = columns
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, ColumnDefinition { primary_key, .. })| *primary_key)
-                    .map(|(i, _)| i)
-                    .collect();
+            .iter()
+            .map(|table_definition| {
                 Ok(SourceSchema {
-                    schema: Schema {
-                        fields: columns
-                            .into_iter()
-                            .map(
-                                |ColumnDefinition {
-                                     name,
-                                     typ,
-                                     nullable,
-                                     ..
-                                 }| {
-                                    FieldDefinition {
-                                        name,
-                                        typ,
-                                        nullable,
-                                        source: SourceDefinition::Dynamic,
-                                        description: None,
-                                    }
-                                },
-                            )
-                            .collect(),
-                        primary_index,
-                    },
+                    schema: table_definition.schema(),
                     cdc_type,
                 })
             })
@@ -234,6 +207,7 @@ impl MySQLConnector {
                         schema: table.schema.clone(),
                         name: table.name.clone(),
                         column_names: table.column_names.clone(),
+                        explicit_columns: false,
                     })
                     .collect::<Vec<TableInfo>>()
                     .as_slice(),
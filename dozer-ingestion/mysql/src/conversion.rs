@@ -1,6 +1,6 @@
 use dozer_ingestion_connector::dozer_types::{
-    chrono::{DateTime, NaiveDate, NaiveDateTime, Offset, Utc},
-    json_types::{serde_json_to_json_value, JsonValue},
+    chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Offset, Timelike, Utc},
+    json_types::{json_to_string, serde_json_to_json_value, JsonValue},
     rust_decimal::Decimal,
     serde_json,
     types::{DozerDuration, DozerPoint, Field, FieldType, TimeUnit},
@@ -137,6 +137,95 @@ pub fn get_field_type_for_sql_type(sql_data_type: &sqlparser::ast::DataType) ->
     }
 }
 
+/// The MySQL column type a Dozer field is written to.
+///
+/// Where possible, this is the type [`get_field_type_for_mysql_column_type`] maps back to the same field type.
+pub fn get_mysql_column_type_for_field_type(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::UInt => "bigint unsigned",
+        FieldType::U128 => "decimal(39, 0) unsigned",
+        FieldType::Int => "bigint",
+        FieldType::Int8 => "tinyint",
+        FieldType::I128 => "decimal(39, 0)",
+        FieldType::Float => "double",
+        FieldType::Boolean => "boolean",
+        FieldType::String => "varchar(255)",
+        FieldType::Text => "longtext",
+        FieldType::Binary => "longblob",
+        FieldType::Decimal => "decimal(65, 30)",
+        FieldType::Timestamp => "datetime(6)",
+        FieldType::Date => "date",
+        FieldType::Json => "json",
+        FieldType::Point => "point",
+        FieldType::Duration => "time(6)",
+    }
+}
+
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+impl IntoValue for &Field {
+    fn into_value(self) -> Value {
+        match self {
+            Field::UInt(value) => Value::UInt(*value),
+            Field::U128(value) => Value::Bytes(value.to_string().into_bytes()),
+            Field::Int(value) => Value::Int(*value),
+            Field::Int8(value) => Value::Int((*value).into()),
+            Field::I128(value) => Value::Bytes(value.to_string().into_bytes()),
+            Field::Float(value) => Value::Double(value.0),
+            Field::Boolean(value) => Value::Int((*value).into()),
+            Field::String(value) | Field::Text(value) => Value::Bytes(value.as_bytes().to_vec()),
+            Field::Binary(value) => Value::Bytes(value.clone()),
+            Field::Decimal(value) => Value::Bytes(value.to_string().into_bytes()),
+            Field::Timestamp(value) => {
+                let date_time = value.naive_utc();
+                Value::Date(
+                    date_time.year() as u16,
+                    date_time.month() as u8,
+                    date_time.day() as u8,
+                    date_time.hour() as u8,
+                    date_time.minute() as u8,
+                    date_time.second() as u8,
+                    date_time.nanosecond() / 1000,
+                )
+            }
+            Field::Date(value) => Value::Date(
+                value.year() as u16,
+                value.month() as u8,
+                value.day() as u8,
+                0,
+                0,
+                0,
+                0,
+            ),
+            Field::Json(value) => Value::Bytes(json_to_string(value).into_bytes()),
+            Field::Point(value) => {
+                // SRID followed by the little endian WKB point, as read by `IntoField`.
+                let mut bytes = Vec::with_capacity(25);
+                bytes.extend_from_slice(&0u32.to_le_bytes());
+                bytes.push(1);
+                bytes.extend_from_slice(&1u32.to_le_bytes());
+                bytes.extend_from_slice(&value.0.x().0.to_le_bytes());
+                bytes.extend_from_slice(&value.0.y().0.to_le_bytes());
+                Value::Bytes(bytes)
+            }
+            Field::Duration(DozerDuration(duration, _)) => {
+                let seconds = duration.as_secs();
+                Value::Time(
+                    false,
+                    (seconds / 86400) as u32,
+                    (seconds % 86400 / 3600) as u8,
+                    (seconds % 3600 / 60) as u8,
+                    (seconds % 60) as u8,
+                    duration.subsec_micros(),
+                )
+            }
+            Field::Null => Value::NULL,
+        }
+    }
+}
+
 pub trait IntoFields<'a> {
     type Ctx: 'a;
 
@@ -433,4 +522,76 @@ mod tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn test_value_conversion_round_trip() {
+        let fields = [
+            (Field::UInt(u64::MAX), FieldType::UInt),
+            (Field::U128(1), FieldType::U128),
+            (Field::Int(-2), FieldType::Int),
+            (Field::Int8(-3), FieldType::Int8),
+            (Field::I128(-4), FieldType::I128),
+            (Field::Float(5.5.into()), FieldType::Float),
+            (Field::Boolean(true), FieldType::Boolean),
+            (Field::String("6".into()), FieldType::String),
+            (Field::Text("7".into()), FieldType::Text),
+            (Field::Binary(b"8".to_vec()), FieldType::Binary),
+            (Field::Decimal(Decimal::new(95, 1)), FieldType::Decimal),
+            (
+                Field::Timestamp(DateTime::from_naive_utc_and_offset(
+                    NaiveDateTime::new(
+                        NaiveDate::from_ymd_opt(2023, 8, 17).unwrap(),
+                        NaiveTime::from_hms_micro_opt(10, 30, 0, 12).unwrap(),
+                    ),
+                    Utc.fix(),
+                )),
+                FieldType::Timestamp,
+            ),
+            (
+                Field::Date(NaiveDate::from_ymd_opt(2023, 8, 11).unwrap()),
+                FieldType::Date,
+            ),
+            (Field::Json(12.0.into()), FieldType::Json),
+            (Field::Point((13.0, 1.5).into()), FieldType::Point),
+            (
+                Field::Duration(DozerDuration(
+                    Duration::from_micros(90_061_000_014),
+                    TimeUnit::Microseconds,
+                )),
+                FieldType::Duration,
+            ),
+            (Field::Null, FieldType::Int),
+        ];
+
+        for (field, field_type) in fields {
+            assert_eq!(
+                field,
+                (&field).into_value().into_field(&field_type).unwrap(),
+                "{field_type:?} did not round trip"
+            );
+        }
+    }
+
+    #[test]
+    fn test_column_type_round_trip() {
+        for field_type in [
+            FieldType::UInt,
+            FieldType::Int,
+            FieldType::Float,
+            FieldType::Text,
+            FieldType::Binary,
+            FieldType::Timestamp,
+            FieldType::Date,
+            FieldType::Json,
+            FieldType::Point,
+            FieldType::Duration,
+        ] {
+            let column_type = get_mysql_column_type_for_field_type(field_type);
+            assert_eq!(
+                get_field_type_for_mysql_column_type(column_type).unwrap(),
+                field_type,
+                "{column_type} does not map back to {field_type:?}"
+            );
+        }
+    }
 }
@@ -8,7 +8,7 @@ use geozero::error::GeozeroError;
 mod binlog;
 mod connection;
 pub mod connector;
-mod conversion;
+pub mod conversion;
 pub(crate) mod helpers;
 mod schema;
 mod state;
@@ -4,7 +4,10 @@ use super::{
     connection::{Conn, QueryResult},
     conversion::get_field_type_for_mysql_column_type,
 };
-use dozer_ingestion_connector::{dozer_types::types::FieldType, TableIdentifier, TableInfo};
+use dozer_ingestion_connector::{
+    dozer_types::types::{FieldDefinition, FieldType, Schema, SourceDefinition},
+    TableIdentifier, TableInfo,
+};
 use mysql_async::{from_row, Pool};
 use mysql_common::Value;
 
@@ -35,6 +38,30 @@ impl TableDefinition {
     pub fn qualified_name(&self) -> String {
         format!("{}.{}", self.database_name, self.table_name)
     }
+
+    /// The Dozer schema of the table, with fields in column order.
+    pub fn schema(&self) -> Schema {
+        Schema {
+            fields: self
+                .columns
+                .iter()
+                .map(|column| FieldDefinition {
+                    name: column.name.clone(),
+                    typ: column.typ,
+                    nullable: column.nullable,
+                    source: SourceDefinition::Dynamic,
+                    description: None,
+                })
+                .collect(),
+            primary_index: self
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| column.primary_key)
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
 }
 
 impl std::fmt::Display for TableDefinition {
@@ -121,6 +148,7 @@ impl SchemaHelper<'_> {
                             schema: Some(table_schema),
                             name: table_name,
                             column_names: vec![column_name],
+                            explicit_columns: false,
                         }),
                     }
 
@@ -613,7 +641,8 @@ mod tests {
             vec![TableInfo {
                 schema: Some("test".into()),
                 name: "test1".into(),
-                column_names: vec!["c1".into(), "c2".into(), "c3".into()]
+                column_names: vec!["c1".into(), "c2".into(), "c3".into()],
+                explicit_columns: false,
             }]
         );
 
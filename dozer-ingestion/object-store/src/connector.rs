@@ -74,6 +74,7 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
                 schema: table.schema,
                 name: table.name,
                 column_names,
+                explicit_columns: false,
             };
             result.push(table_info);
         }
@@ -146,6 +147,7 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
                 schema: table_info.schema.clone(),
                 name: table_info.name.clone(),
                 column_names: table_info.column_names.clone(),
+                explicit_columns: false,
             };
 
             let mut found = false;
@@ -202,6 +204,7 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
                 schema: table_info.schema,
                 name: table_info.name,
                 column_names: table_info.column_names,
+                explicit_columns: false,
             };
 
             for table in self.config.tables() {
@@ -11,7 +11,9 @@ use dozer_ingestion_connector::{
     tokio::{self, sync::Mutex},
 };
 use tokio_postgres::types::ToSql;
-use tokio_postgres::{Config, CopyBothDuplex, Row, SimpleQueryMessage, Statement, ToStatement};
+use tokio_postgres::{
+    Config, CopyBothDuplex, Row, SimpleQueryMessage, Statement, ToStatement, Transaction,
+};
 
 use crate::connection::helper::is_network_failure;
 use crate::PostgresConnectorError;
@@ -123,6 +125,11 @@ impl Client {
         )
     }
 
+    /// Starts a transaction. Not retried on network failure, the caller should retry the whole transaction.
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>, tokio_postgres::Error> {
+        self.inner.transaction().await
+    }
+
     pub async fn reconnect(&mut self) -> Result<(), tokio_postgres::Error> {
         let new_client = Self::connect(self.config.clone()).await?;
         self.inner = new_client.inner;
@@ -138,6 +138,7 @@ impl Connector for PostgresConnector {
                 schema: Some(table.schema),
                 name: table.name,
                 column_names: table.columns,
+                explicit_columns: false,
             })
             .collect())
     }
@@ -184,6 +185,7 @@ impl Connector for PostgresConnector {
             create_publication(client, &self.name, Some(&table_identifiers)).await?;
         }
 
+        let explicit_columns = tables.iter().map(|table| table.explicit_columns).collect();
         let tables = tables
             .into_iter()
             .map(|table| ListOrFilterColumns {
@@ -192,11 +194,21 @@ impl Connector for PostgresConnector {
                 columns: Some(table.column_names),
             })
             .collect::<Vec<_>>();
+        // Replication compares relation changes against the schemas the pipeline was built with.
+        let schemas = self
+            .schema_helper
+            .get_schemas(&tables)
+            .await?
+            .into_iter()
+            .map(|schema_result| schema_result.map(|source_schema| source_schema.schema))
+            .collect::<Result<Vec<_>, _>>()?;
         let iterator = PostgresIterator::new(
             self.name.clone(),
             get_publication_name(&self.name),
             self.slot_name.clone(),
             self.schema_helper.get_tables(Some(&tables)).await?,
+            schemas,
+            explicit_columns,
             self.replication_conn_config.clone(),
             ingestor,
             self.conn_config.clone(),
@@ -2,6 +2,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use dozer_ingestion_connector::dozer_types::log::debug;
+use dozer_ingestion_connector::dozer_types::types::Schema;
 use dozer_ingestion_connector::utils::ListOrFilterColumns;
 use dozer_ingestion_connector::Ingestor;
 use postgres_types::PgLsn;
@@ -20,6 +21,10 @@ pub struct Details {
     publication_name: String,
     slot_name: String,
     tables: Vec<PostgresTableInfo>,
+    /// Schemas of `tables`, as the pipeline was built with.
+    schemas: Vec<Schema>,
+    /// Whether the columns of `tables` were configured. Columns added to the other tables are replicated too.
+    explicit_columns: Vec<bool>,
     replication_conn_config: tokio_postgres::Config,
     conn_config: tokio_postgres::Config,
     schema: Option<String>,
@@ -45,6 +50,8 @@ impl<'a> PostgresIterator<'a> {
         publication_name: String,
         slot_name: String,
         tables: Vec<PostgresTableInfo>,
+        schemas: Vec<Schema>,
+        explicit_columns: Vec<bool>,
         replication_conn_config: tokio_postgres::Config,
         ingestor: &'a Ingestor,
         conn_config: tokio_postgres::Config,
@@ -56,6 +63,8 @@ impl<'a> PostgresIterator<'a> {
             publication_name,
             slot_name,
             tables,
+            schemas,
+            explicit_columns,
             replication_conn_config,
             conn_config,
             schema,
@@ -197,6 +206,8 @@ impl<'a> PostgresIteratorHandler<'a> {
         let publication_name = self.details.publication_name.clone();
         let slot_name = self.details.slot_name.clone();
         let tables = self.details.tables.clone();
+        let schemas = self.details.schemas.clone();
+        let explicit_columns = self.details.explicit_columns.clone();
         let mut replicator = CDCHandler {
             replication_conn_config: self.details.replication_conn_config.clone(),
             ingestor: self.ingestor,
@@ -208,6 +219,6 @@ impl<'a> PostgresIteratorHandler<'a> {
             last_commit_lsn: 0,
             name: self.details.name.clone(),
         };
-        replicator.start(tables).await
+        replicator.start(tables, schemas, explicit_columns).await
     }
 }
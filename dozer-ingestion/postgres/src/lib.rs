@@ -134,14 +134,6 @@ pub enum PostgresConnectorError {
         column_index: usize,
     },
 
-    #[error("Column type changed in table {table_index} column {column_name} from {old_type} to {new_type}")]
-    ColumnTypeChanged {
-        table_index: usize,
-        column_name: String,
-        old_type: postgres_types::Type,
-        new_type: postgres_types::Type,
-    },
-
     #[error("Unexpected query message")]
     UnexpectedQueryMessageError,
 }
@@ -5,6 +5,7 @@ use dozer_ingestion_connector::dozer_types::models::ingestion_types::{
     IngestionMessage, TransactionInfo,
 };
 use dozer_ingestion_connector::dozer_types::node::OpIdentifier;
+use dozer_ingestion_connector::dozer_types::types::{Operation, Schema};
 use dozer_ingestion_connector::futures::StreamExt;
 use dozer_ingestion_connector::Ingestor;
 use postgres_protocol::message::backend::ReplicationMessage::*;
@@ -42,6 +43,8 @@ impl<'a> CDCHandler<'a> {
     pub async fn start(
         &mut self,
         tables: Vec<PostgresTableInfo>,
+        schemas: Vec<Schema>,
+        explicit_columns: Vec<bool>,
     ) -> Result<(), PostgresConnectorError> {
         let replication_conn_config = self.replication_conn_config.clone();
         let client = helper::connect(replication_conn_config).await?;
@@ -69,12 +72,16 @@ impl<'a> CDCHandler<'a> {
 
         let tables_columns = tables
             .into_iter()
+            .zip(explicit_columns)
             .enumerate()
-            .map(|(table_index, table_info)| {
-                (table_info.relation_id, (table_index, table_info.columns))
+            .map(|(table_index, (table_info, explicit_columns))| {
+                (
+                    table_info.relation_id,
+                    (table_index, table_info.columns, explicit_columns),
+                )
             })
             .collect();
-        let mut mapper = XlogMapper::new(tables_columns);
+        let mut mapper = XlogMapper::new(tables_columns, schemas);
 
         loop {
             let message = stream.next().await;
@@ -151,7 +158,41 @@ impl<'a> CDCHandler<'a> {
                             return Ok(());
                         }
                     }
-                    None => {}
+                    Some(MappedReplicationMessage::SchemaChanged {
+                        table_index,
+                        schema,
+                    }) => {
+                        if self
+                            .ingestor
+                            .handle_message(IngestionMessage::SchemaChanged {
+                                table_index,
+                                schema,
+                            })
+                            .await
+                            .is_err()
+                        {
+                            return Ok(());
+                        }
+                    }
+                    Some(MappedReplicationMessage::Truncate { table_indexes })
+                        if self.begin_lsn != self.offset_lsn =>
+                    {
+                        for table_index in table_indexes {
+                            if self
+                                .ingestor
+                                .handle_message(IngestionMessage::OperationEvent {
+                                    table_index,
+                                    op: Operation::Truncate,
+                                    id: Some(OpIdentifier::new(self.begin_lsn, 0)),
+                                })
+                                .await
+                                .is_err()
+                            {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Some(MappedReplicationMessage::Truncate { .. }) | None => {}
                 }
 
                 Ok(())
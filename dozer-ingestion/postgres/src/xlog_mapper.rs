@@ -1,6 +1,9 @@
-use dozer_ingestion_connector::dozer_types::types::{Field, Operation, Record};
+use dozer_ingestion_connector::dozer_types::log::info;
+use dozer_ingestion_connector::dozer_types::types::{
+    Field, FieldDefinition, Operation, Record, Schema, SourceDefinition,
+};
 use postgres_protocol::message::backend::LogicalReplicationMessage::{
-    Begin, Commit, Delete, Insert, Relation, Update,
+    Begin, Commit, Delete, Insert, Relation, Truncate, Update,
 };
 use postgres_protocol::message::backend::{
     LogicalReplicationMessage, RelationBody, ReplicaIdentity, TupleData, UpdateBody, XLogDataBody,
@@ -18,6 +21,8 @@ use crate::{
 #[derive(Debug)]
 pub struct Table {
     columns: Vec<TableColumn>,
+    /// Names of all columns of the relation, including the ones not replicated.
+    relation_columns: Vec<String>,
     replica_identity: ReplicaIdentity,
 }
 
@@ -34,21 +39,29 @@ pub enum MappedReplicationMessage {
     Begin,
     Commit(Lsn),
     Operation { table_index: usize, op: Operation },
+    SchemaChanged { table_index: usize, schema: Schema },
+    Truncate { table_indexes: Vec<usize> },
 }
 
 #[derive(Debug, Default)]
 pub struct XlogMapper {
     /// Relation id to table info from replication `Relation` message.
     relations_map: HashMap<u32, Table>,
-    /// Relation id to (table index, column names).
-    tables_columns: HashMap<u32, (usize, Vec<String>)>,
+    /// Relation id to (table index, column names, whether the column names were configured).
+    tables_columns: HashMap<u32, (usize, Vec<String>, bool)>,
+    /// Current schemas of the tables, indexed by table index.
+    schemas: Vec<Schema>,
 }
 
 impl XlogMapper {
-    pub fn new(tables_columns: HashMap<u32, (usize, Vec<String>)>) -> Self {
+    pub fn new(
+        tables_columns: HashMap<u32, (usize, Vec<String>, bool)>,
+        schemas: Vec<Schema>,
+    ) -> Self {
         XlogMapper {
             relations_map: HashMap::<u32, Table>::new(),
             tables_columns,
+            schemas,
         }
     }
 
@@ -58,7 +71,7 @@ impl XlogMapper {
     ) -> Result<Option<MappedReplicationMessage>, PostgresConnectorError> {
         match &message.data() {
             Relation(relation) => {
-                self.ingest_schema(relation)?;
+                return self.ingest_schema(relation);
             }
             Commit(commit) => {
                 return Ok(Some(MappedReplicationMessage::Commit(commit.end_lsn())));
@@ -129,33 +142,75 @@ impl XlogMapper {
                     op: event,
                 }));
             }
+            Truncate(truncate) => {
+                // One `TRUNCATE` can empty several tables.
+                let table_indexes = truncate
+                    .rel_ids()
+                    .iter()
+                    .filter_map(|rel_id| self.tables_columns.get(rel_id))
+                    .map(|(table_index, _, _)| *table_index)
+                    .collect::<Vec<_>>();
+                if !table_indexes.is_empty() {
+                    return Ok(Some(MappedReplicationMessage::Truncate { table_indexes }));
+                }
+            }
             _ => {}
         }
 
         Ok(None)
     }
 
-    fn ingest_schema(&mut self, relation: &RelationBody) -> Result<(), PostgresConnectorError> {
+    /// Returns the new schema of the table if the relation changed since its last `Relation` message.
+    fn ingest_schema(
+        &mut self,
+        relation: &RelationBody,
+    ) -> Result<Option<MappedReplicationMessage>, PostgresConnectorError> {
         let rel_id = relation.rel_id();
-        let Some((table_index, wanted_columns)) = self.tables_columns.get(&rel_id) else {
-            return Ok(());
+        let Some((table_index, wanted_columns, explicit_columns)) =
+            self.tables_columns.get_mut(&rel_id)
+        else {
+            return Ok(None);
         };
+        let table_index = *table_index;
 
-        let mut columns = vec![];
+        let mut relation_columns = vec![];
         for (column_index, column) in relation.columns().iter().enumerate() {
             let column_name =
                 column
                     .name()
                     .map_err(|_| PostgresConnectorError::NonUtf8ColumnName {
-                        table_index: *table_index,
+                        table_index,
                         column_index,
                     })?;
+            relation_columns.push(column_name.to_string());
+        }
 
-            if !wanted_columns.is_empty()
-                && !wanted_columns
-                    .iter()
-                    .any(|column| column.as_str() == column_name)
-            {
+        // Columns added to the table since the last relation message are replicated as well,
+        // unless the columns to replicate were configured.
+        if let Some(existing_table) = self.relations_map.get(&rel_id) {
+            if !wanted_columns.is_empty() {
+                for column_name in &relation_columns {
+                    if existing_table.relation_columns.contains(column_name)
+                        || wanted_columns.contains(column_name)
+                    {
+                        continue;
+                    }
+                    if *explicit_columns {
+                        info!(
+                            "Column {column_name} added to table {table_index} is not in its configured columns, not replicating it"
+                        );
+                    } else {
+                        wanted_columns.push(column_name.clone());
+                    }
+                }
+            }
+        }
+
+        let mut columns = vec![];
+        for (column_index, column) in relation.columns().iter().enumerate() {
+            let column_name = &relation_columns[column_index];
+
+            if !wanted_columns.is_empty() && !wanted_columns.contains(column_name) {
                 continue;
             }
 
@@ -194,6 +249,7 @@ impl XlogMapper {
 
         let table = Table {
             columns,
+            relation_columns,
             replica_identity,
         };
 
@@ -203,26 +259,71 @@ impl XlogMapper {
 
         match self.relations_map.entry(rel_id) {
             Entry::Occupied(mut entry) => {
-                // Check if type has changed.
-                for (existing_column, column) in entry.get().columns.iter().zip(&table.columns) {
-                    if existing_column.r#type != column.r#type {
-                        return Err(PostgresConnectorError::ColumnTypeChanged {
-                            table_index: *table_index,
-                            column_name: existing_column.name.clone(),
-                            old_type: existing_column.r#type.clone(),
-                            new_type: column.r#type.clone(),
-                        });
-                    }
-                }
-
+                let schema = Self::map_schema(&self.schemas[table_index], entry.get(), &table)?;
                 entry.insert(table);
+
+                if schema != self.schemas[table_index] {
+                    self.schemas[table_index] = schema.clone();
+                    return Ok(Some(MappedReplicationMessage::SchemaChanged {
+                        table_index,
+                        schema,
+                    }));
+                }
             }
             Entry::Vacant(entry) => {
                 entry.insert(table);
             }
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// Maps the columns of `table` to the schema they replace, `schema` being the one of `existing_table`.
+    ///
+    /// Fields keep their definition unless the column type changed. Added columns are nullable.
+    fn map_schema(
+        schema: &Schema,
+        existing_table: &Table,
+        table: &Table,
+    ) -> Result<Schema, PostgresConnectorError> {
+        let mut fields = vec![];
+        for column in &table.columns {
+            let existing_field = schema.fields.iter().find(|field| field.name == column.name);
+            let existing_column = existing_table
+                .columns
+                .iter()
+                .find(|existing_column| existing_column.name == column.name);
+            let field = match (existing_field, existing_column) {
+                (Some(field), Some(existing_column)) if existing_column.r#type == column.r#type => {
+                    field.clone()
+                }
+                (Some(field), _) => FieldDefinition {
+                    typ: postgres_type_to_dozer_type(column.r#type.clone())?,
+                    ..field.clone()
+                },
+                (None, _) => FieldDefinition::new(
+                    column.name.clone(),
+                    postgres_type_to_dozer_type(column.r#type.clone())?,
+                    true,
+                    SourceDefinition::Dynamic,
+                ),
+            };
+            fields.push(field);
+        }
+
+        let primary_index = schema
+            .primary_index
+            .iter()
+            .filter_map(|index| {
+                fields
+                    .iter()
+                    .position(|field| field.name == schema.fields[*index].name)
+            })
+            .collect();
+        Ok(Schema {
+            fields,
+            primary_index,
+        })
     }
 
     fn convert_values_to_fields(
@@ -93,6 +93,7 @@ impl Connector for SnowflakeConnector {
                 schema: None,
                 name,
                 column_names,
+                explicit_columns: false,
             });
         }
         Ok(result)
@@ -65,6 +65,7 @@ pub async fn run_test_suite_basic_data_ready<T: DataReadyConnectorTest>(runtime:
                         assert_record_matches_source_schema(op, &schemas[*table_index], true);
                     }
                 }
+                Operation::Truncate => (),
             }
         }
     }
@@ -217,6 +218,7 @@ pub async fn run_test_suite_basic_cud<T: CudConnectorTest>(runtime: Arc<Runtime>
             .into_iter()
             .map(|field| field.name)
             .collect(),
+        explicit_columns: false,
     }];
     let mut schemas = connector.get_schemas(&tables).await.unwrap();
     let actual_schema = schemas.remove(0).unwrap().schema;
@@ -259,6 +261,7 @@ pub async fn run_test_suite_basic_cud<T: CudConnectorTest>(runtime: Arc<Runtime>
                     records.append_operation(RecordsOperation::Insert { new: new.values });
                 }
             }
+            Operation::Truncate => records.clear(),
         }
     }
 
@@ -23,6 +23,10 @@ impl Records {
         }
     }
 
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
     pub fn append_operation(&mut self, operation: Operation) {
         match operation {
             Operation::Insert { new } => {
@@ -98,6 +98,7 @@ impl Connector for WebhookConnector {
                         schema: table.schema,
                         name: table.name,
                         column_names,
+                        explicit_columns: false,
                     })
                 }
                 None => {
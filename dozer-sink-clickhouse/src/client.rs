@@ -2,9 +2,9 @@
 use super::ddl::get_create_table_query;
 use super::types::ValueWrapper;
 use crate::errors::QueryError;
-use crate::types::{insert_multi, map_value_wrapper_to_field};
+use crate::types::{insert_multi, insert_multi_with_columns, map_value_wrapper_to_field};
 use clickhouse_rs::types::Query;
-use clickhouse_rs::{ClientHandle, Pool};
+use clickhouse_rs::{Block, ClientHandle, Pool, Simple};
 use dozer_types::log::{debug, info};
 use dozer_types::models::sink::{ClickhouseSinkConfig, ClickhouseTableOptions};
 use dozer_types::types::{Field, FieldDefinition};
@@ -57,6 +57,13 @@ impl ClickhouseClient {
         Ok(())
     }
 
+    pub async fn execute(&self, query: &str) -> Result<(), QueryError> {
+        let mut client = self.pool.get_handle().await?;
+        info!("{query}");
+        client.execute(query).await?;
+        Ok(())
+    }
+
     pub async fn create_table(
         &self,
         datasource_name: &str,
@@ -140,4 +147,16 @@ impl ClickhouseClient {
         let client = self.pool.get_handle().await?;
         insert_multi(client, table_name, fields, rows, query_id).await
     }
+
+    pub async fn insert_multi_with_columns(
+        &self,
+        table_name: &str,
+        fields: &[FieldDefinition],
+        rows: Vec<Vec<Field>>,
+        columns: Block<Simple>,
+        query_id: Option<String>,
+    ) -> Result<(), QueryError> {
+        let client = self.pool.get_handle().await?;
+        insert_multi_with_columns(client, table_name, fields, rows, columns, query_id).await
+    }
 }
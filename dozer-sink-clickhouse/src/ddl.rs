@@ -5,6 +5,10 @@ use crate::schema::map_field_to_type;
 
 const DEFAULT_TABLE_ENGINE: &str = "MergeTree()";
 
+// Hidden columns of `ReplacingMergeTree` sink tables
+pub const REPLACING_VERSION_COL: &str = "__dozer_version";
+pub const REPLACING_IS_DELETED_COL: &str = "__dozer_is_deleted";
+
 pub fn get_create_table_query(
     table_name: &str,
     fields: &[FieldDefinition],
@@ -14,10 +18,12 @@ pub fn get_create_table_query(
         .as_ref()
         .and_then(|c| c.engine.clone())
         .unwrap_or_else(|| DEFAULT_TABLE_ENGINE.to_string());
-    let engine_name = if engine == "CollapsingMergeTree" {
-        "CollapsingMergeTree(sign)".to_string()
-    } else {
-        engine.to_owned()
+    let engine_name = match engine.as_str() {
+        "CollapsingMergeTree" => "CollapsingMergeTree(sign)".to_string(),
+        "ReplacingMergeTree" => {
+            format!("ReplacingMergeTree({REPLACING_VERSION_COL}, {REPLACING_IS_DELETED_COL})")
+        }
+        _ => engine.to_owned(),
     };
     let mut parts = fields
         .iter()
@@ -29,6 +35,10 @@ pub fn get_create_table_query(
     if engine == "CollapsingMergeTree" {
         parts.push("sign Int8".to_string());
     }
+    if engine == "ReplacingMergeTree" {
+        parts.push(format!("{REPLACING_VERSION_COL} UInt128"));
+        parts.push(format!("{REPLACING_IS_DELETED_COL} UInt8"));
+    }
 
     parts.push(
         table_options
@@ -77,3 +87,29 @@ pub fn get_create_table_query(
             ",
     )
 }
+
+/// Adds `fields` to an existing table, skipping the ones it already has.
+pub fn get_add_columns_query(
+    table_name: &str,
+    fields: &[FieldDefinition],
+    cluster: Option<&str>,
+) -> String {
+    let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+    let columns = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "ADD COLUMN IF NOT EXISTS {} {}",
+                field.name,
+                map_field_to_type(field)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("ALTER TABLE {table_name}{cluster} {columns}")
+}
+
+pub fn get_truncate_table_query(table_name: &str, cluster: Option<&str>) -> String {
+    let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+    format!("TRUNCATE TABLE {table_name}{cluster}")
+}
@@ -5,7 +5,9 @@ use dozer_types::{
 
 #[derive(Error, Debug)]
 pub enum ClickhouseSinkError {
-    #[error("Only MergeTree engine is supported for delete operation")]
+    #[error(
+        "Updates and deletes need a CollapsingMergeTree or a versioned ReplacingMergeTree table"
+    )]
     UnsupportedOperation,
 
     #[error("Column {0} not found in sink table")]
@@ -1,15 +1,40 @@
+use clickhouse_rs::Block;
+use dozer_types::node::OpIdentifier;
 use dozer_types::types::{FieldDefinition, FieldType, Schema, SourceDefinition};
 
+use crate::client::ClickhouseClient;
+use crate::errors::QueryError;
+
 // Replication Metadata Constants
 pub const REPLICA_METADATA_TABLE: &str = "__dozer_replication_metadata";
 pub const META_TABLE_COL: &str = "table";
 pub const META_TXN_ID_COL: &str = "txn_id";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
 
 pub struct ReplicationMetadata {
     pub schema: Schema,
     pub table_name: String,
 }
 
+/// The position a sink table has been replicated up to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationState {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl ReplicationState {
+    /// Tables of a sink are flushed together, but one may have been added later,
+    /// so resume from the one that is furthest behind.
+    pub fn oldest(states: impl IntoIterator<Item = ReplicationState>) -> ReplicationState {
+        states
+            .into_iter()
+            .min_by_key(|state| state.op_id)
+            .unwrap_or_default()
+    }
+}
+
 impl ReplicationMetadata {
     pub fn schema(&self) -> &Schema {
         &self.schema
@@ -43,7 +68,97 @@ impl ReplicationMetadata {
                     },
                     false,
                 )
+                .field(
+                    FieldDefinition {
+                        name: META_SEQ_IN_TX_COL.to_owned(),
+                        typ: FieldType::UInt,
+                        nullable: false,
+                        source: SourceDefinition::Dynamic,
+                        description: None,
+                    },
+                    false,
+                )
+                // Stored as raw bytes in a `String` column, empty if there's no state.
+                .field(
+                    FieldDefinition {
+                        name: META_SOURCE_STATE_COL.to_owned(),
+                        typ: FieldType::String,
+                        nullable: false,
+                        source: SourceDefinition::Dynamic,
+                        description: None,
+                    },
+                    false,
+                )
                 .clone(),
         }
     }
+
+    /// Adds the columns that tables created by older versions are missing.
+    pub fn get_migration_query(&self, cluster: Option<&str>) -> String {
+        let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+        format!(
+            "ALTER TABLE {}{cluster} ADD COLUMN IF NOT EXISTS {META_SEQ_IN_TX_COL} UInt64, ADD COLUMN IF NOT EXISTS {META_SOURCE_STATE_COL} String",
+            self.table_name
+        )
+    }
+
+    pub fn get_latest_state_query(&self, sink_table_name: &str) -> String {
+        format!(
+            "SELECT {META_TXN_ID_COL}, {META_SEQ_IN_TX_COL}, {META_SOURCE_STATE_COL} FROM {} FINAL WHERE `{META_TABLE_COL}` = '{}' ORDER BY {META_TXN_ID_COL} DESC, {META_SEQ_IN_TX_COL} DESC LIMIT 1",
+            self.table_name,
+            sink_table_name.replace('\\', "\\\\").replace('\'', "\\'")
+        )
+    }
+
+    pub async fn load_state(
+        &self,
+        client: &ClickhouseClient,
+        sink_table_name: &str,
+    ) -> Result<ReplicationState, QueryError> {
+        let mut handle = client.get_client_handle().await?;
+        let block = handle
+            .query(self.get_latest_state_query(sink_table_name))
+            .fetch_all()
+            .await?;
+
+        let Some(row) = block.rows().next() else {
+            return Ok(ReplicationState::default());
+        };
+        let txid: u64 = row.get(META_TXN_ID_COL)?;
+        let seq_in_tx: u64 = row.get(META_SEQ_IN_TX_COL)?;
+        let source_state: &[u8] = row.get(META_SOURCE_STATE_COL)?;
+        Ok(ReplicationState {
+            op_id: Some(OpIdentifier::new(txid, seq_in_tx)),
+            source_state: (!source_state.is_empty()).then(|| source_state.to_vec()),
+        })
+    }
+
+    /// Records the same state for all the given tables in a single insert.
+    pub async fn store_state(
+        &self,
+        client: &ClickhouseClient,
+        sink_table_names: &[String],
+        state: &ReplicationState,
+    ) -> Result<(), QueryError> {
+        // Without an op id there's no position to resume from.
+        let Some(op_id) = state.op_id else {
+            return Ok(());
+        };
+        if sink_table_names.is_empty() {
+            return Ok(());
+        }
+
+        let rows = sink_table_names.len();
+        let block = Block::new()
+            .column(META_TABLE_COL, sink_table_names.to_vec())
+            .column(META_TXN_ID_COL, vec![op_id.txid; rows])
+            .column(META_SEQ_IN_TX_COL, vec![op_id.seq_in_tx; rows])
+            .column(
+                META_SOURCE_STATE_COL,
+                vec![state.source_state.clone().unwrap_or_default(); rows],
+            );
+        let mut handle = client.get_client_handle().await?;
+        handle.insert(&self.table_name, block).await?;
+        Ok(())
+    }
 }
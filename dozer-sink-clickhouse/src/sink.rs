@@ -1,27 +1,34 @@
 use dozer_core::epoch::Epoch;
+use dozer_core::errors::SchemaChangeError;
 use dozer_core::event::EventHub;
 use dozer_core::node::{PortHandle, Sink, SinkFactory};
-use dozer_core::tokio::runtime::Runtime;
-use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_core::tokio::{self, runtime::Runtime, task::JoinHandle};
 use dozer_types::errors::internal::BoxedError;
 
-use dozer_types::log::debug;
-use dozer_types::models::sink::{ClickhouseSinkConfig, ClickhouseTableOptions};
-use dozer_types::node::OpIdentifier;
+use dozer_types::json_types::json_to_bytes;
+use dozer_types::log::{debug, warn};
+use dozer_types::models::sink::{
+    ClickhouseSinkConfig, ClickhouseSinkTable, ClickhouseTableOptions,
+};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
 
 use crate::client::ClickhouseClient;
-use crate::errors::ClickhouseSinkError;
-use crate::metadata::{
-    ReplicationMetadata, META_TABLE_COL, META_TXN_ID_COL, REPLICA_METADATA_TABLE,
+use crate::ddl::{
+    get_add_columns_query, get_truncate_table_query, REPLACING_IS_DELETED_COL,
+    REPLACING_VERSION_COL,
 };
+use crate::errors::{ClickhouseSinkError, QueryError};
+use crate::metadata::{ReplicationMetadata, ReplicationState};
 use crate::schema::{ClickhouseSchema, ClickhouseTable};
+use clickhouse_rs::Block;
 use dozer_types::tonic::async_trait;
-use dozer_types::types::{Field, FieldDefinition, Operation, Schema, TableOperation};
+use dozer_types::types::{Field, FieldDefinition, Operation, Schema, SchemaDiff, TableOperation};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
 
-const BATCH_SIZE: usize = 100;
+const INSERT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 pub struct ClickhouseSinkFactory {
@@ -34,23 +41,29 @@ impl ClickhouseSinkFactory {
         Self { config, runtime }
     }
 
-    pub async fn create_replication_metadata_table(&self) -> Result<(), BoxedError> {
-        let client = ClickhouseClient::new(self.config.clone());
+    pub async fn create_replication_metadata_table(
+        &self,
+        client: &ClickhouseClient,
+    ) -> Result<(), BoxedError> {
         let repl_metadata = ReplicationMetadata::get_metadata();
 
         let primary_keys = repl_metadata.get_primary_keys();
         let partition_by = format!("({})", primary_keys.join(","));
+        // The metadata table is shared by all tables, so it goes to the first cluster configured.
+        let cluster = self.config.tables.iter().find_map(|table| {
+            table
+                .create_table_options
+                .as_ref()
+                .and_then(|o| o.cluster.clone())
+        });
         let create_table_options = ClickhouseTableOptions {
-            engine: Some("ReplacingMergeTree".to_string()),
+            // Without arguments, so the latest insert wins and no version columns are added.
+            engine: Some("ReplacingMergeTree()".to_string()),
             primary_keys: Some(repl_metadata.get_primary_keys()),
             partition_by: Some(partition_by),
             // Replaced using this key
             order_by: Some(repl_metadata.get_primary_keys()),
-            cluster: self
-                .config
-                .create_table_options
-                .as_ref()
-                .and_then(|o| o.cluster.clone()),
+            cluster: cluster.clone(),
             sample_by: None,
         };
         client
@@ -61,9 +74,47 @@ impl ClickhouseSinkFactory {
                 None,
             )
             .await?;
+        client
+            .execute(&repl_metadata.get_migration_query(cluster.as_deref()))
+            .await?;
 
         Ok(())
     }
+
+    async fn build_table(
+        &self,
+        client: &ClickhouseClient,
+        table_config: &ClickhouseSinkTable,
+        schema: Schema,
+    ) -> Result<(SinkTable, ReplicationState), BoxedError> {
+        let sink_table_name = &table_config.sink_table_name;
+        if table_config.create_table_options.is_some() {
+            client
+                .create_table(
+                    sink_table_name,
+                    &schema.fields,
+                    table_config.create_table_options.clone(),
+                    None,
+                )
+                .await?;
+        }
+        let table = ClickhouseSchema::get_clickhouse_table(client.clone(), sink_table_name).await?;
+
+        ClickhouseSchema::compare_with_dozer_schema(client.clone(), &schema, &table).await?;
+
+        let state = ReplicationMetadata::get_metadata()
+            .load_state(client, sink_table_name)
+            .await?;
+        debug!(
+            "[Sink] Restored replication state for {}: {:?}",
+            sink_table_name, state.op_id
+        );
+
+        Ok((
+            SinkTable::new(sink_table_name.clone(), schema, &table, &state),
+            state,
+        ))
+    }
 }
 
 #[async_trait]
@@ -73,15 +124,15 @@ impl SinkFactory for ClickhouseSinkFactory {
     }
 
     fn get_input_ports(&self) -> Vec<PortHandle> {
-        vec![DEFAULT_PORT_HANDLE]
+        (0..self.config.tables.len() as PortHandle).collect()
     }
 
-    fn get_input_port_name(&self, _port: &PortHandle) -> String {
-        self.config.source_table_name.clone()
+    fn get_input_port_name(&self, port: &PortHandle) -> String {
+        self.config.tables[*port as usize].source_table_name.clone()
     }
 
     fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
-        debug_assert!(input_schemas.len() == 1);
+        debug_assert!(input_schemas.len() == self.config.tables.len());
         Ok(())
     }
 
@@ -90,73 +141,120 @@ impl SinkFactory for ClickhouseSinkFactory {
         mut input_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
     ) -> Result<Box<dyn Sink>, BoxedError> {
-        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
-
+        // All tables share one connection pool.
         let client = ClickhouseClient::new(self.config.clone());
 
-        let config = &self.config;
-
-        // Create Sink Table
-        self.create_replication_metadata_table().await?;
+        self.create_replication_metadata_table(&client).await?;
 
-        // Create Sink Table
-        if self.config.create_table_options.is_some() {
-            client
-                .create_table(
-                    &config.sink_table_name,
-                    &schema.fields,
-                    self.config.create_table_options.clone(),
-                    None,
-                )
-                .await?;
+        // Sink methods are called from within the runtime while the DAG is built, so restore the state here.
+        let mut tables = Vec::with_capacity(self.config.tables.len());
+        let mut states = Vec::with_capacity(self.config.tables.len());
+        for (port, table_config) in self.config.tables.iter().enumerate() {
+            let schema = input_schemas.remove(&(port as PortHandle)).unwrap();
+            let (table, state) = self.build_table(&client, table_config, schema).await?;
+            tables.push(table);
+            states.push(state);
         }
-        let table = ClickhouseSchema::get_clickhouse_table(client.clone(), &self.config).await?;
 
-        ClickhouseSchema::compare_with_dozer_schema(client.clone(), &schema, &table).await?;
         let sink = ClickhouseSink::new(
             client,
             self.config.clone(),
-            schema,
             self.runtime.clone(),
-            table,
+            tables,
+            ReplicationState::oldest(states),
         );
 
         Ok(Box::new(sink))
     }
 }
 
-pub(crate) struct ClickhouseSink {
-    pub(crate) client: ClickhouseClient,
-    pub(crate) runtime: Arc<Runtime>,
-    pub(crate) schema: Schema,
-    pub(crate) sink_table_name: String,
-    pub(crate) table: ClickhouseTable,
-    batch: Vec<Vec<Field>>,
-    metadata: ReplicationMetadata,
-    latest_txid: Option<u64>,
+/// How updates and deletes are written, decided by the engine of the sink table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableMode {
+    /// Only inserts are supported.
+    Append,
+    /// `CollapsingMergeTree(sign)`, a row is cancelled by a row with the opposite sign.
+    Collapsing,
+    /// `ReplacingMergeTree(version, is_deleted)`, the row with the highest version wins.
+    Replacing,
 }
 
-impl Debug for ClickhouseSink {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ClickhouseSink")
-            .field("sink_table_name", &self.sink_table_name)
-            .field("table", &self.table)
-            .field("schema", &self.schema)
-            .finish()
+impl TableMode {
+    fn from_table(table: &ClickhouseTable) -> Self {
+        if table.engine == "CollapsingMergeTree" {
+            TableMode::Collapsing
+        } else if table.engine.ends_with("ReplacingMergeTree")
+            && table.engine_full.contains(REPLACING_IS_DELETED_COL)
+        {
+            TableMode::Replacing
+        } else {
+            TableMode::Append
+        }
     }
 }
 
-impl ClickhouseSink {
-    pub fn new(
-        client: ClickhouseClient,
-        config: ClickhouseSinkConfig,
-        schema: Schema,
-        runtime: Arc<Runtime>,
-        table: ClickhouseTable,
+fn op_id_to_version(op_id: OpIdentifier) -> u128 {
+    (u128::from(op_id.txid) << 64) | u128::from(op_id.seq_in_tx)
+}
+
+/// Rows buffered until the next flush.
+#[derive(Debug, Default)]
+struct InsertBatch {
+    rows: Vec<Vec<Field>>,
+    // Hidden column values of `rows`, only used in `TableMode::Replacing`
+    versions: Vec<u128>,
+    is_deleted: Vec<u8>,
+    /// Estimated size of `rows`.
+    bytes: u64,
+}
+
+impl InsertBatch {
+    fn push(&mut self, values: Vec<Field>) {
+        self.bytes += values.iter().map(estimate_field_size).sum::<u64>();
+        self.rows.push(values);
+    }
+
+    fn hidden_columns(&self, mode: TableMode) -> Block {
+        if mode == TableMode::Replacing {
+            Block::new()
+                .column(REPLACING_VERSION_COL, self.versions.clone())
+                .column(REPLACING_IS_DELETED_COL, self.is_deleted.clone())
+        } else {
+            Block::new()
+        }
+    }
+}
+
+fn estimate_field_size(field: &Field) -> u64 {
+    let heap_size = match field {
+        Field::String(value) | Field::Text(value) => value.len(),
+        Field::Binary(value) => value.len(),
+        Field::Json(value) => json_to_bytes(value).len(),
+        _ => 0,
+    };
+    (std::mem::size_of::<Field>() + heap_size) as u64
+}
+
+/// A sink table and the rows buffered for it.
+#[derive(Debug)]
+struct SinkTable {
+    sink_table_name: String,
+    schema: Schema,
+    mode: TableMode,
+    batch: InsertBatch,
+    last_version: u128,
+}
+
+impl SinkTable {
+    fn new(
+        sink_table_name: String,
+        mut schema: Schema,
+        table: &ClickhouseTable,
+        state: &ReplicationState,
     ) -> Self {
-        let mut schema = schema.clone();
+        let mode = TableMode::from_table(table);
 
-        if table.engine == "CollapsingMergeTree" && !schema.fields.is_empty() {
+        if mode == TableMode::Collapsing && !schema.fields.is_empty() {
             // get source from any field in schema
             let source = schema.fields[0].source.clone();
             schema.fields.push(FieldDefinition {
@@ -168,142 +266,296 @@ impl ClickhouseSink {
             });
         }
         Self {
-            client,
-            runtime,
+            sink_table_name,
             schema,
-            sink_table_name: config.sink_table_name,
-            table,
-            batch: Vec::new(),
-            latest_txid: None,
-            metadata: ReplicationMetadata::get_metadata(),
+            mode,
+            batch: InsertBatch::default(),
+            // Versions are derived from op ids, so continue after the last committed one.
+            last_version: state.op_id.map_or(0, op_id_to_version),
         }
     }
 
-    pub async fn insert_metadata(&self) -> Result<(), BoxedError> {
+    /// Adds a row to the batch, or a row that marks it deleted if `deleted` is set.
+    fn insert_values(
+        &mut self,
+        mut values: Vec<Field>,
+        deleted: bool,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        match self.mode {
+            TableMode::Append => {
+                if deleted {
+                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
+                }
+            }
+            TableMode::Collapsing => values.push(Field::Int8(if deleted { -1 } else { 1 })),
+            TableMode::Replacing => {
+                let version = self.next_version(id);
+                self.batch.versions.push(version);
+                self.batch.is_deleted.push(deleted.into());
+            }
+        }
+        // add values to batch instead of inserting immediately
+        self.batch.push(values);
+        Ok(())
+    }
+
+    /// Versions must increase even if operations have no id, or share one.
+    fn next_version(&mut self, id: Option<OpIdentifier>) -> u128 {
+        let version = id.map_or(0, op_id_to_version).max(self.last_version + 1);
+        self.last_version = version;
+        version
+    }
+
+    fn process(&mut self, op: Operation, id: Option<OpIdentifier>) -> Result<(), BoxedError> {
+        match op {
+            Operation::Insert { new } => {
+                self.insert_values(new.values, false, id)?;
+            }
+            Operation::Delete { old } => {
+                self.insert_values(old.values, true, id)?;
+            }
+            Operation::Update { new, old } => {
+                if self.mode == TableMode::Append {
+                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
+                }
+                // A newer version of the same key replaces the old row by itself.
+                let primary_index = &self.schema.primary_index;
+                if self.mode == TableMode::Collapsing
+                    || primary_index.is_empty()
+                    || old.get_key(primary_index) != new.get_key(primary_index)
+                {
+                    self.insert_values(old.values, true, id)?;
+                }
+                self.insert_values(new.values, false, id)?;
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.insert_values(record.values, false, id)?;
+                }
+            }
+            Operation::Truncate => unreachable!("truncates are executed by the sink"),
+        }
+        Ok(())
+    }
+
+    /// Adds the fields appended by a schema change. Buffered rows get nulls for them.
+    fn add_fields(&mut self, fields: &[FieldDefinition]) {
+        // In `TableMode::Collapsing` the sign column stays last.
+        let position = if self.mode == TableMode::Collapsing {
+            self.schema.fields.len().saturating_sub(1)
+        } else {
+            self.schema.fields.len()
+        };
+        self.schema
+            .fields
+            .splice(position..position, fields.iter().cloned());
+        for row in &mut self.batch.rows {
+            row.splice(position..position, fields.iter().map(|_| Field::Null));
+        }
+    }
+
+    /// Moves the buffered rows out, so they can be inserted in the background.
+    fn take_insert(&mut self) -> TableInsert {
+        TableInsert {
+            sink_table_name: self.sink_table_name.clone(),
+            fields: self.schema.fields.clone(),
+            mode: self.mode,
+            batch: std::mem::take(&mut self.batch),
+        }
+    }
+}
+
+/// Rows to insert into one table.
+struct TableInsert {
+    sink_table_name: String,
+    fields: Vec<FieldDefinition>,
+    mode: TableMode,
+    batch: InsertBatch,
+}
+
+/// Everything a background insert needs, so it doesn't borrow the sink.
+struct InsertTask {
+    client: ClickhouseClient,
+    inserts: Vec<TableInsert>,
+    /// All tables of the sink, they are recorded at the same state.
+    sink_table_names: Vec<String>,
+    state: ReplicationState,
+    retries: u32,
+}
+
+impl InsertTask {
+    async fn run(self) -> Result<(), QueryError> {
+        for insert in &self.inserts {
+            if !insert.batch.rows.is_empty() {
+                self.insert_with_retries(insert).await?;
+            }
+        }
+
         debug!(
-            "[Sink] Inserting metadata record {:?} {}",
-            self.latest_txid,
-            self.sink_table_name.clone()
+            "[Sink] Inserting metadata record {:?} {:?}",
+            self.state.op_id, self.sink_table_names
         );
-        if let Some(txid) = self.latest_txid {
-            self.client
-                .insert(
-                    REPLICA_METADATA_TABLE,
-                    &self.metadata.schema.fields,
-                    vec![
-                        Field::String(self.sink_table_name.clone()),
-                        Field::UInt(txid),
-                    ],
+        ReplicationMetadata::get_metadata()
+            .store_state(&self.client, &self.sink_table_names, &self.state)
+            .await
+    }
+
+    async fn insert_with_retries(&self, insert: &TableInsert) -> Result<(), QueryError> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client
+                .insert_multi_with_columns(
+                    &insert.sink_table_name,
+                    &insert.fields,
+                    insert.batch.rows.clone(),
+                    insert.batch.hidden_columns(insert.mode),
                     None,
                 )
-                .await?;
+                .await;
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.retries => {
+                    attempt += 1;
+                    let interval = INSERT_RETRY_INTERVAL * attempt;
+                    warn!(
+                        "[Sink] Insert into {} failed: {e:?}. Retrying in {interval:?} ({attempt}/{})",
+                        insert.sink_table_name, self.retries
+                    );
+                    tokio::time::sleep(interval).await;
+                }
+                Err(e) => return Err(e),
+            }
         }
-        Ok(())
     }
+}
 
-    fn insert_values(&mut self, values: &[Field]) -> Result<(), BoxedError> {
-        // add values to batch instead of inserting immediately
-        self.batch.push(values.to_vec());
-        Ok(())
+pub(crate) struct ClickhouseSink {
+    pub(crate) client: ClickhouseClient,
+    pub(crate) runtime: Arc<Runtime>,
+    config: ClickhouseSinkConfig,
+    /// Indexed by input port.
+    tables: Vec<SinkTable>,
+    /// State to be written with the next batch.
+    state: ReplicationState,
+    /// State written with the last batch.
+    flushed_state: ReplicationState,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+    /// At most one batch is inserted at a time, so batches and their metadata land in order.
+    pending_insert: Option<JoinHandle<Result<(), QueryError>>>,
+}
+
+impl Debug for ClickhouseSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClickhouseSink")
+            .field("tables", &self.tables)
+            .finish()
     }
+}
 
-    fn commit_batch(&mut self) -> Result<(), BoxedError> {
-        let batch = std::mem::take(&mut self.batch);
-        self.runtime.block_on(async {
-            //Insert batch
-            self.client
-                .insert_multi(&self.sink_table_name, &self.schema.fields, batch, None)
-                .await?;
+impl ClickhouseSink {
+    fn new(
+        client: ClickhouseClient,
+        config: ClickhouseSinkConfig,
+        runtime: Arc<Runtime>,
+        tables: Vec<SinkTable>,
+        state: ReplicationState,
+    ) -> Self {
+        Self {
+            client,
+            runtime,
+            config,
+            tables,
+            flushed_state: state.clone(),
+            state,
+            source: None,
+            pending_insert: None,
+        }
+    }
 
-            self.insert_metadata().await?;
-            Ok::<(), BoxedError>(())
-        })?;
+    fn batch_bytes(&self) -> u64 {
+        self.tables.iter().map(|table| table.batch.bytes).sum()
+    }
 
+    fn wait_for_pending_insert(&mut self) -> Result<(), BoxedError> {
+        if let Some(pending_insert) = self.pending_insert.take() {
+            self.runtime.block_on(pending_insert)??;
+        }
         Ok(())
     }
 
-    fn _get_latest_op(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
-        let op = self.runtime.block_on(async {
-            let mut client = self.client.get_client_handle().await?;
-            let table_name = self.sink_table_name.clone();
-            let query = format!("SELECT \"{META_TXN_ID_COL}\" FROM \"{REPLICA_METADATA_TABLE}\" WHERE \"{META_TABLE_COL}\" = '\"{table_name}\"' ORDER BY \"{META_TXN_ID_COL}\" LIMIT 1");
-            let block = client
-                .query(query)
-                .fetch_all()
-                .await?;
-
-            let row = block.rows().next();
-            match row {
-                Some(row) => {
-                    let txid: u64 = row.get(META_TXN_ID_COL)?;
-                    Ok::<Option<dozer_types::node::OpIdentifier>, BoxedError>(Some(OpIdentifier { txid, seq_in_tx: 0 }))
-                },
-                None => Ok::<Option<dozer_types::node::OpIdentifier>, BoxedError>(None),
-            }
-        })?;
-        Ok(op)
+    /// Empties the table of `port`. Buffered rows are dropped, and rows being inserted land before the truncate.
+    fn truncate(&mut self, port: PortHandle) -> Result<(), BoxedError> {
+        self.tables[port as usize].batch = InsertBatch::default();
+        self.wait_for_pending_insert()?;
+
+        let table_config = &self.config.tables[port as usize];
+        let cluster = table_config
+            .create_table_options
+            .as_ref()
+            .and_then(|options| options.cluster.as_deref());
+        let query = get_truncate_table_query(&table_config.sink_table_name, cluster);
+        self.runtime.block_on(self.client.execute(&query))?;
+        Ok(())
     }
 }
 
 impl Sink for ClickhouseSink {
-    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.state.op_id = Some(*op_id);
+        }
+
+        // The flush scheduler only knows about row counts.
+        if self
+            .config
+            .batch_bytes
+            .is_some_and(|batch_bytes| self.batch_bytes() >= batch_bytes)
+        {
+            self.flush_batch()?;
+        }
         Ok(())
     }
 
     fn flush_batch(&mut self) -> Result<(), BoxedError> {
-        self.commit_batch()?;
+        self.wait_for_pending_insert()?;
+        if self.tables.iter().all(|table| table.batch.rows.is_empty())
+            && self.state == self.flushed_state
+        {
+            return Ok(());
+        }
+
+        let task = InsertTask {
+            client: self.client.clone(),
+            inserts: self.tables.iter_mut().map(SinkTable::take_insert).collect(),
+            sink_table_names: self
+                .tables
+                .iter()
+                .map(|table| table.sink_table_name.clone())
+                .collect(),
+            state: self.state.clone(),
+            retries: self.config.insert_retries,
+        };
+        self.flushed_state = self.state.clone();
+        self.pending_insert = Some(self.runtime.spawn(task.run()));
         Ok(())
     }
 
     fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
-        self.latest_txid = op.id.map(|id| id.txid);
-        match op.op {
-            Operation::Insert { new } => {
-                if self.table.engine == "CollapsingMergeTree" {
-                    let mut values = new.values;
-                    values.push(Field::Int8(1));
-                    self.insert_values(&values)?;
-                } else {
-                    self.insert_values(&new.values)?;
-                }
-
-                if self.batch.len() > BATCH_SIZE - 1 {
-                    self.commit_batch()?;
-                }
-            }
-            Operation::Delete { old } => {
-                if self.table.engine != "CollapsingMergeTree" {
-                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
-                }
-                let mut values = old.values;
-                values.push(Field::Int(-1));
-                self.insert_values(&values)?;
-            }
-            Operation::Update { new, old } => {
-                if self.table.engine != "CollapsingMergeTree" {
-                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
-                }
-                let mut values = old.values;
-                values.push(Field::Int8(-1));
-                self.insert_values(&values)?;
-
-                let mut values = new.values;
-                values.push(Field::Int8(1));
-                self.insert_values(&values)?;
-            }
-            Operation::BatchInsert { new } => {
-                for record in new {
-                    let mut values = record.values;
-                    if self.table.engine == "CollapsingMergeTree" {
-                        values.push(Field::Int8(1));
-                    }
-                    self.insert_values(&values)?;
-                }
-                self.commit_batch()?;
-            }
+        if op.id.is_some() {
+            self.state.op_id = op.id;
         }
-
-        Ok(())
+        if let Operation::Truncate = op.op {
+            return self.truncate(op.port);
+        }
+        self.tables[op.port as usize].process(op.op, op.id)
     }
 
     fn on_source_snapshotting_started(
@@ -318,21 +570,61 @@ impl Sink for ClickhouseSink {
         _connection_name: String,
         id: Option<OpIdentifier>,
     ) -> Result<(), BoxedError> {
-        self.latest_txid = id.map(|opid| opid.txid);
-        self.commit_batch()?;
+        if id.is_some() {
+            self.state.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
         Ok(())
     }
 
-    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.state.source_state = Some(source_state.to_vec());
         Ok(())
     }
 
     fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
-        Ok(None)
+        Ok(self.state.source_state.clone())
     }
 
     fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
-        // self.get_latest_op()
-        Ok(None)
+        Ok(self.state.op_id)
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        self.config.batch_size
+    }
+
+    fn max_batch_duration_ms(&self) -> Option<u64> {
+        self.config.max_batch_duration_ms
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+    fn on_schema_change(
+        &mut self,
+        port: PortHandle,
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<(), BoxedError> {
+        let diff = SchemaDiff::new(old, new);
+        if !diff.is_additive() {
+            return Err(SchemaChangeError::NotAdditive.into());
+        }
+
+        let table_config = &self.config.tables[port as usize];
+        let cluster = table_config
+            .create_table_options
+            .as_ref()
+            .and_then(|options| options.cluster.as_deref());
+        let query = get_add_columns_query(&table_config.sink_table_name, &diff.added, cluster);
+        self.runtime.block_on(self.client.execute(&query))?;
+
+        self.tables[port as usize].add_fields(&diff.added);
+        Ok(())
     }
 }
@@ -1,9 +1,14 @@
 use crate::client::ClickhouseClient;
+use crate::ddl::{get_add_columns_query, get_create_table_query, get_truncate_table_query};
+use crate::metadata::ReplicationState;
 use crate::schema::ClickhouseSchema;
 use clickhouse_rs::types::Query;
 use dozer_core::tokio;
-use dozer_types::models::sink::ClickhouseSinkConfig;
-use dozer_types::types::{FieldDefinition, FieldType, Schema};
+use dozer_types::models::sink::{
+    ClickhouseSinkConfig, ClickhouseSinkTable, ClickhouseTableOptions,
+};
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{FieldDefinition, FieldType, Schema, SourceDefinition};
 
 fn get_client() -> ClickhouseClient {
     ClickhouseClient::new(get_sink_config())
@@ -11,16 +16,22 @@ fn get_client() -> ClickhouseClient {
 
 fn get_sink_config() -> ClickhouseSinkConfig {
     ClickhouseSinkConfig {
-        source_table_name: "source_table".to_string(),
-        sink_table_name: "sink_table".to_string(),
+        tables: vec![ClickhouseSinkTable {
+            source_table_name: "source_table".to_string(),
+            sink_table_name: "sink_table".to_string(),
+            create_table_options: None,
+        }],
         scheme: "tcp".to_string(),
-        create_table_options: None,
         user: "default".to_string(),
         password: None,
         database: "default".to_string(),
         host: "localhost".to_string(),
         port: 9000,
         options: vec![],
+        batch_size: None,
+        batch_bytes: None,
+        max_batch_duration_ms: None,
+        insert_retries: 3,
     }
 }
 
@@ -63,12 +74,12 @@ async fn create_table(table_name: &str) {
 #[ignore]
 async fn test_get_clickhouse_table() {
     let client = get_client();
-    let sink_config = get_sink_config();
-    create_table(&sink_config.sink_table_name).await;
-    let clickhouse_table = ClickhouseSchema::get_clickhouse_table(client, &sink_config)
+    let sink_table_name = &get_sink_config().tables[0].sink_table_name;
+    create_table(sink_table_name).await;
+    let clickhouse_table = ClickhouseSchema::get_clickhouse_table(client, sink_table_name)
         .await
         .unwrap();
-    assert_eq!(clickhouse_table.name, sink_config.sink_table_name);
+    assert_eq!(&clickhouse_table.name, sink_table_name);
 }
 
 use clickhouse_rs::{Block, Pool};
@@ -103,3 +114,82 @@ async fn clickhouse_test() -> Result<(), Box<dyn Error>> {
     client.insert(table, block).await?;
     Ok(())
 }
+
+#[test]
+fn test_latest_state_query() {
+    let metadata = crate::metadata::ReplicationMetadata::get_metadata();
+    assert_eq!(
+        metadata.get_latest_state_query("sink_'table"),
+        "SELECT txn_id, seq_in_tx, source_state FROM __dozer_replication_metadata FINAL WHERE `table` = 'sink_\\'table' ORDER BY txn_id DESC, seq_in_tx DESC LIMIT 1"
+    );
+}
+
+#[test]
+fn test_resume_from_oldest_table_state() {
+    let state = |op_id| ReplicationState {
+        op_id,
+        source_state: None,
+    };
+    let oldest = ReplicationState::oldest([
+        state(Some(OpIdentifier::new(2, 0))),
+        state(Some(OpIdentifier::new(1, 5))),
+    ]);
+    assert_eq!(oldest.op_id, Some(OpIdentifier::new(1, 5)));
+
+    // A table that has never been written to has to start over.
+    let oldest = ReplicationState::oldest([state(Some(OpIdentifier::new(2, 0))), state(None)]);
+    assert_eq!(oldest.op_id, None);
+}
+
+#[test]
+fn test_replacing_merge_tree_ddl() {
+    let options = ClickhouseTableOptions {
+        engine: Some("ReplacingMergeTree".to_string()),
+        primary_keys: Some(vec!["id".to_string()]),
+        partition_by: None,
+        sample_by: None,
+        order_by: Some(vec!["id".to_string()]),
+        cluster: None,
+    };
+    let query = get_create_table_query("sink_table", &_get_dozer_schema().fields, Some(options));
+    assert!(query.contains("__dozer_version UInt128,\n__dozer_is_deleted UInt8"));
+    assert!(query.contains("ENGINE = ReplacingMergeTree(__dozer_version, __dozer_is_deleted)"));
+}
+
+#[test]
+fn test_add_columns_ddl() {
+    let fields = vec![
+        FieldDefinition::new(
+            "name".to_string(),
+            FieldType::String,
+            true,
+            SourceDefinition::Dynamic,
+        ),
+        FieldDefinition::new(
+            "age".to_string(),
+            FieldType::Int,
+            true,
+            SourceDefinition::Dynamic,
+        ),
+    ];
+    assert_eq!(
+        get_add_columns_query("sink_table", &fields, None),
+        "ALTER TABLE sink_table ADD COLUMN IF NOT EXISTS name Nullable(String), ADD COLUMN IF NOT EXISTS age Nullable(Int64)"
+    );
+    assert_eq!(
+        get_add_columns_query("sink_table", &fields[..1], Some("cluster")),
+        "ALTER TABLE sink_table ON CLUSTER cluster ADD COLUMN IF NOT EXISTS name Nullable(String)"
+    );
+}
+
+#[test]
+fn test_truncate_table_ddl() {
+    assert_eq!(
+        get_truncate_table_query("sink_table", None),
+        "TRUNCATE TABLE sink_table"
+    );
+    assert_eq!(
+        get_truncate_table_query("sink_table", Some("cluster")),
+        "TRUNCATE TABLE sink_table ON CLUSTER cluster"
+    );
+}
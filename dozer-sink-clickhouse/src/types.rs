@@ -270,13 +270,24 @@ fn add_last_column_to_block(
 }
 
 pub async fn insert_multi(
+    client: ClientHandle,
+    table_name: &str,
+    fields: &[FieldDefinition],
+    rows: Vec<Vec<Field>>,
+    query_id: Option<String>,
+) -> Result<(), QueryError> {
+    insert_multi_with_columns(client, table_name, fields, rows, Block::new(), query_id).await
+}
+
+/// Like [`insert_multi`], but also inserts the columns already in `block`, which aren't part of `fields`.
+pub async fn insert_multi_with_columns(
     mut client: ClientHandle,
     table_name: &str,
     fields: &[FieldDefinition],
     mut rows: Vec<Vec<Field>>,
+    mut block: Block<clickhouse_rs::Simple>,
     query_id: Option<String>,
 ) -> Result<(), QueryError> {
-    let mut block = Block::<clickhouse_rs::Simple>::new();
     for field in fields.iter().rev() {
         block = add_last_column_to_block(block, &field.name, &mut rows, field.typ, field.nullable)?;
     }
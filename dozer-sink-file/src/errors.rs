@@ -0,0 +1,36 @@
+use dozer_types::arrow::error::ArrowError;
+use dozer_types::serde_json;
+use dozer_types::thiserror::{self, Error};
+use parquet::errors::ParquetError;
+
+#[derive(Error, Debug)]
+pub enum FileSinkError {
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Failed to create directory {0}: {1}")]
+    CreateDirectory(String, #[source] std::io::Error),
+
+    #[error("Failed to decode sink metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+
+    #[error("Connection {0} is not an S3 or local storage connection")]
+    UnsupportedConnection(String),
+
+    #[error("Partition column {0} not found")]
+    PartitionColumnNotFound(String),
+
+    #[error(
+        "Only inserts are supported, set `on_change` to `AppendOp` to write updates and deletes"
+    )]
+    UnsupportedOperation,
+
+    #[error("Files are append only, so truncates can't be written")]
+    UnsupportedTruncate,
+}
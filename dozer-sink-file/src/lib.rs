@@ -0,0 +1,8 @@
+pub mod errors;
+pub mod metadata;
+mod partition;
+mod sink;
+mod writer;
+pub use sink::FileSinkFactory;
+#[cfg(test)]
+mod tests;
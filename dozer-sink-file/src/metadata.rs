@@ -0,0 +1,62 @@
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::serde_json;
+use object_store::path::Path;
+use object_store::ObjectStore;
+
+use crate::errors::FileSinkError;
+
+/// Written next to the files, the leading underscore makes readers skip it.
+pub const METADATA_FILE_NAME: &str = "_dozer_metadata.json";
+
+/// The position the finished files have been written up to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct FileSinkMetadata {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl FileSinkMetadata {
+    pub async fn load(store: &dyn ObjectStore, path: &Path) -> Result<Self, FileSinkError> {
+        match store.get(path).await {
+            Ok(result) => Ok(serde_json::from_slice(&result.bytes().await?)?),
+            Err(object_store::Error::NotFound { .. }) => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn store(&self, store: &dyn ObjectStore, path: &Path) -> Result<(), FileSinkError> {
+        let contents = serde_json::to_vec(self)?;
+        store.put(path, contents.into()).await?;
+        Ok(())
+    }
+}
+
+/// Directory finished files are staged in until their epoch commits. The leading underscore makes readers skip it.
+pub const PREPARED_DIRECTORY: &str = "_dozer_prepared";
+
+/// Written once an epoch is prepared: the staged files to move in place, and the metadata that covers them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct PreparedEpoch {
+    /// Staged and final paths of the files.
+    pub files: Vec<(String, String)>,
+    pub metadata: FileSinkMetadata,
+}
+
+impl PreparedEpoch {
+    pub async fn load(store: &dyn ObjectStore, path: &Path) -> Result<Option<Self>, FileSinkError> {
+        match store.get(path).await {
+            Ok(result) => Ok(Some(serde_json::from_slice(&result.bytes().await?)?)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn store(&self, store: &dyn ObjectStore, path: &Path) -> Result<(), FileSinkError> {
+        let contents = serde_json::to_vec(self)?;
+        store.put(path, contents.into()).await?;
+        Ok(())
+    }
+}
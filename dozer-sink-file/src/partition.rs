@@ -0,0 +1,29 @@
+use dozer_types::types::Field;
+
+/// Directory Hive uses for null partition values.
+const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// The Hive style `column=value` directory of a partition value.
+pub fn partition_directory(column: &str, value: &Field) -> String {
+    let value = match value {
+        Field::Null => NULL_PARTITION.to_string(),
+        value => escape(&value.to_string()),
+    };
+    format!("{}={value}", escape(column))
+}
+
+/// Percent-encodes the characters that can't be part of a path segment, the way Hive does.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_control() || matches!(c, '/' | '\\' | '=' | '%' | ':' | '#' | '?' | '"' | '\'') {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
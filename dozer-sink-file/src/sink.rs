@@ -0,0 +1,525 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::arrow::datatypes::SchemaRef;
+use dozer_types::arrow_types::to_arrow::{map_records_to_arrow, map_to_arrow_schema};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::connection::ConnectionConfig;
+use dozer_types::models::sink::{FileSinkChangeHandling, FileSinkConfig};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::errors::FileSinkError;
+use crate::metadata::{FileSinkMetadata, PreparedEpoch, METADATA_FILE_NAME, PREPARED_DIRECTORY};
+use crate::partition::partition_directory;
+use crate::writer::{file_extension, FileWriter};
+
+pub const OP_COLUMN: &str = "op";
+
+/// Records are converted to Arrow in batches of this size.
+const WRITE_BATCH_SIZE: usize = 1024;
+
+#[derive(Debug)]
+pub struct FileSinkFactory {
+    runtime: Arc<Runtime>,
+    config: FileSinkConfig,
+    connection: Option<ConnectionConfig>,
+}
+
+impl FileSinkFactory {
+    pub fn new(
+        config: FileSinkConfig,
+        connection: Option<ConnectionConfig>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            runtime,
+            config,
+            connection,
+        }
+    }
+
+    /// The store to write to, and the prefix of all files within it.
+    fn object_store(&self) -> Result<(Arc<dyn ObjectStore>, Path), FileSinkError> {
+        let local_file_system = |path: &str| {
+            std::fs::create_dir_all(path)
+                .map_err(|e| FileSinkError::CreateDirectory(path.to_string(), e))?;
+            Ok::<Arc<dyn ObjectStore>, FileSinkError>(Arc::new(LocalFileSystem::new_with_prefix(
+                path,
+            )?))
+        };
+
+        Ok(match &self.connection {
+            None => (local_file_system(&self.config.path)?, Path::default()),
+            Some(ConnectionConfig::LocalStorage(storage)) => (
+                local_file_system(&storage.details.path)?,
+                Path::from(self.config.path.as_str()),
+            ),
+            Some(ConnectionConfig::S3Storage(storage)) => {
+                let details = &storage.details;
+                let store = AmazonS3Builder::new()
+                    .with_bucket_name(&details.bucket_name)
+                    .with_region(&details.region)
+                    .with_access_key_id(&details.access_key_id)
+                    .with_secret_access_key(&details.secret_access_key)
+                    .build()?;
+                (
+                    Arc::new(store) as Arc<dyn ObjectStore>,
+                    Path::from(self.config.path.as_str()),
+                )
+            }
+            Some(connection) => {
+                return Err(FileSinkError::UnsupportedConnection(
+                    connection.get_type_name(),
+                ))
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl SinkFactory for FileSinkFactory {
+    fn type_name(&self) -> String {
+        "file".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.source_table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let mut schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+
+        let partition_index = match &self.config.partition_by {
+            Some(column) => Some(
+                schema
+                    .get_field_index(column)
+                    .map_err(|_| FileSinkError::PartitionColumnNotFound(column.clone()))?
+                    .0,
+            ),
+            None => None,
+        };
+        if self.config.on_change == FileSinkChangeHandling::AppendOp {
+            schema.fields.push(FieldDefinition {
+                name: OP_COLUMN.to_string(),
+                typ: FieldType::String,
+                nullable: false,
+                source: SourceDefinition::Dynamic,
+                description: None,
+            });
+        }
+        let arrow_schema = Arc::new(map_to_arrow_schema(&schema)?);
+
+        let (store, prefix) = self.object_store()?;
+        // Sink methods are called from within the runtime while the DAG is built, so restore the metadata here.
+        let metadata_path = prefix.child(METADATA_FILE_NAME);
+        let metadata = FileSinkMetadata::load(store.as_ref(), &metadata_path).await?;
+        debug!(
+            "[Sink] Restored metadata for {}: {:?}",
+            self.config.path, metadata.op_id
+        );
+
+        let sink = FileSink {
+            store,
+            prefix,
+            metadata_path,
+            runtime: self.runtime.clone(),
+            config: self.config.clone(),
+            schema,
+            arrow_schema,
+            partition_index,
+            files: HashMap::new(),
+            opened_at: None,
+            // Files of earlier runs must not be overwritten.
+            run_id: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis()),
+            file_count: 0,
+            finished_files: vec![],
+            committed_metadata: metadata.clone(),
+            metadata,
+            source: None,
+        };
+
+        Ok(Box::new(sink))
+    }
+}
+
+/// A file that is being written, invisible until it's finished.
+struct OpenFile {
+    writer: FileWriter,
+    /// Records not yet converted to Arrow.
+    buffer: Vec<Record>,
+    rows: u64,
+}
+
+struct FileSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    metadata_path: Path,
+    runtime: Arc<Runtime>,
+    config: FileSinkConfig,
+    schema: Schema,
+    arrow_schema: SchemaRef,
+    partition_index: Option<usize>,
+    /// Open files by partition directory, empty if not partitioned.
+    files: HashMap<String, OpenFile>,
+    opened_at: Option<Instant>,
+    run_id: u128,
+    file_count: u64,
+    /// Contents of finished files that are not staged yet, by the path they belong at.
+    finished_files: Vec<(Path, Vec<u8>)>,
+    /// Metadata to be written when the open files are finished.
+    metadata: FileSinkMetadata,
+    /// Metadata of the last finished files.
+    committed_metadata: FileSinkMetadata,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+}
+
+impl Debug for FileSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSink")
+            .field("prefix", &self.prefix)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl FileSink {
+    fn write(&mut self, mut values: Vec<Field>, op: &str) -> Result<(), FileSinkError> {
+        if op != "I" && self.config.on_change == FileSinkChangeHandling::Reject {
+            return Err(FileSinkError::UnsupportedOperation);
+        }
+
+        let partition = match self.partition_index {
+            Some(index) => partition_directory(&self.schema.fields[index].name, &values[index]),
+            None => String::new(),
+        };
+        if self.config.on_change == FileSinkChangeHandling::AppendOp {
+            values.push(Field::String(op.to_string()));
+        }
+
+        let file = match self.files.entry(partition) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                self.opened_at.get_or_insert_with(Instant::now);
+                entry.insert(OpenFile {
+                    writer: FileWriter::new(self.config.format, self.arrow_schema.clone())?,
+                    buffer: vec![],
+                    rows: 0,
+                })
+            }
+        };
+        file.buffer.push(Record::new(values));
+        file.rows += 1;
+        if file.buffer.len() >= WRITE_BATCH_SIZE {
+            write_buffer(file, &self.schema)?;
+        }
+        Ok(())
+    }
+
+    fn should_finish_files(&self) -> bool {
+        let Some(opened_at) = self.opened_at else {
+            return false;
+        };
+        match (
+            self.config.max_rows_per_file,
+            self.config.max_file_duration_ms,
+        ) {
+            (None, None) => true,
+            (max_rows, max_duration_ms) => {
+                max_rows.is_some_and(|max_rows| self.files.values().any(|f| f.rows >= max_rows))
+                    || max_duration_ms.is_some_and(|max_duration_ms| {
+                        opened_at.elapsed() >= Duration::from_millis(max_duration_ms)
+                    })
+            }
+        }
+    }
+
+    /// Finishes the open files, returning their contents and the paths they belong at.
+    fn take_finished_files(&mut self) -> Result<Vec<(Path, Vec<u8>)>, FileSinkError> {
+        let extension = file_extension(self.config.format);
+        let mut finished_files = vec![];
+        for (partition, mut file) in std::mem::take(&mut self.files) {
+            write_buffer(&mut file, &self.schema)?;
+            let contents = file.writer.finish()?;
+
+            let name = format!("part-{}-{:05}.{extension}", self.run_id, self.file_count);
+            self.file_count += 1;
+            let path = if partition.is_empty() {
+                self.prefix.child(name)
+            } else {
+                self.prefix.child(partition).child(name)
+            };
+            debug!("[Sink] Writing {} rows to {path}", file.rows);
+            finished_files.push((path, contents));
+        }
+        self.opened_at = None;
+        Ok(finished_files)
+    }
+
+    /// Uploads the open files, then the metadata that covers them.
+    fn finish_files(&mut self) -> Result<(), FileSinkError> {
+        for (path, contents) in self.take_finished_files()? {
+            self.runtime
+                .block_on(self.store.put(&path, contents.into()))?;
+        }
+        self.store_metadata()
+    }
+
+    fn update_op_id(&mut self, epoch_details: &Epoch) {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.metadata.op_id = Some(*op_id);
+        }
+    }
+
+    fn prepared_directory(&self) -> Path {
+        self.prefix.child(PREPARED_DIRECTORY)
+    }
+
+    fn prepared_path(&self, epoch_id: u64) -> Path {
+        self.prepared_directory().child(format!("{epoch_id}.json"))
+    }
+
+    /// Stages the finished files in the prepared directory, and records them with the metadata that covers them.
+    fn prepare_epoch(&mut self, epoch_details: &Epoch) -> Result<(), FileSinkError> {
+        self.update_op_id(epoch_details);
+        if self.should_finish_files() {
+            let finished_files = self.take_finished_files()?;
+            self.finished_files.extend(finished_files);
+        }
+        // Without finished files, the position can only move on by itself if nothing is pending.
+        if self.finished_files.is_empty()
+            && (!self.files.is_empty() || self.metadata == self.committed_metadata)
+        {
+            return Ok(());
+        }
+
+        let epoch_id = epoch_details.common_info.id;
+        let epoch_directory = self.prepared_directory().child(epoch_id.to_string());
+        let mut files = vec![];
+        for (index, (path, contents)) in self.finished_files.iter().enumerate() {
+            let staged_path = epoch_directory.child(index.to_string());
+            self.runtime
+                .block_on(self.store.put(&staged_path, contents.clone().into()))?;
+            files.push((staged_path.to_string(), path.to_string()));
+        }
+        let prepared = PreparedEpoch {
+            files,
+            metadata: self.metadata.clone(),
+        };
+        self.runtime
+            .block_on(prepared.store(self.store.as_ref(), &self.prepared_path(epoch_id)))?;
+        self.finished_files.clear();
+        Ok(())
+    }
+
+    /// Moves the staged files in place, then stores the metadata that covers them.
+    fn commit_prepared_epoch(&mut self, epoch_id: u64) -> Result<(), FileSinkError> {
+        let prepared_path = self.prepared_path(epoch_id);
+        let Some(prepared) = self
+            .runtime
+            .block_on(PreparedEpoch::load(self.store.as_ref(), &prepared_path))?
+        else {
+            return Ok(());
+        };
+
+        for (staged_path, path) in &prepared.files {
+            let staged_path = Path::parse(staged_path).map_err(object_store::Error::from)?;
+            let path = Path::parse(path).map_err(object_store::Error::from)?;
+            match self
+                .runtime
+                .block_on(self.store.rename(&staged_path, &path))
+            {
+                // Files that are missing were moved by an earlier attempt.
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.metadata = prepared.metadata;
+        self.store_metadata()?;
+        self.delete(&prepared_path)
+    }
+
+    /// Deletes the files staged for the epoch.
+    fn abort_prepared_epoch(&mut self, epoch_id: u64) -> Result<(), FileSinkError> {
+        self.finished_files.clear();
+        let epoch_directory = self.prepared_directory().child(epoch_id.to_string());
+        for object in self.list(&epoch_directory)? {
+            self.delete(&object.location)?;
+        }
+        self.delete(&self.prepared_path(epoch_id))
+    }
+
+    fn prepared_epoch_ids(&self) -> Result<Vec<u64>, FileSinkError> {
+        Ok(self
+            .list(&self.prepared_directory())?
+            .iter()
+            .filter_map(|object| {
+                object
+                    .location
+                    .filename()?
+                    .strip_suffix(".json")?
+                    .parse()
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// Lists the objects directly within `directory`.
+    fn list(&self, directory: &Path) -> Result<Vec<ObjectMeta>, FileSinkError> {
+        match self
+            .runtime
+            .block_on(self.store.list_with_delimiter(Some(directory)))
+        {
+            Ok(result) => Ok(result.objects),
+            Err(object_store::Error::NotFound { .. }) => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), FileSinkError> {
+        match self.runtime.block_on(self.store.delete(path)) {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn store_metadata(&mut self) -> Result<(), FileSinkError> {
+        self.runtime.block_on(
+            self.metadata
+                .store(self.store.as_ref(), &self.metadata_path),
+        )?;
+        self.committed_metadata = self.metadata.clone();
+        Ok(())
+    }
+}
+
+fn write_buffer(file: &mut OpenFile, schema: &Schema) -> Result<(), FileSinkError> {
+    if file.buffer.is_empty() {
+        return Ok(());
+    }
+    let batch = map_records_to_arrow(std::mem::take(&mut file.buffer), schema)?;
+    file.writer.write(&batch)
+}
+
+impl Sink for FileSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        self.update_op_id(epoch_details);
+        if self.should_finish_files() {
+            self.finish_files()?;
+        } else if self.files.is_empty() && self.metadata != self.committed_metadata {
+            // Nothing is pending, so the position can move on by itself.
+            self.store_metadata()?;
+        }
+        Ok(())
+    }
+
+    fn supports_two_phase_commit(&self) -> bool {
+        true
+    }
+
+    fn prepare(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(self.prepare_epoch(epoch_details)?)
+    }
+
+    fn commit_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        Ok(self.commit_prepared_epoch(epoch_id)?)
+    }
+
+    fn abort_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        Ok(self.abort_prepared_epoch(epoch_id)?)
+    }
+
+    fn prepared_epochs(&mut self) -> Result<Vec<u64>, BoxedError> {
+        Ok(self.prepared_epoch_ids()?)
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        if op.id.is_some() {
+            self.metadata.op_id = op.id;
+        }
+        match op.op {
+            Operation::Insert { new } => self.write(new.values, "I")?,
+            Operation::Delete { old } => self.write(old.values, "D")?,
+            Operation::Update { new, .. } => self.write(new.values, "U")?,
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.write(record.values, "I")?;
+                }
+            }
+            Operation::Truncate => return Err(FileSinkError::UnsupportedTruncate.into()),
+        }
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.metadata.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.metadata.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.committed_metadata.source_state.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.committed_metadata.op_id)
+    }
+}
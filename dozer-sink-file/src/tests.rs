@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::models::sink::{FileFormat, FileSinkChangeHandling, FileSinkConfig};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::serde_json;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::metadata::{FileSinkMetadata, METADATA_FILE_NAME, PREPARED_DIRECTORY};
+use crate::partition::partition_directory;
+use crate::FileSinkFactory;
+
+fn get_schema() -> Schema {
+    let field = |name: &str, typ| FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable: true,
+        source: SourceDefinition::Dynamic,
+        description: None,
+    };
+    Schema {
+        fields: vec![
+            field("id", FieldType::Int),
+            field("country", FieldType::String),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn insert(id: i64, country: Field) -> TableOperation {
+    TableOperation::without_id(
+        Operation::Insert {
+            new: Record::new(vec![Field::Int(id), country]),
+        },
+        DEFAULT_PORT_HANDLE,
+    )
+}
+
+fn get_config(path: &str) -> FileSinkConfig {
+    FileSinkConfig {
+        connection: None,
+        path: path.to_string(),
+        source_table_name: "users".to_string(),
+        format: FileFormat::Csv,
+        max_rows_per_file: None,
+        max_file_duration_ms: None,
+        partition_by: None,
+        on_change: FileSinkChangeHandling::Reject,
+    }
+}
+
+fn get_epoch(txid: u64) -> Epoch {
+    let source_states = [(
+        NodeHandle::new(None, "source".to_string()),
+        SourceState::Restartable(OpIdentifier::new(txid, 0)),
+    )]
+    .into_iter()
+    .collect();
+    Epoch::new(txid, Arc::new(source_states), SystemTime::now())
+}
+
+fn build_sink(runtime: &Runtime, factory: &FileSinkFactory) -> Box<dyn Sink> {
+    let schemas = HashMap::from([(DEFAULT_PORT_HANDLE, get_schema())]);
+    let mut sink = runtime
+        .block_on(factory.build(schemas, EventHub::new(1)))
+        .unwrap();
+    sink.set_source(&NodeHandle::new(None, "source".to_string()))
+        .unwrap();
+    sink
+}
+
+/// Contents of the data files below `dir`, sorted by path. Staged files are skipped.
+fn read_files(dir: &std::path::Path) -> Vec<(String, String)> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('_'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(read_files(&path));
+        } else if !path.ends_with(METADATA_FILE_NAME) {
+            let contents = std::fs::read_to_string(&path).unwrap();
+            files.push((path.to_string_lossy().into_owned(), contents));
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn partition_directory_escapes_values() {
+    assert_eq!(
+        partition_directory("country", &Field::String("US".to_string())),
+        "country=US"
+    );
+    assert_eq!(
+        partition_directory("path", &Field::String("a/b=c".to_string())),
+        "path=a%2Fb%3Dc"
+    );
+    assert_eq!(
+        partition_directory("country", &Field::Null),
+        "country=__HIVE_DEFAULT_PARTITION__"
+    );
+}
+
+#[test]
+fn files_are_written_on_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = get_config(dir.path().to_str().unwrap());
+    let factory = FileSinkFactory::new(config, None, runtime.clone());
+
+    let mut sink = build_sink(&runtime, &factory);
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+
+    sink.process(insert(1, Field::String("US".to_string())))
+        .unwrap();
+    sink.process(insert(2, Field::String("DE".to_string())))
+        .unwrap();
+    assert!(read_files(dir.path()).is_empty());
+
+    sink.commit(&get_epoch(1)).unwrap();
+    let files = read_files(dir.path());
+    assert_eq!(files.len(), 1);
+    assert!(files[0].0.ends_with(".csv"));
+    assert_eq!(files[0].1, "id,country\n1,US\n2,DE\n");
+
+    // Updates are rejected unless the operation is recorded.
+    let update = TableOperation::without_id(
+        Operation::Update {
+            old: Record::new(vec![Field::Int(1), Field::Null]),
+            new: Record::new(vec![Field::Int(1), Field::Null]),
+        },
+        DEFAULT_PORT_HANDLE,
+    );
+    assert!(sink.process(update).is_err());
+
+    // A new sink resumes from the metadata of the finished files.
+    let mut sink = build_sink(&runtime, &factory);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+    let metadata: FileSinkMetadata =
+        serde_json::from_slice(&std::fs::read(dir.path().join(METADATA_FILE_NAME)).unwrap())
+            .unwrap();
+    assert_eq!(metadata.op_id, Some(OpIdentifier::new(1, 0)));
+}
+
+#[test]
+fn files_are_partitioned_and_rolled() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = FileSinkConfig {
+        max_rows_per_file: Some(2),
+        partition_by: Some("country".to_string()),
+        on_change: FileSinkChangeHandling::AppendOp,
+        ..get_config(dir.path().to_str().unwrap())
+    };
+    let factory = FileSinkFactory::new(config, None, runtime.clone());
+
+    let mut sink = build_sink(&runtime, &factory);
+
+    sink.process(insert(1, Field::String("US".to_string())))
+        .unwrap();
+    sink.process(insert(2, Field::Null)).unwrap();
+    sink.commit(&get_epoch(1)).unwrap();
+    // Neither file is full yet.
+    assert!(read_files(dir.path()).is_empty());
+
+    sink.process(TableOperation::without_id(
+        Operation::Delete {
+            old: Record::new(vec![Field::Int(1), Field::String("US".to_string())]),
+        },
+        DEFAULT_PORT_HANDLE,
+    ))
+    .unwrap();
+    sink.commit(&get_epoch(2)).unwrap();
+
+    let files = read_files(dir.path());
+    assert_eq!(files.len(), 2);
+    assert!(files[0].0.contains("country=US"));
+    assert_eq!(files[0].1, "id,country,op\n1,US,I\n1,US,D\n");
+    assert!(files[1].0.contains("country=__HIVE_DEFAULT_PARTITION__"));
+    assert_eq!(files[1].1, "id,country,op\n2,,I\n");
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(2, 0))
+    );
+}
+
+#[test]
+fn prepared_files_are_moved_on_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = get_config(dir.path().to_str().unwrap());
+    let factory = FileSinkFactory::new(config, None, runtime.clone());
+
+    let mut sink = build_sink(&runtime, &factory);
+    assert!(sink.supports_two_phase_commit());
+
+    sink.process(insert(1, Field::String("US".to_string())))
+        .unwrap();
+    sink.prepare(&get_epoch(1)).unwrap();
+    // Prepared files are staged, not visible.
+    assert!(read_files(dir.path()).is_empty());
+    assert!(dir.path().join(PREPARED_DIRECTORY).join("1.json").exists());
+    assert_eq!(sink.prepared_epochs().unwrap(), vec![1]);
+
+    sink.commit_prepared(1).unwrap();
+    let files = read_files(dir.path());
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].1, "id,country\n1,US\n");
+    assert!(sink.prepared_epochs().unwrap().is_empty());
+    // Committing again does nothing.
+    sink.commit_prepared(1).unwrap();
+
+    // An aborted epoch leaves nothing behind.
+    sink.process(insert(2, Field::String("DE".to_string())))
+        .unwrap();
+    sink.prepare(&get_epoch(2)).unwrap();
+    sink.abort_prepared(2).unwrap();
+    assert!(sink.prepared_epochs().unwrap().is_empty());
+    assert_eq!(read_files(dir.path()), files);
+
+    // An epoch left in doubt is committed by the next sink.
+    sink.process(insert(3, Field::String("FR".to_string())))
+        .unwrap();
+    sink.prepare(&get_epoch(3)).unwrap();
+    let mut sink = build_sink(&runtime, &factory);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+    assert_eq!(sink.prepared_epochs().unwrap(), vec![3]);
+    sink.commit_prepared(3).unwrap();
+    assert_eq!(read_files(dir.path()).len(), 2);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(3, 0))
+    );
+}
+
+#[test]
+fn op_id_of_own_source_is_stored() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = get_config(dir.path().to_str().unwrap());
+    let factory = FileSinkFactory::new(config, None, runtime.clone());
+    let mut sink = build_sink(&runtime, &factory);
+
+    let source_states = [
+        (
+            NodeHandle::new(None, "other".to_string()),
+            SourceState::Restartable(OpIdentifier::new(7, 0)),
+        ),
+        (
+            NodeHandle::new(None, "source".to_string()),
+            SourceState::Restartable(OpIdentifier::new(3, 0)),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    sink.process(insert(1, Field::String("US".to_string())))
+        .unwrap();
+    sink.commit(&Epoch::new(1, Arc::new(source_states), SystemTime::now()))
+        .unwrap();
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(3, 0))
+    );
+}
@@ -0,0 +1,54 @@
+use dozer_types::arrow::csv;
+use dozer_types::arrow::datatypes::SchemaRef;
+use dozer_types::arrow::json::LineDelimitedWriter;
+use dozer_types::arrow::record_batch::RecordBatch;
+use dozer_types::models::sink::FileFormat;
+use parquet::arrow::ArrowWriter;
+
+use crate::errors::FileSinkError;
+
+pub fn file_extension(format: FileFormat) -> &'static str {
+    match format {
+        FileFormat::Parquet => "parquet",
+        FileFormat::Csv => "csv",
+        FileFormat::JsonLines => "jsonl",
+    }
+}
+
+/// Encodes record batches into an in-memory file, which is only uploaded once it's complete.
+pub enum FileWriter {
+    Parquet(ArrowWriter<Vec<u8>>),
+    Csv(csv::Writer<Vec<u8>>),
+    JsonLines(LineDelimitedWriter<Vec<u8>>),
+}
+
+impl FileWriter {
+    pub fn new(format: FileFormat, schema: SchemaRef) -> Result<Self, FileSinkError> {
+        Ok(match format {
+            FileFormat::Parquet => FileWriter::Parquet(ArrowWriter::try_new(vec![], schema, None)?),
+            FileFormat::Csv => FileWriter::Csv(csv::Writer::new(vec![])),
+            FileFormat::JsonLines => FileWriter::JsonLines(LineDelimitedWriter::new(vec![])),
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), FileSinkError> {
+        match self {
+            FileWriter::Parquet(writer) => writer.write(batch)?,
+            FileWriter::Csv(writer) => writer.write(batch)?,
+            FileWriter::JsonLines(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    /// Writes the footer, if the format has one, and returns the file contents.
+    pub fn finish(self) -> Result<Vec<u8>, FileSinkError> {
+        Ok(match self {
+            FileWriter::Parquet(writer) => writer.into_inner()?,
+            FileWriter::Csv(writer) => writer.into_inner(),
+            FileWriter::JsonLines(mut writer) => {
+                writer.finish()?;
+                writer.into_inner()
+            }
+        })
+    }
+}
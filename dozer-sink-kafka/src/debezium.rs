@@ -0,0 +1,268 @@
+//! Debezium change events for Dozer records, the reverse of
+//! [`dozer_ingestion_kafka::debezium::mapper`].
+
+use base64::{engine, Engine};
+use dozer_ingestion_kafka::debezium::stream_consumer::{DebeziumPayload, DebeziumSchemaStruct};
+use dozer_types::chrono::Datelike;
+use dozer_types::json_types::json_to_string;
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::Serialize;
+use dozer_types::serde_json::{self, json, Map, Value};
+use dozer_types::types::{Field, FieldDefinition, FieldType, Record, Schema};
+
+pub const OP_CREATE: &str = "c";
+pub const OP_UPDATE: &str = "u";
+pub const OP_DELETE: &str = "d";
+/// Truncate events have neither a before nor an after, and no key.
+pub const OP_TRUNCATE: &str = "t";
+
+const CONNECTOR_NAME: &str = "dozer";
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Kafka Connect's JSON converter layout, with the schema embedded in every message.
+#[derive(Serialize)]
+#[serde(crate = "dozer_types::serde")]
+struct WithSchema<'a, T> {
+    schema: &'a DebeziumSchemaStruct,
+    payload: T,
+}
+
+/// Kafka Connect type and logical type a field is published as.
+fn connect_type(typ: FieldType) -> (&'static str, Option<&'static str>) {
+    match typ {
+        // Connect has no unsigned types.
+        FieldType::UInt | FieldType::Int => ("int64", None),
+        FieldType::Int8 => ("int8", None),
+        FieldType::U128 | FieldType::I128 => ("string", None),
+        FieldType::Float => ("double", None),
+        FieldType::Boolean => ("boolean", None),
+        FieldType::String | FieldType::Text => ("string", None),
+        FieldType::Binary => ("bytes", None),
+        // Same as Debezium's `decimal.handling.mode=string`.
+        FieldType::Decimal => ("string", None),
+        FieldType::Timestamp => ("int64", Some("io.debezium.time.MicroTimestamp")),
+        FieldType::Date => ("int32", Some("io.debezium.time.Date")),
+        FieldType::Json => ("string", Some("io.debezium.data.Json")),
+        FieldType::Point => ("struct", Some("io.debezium.data.geometry.Point")),
+        FieldType::Duration => ("int64", Some("io.debezium.time.MicroDuration")),
+    }
+}
+
+fn primitive_schema(
+    typ: &str,
+    name: Option<&str>,
+    field: &str,
+    optional: bool,
+) -> DebeziumSchemaStruct {
+    DebeziumSchemaStruct {
+        r#type: Value::from(typ),
+        fields: None,
+        optional: Some(optional),
+        name: name.map(str::to_string),
+        field: Some(field.to_string()),
+        version: None,
+        parameters: None,
+    }
+}
+
+fn struct_schema(
+    fields: Vec<DebeziumSchemaStruct>,
+    name: String,
+    field: Option<&str>,
+    optional: bool,
+) -> DebeziumSchemaStruct {
+    DebeziumSchemaStruct {
+        r#type: Value::from("struct"),
+        fields: Some(fields),
+        optional: Some(optional),
+        name: Some(name),
+        field: field.map(str::to_string),
+        version: None,
+        parameters: None,
+    }
+}
+
+pub fn field_schema(field: &FieldDefinition) -> DebeziumSchemaStruct {
+    let (typ, name) = connect_type(field.typ);
+    let mut schema = primitive_schema(typ, name, &field.name, field.nullable);
+    if field.typ == FieldType::Point {
+        schema.fields = Some(vec![
+            primitive_schema("double", None, "x", false),
+            primitive_schema("double", None, "y", false),
+        ]);
+    }
+    schema
+}
+
+fn source_schema() -> DebeziumSchemaStruct {
+    struct_schema(
+        vec![
+            primitive_schema("string", None, "connector", false),
+            primitive_schema("string", None, "table", false),
+            primitive_schema("int64", None, "txId", true),
+            primitive_schema("int64", None, "seq", true),
+        ],
+        "io.dozer.connector.Source".to_string(),
+        Some("source"),
+        false,
+    )
+}
+
+pub fn field_to_json(field: &Field) -> Value {
+    match field {
+        Field::UInt(value) => json!(value),
+        Field::U128(value) => Value::from(value.to_string()),
+        Field::Int(value) => json!(value),
+        Field::Int8(value) => json!(value),
+        Field::I128(value) => Value::from(value.to_string()),
+        Field::Float(value) => json!(value.0),
+        Field::Boolean(value) => Value::from(*value),
+        Field::String(value) | Field::Text(value) => Value::from(value.as_str()),
+        Field::Binary(value) => Value::from(engine::general_purpose::STANDARD.encode(value)),
+        Field::Decimal(value) => Value::from(value.to_string()),
+        Field::Timestamp(value) => Value::from(value.timestamp_micros()),
+        Field::Date(value) => Value::from(value.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE),
+        Field::Json(value) => Value::from(json_to_string(value)),
+        Field::Point(value) => json!({ "x": value.0.x().0, "y": value.0.y().0 }),
+        Field::Duration(value) => {
+            Value::from(i64::try_from(value.0.as_micros()).unwrap_or(i64::MAX))
+        }
+        Field::Null => Value::Null,
+    }
+}
+
+/// Encodes the change events of one table.
+#[derive(Debug)]
+pub struct DebeziumEncoder {
+    table_name: String,
+    schema: Schema,
+    key_schema: Option<DebeziumSchemaStruct>,
+    value_schema: DebeziumSchemaStruct,
+}
+
+impl DebeziumEncoder {
+    pub fn new(table_name: String, schema: Schema) -> Self {
+        // Like Debezium, tables without a primary key produce messages without a key.
+        let key_schema = (!schema.primary_index.is_empty()).then(|| {
+            struct_schema(
+                schema
+                    .primary_index
+                    .iter()
+                    .map(|index| field_schema(&schema.fields[*index]))
+                    .collect(),
+                format!("{table_name}.Key"),
+                None,
+                false,
+            )
+        });
+
+        let row_schema = |field| {
+            struct_schema(
+                schema.fields.iter().map(field_schema).collect(),
+                format!("{table_name}.Value"),
+                Some(field),
+                true,
+            )
+        };
+        let value_schema = struct_schema(
+            vec![
+                row_schema("before"),
+                row_schema("after"),
+                primitive_schema("string", None, "op", false),
+                source_schema(),
+                primitive_schema("int64", None, "ts_ms", true),
+            ],
+            format!("{table_name}.Envelope"),
+            None,
+            false,
+        );
+
+        Self {
+            table_name,
+            schema,
+            key_schema,
+            value_schema,
+        }
+    }
+
+    pub fn key_schema(&self) -> Option<&DebeziumSchemaStruct> {
+        self.key_schema.as_ref()
+    }
+
+    pub fn value_schema(&self) -> &DebeziumSchemaStruct {
+        &self.value_schema
+    }
+
+    fn row(&self, record: &Record) -> Value {
+        let row = self
+            .schema
+            .fields
+            .iter()
+            .zip(&record.values)
+            .map(|(field, value)| (field.name.clone(), field_to_json(value)))
+            .collect::<Map<_, _>>();
+        Value::Object(row)
+    }
+
+    /// The primary key of `record`, `None` if the table has none.
+    pub fn key(&self, record: &Record) -> Option<Value> {
+        self.key_schema.as_ref()?;
+        let key = self
+            .schema
+            .primary_index
+            .iter()
+            .map(|index| {
+                (
+                    self.schema.fields[*index].name.clone(),
+                    field_to_json(&record.values[*index]),
+                )
+            })
+            .collect::<Map<_, _>>();
+        Some(Value::Object(key))
+    }
+
+    pub fn payload(
+        &self,
+        op: &str,
+        before: Option<&Record>,
+        after: Option<&Record>,
+        id: Option<OpIdentifier>,
+        ts_ms: i64,
+    ) -> DebeziumPayload {
+        DebeziumPayload {
+            before: before.map(|record| self.row(record)),
+            after: after.map(|record| self.row(record)),
+            op: Some(op.to_string()),
+            source: Some(json!({
+                "connector": CONNECTOR_NAME,
+                "table": self.table_name,
+                "txId": id.map(|id| id.txid),
+                "seq": id.map(|id| id.seq_in_tx),
+            })),
+            ts_ms: Some(ts_ms),
+        }
+    }
+
+    /// Serializes a key with its schema embedded.
+    pub fn encode_key_with_schema(&self, key: &Value) -> Result<Vec<u8>, serde_json::Error> {
+        let schema = self
+            .key_schema
+            .as_ref()
+            .expect("keys are only produced for tables with a primary key");
+        serde_json::to_vec(&WithSchema {
+            schema,
+            payload: key,
+        })
+    }
+
+    /// Serializes a change event with its schema embedded.
+    pub fn encode_value_with_schema(
+        &self,
+        payload: &DebeziumPayload,
+    ) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&WithSchema {
+            schema: &self.value_schema,
+            payload,
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use dozer_types::serde_json;
+use dozer_types::thiserror::{self, Error};
+use schema_registry_converter::error::SRCError;
+
+#[derive(Error, Debug)]
+pub enum KafkaSinkError {
+    #[error("Kafka error: {0}")]
+    Kafka(#[from] rdkafka::error::KafkaError),
+
+    #[error("Message to topic {0} was not delivered")]
+    DeliveryCanceled(String),
+
+    #[error("Failed to register schema for subject {0}: {1}")]
+    SchemaRegistry(String, #[source] SRCError),
+
+    #[error("Connection {0} has no schema registry url")]
+    SchemaRegistryUrlNotFound(String),
+
+    #[error("Failed to create topic {0}: {1}")]
+    CreateTopic(String, rdkafka::types::RDKafkaErrorCode),
+
+    #[error("Timed out reading the sink metadata from topic {0}")]
+    MetadataReadTimeout(String),
+
+    #[error("JSON encode error: {0}")]
+    Json(#[from] serde_json::Error),
+}
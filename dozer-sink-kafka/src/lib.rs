@@ -0,0 +1,8 @@
+pub mod debezium;
+pub mod errors;
+mod metadata;
+mod registry;
+mod sink;
+pub use sink::KafkaSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::serde_json;
+use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+use rdkafka::client::DefaultClientContext;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::{ClientConfig, Message, Offset, TopicPartitionList};
+
+use crate::errors::KafkaSinkError;
+
+/// Topic the sinks store what they have committed in, unless configured otherwise.
+pub const DEFAULT_METADATA_TOPIC: &str = "__dozer_sink_metadata";
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a sink has committed, keyed by the sink's name in the compacted metadata topic.
+///
+/// A transactional sink produces it in the same transaction as the data, so it never runs ahead of or behind the topics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct ReplicationMetadata {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl ReplicationMetadata {
+    /// Returns the last committed metadata of sink `key`, reading the topic from the beginning.
+    ///
+    /// Blocks, so it's called outside of the runtime.
+    pub fn load(
+        client_config: &ClientConfig,
+        topic: &str,
+        key: &str,
+    ) -> Result<Self, KafkaSinkError> {
+        let consumer: BaseConsumer = client_config
+            .clone()
+            .set("group.id", format!("{topic}_{key}"))
+            .set("enable.auto.commit", "false")
+            .set("enable.partition.eof", "true")
+            .set("isolation.level", "read_committed")
+            .create()?;
+        let mut partitions = TopicPartitionList::new();
+        partitions.add_partition_offset(topic, 0, Offset::Beginning)?;
+        consumer.assign(&partitions)?;
+
+        let mut metadata = Self::default();
+        loop {
+            match consumer.poll(READ_TIMEOUT) {
+                Some(Ok(message)) => {
+                    if message.key() != Some(key.as_bytes()) {
+                        continue;
+                    }
+                    metadata = match message.payload() {
+                        Some(payload) => serde_json::from_slice(payload)?,
+                        None => Self::default(),
+                    };
+                }
+                Some(Err(KafkaError::PartitionEOF(_))) => return Ok(metadata),
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err(KafkaSinkError::MetadataReadTimeout(topic.to_string())),
+            }
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, KafkaSinkError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+/// Creates the compacted metadata topic if it doesn't exist. It has one partition, so the sinks' messages stay in order.
+pub async fn create_metadata_topic(
+    client_config: &ClientConfig,
+    topic: &str,
+) -> Result<(), KafkaSinkError> {
+    let admin: AdminClient<DefaultClientContext> = client_config.create()?;
+    // A replication factor of -1 uses the broker's default.
+    let new_topic =
+        NewTopic::new(topic, 1, TopicReplication::Fixed(-1)).set("cleanup.policy", "compact");
+    for result in admin
+        .create_topics(&[new_topic], &AdminOptions::new())
+        .await?
+    {
+        match result {
+            Ok(_) | Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+            Err((topic, code)) => return Err(KafkaSinkError::CreateTopic(topic, code)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use dozer_ingestion_kafka::debezium::stream_consumer::DebeziumSchemaStruct;
+use dozer_types::serde_json::{json, Map, Value};
+use schema_registry_converter::async_impl::schema_registry::{post_schema, SrSettings};
+use schema_registry_converter::schema_registry_common::{SchemaType, SuppliedSchema};
+
+use crate::debezium::DebeziumEncoder;
+use crate::errors::KafkaSinkError;
+
+/// Confluent wire format, a magic byte and the schema id precede the payload.
+const MAGIC_BYTE: u8 = 0;
+
+/// Ids of the schemas registered for a topic.
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaIds {
+    pub key: Option<u32>,
+    pub value: u32,
+}
+
+/// Registers the key and value schemas of a table under the topic name strategy.
+pub async fn register_schemas(
+    sr_settings: &SrSettings,
+    topic: &str,
+    encoder: &DebeziumEncoder,
+) -> Result<SchemaIds, KafkaSinkError> {
+    let key = match encoder.key_schema() {
+        Some(schema) => Some(register_schema(sr_settings, format!("{topic}-key"), schema).await?),
+        None => None,
+    };
+    let value = register_schema(
+        sr_settings,
+        format!("{topic}-value"),
+        encoder.value_schema(),
+    )
+    .await?;
+    Ok(SchemaIds { key, value })
+}
+
+async fn register_schema(
+    sr_settings: &SrSettings,
+    subject: String,
+    schema: &DebeziumSchemaStruct,
+) -> Result<u32, KafkaSinkError> {
+    let supplied_schema = SuppliedSchema {
+        name: schema.name.clone(),
+        schema_type: SchemaType::Json,
+        schema: to_json_schema(schema).to_string(),
+        references: vec![],
+    };
+    let registered_schema = post_schema(sr_settings, subject.clone(), supplied_schema)
+        .await
+        .map_err(|e| KafkaSinkError::SchemaRegistry(subject, e))?;
+    Ok(registered_schema.id)
+}
+
+/// Translates a Kafka Connect schema to the JSON Schema registered for it.
+pub fn to_json_schema(schema: &DebeziumSchemaStruct) -> Value {
+    let mut json_schema = match schema.r#type.as_str().unwrap_or_default() {
+        "struct" => {
+            let fields = schema.fields.as_deref().unwrap_or_default();
+            let properties = fields
+                .iter()
+                .filter_map(|field| Some((field.field.clone()?, to_json_schema(field))))
+                .collect::<Map<_, _>>();
+            let required = fields
+                .iter()
+                .filter(|field| field.optional != Some(true))
+                .filter_map(|field| field.field.clone())
+                .collect::<Vec<_>>();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+        "int8" | "int16" | "int32" | "int64" => json!({ "type": "integer" }),
+        "float" | "float32" | "float64" | "double" => json!({ "type": "number" }),
+        "boolean" => json!({ "type": "boolean" }),
+        "bytes" => json!({ "type": "string", "contentEncoding": "base64" }),
+        _ => json!({ "type": "string" }),
+    };
+    if let Some(name) = &schema.name {
+        json_schema["title"] = Value::from(name.as_str());
+    }
+
+    if schema.optional == Some(true) {
+        json!({ "oneOf": [{ "type": "null" }, json_schema] })
+    } else {
+        json_schema
+    }
+}
+
+pub fn frame(schema_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(payload.len() + 5);
+    message.push(MAGIC_BYTE);
+    message.extend_from_slice(&schema_id.to_be_bytes());
+    message.extend_from_slice(payload);
+    message
+}
@@ -0,0 +1,496 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::{self, runtime::Runtime};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::ingestion_types::KafkaConfig;
+use dozer_types::models::sink::KafkaSinkConfig;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
+use rdkafka::ClientConfig;
+use schema_registry_converter::async_impl::schema_registry::SrSettings;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::debezium::{DebeziumEncoder, OP_CREATE, OP_DELETE, OP_TRUNCATE, OP_UPDATE};
+use crate::errors::KafkaSinkError;
+use crate::metadata::{create_metadata_topic, ReplicationMetadata, DEFAULT_METADATA_TOPIC};
+use crate::registry::{frame, register_schemas, SchemaIds};
+
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub struct KafkaSinkFactory {
+    runtime: Arc<Runtime>,
+    config: KafkaSinkConfig,
+    /// Keys the sink's messages in the metadata topic.
+    sink_name: String,
+    connection_name: String,
+    connection: KafkaConfig,
+}
+
+impl KafkaSinkFactory {
+    pub fn new(
+        config: KafkaSinkConfig,
+        sink_name: String,
+        connection_name: String,
+        connection: KafkaConfig,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            runtime,
+            config,
+            sink_name,
+            connection_name,
+            connection,
+        }
+    }
+
+    fn client_config(&self) -> ClientConfig {
+        let mut client_config = ClientConfig::new();
+        client_config.set("bootstrap.servers", &self.connection.broker);
+        client_config
+    }
+
+    fn create_producer(&self) -> Result<FutureProducer, KafkaSinkError> {
+        let mut client_config = self.client_config();
+        client_config.set("enable.idempotence", "true");
+        if let Some(transactional_id) = &self.config.transactional_id {
+            client_config.set("transactional.id", transactional_id);
+        }
+        Ok(client_config.create()?)
+    }
+
+    fn schema_registry(&self) -> Result<Option<SrSettings>, KafkaSinkError> {
+        if !self.config.use_schema_registry {
+            return Ok(None);
+        }
+        let url = self.connection.schema_registry_url.clone().ok_or_else(|| {
+            KafkaSinkError::SchemaRegistryUrlNotFound(self.connection_name.clone())
+        })?;
+        Ok(Some(SrSettings::new(url)))
+    }
+}
+
+#[async_trait]
+impl SinkFactory for KafkaSinkFactory {
+    fn type_name(&self) -> String {
+        "kafka".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        (0..self.config.tables.len() as PortHandle).collect()
+    }
+
+    fn get_input_port_name(&self, port: &PortHandle) -> String {
+        self.config.tables[*port as usize].source_table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == self.config.tables.len());
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let producer = self.create_producer()?;
+        let transactional = self.config.transactional_id.is_some();
+        if transactional {
+            // Fences off older producers with the same id, which blocks.
+            let producer = producer.clone();
+            tokio::task::spawn_blocking(move || producer.init_transactions(TRANSACTION_TIMEOUT))
+                .await?
+                .map_err(KafkaSinkError::Kafka)?;
+        }
+
+        let metadata_topic = self
+            .config
+            .metadata_topic
+            .clone()
+            .unwrap_or_else(|| DEFAULT_METADATA_TOPIC.to_string());
+        let client_config = self.client_config();
+        create_metadata_topic(&client_config, &metadata_topic).await?;
+        let metadata = {
+            let (metadata_topic, sink_name) = (metadata_topic.clone(), self.sink_name.clone());
+            tokio::task::spawn_blocking(move || {
+                ReplicationMetadata::load(&client_config, &metadata_topic, &sink_name)
+            })
+            .await??
+        };
+        debug!(
+            "[Sink] Restored metadata for {}: {:?}",
+            self.sink_name, metadata.op_id
+        );
+
+        let sr_settings = self.schema_registry()?;
+        let mut topics = Vec::with_capacity(self.config.tables.len());
+        for (port, table) in self.config.tables.iter().enumerate() {
+            let schema = input_schemas.remove(&(port as PortHandle)).unwrap();
+            let topic = self.config.topic(table);
+            let encoder = DebeziumEncoder::new(table.source_table_name.clone(), schema);
+            let schema_ids = match &sr_settings {
+                Some(sr_settings) => Some(register_schemas(sr_settings, &topic, &encoder).await?),
+                None => None,
+            };
+            debug!(
+                "[Sink] Publishing {} to topic {topic}",
+                table.source_table_name
+            );
+            topics.push(TopicWriter {
+                topic,
+                encoder,
+                schema_ids,
+            });
+        }
+
+        let sink = KafkaSink {
+            producer,
+            runtime: self.runtime.clone(),
+            topics,
+            transactional,
+            in_transaction: false,
+            tombstones_on_delete: self.config.tombstones_on_delete,
+            pending_deliveries: vec![],
+            metadata_topic,
+            sink_name: self.sink_name.clone(),
+            committed_metadata: metadata.clone(),
+            metadata,
+            source: None,
+        };
+
+        Ok(Box::new(sink))
+    }
+}
+
+/// Encodes the messages of one table for its topic.
+#[derive(Debug)]
+struct TopicWriter {
+    topic: String,
+    encoder: DebeziumEncoder,
+    schema_ids: Option<SchemaIds>,
+}
+
+/// A message ready to be produced.
+struct Message {
+    key: Option<Vec<u8>>,
+    /// `None` for tombstones.
+    payload: Option<Vec<u8>>,
+}
+
+impl TopicWriter {
+    fn event(
+        &self,
+        op: &str,
+        before: Option<&Record>,
+        after: Option<&Record>,
+        id: Option<OpIdentifier>,
+    ) -> Result<Message, KafkaSinkError> {
+        let key = match after.or(before) {
+            Some(record) => self.key(record)?,
+            None => None,
+        };
+        let payload = self.encoder.payload(op, before, after, id, now_ms());
+        let payload = match self.schema_ids {
+            Some(schema_ids) => frame(schema_ids.value, &to_json_vec(&payload)?),
+            None => self.encoder.encode_value_with_schema(&payload)?,
+        };
+        Ok(Message {
+            key,
+            payload: Some(payload),
+        })
+    }
+
+    fn tombstone(&self, record: &Record) -> Result<Option<Message>, KafkaSinkError> {
+        // Compaction can't remove messages without a key.
+        let Some(key) = self.key(record)? else {
+            return Ok(None);
+        };
+        Ok(Some(Message {
+            key: Some(key),
+            payload: None,
+        }))
+    }
+
+    fn key(&self, record: &Record) -> Result<Option<Vec<u8>>, KafkaSinkError> {
+        let Some(key) = self.encoder.key(record) else {
+            return Ok(None);
+        };
+        let key = match self.schema_ids.and_then(|schema_ids| schema_ids.key) {
+            Some(schema_id) => frame(schema_id, &to_json_vec(&key)?),
+            None => self.encoder.encode_key_with_schema(&key)?,
+        };
+        Ok(Some(key))
+    }
+}
+
+fn to_json_vec<T: dozer_types::serde::Serialize>(value: &T) -> Result<Vec<u8>, KafkaSinkError> {
+    Ok(dozer_types::serde_json::to_vec(value)?)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+struct KafkaSink {
+    producer: FutureProducer,
+    runtime: Arc<Runtime>,
+    /// Indexed by input port.
+    topics: Vec<TopicWriter>,
+    transactional: bool,
+    in_transaction: bool,
+    tombstones_on_delete: bool,
+    pending_deliveries: Vec<(String, DeliveryFuture)>,
+    metadata_topic: String,
+    sink_name: String,
+    /// Metadata to be produced with the next epoch.
+    metadata: ReplicationMetadata,
+    /// Metadata as it is in the metadata topic.
+    committed_metadata: ReplicationMetadata,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+}
+
+impl Debug for KafkaSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSink")
+            .field("topics", &self.topics)
+            .field("transactional", &self.transactional)
+            .finish()
+    }
+}
+
+impl KafkaSink {
+    fn send(&mut self, port: PortHandle, message: Message) -> Result<(), KafkaSinkError> {
+        let topic = self.topics[port as usize].topic.clone();
+        self.produce(&topic, message)
+    }
+
+    fn produce(&mut self, topic: &str, message: Message) -> Result<(), KafkaSinkError> {
+        if self.transactional && !self.in_transaction {
+            self.producer.begin_transaction()?;
+            self.in_transaction = true;
+        }
+
+        loop {
+            let mut record = FutureRecord::<[u8], [u8]>::to(topic);
+            if let Some(key) = &message.key {
+                record = record.key(key.as_slice());
+            }
+            if let Some(payload) = &message.payload {
+                record = record.payload(payload.as_slice());
+            }
+            match self.producer.send_result(record) {
+                Ok(delivery) => {
+                    self.pending_deliveries.push((topic.to_string(), delivery));
+                    return Ok(());
+                }
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _))
+                    if !self.pending_deliveries.is_empty() =>
+                {
+                    wait_for_deliveries(&self.runtime, &mut self.pending_deliveries)?;
+                }
+                Err((e, _)) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn send_event(
+        &mut self,
+        port: PortHandle,
+        op: &str,
+        before: Option<&Record>,
+        after: Option<&Record>,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), KafkaSinkError> {
+        let message = self.topics[port as usize].event(op, before, after, id)?;
+        self.send(port, message)
+    }
+
+    fn update_op_id(&mut self, epoch_details: &Epoch) {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.metadata.op_id = Some(*op_id);
+        }
+    }
+
+    /// Produces the metadata if it changed, in the open transaction if there is one.
+    fn send_metadata(&mut self) -> Result<(), KafkaSinkError> {
+        if self.metadata == self.committed_metadata {
+            return Ok(());
+        }
+        let message = Message {
+            key: Some(self.sink_name.as_bytes().to_vec()),
+            payload: Some(self.metadata.encode()?),
+        };
+        let topic = self.metadata_topic.clone();
+        self.produce(&topic, message)
+    }
+
+    fn send_delete(
+        &mut self,
+        port: PortHandle,
+        old: &Record,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), KafkaSinkError> {
+        self.send_event(port, OP_DELETE, Some(old), None, id)?;
+        if self.tombstones_on_delete {
+            if let Some(tombstone) = self.topics[port as usize].tombstone(old)? {
+                self.send(port, tombstone)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn wait_for_deliveries(
+    runtime: &Runtime,
+    pending_deliveries: &mut Vec<(String, DeliveryFuture)>,
+) -> Result<(), KafkaSinkError> {
+    for (topic, delivery) in pending_deliveries.drain(..) {
+        match runtime.block_on(delivery) {
+            Ok(Ok(_)) => {}
+            Ok(Err((e, _))) => return Err(e.into()),
+            Err(_) => return Err(KafkaSinkError::DeliveryCanceled(topic)),
+        }
+    }
+    Ok(())
+}
+
+impl Sink for KafkaSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        // Without transactions, the epoch is committed once its messages are delivered.
+        // The metadata is produced after them, so it never runs ahead of the topics.
+        self.update_op_id(epoch_details);
+        wait_for_deliveries(&self.runtime, &mut self.pending_deliveries)?;
+        self.send_metadata()?;
+        wait_for_deliveries(&self.runtime, &mut self.pending_deliveries)?;
+        self.committed_metadata = self.metadata.clone();
+        Ok(())
+    }
+
+    fn supports_two_phase_commit(&self) -> bool {
+        self.transactional
+    }
+
+    fn prepare(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        self.update_op_id(epoch_details);
+        self.send_metadata()?;
+        self.producer
+            .flush(TRANSACTION_TIMEOUT)
+            .map_err(KafkaSinkError::Kafka)?;
+        wait_for_deliveries(&self.runtime, &mut self.pending_deliveries)?;
+        Ok(())
+    }
+
+    fn commit_prepared(&mut self, _epoch_id: u64) -> Result<(), BoxedError> {
+        if self.in_transaction {
+            self.producer
+                .commit_transaction(TRANSACTION_TIMEOUT)
+                .map_err(KafkaSinkError::Kafka)?;
+            self.in_transaction = false;
+        }
+        self.committed_metadata = self.metadata.clone();
+        Ok(())
+    }
+
+    fn abort_prepared(&mut self, _epoch_id: u64) -> Result<(), BoxedError> {
+        self.pending_deliveries.clear();
+        self.metadata = self.committed_metadata.clone();
+        if self.in_transaction {
+            self.producer
+                .abort_transaction(TRANSACTION_TIMEOUT)
+                .map_err(KafkaSinkError::Kafka)?;
+            self.in_transaction = false;
+        }
+        Ok(())
+    }
+
+    fn prepared_epochs(&mut self) -> Result<Vec<u64>, BoxedError> {
+        // A transaction doesn't outlive its producer: `init_transactions` aborts whatever the
+        // previous producer with the same id left open. The metadata produced in that transaction is
+        // aborted with it, so the sink resumes from the last committed op id and the epochs are sent again.
+        Ok(vec![])
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        let port = op.port;
+        match op.op {
+            Operation::Insert { new } => {
+                self.send_event(port, OP_CREATE, None, Some(&new), op.id)?;
+            }
+            Operation::Delete { old } => {
+                self.send_delete(port, &old, op.id)?;
+            }
+            Operation::Update { old, new } => {
+                let encoder = &self.topics[port as usize].encoder;
+                // Like Debezium, a changed key is a delete of the old key and a create of the new one.
+                if encoder.key(&old) != encoder.key(&new) {
+                    self.send_delete(port, &old, op.id)?;
+                    self.send_event(port, OP_CREATE, None, Some(&new), op.id)?;
+                } else {
+                    self.send_event(port, OP_UPDATE, Some(&old), Some(&new), op.id)?;
+                }
+            }
+            Operation::BatchInsert { new } => {
+                for record in &new {
+                    self.send_event(port, OP_CREATE, None, Some(record), op.id)?;
+                }
+            }
+            Operation::Truncate => {
+                self.send_event(port, OP_TRUNCATE, None, None, op.id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.metadata.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.metadata.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.metadata.source_state.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.metadata.op_id)
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::resolve_prepared_epochs;
+use dozer_core::tokio::runtime::Runtime;
+use dozer_ingestion_kafka::debezium::schema::map_schema;
+use dozer_ingestion_kafka::debezium::stream_consumer::DebeziumMessage;
+use dozer_types::chrono::{DateTime, NaiveDate};
+use dozer_types::models::ingestion_types::KafkaConfig;
+use dozer_types::models::sink::{KafkaSinkConfig, KafkaSinkTable};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde_json::{self, json};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::{ClientConfig, Message};
+
+use crate::debezium::{field_to_json, DebeziumEncoder, OP_UPDATE};
+use crate::registry::{frame, to_json_schema};
+use crate::KafkaSinkFactory;
+
+const BROKER: &str = "localhost:9092";
+
+fn field(name: &str, typ: FieldType, nullable: bool) -> FieldDefinition {
+    FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable,
+        source: SourceDefinition::Dynamic,
+        description: None,
+    }
+}
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("id", FieldType::Int, false),
+            field("name", FieldType::String, true),
+            field("price", FieldType::Float, false),
+            field("active", FieldType::Boolean, false),
+            field("data", FieldType::Binary, true),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_record(id: i64, name: &str) -> Record {
+    Record::new(vec![
+        Field::Int(id),
+        Field::String(name.to_string()),
+        Field::Float(1.5.into()),
+        Field::Boolean(true),
+        Field::Null,
+    ])
+}
+
+#[test]
+fn test_field_to_json() {
+    assert_eq!(field_to_json(&Field::Int(-5)), json!(-5));
+    assert_eq!(field_to_json(&Field::Binary(vec![1, 2, 3])), json!("AQID"));
+    assert_eq!(
+        field_to_json(&Field::Decimal(Decimal::new(12345, 2))),
+        json!("123.45")
+    );
+    assert_eq!(
+        field_to_json(&Field::Date(NaiveDate::from_ymd_opt(1970, 1, 11).unwrap())),
+        json!(10)
+    );
+    assert_eq!(
+        field_to_json(&Field::Timestamp(
+            DateTime::parse_from_rfc3339("1970-01-01T01:00:00.000001+01:00").unwrap()
+        )),
+        json!(1)
+    );
+    assert_eq!(field_to_json(&Field::Null), json!(null));
+}
+
+#[test]
+fn test_envelope_can_be_ingested() {
+    let schema = get_schema();
+    let encoder = DebeziumEncoder::new("products".to_string(), schema.clone());
+    let (old, new) = (get_record(1, "old"), get_record(1, "new"));
+
+    let payload = encoder.payload(
+        OP_UPDATE,
+        Some(&old),
+        Some(&new),
+        Some(OpIdentifier::new(7, 2)),
+        0,
+    );
+    let value: DebeziumMessage =
+        serde_json::from_slice(&encoder.encode_value_with_schema(&payload).unwrap()).unwrap();
+    let key: DebeziumMessage = serde_json::from_slice(
+        &encoder
+            .encode_key_with_schema(&encoder.key(&new).unwrap())
+            .unwrap(),
+    )
+    .unwrap();
+
+    let (ingested_schema, _) = map_schema(&value.schema, &key.schema).unwrap();
+    assert_eq!(ingested_schema, schema);
+
+    assert_eq!(value.payload.op.as_deref(), Some(OP_UPDATE));
+    assert_eq!(value.payload.before.unwrap()["name"], json!("old"));
+    assert_eq!(value.payload.after.unwrap()["name"], json!("new"));
+    assert_eq!(value.payload.source.unwrap()["txId"], json!(7));
+}
+
+#[test]
+fn test_tables_without_primary_key_have_no_key() {
+    let schema = Schema {
+        primary_index: vec![],
+        ..get_schema()
+    };
+    let encoder = DebeziumEncoder::new("products".to_string(), schema);
+    assert!(encoder.key_schema().is_none());
+    assert!(encoder.key(&get_record(1, "a")).is_none());
+}
+
+#[test]
+fn test_json_schema() {
+    let encoder = DebeziumEncoder::new("products".to_string(), get_schema());
+    let key_schema = to_json_schema(encoder.key_schema().unwrap());
+    assert_eq!(
+        key_schema,
+        json!({
+            "type": "object",
+            "properties": { "id": { "type": "integer" } },
+            "required": ["id"],
+            "title": "products.Key",
+        })
+    );
+
+    let value_schema = to_json_schema(encoder.value_schema());
+    assert_eq!(value_schema["required"], json!(["op", "source"]));
+    assert_eq!(
+        value_schema["properties"]["after"]["oneOf"][1]["properties"]["name"],
+        json!({ "oneOf": [{ "type": "null" }, { "type": "string" }] })
+    );
+}
+
+#[test]
+fn test_frame() {
+    assert_eq!(frame(258, b"{}"), vec![0, 0, 0, 1, 2, b'{', b'}']);
+}
+
+fn get_epoch(id: u64) -> Epoch {
+    let source_states = [(
+        NodeHandle::new(None, "source".to_string()),
+        SourceState::Restartable(OpIdentifier::new(id, 0)),
+    )]
+    .into_iter()
+    .collect();
+    Epoch::new(id, Arc::new(source_states), SystemTime::now())
+}
+
+fn build_transactional_sink(runtime: &Arc<Runtime>, topic: &str) -> Box<dyn Sink> {
+    let config = KafkaSinkConfig {
+        connection: "kafka".to_string(),
+        tables: vec![KafkaSinkTable {
+            source_table_name: "products".to_string(),
+            topic: Some(topic.to_string()),
+        }],
+        topic_prefix: None,
+        use_schema_registry: false,
+        transactional_id: Some(format!("{topic}_producer")),
+        tombstones_on_delete: true,
+        metadata_topic: Some(format!("{topic}_metadata")),
+    };
+    let connection = KafkaConfig {
+        broker: BROKER.to_string(),
+        schema_registry_url: None,
+    };
+    let factory = KafkaSinkFactory::new(
+        config,
+        "sink".to_string(),
+        "kafka".to_string(),
+        connection,
+        runtime.clone(),
+    );
+    let mut sink = runtime
+        .block_on(factory.build(HashMap::from([(0, get_schema())]), EventHub::new(1)))
+        .unwrap();
+    sink.set_source(&NodeHandle::new(None, "source".to_string()))
+        .unwrap();
+    sink
+}
+
+/// The ids of the records created in `topic`, as a consumer that only reads committed messages sees them.
+fn read_committed_ids(topic: &str) -> Vec<serde_json::Value> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", BROKER)
+        .set("group.id", topic)
+        .set("isolation.level", "read_committed")
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .unwrap();
+    consumer.subscribe(&[topic]).unwrap();
+    let mut ids = vec![];
+    while let Some(message) = consumer.poll(Duration::from_secs(10)) {
+        let message = message.unwrap();
+        let value: DebeziumMessage = serde_json::from_slice(message.payload().unwrap()).unwrap();
+        ids.push(value.payload.after.unwrap()["id"].clone());
+    }
+    ids
+}
+
+#[test]
+#[ignore]
+fn test_prepared_epochs_are_resolved() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let topic = format!("two_phase_commit_{millis}");
+    let insert = |id: i64| {
+        TableOperation::without_id(
+            Operation::Insert {
+                new: get_record(id, "name"),
+            },
+            0,
+        )
+    };
+    let handle = NodeHandle::new(None, "sink".to_string());
+
+    let mut sink = build_transactional_sink(&runtime, &topic);
+    assert!(sink.supports_two_phase_commit());
+    sink.process(insert(1)).unwrap();
+    sink.prepare(&get_epoch(1)).unwrap();
+    drop(sink);
+
+    // The new producer aborts the transaction left open, so there's nothing to commit.
+    let mut sink = build_transactional_sink(&runtime, &topic);
+    assert!(sink.prepared_epochs().unwrap().is_empty());
+    resolve_prepared_epochs(&handle, sink.as_mut()).unwrap();
+
+    sink.process(insert(2)).unwrap();
+    sink.prepare(&get_epoch(2)).unwrap();
+    sink.commit_prepared(2).unwrap();
+    sink.process(insert(3)).unwrap();
+    sink.prepare(&get_epoch(3)).unwrap();
+    sink.abort_prepared(3).unwrap();
+    // Committing an aborted epoch does nothing.
+    sink.commit_prepared(3).unwrap();
+
+    assert_eq!(read_committed_ids(&topic), vec![json!(2)]);
+}
+
+#[test]
+#[ignore]
+fn test_committed_op_id_is_restored() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let topic = format!("op_id_{millis}");
+
+    let mut sink = build_transactional_sink(&runtime, &topic);
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    sink.process(TableOperation::without_id(
+        Operation::Insert {
+            new: get_record(1, "name"),
+        },
+        0,
+    ))
+    .unwrap();
+    sink.prepare(&get_epoch(1)).unwrap();
+    sink.commit_prepared(1).unwrap();
+    // Epoch 2 is left open, and aborted when the next producer starts.
+    sink.prepare(&get_epoch(2)).unwrap();
+    drop(sink);
+
+    let mut sink = build_transactional_sink(&runtime, &topic);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+}
@@ -0,0 +1,94 @@
+use dozer_types::indexmap::IndexMap;
+use dozer_types::types::Record;
+
+use crate::errors::MySQLSinkError;
+
+/// The net effect of all operations on one primary key since the last flush.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PendingOperation {
+    Upsert(Record),
+    Delete(Record),
+}
+
+/// Operations buffered between two flushes, collapsed by primary key.
+///
+/// Only the last operation on a key matters, so the flush can apply all deletes and all upserts
+/// in separate batched statements.
+#[derive(Debug)]
+pub(crate) struct Batch {
+    table_name: String,
+    primary_index: Vec<usize>,
+    operations: IndexMap<Vec<u8>, PendingOperation>,
+    inserts_without_key: Vec<Record>,
+    /// If the table must be emptied before the operations are applied.
+    truncate: bool,
+}
+
+impl Batch {
+    pub fn new(table_name: String, primary_index: Vec<usize>) -> Self {
+        Self {
+            table_name,
+            primary_index,
+            operations: IndexMap::new(),
+            inserts_without_key: vec![],
+            truncate: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty() && self.inserts_without_key.is_empty() && !self.truncate
+    }
+
+    /// Returns if the table must be emptied before the operations of [Batch::take] are applied.
+    pub fn take_truncate(&mut self) -> bool {
+        std::mem::take(&mut self.truncate)
+    }
+
+    pub fn take(&mut self) -> (Vec<PendingOperation>, Vec<Record>) {
+        (
+            std::mem::take(&mut self.operations).into_values().collect(),
+            std::mem::take(&mut self.inserts_without_key),
+        )
+    }
+
+    /// Drops the buffered operations, they are emptied from the table anyway.
+    pub fn truncate(&mut self) {
+        self.operations.clear();
+        self.inserts_without_key.clear();
+        self.truncate = true;
+    }
+
+    pub fn insert(&mut self, new: Record) {
+        if self.primary_index.is_empty() {
+            self.inserts_without_key.push(new);
+        } else {
+            let key = new.get_key(&self.primary_index);
+            self.operations.insert(key, PendingOperation::Upsert(new));
+        }
+    }
+
+    pub fn update(&mut self, old: Record, new: Record) -> Result<(), MySQLSinkError> {
+        let old_key = self.get_key(&old)?;
+        let new_key = self.get_key(&new)?;
+        if old_key != new_key {
+            self.operations
+                .insert(old_key, PendingOperation::Delete(old));
+        }
+        self.operations
+            .insert(new_key, PendingOperation::Upsert(new));
+        Ok(())
+    }
+
+    pub fn delete(&mut self, old: Record) -> Result<(), MySQLSinkError> {
+        let key = self.get_key(&old)?;
+        self.operations.insert(key, PendingOperation::Delete(old));
+        Ok(())
+    }
+
+    fn get_key(&self, record: &Record) -> Result<Vec<u8>, MySQLSinkError> {
+        if self.primary_index.is_empty() {
+            return Err(MySQLSinkError::PrimaryKeyNotFound(self.table_name.clone()));
+        }
+        Ok(record.get_key(&self.primary_index))
+    }
+}
@@ -0,0 +1,52 @@
+use dozer_ingestion_mysql::conversion::get_mysql_column_type_for_field_type;
+use dozer_types::types::{FieldDefinition, FieldType, Schema};
+
+/// Quotes an identifier so it can be used verbatim in a mysql statement.
+pub fn quote_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+pub fn get_create_table_query(table_name: &str, schema: &Schema) -> String {
+    let mut parts = schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            format!(
+                "{} {}",
+                quote_identifier(&field.name),
+                get_column_type(field, schema.primary_index.contains(&index))
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if !schema.primary_index.is_empty() {
+        let primary_key = schema
+            .primary_index
+            .iter()
+            .map(|index| quote_identifier(&schema.fields[*index].name))
+            .collect::<Vec<_>>();
+        parts.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+    }
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n    {}\n)",
+        quote_identifier(table_name),
+        parts.join(",\n    ")
+    )
+}
+
+fn get_column_type(field: &FieldDefinition, is_key: bool) -> String {
+    let typ = match field.typ {
+        // Unbounded text and blob columns can't be part of a primary key.
+        FieldType::Text if is_key => "varchar(255)",
+        FieldType::Binary if is_key => "varbinary(255)",
+        typ => get_mysql_column_type_for_field_type(typ),
+    };
+
+    if field.nullable {
+        typ.to_string()
+    } else {
+        format!("{typ} NOT NULL")
+    }
+}
@@ -0,0 +1,14 @@
+use dozer_ingestion_mysql::MySQLConnectorError;
+use dozer_types::thiserror::{self, Error};
+
+#[derive(Error, Debug)]
+pub enum MySQLSinkError {
+    #[error("Failed to connect to mysql: {0}")]
+    Connection(#[from] MySQLConnectorError),
+
+    #[error("MySQL error: {0}")]
+    MySQL(#[from] mysql_async::Error),
+
+    #[error("Table {0} has no primary key, only inserts are supported")]
+    PrimaryKeyNotFound(String),
+}
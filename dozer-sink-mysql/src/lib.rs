@@ -0,0 +1,9 @@
+mod batch;
+pub mod ddl;
+pub mod errors;
+pub mod metadata;
+mod query;
+mod sink;
+pub use sink::MySQLSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,91 @@
+use dozer_types::node::OpIdentifier;
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, Transaction};
+
+use crate::ddl::quote_identifier;
+use crate::errors::MySQLSinkError;
+
+// Replication Metadata Constants
+pub const REPLICA_METADATA_TABLE: &str = "__dozer_replication_metadata";
+pub const META_TABLE_COL: &str = "table";
+pub const META_TXN_ID_COL: &str = "txn_id";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
+
+/// What the sink has committed for one sink table.
+///
+/// It's written in the same transaction as the data, so it never runs ahead of or behind the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationMetadata {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl ReplicationMetadata {
+    pub fn get_create_table_query() -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+    {} VARCHAR(255) PRIMARY KEY,
+    {} BIGINT UNSIGNED,
+    {} BIGINT UNSIGNED,
+    {} LONGBLOB
+)",
+            quote_identifier(REPLICA_METADATA_TABLE),
+            quote_identifier(META_TABLE_COL),
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+        )
+    }
+
+    pub async fn load(conn: &mut Conn, table_name: &str) -> Result<Self, MySQLSinkError> {
+        let query = format!(
+            "SELECT {}, {}, {} FROM {} WHERE {} = ?",
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+            quote_identifier(REPLICA_METADATA_TABLE),
+            quote_identifier(META_TABLE_COL),
+        );
+        let row: Option<(Option<u64>, Option<u64>, Option<Vec<u8>>)> =
+            conn.exec_first(query, (table_name,)).await?;
+        let Some((txid, seq_in_tx, source_state)) = row else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            op_id: txid.map(|txid| OpIdentifier::new(txid, seq_in_tx.unwrap_or(0))),
+            source_state,
+        })
+    }
+
+    pub async fn store(
+        &self,
+        transaction: &mut Transaction<'_>,
+        table_name: &str,
+    ) -> Result<(), MySQLSinkError> {
+        let update_columns = [META_TXN_ID_COL, META_SEQ_IN_TX_COL, META_SOURCE_STATE_COL]
+            .map(|column| format!("{0} = VALUES({0})", quote_identifier(column)));
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}) VALUES (?, ?, ?, ?) ON DUPLICATE KEY UPDATE {}",
+            quote_identifier(REPLICA_METADATA_TABLE),
+            quote_identifier(META_TABLE_COL),
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+            update_columns.join(", "),
+        );
+        transaction
+            .exec_drop(
+                query,
+                (
+                    table_name,
+                    self.op_id.map(|op_id| op_id.txid),
+                    self.op_id.map(|op_id| op_id.seq_in_tx),
+                    self.source_state.as_deref(),
+                ),
+            )
+            .await?;
+        Ok(())
+    }
+}
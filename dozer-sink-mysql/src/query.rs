@@ -0,0 +1,105 @@
+use dozer_types::types::Schema;
+
+use crate::ddl::quote_identifier;
+
+/// MySQL doesn't accept more placeholders than this in one statement.
+const MAX_PLACEHOLDERS: usize = u16::MAX as usize;
+
+/// Builds the DML statements for one sink table.
+#[derive(Debug, Clone)]
+pub struct TableQueries {
+    table_name: String,
+    columns: Vec<String>,
+    key_columns: Vec<String>,
+    non_key_columns: Vec<String>,
+}
+
+impl TableQueries {
+    pub fn new(table_name: &str, schema: &Schema) -> Self {
+        let columns = schema
+            .fields
+            .iter()
+            .map(|field| quote_identifier(&field.name))
+            .collect::<Vec<_>>();
+        let key_columns = schema
+            .primary_index
+            .iter()
+            .map(|index| columns[*index].clone())
+            .collect();
+        let non_key_columns = columns
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !schema.primary_index.contains(index))
+            .map(|(_, column)| column.clone())
+            .collect();
+        Self {
+            table_name: quote_identifier(table_name),
+            columns,
+            key_columns,
+            non_key_columns,
+        }
+    }
+
+    /// How many records fit in one `INSERT`.
+    pub fn max_insert_rows(&self) -> usize {
+        (MAX_PLACEHOLDERS / self.columns.len().max(1)).max(1)
+    }
+
+    /// How many keys fit in one `DELETE`.
+    pub fn max_delete_rows(&self) -> usize {
+        (MAX_PLACEHOLDERS / self.key_columns.len().max(1)).max(1)
+    }
+
+    pub fn insert(&self, num_rows: usize) -> String {
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.table_name,
+            self.columns.join(", "),
+            placeholders(self.columns.len(), num_rows)
+        )
+    }
+
+    /// Inserts the records, replacing the rows with the same primary key.
+    pub fn upsert(&self, num_rows: usize) -> String {
+        if self.non_key_columns.is_empty() {
+            // Nothing to update if every column is part of the primary key.
+            return format!(
+                "INSERT IGNORE INTO {} ({}) VALUES {}",
+                self.table_name,
+                self.columns.join(", "),
+                placeholders(self.columns.len(), num_rows)
+            );
+        }
+
+        let assignments = self
+            .non_key_columns
+            .iter()
+            .map(|column| format!("{column} = VALUES({column})"))
+            .collect::<Vec<_>>();
+        format!(
+            "{} ON DUPLICATE KEY UPDATE {}",
+            self.insert(num_rows),
+            assignments.join(", ")
+        )
+    }
+
+    /// Parameters are the key fields of every record.
+    pub fn delete(&self, num_rows: usize) -> String {
+        format!(
+            "DELETE FROM {} WHERE ({}) IN ({})",
+            self.table_name,
+            self.key_columns.join(", "),
+            placeholders(self.key_columns.len(), num_rows)
+        )
+    }
+
+    /// Deletes all rows. Unlike `TRUNCATE TABLE`, it doesn't commit the surrounding transaction.
+    pub fn delete_all(&self) -> String {
+        format!("DELETE FROM {}", self.table_name)
+    }
+}
+
+fn placeholders(num_columns: usize, num_rows: usize) -> String {
+    let row = format!("({})", vec!["?"; num_columns].join(", "));
+    vec![row; num_rows].join(", ")
+}
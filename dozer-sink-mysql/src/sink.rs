@@ -0,0 +1,292 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_ingestion_mysql::connector::mysql_connection_opts_from_url;
+use dozer_ingestion_mysql::conversion::IntoValue;
+use dozer_ingestion_mysql::MySQLConnectorError;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::ingestion_types::MySQLConfig;
+use dozer_types::models::sink::MySQLSinkConfig;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, Pool, Transaction, TxOpts, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::batch::{Batch, PendingOperation};
+use crate::ddl::get_create_table_query;
+use crate::errors::MySQLSinkError;
+use crate::metadata::ReplicationMetadata;
+use crate::query::TableQueries;
+
+#[derive(Debug)]
+pub struct MySQLSinkFactory {
+    runtime: Arc<Runtime>,
+    config: MySQLSinkConfig,
+    connection: MySQLConfig,
+}
+
+impl MySQLSinkFactory {
+    pub fn new(config: MySQLSinkConfig, connection: MySQLConfig, runtime: Arc<Runtime>) -> Self {
+        Self {
+            runtime,
+            config,
+            connection,
+        }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for MySQLSinkFactory {
+    fn type_name(&self) -> String {
+        "mysql".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.source_table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+
+        let opts = mysql_connection_opts_from_url(&self.connection.url)?;
+        let mut conn = Pool::new(opts).get_conn().await.map_err(|e| {
+            MySQLSinkError::Connection(MySQLConnectorError::ConnectionFailure(
+                self.connection.url.clone(),
+                e,
+            ))
+        })?;
+
+        let table_name = &self.config.sink_table_name;
+        // Create Sink Table
+        conn.query_drop(get_create_table_query(table_name, &schema))
+            .await
+            .map_err(MySQLSinkError::MySQL)?;
+        // Create Metadata Table
+        conn.query_drop(ReplicationMetadata::get_create_table_query())
+            .await
+            .map_err(MySQLSinkError::MySQL)?;
+
+        // Sink methods are called from within the runtime while the DAG is built, so restore the metadata here.
+        let metadata = ReplicationMetadata::load(&mut conn, table_name).await?;
+        debug!(
+            "[Sink] Restored metadata for {}: {:?}",
+            table_name, metadata.op_id
+        );
+
+        let writer = MySQLWriter {
+            conn,
+            queries: TableQueries::new(table_name, &schema),
+            primary_index: schema.primary_index.clone(),
+            table_name: table_name.clone(),
+        };
+        let sink = MySQLSink {
+            writer,
+            runtime: self.runtime.clone(),
+            batch: Batch::new(table_name.clone(), schema.primary_index),
+            committed_metadata: metadata.clone(),
+            metadata,
+            source: None,
+            preferred_batch_size: self.config.preferred_batch_size,
+            max_batch_duration_ms: self.config.max_batch_duration_ms,
+        };
+
+        Ok(Box::new(sink))
+    }
+}
+
+#[derive(Debug)]
+struct MySQLWriter {
+    conn: Conn,
+    queries: TableQueries,
+    primary_index: Vec<usize>,
+    table_name: String,
+}
+
+impl MySQLWriter {
+    /// Applies a collapsed batch and the metadata in a single transaction.
+    async fn write(
+        &mut self,
+        truncate: bool,
+        operations: Vec<PendingOperation>,
+        inserts_without_key: Vec<Record>,
+        metadata: &ReplicationMetadata,
+    ) -> Result<(), MySQLSinkError> {
+        let mut deletes = vec![];
+        let mut upserts = vec![];
+        for operation in operations {
+            match operation {
+                PendingOperation::Upsert(record) => upserts.push(record),
+                PendingOperation::Delete(record) => deletes.push(record),
+            }
+        }
+
+        let mut transaction = self.conn.start_transaction(TxOpts::default()).await?;
+
+        if truncate {
+            transaction.query_drop(self.queries.delete_all()).await?;
+        }
+
+        for chunk in deletes.chunks(self.queries.max_delete_rows()) {
+            let params = chunk
+                .iter()
+                .flat_map(|record| {
+                    self.primary_index
+                        .iter()
+                        .map(|index| record.values[*index].into_value())
+                })
+                .collect::<Vec<_>>();
+            transaction
+                .exec_drop(self.queries.delete(chunk.len()), params)
+                .await?;
+        }
+        for chunk in upserts.chunks(self.queries.max_insert_rows()) {
+            transaction
+                .exec_drop(self.queries.upsert(chunk.len()), record_params(chunk))
+                .await?;
+        }
+        for chunk in inserts_without_key.chunks(self.queries.max_insert_rows()) {
+            transaction
+                .exec_drop(self.queries.insert(chunk.len()), record_params(chunk))
+                .await?;
+        }
+
+        metadata.store(&mut transaction, &self.table_name).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+fn record_params(records: &[Record]) -> Vec<Value> {
+    records
+        .iter()
+        .flat_map(|record| record.values.iter().map(IntoValue::into_value))
+        .collect()
+}
+
+#[derive(Debug)]
+pub(crate) struct MySQLSink {
+    writer: MySQLWriter,
+    runtime: Arc<Runtime>,
+    batch: Batch,
+    /// Metadata to be written on next flush.
+    metadata: ReplicationMetadata,
+    /// Metadata as it is in the database.
+    committed_metadata: ReplicationMetadata,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+    preferred_batch_size: Option<u64>,
+    max_batch_duration_ms: Option<u64>,
+}
+
+impl Sink for MySQLSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.metadata.op_id = Some(*op_id);
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.batch.insert(new),
+            Operation::Delete { old } => self.batch.delete(old)?,
+            Operation::Update { old, new } => self.batch.update(old, new)?,
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.batch.insert(record);
+                }
+            }
+            Operation::Truncate => self.batch.truncate(),
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        if self.batch.is_empty() && self.metadata == self.committed_metadata {
+            return Ok(());
+        }
+
+        let truncate = self.batch.take_truncate();
+        let (operations, inserts_without_key) = self.batch.take();
+        self.runtime.block_on(self.writer.write(
+            truncate,
+            operations,
+            inserts_without_key,
+            &self.metadata,
+        ))?;
+        self.committed_metadata = self.metadata.clone();
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.metadata.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.metadata.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.metadata.source_state.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.metadata.op_id)
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        self.preferred_batch_size
+    }
+
+    fn max_batch_duration_ms(&self) -> Option<u64> {
+        self.max_batch_duration_ms
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,176 @@
+use dozer_types::indexmap::IndexMap;
+use dozer_types::models::sink::{
+    ConflictResolution, OnDeleteResolutionTypes, OnInsertResolutionTypes, OnUpdateResolutionTypes,
+};
+use dozer_types::types::Record;
+
+use crate::errors::PostgresSinkError;
+
+/// The net effect of all operations on one primary key since the last flush.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PendingOperation {
+    Insert(Record),
+    Update(Record),
+    /// The record must exist after the flush, whether or not it existed before.
+    Upsert(Record),
+    Delete(Record),
+}
+
+/// Operations buffered between two flushes, collapsed by primary key.
+///
+/// Because every key appears at most once, the flush can apply deletes, inserts and updates
+/// in separate batched statements without caring about the order they arrived in.
+#[derive(Debug, Clone)]
+pub(crate) struct Batch {
+    table_name: String,
+    primary_index: Vec<usize>,
+    conflict_resolution: ConflictResolution,
+    operations: IndexMap<Vec<u8>, PendingOperation>,
+    inserts_without_key: Vec<Record>,
+    /// If the table must be emptied before the operations are applied.
+    truncate: bool,
+}
+
+impl Batch {
+    pub fn new(
+        table_name: String,
+        primary_index: Vec<usize>,
+        conflict_resolution: ConflictResolution,
+    ) -> Self {
+        Self {
+            table_name,
+            primary_index,
+            conflict_resolution,
+            operations: IndexMap::new(),
+            inserts_without_key: vec![],
+            truncate: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty() && self.inserts_without_key.is_empty() && !self.truncate
+    }
+
+    /// Returns if the table must be emptied before the operations of [Batch::take] are applied.
+    pub fn take_truncate(&mut self) -> bool {
+        std::mem::take(&mut self.truncate)
+    }
+
+    /// Drops the buffered operations, they are emptied from the table anyway.
+    pub fn truncate(&mut self) {
+        self.operations.clear();
+        self.inserts_without_key.clear();
+        self.truncate = true;
+    }
+
+    pub fn take(&mut self) -> (Vec<PendingOperation>, Vec<Record>) {
+        (
+            std::mem::take(&mut self.operations).into_values().collect(),
+            std::mem::take(&mut self.inserts_without_key),
+        )
+    }
+
+    pub fn insert(&mut self, new: Record) -> Result<(), PostgresSinkError> {
+        if self.primary_index.is_empty() {
+            self.inserts_without_key.push(new);
+            return Ok(());
+        }
+
+        let key = new.get_key(&self.primary_index);
+        let Some(existing) = self.operations.get_mut(&key) else {
+            self.operations.insert(key, PendingOperation::Insert(new));
+            return Ok(());
+        };
+        match existing {
+            PendingOperation::Delete(_) => *existing = PendingOperation::Upsert(new),
+            _ => match self.conflict_resolution.on_insert {
+                OnInsertResolutionTypes::Nothing => (),
+                OnInsertResolutionTypes::Update => replace_record(existing, new),
+                OnInsertResolutionTypes::Panic => {
+                    return Err(PostgresSinkError::RecordAlreadyExists(
+                        self.table_name.clone(),
+                    ))
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, old: Record, new: Record) -> Result<(), PostgresSinkError> {
+        if self.primary_index.is_empty() {
+            return Err(PostgresSinkError::PrimaryKeyNotFound(
+                self.table_name.clone(),
+            ));
+        }
+
+        let old_key = old.get_key(&self.primary_index);
+        let new_key = new.get_key(&self.primary_index);
+        if old_key != new_key {
+            // The primary key changed, so the old row goes away and the new one replaces whatever is there.
+            self.delete(old)?;
+            self.operations
+                .insert(new_key, PendingOperation::Upsert(new));
+            return Ok(());
+        }
+
+        let Some(existing) = self.operations.get_mut(&new_key) else {
+            self.operations
+                .insert(new_key, PendingOperation::Update(new));
+            return Ok(());
+        };
+        match existing {
+            PendingOperation::Delete(_) => match self.conflict_resolution.on_update {
+                OnUpdateResolutionTypes::Nothing => (),
+                OnUpdateResolutionTypes::Upsert => *existing = PendingOperation::Upsert(new),
+                OnUpdateResolutionTypes::Panic => {
+                    return Err(not_found(&self.table_name, "updated"))
+                }
+            },
+            _ => replace_record(existing, new),
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, old: Record) -> Result<(), PostgresSinkError> {
+        if self.primary_index.is_empty() {
+            return Err(PostgresSinkError::PrimaryKeyNotFound(
+                self.table_name.clone(),
+            ));
+        }
+
+        let key = old.get_key(&self.primary_index);
+        match self.operations.get_mut(&key) {
+            // The record was created in this batch, so there's nothing to delete.
+            Some(PendingOperation::Insert(_)) => {
+                self.operations.swap_remove(&key);
+            }
+            Some(PendingOperation::Delete(_)) => {
+                if self.conflict_resolution.on_delete == OnDeleteResolutionTypes::Panic {
+                    return Err(not_found(&self.table_name, "deleted"));
+                }
+            }
+            Some(existing) => *existing = PendingOperation::Delete(old),
+            None => {
+                self.operations.insert(key, PendingOperation::Delete(old));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn not_found(table_name: &str, operation: &'static str) -> PostgresSinkError {
+    PostgresSinkError::RecordNotFound {
+        table: table_name.to_string(),
+        operation,
+        expected: 1,
+        actual: 0,
+    }
+}
+
+fn replace_record(existing: &mut PendingOperation, new: Record) {
+    *existing = match existing {
+        PendingOperation::Insert(_) => PendingOperation::Insert(new),
+        PendingOperation::Update(_) => PendingOperation::Update(new),
+        PendingOperation::Upsert(_) | PendingOperation::Delete(_) => PendingOperation::Upsert(new),
+    };
+}
@@ -0,0 +1,45 @@
+use dozer_types::types::Schema;
+
+use crate::errors::PostgresSinkError;
+use crate::types::{map_field_to_type, quote_identifier};
+
+pub const DEFAULT_SCHEMA: &str = "public";
+
+pub fn get_qualified_table_name(schema: Option<&str>, table_name: &str) -> String {
+    format!(
+        "{}.{}",
+        quote_identifier(schema.unwrap_or(DEFAULT_SCHEMA)),
+        quote_identifier(table_name)
+    )
+}
+
+pub fn get_create_table_query(
+    table_name: &str,
+    schema: &Schema,
+) -> Result<String, PostgresSinkError> {
+    let mut parts = schema
+        .fields
+        .iter()
+        .map(|field| {
+            Ok(format!(
+                "{} {}",
+                quote_identifier(&field.name),
+                map_field_to_type(field)?
+            ))
+        })
+        .collect::<Result<Vec<_>, PostgresSinkError>>()?;
+
+    if !schema.primary_index.is_empty() {
+        let primary_key = schema
+            .primary_index
+            .iter()
+            .map(|index| quote_identifier(&schema.fields[*index].name))
+            .collect::<Vec<_>>();
+        parts.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+    }
+
+    Ok(format!(
+        "CREATE TABLE IF NOT EXISTS {table_name} (\n    {}\n)",
+        parts.join(",\n    ")
+    ))
+}
@@ -0,0 +1,31 @@
+use dozer_ingestion_postgres::PostgresConnectorError;
+use dozer_types::{
+    thiserror::{self, Error},
+    types::FieldType,
+};
+
+#[derive(Error, Debug)]
+pub enum PostgresSinkError {
+    #[error("Failed to connect to postgres: {0}")]
+    Connection(#[from] PostgresConnectorError),
+
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+
+    #[error("Field type {0:?} is not supported by the postgres sink")]
+    UnsupportedFieldType(FieldType),
+
+    #[error("Table {0} has no primary key, only inserts are supported")]
+    PrimaryKeyNotFound(String),
+
+    #[error("Record with the same primary key already exists in {0}")]
+    RecordAlreadyExists(String),
+
+    #[error("Expected {expected} records to be {operation} in {table}, but {actual} were")]
+    RecordNotFound {
+        table: String,
+        operation: &'static str,
+        expected: u64,
+        actual: u64,
+    },
+}
@@ -0,0 +1,10 @@
+mod batch;
+pub mod ddl;
+pub mod errors;
+pub mod metadata;
+mod query;
+mod sink;
+pub use sink::PostgresSinkFactory;
+#[cfg(test)]
+mod tests;
+pub mod types;
@@ -0,0 +1,91 @@
+use dozer_ingestion_postgres::connection::client::Client;
+use dozer_types::node::OpIdentifier;
+use tokio_postgres::Transaction;
+
+use crate::errors::PostgresSinkError;
+use crate::types::quote_identifier;
+
+// Replication Metadata Constants
+pub const REPLICA_METADATA_TABLE: &str = "__dozer_replication_metadata";
+pub const META_TABLE_COL: &str = "table";
+pub const META_TXN_ID_COL: &str = "txn_id";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
+
+/// What the sink has committed for one sink table.
+///
+/// It's written in the same transaction as the data, so it never runs ahead of or behind the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationMetadata {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl ReplicationMetadata {
+    pub fn get_create_table_query(metadata_table_name: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {metadata_table_name} (
+    {} TEXT PRIMARY KEY,
+    {} BIGINT,
+    {} BIGINT,
+    {} BYTEA
+)",
+            quote_identifier(META_TABLE_COL),
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+        )
+    }
+
+    pub async fn load(
+        client: &mut Client,
+        metadata_table_name: &str,
+        table_name: &str,
+    ) -> Result<Self, PostgresSinkError> {
+        let query = format!(
+            "SELECT {}, {}, {} FROM {metadata_table_name} WHERE {} = $1",
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+            quote_identifier(META_TABLE_COL),
+        );
+        let rows = client.query(query.as_str(), &[&table_name]).await?;
+        let Some(row) = rows.first() else {
+            return Ok(Self::default());
+        };
+
+        // `u64`s are stored as `BIGINT`, reinterpreting the bits.
+        let txid: Option<i64> = row.try_get(0)?;
+        let seq_in_tx: Option<i64> = row.try_get(1)?;
+        let op_id = txid.map(|txid| OpIdentifier::new(txid as u64, seq_in_tx.unwrap_or(0) as u64));
+        Ok(Self {
+            op_id,
+            source_state: row.try_get(2)?,
+        })
+    }
+
+    pub async fn store(
+        &self,
+        transaction: &Transaction<'_>,
+        metadata_table_name: &str,
+        table_name: &str,
+    ) -> Result<(), PostgresSinkError> {
+        let query = format!(
+            "INSERT INTO {metadata_table_name} ({table}, {txid}, {seq}, {state}) VALUES ($1, $2, $3, $4)
+ON CONFLICT ({table}) DO UPDATE SET {txid} = EXCLUDED.{txid}, {seq} = EXCLUDED.{seq}, {state} = EXCLUDED.{state}",
+            table = quote_identifier(META_TABLE_COL),
+            txid = quote_identifier(META_TXN_ID_COL),
+            seq = quote_identifier(META_SEQ_IN_TX_COL),
+            state = quote_identifier(META_SOURCE_STATE_COL),
+        );
+        let txid = self.op_id.map(|op_id| op_id.txid as i64);
+        let seq_in_tx = self.op_id.map(|op_id| op_id.seq_in_tx as i64);
+        transaction
+            .execute(
+                query.as_str(),
+                &[&table_name, &txid, &seq_in_tx, &self.source_state],
+            )
+            .await?;
+        Ok(())
+    }
+}
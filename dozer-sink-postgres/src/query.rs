@@ -0,0 +1,136 @@
+use dozer_types::types::Schema;
+
+use crate::types::quote_identifier;
+
+/// Postgres doesn't accept more bind parameters than this in one statement.
+const MAX_PARAMETERS: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    Error,
+    DoNothing,
+    DoUpdate,
+}
+
+/// Builds the DML statements for one sink table.
+#[derive(Debug, Clone)]
+pub struct TableQueries {
+    table_name: String,
+    columns: Vec<String>,
+    key_columns: Vec<String>,
+    non_key_columns: Vec<String>,
+}
+
+impl TableQueries {
+    pub fn new(table_name: String, schema: &Schema) -> Self {
+        let columns = schema
+            .fields
+            .iter()
+            .map(|field| quote_identifier(&field.name))
+            .collect::<Vec<_>>();
+        let key_columns = schema
+            .primary_index
+            .iter()
+            .map(|index| columns[*index].clone())
+            .collect();
+        let non_key_columns = columns
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !schema.primary_index.contains(index))
+            .map(|(_, column)| column.clone())
+            .collect();
+        Self {
+            table_name,
+            columns,
+            key_columns,
+            non_key_columns,
+        }
+    }
+
+    /// How many records fit in one `INSERT`.
+    pub fn max_insert_rows(&self) -> usize {
+        (MAX_PARAMETERS / self.columns.len().max(1)).max(1)
+    }
+
+    /// How many keys fit in one `DELETE`.
+    pub fn max_delete_rows(&self) -> usize {
+        (MAX_PARAMETERS / self.key_columns.len().max(1)).max(1)
+    }
+
+    pub fn insert(&self, num_rows: usize, on_conflict: OnConflict) -> String {
+        let mut query = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.table_name,
+            self.columns.join(", "),
+            placeholders(self.columns.len(), num_rows)
+        );
+        match on_conflict {
+            OnConflict::Error => (),
+            OnConflict::DoUpdate if !self.non_key_columns.is_empty() => {
+                let assignments = self
+                    .non_key_columns
+                    .iter()
+                    .map(|column| format!("{column} = EXCLUDED.{column}"))
+                    .collect::<Vec<_>>();
+                query.push_str(&format!(
+                    " ON CONFLICT ({}) DO UPDATE SET {}",
+                    self.key_columns.join(", "),
+                    assignments.join(", ")
+                ));
+            }
+            // Nothing to update if every column is part of the primary key.
+            OnConflict::DoNothing | OnConflict::DoUpdate => {
+                query.push_str(" ON CONFLICT DO NOTHING");
+            }
+        }
+        query
+    }
+
+    /// Parameters are the key fields of every record.
+    pub fn delete(&self, num_rows: usize) -> String {
+        format!(
+            "DELETE FROM {} WHERE ({}) IN ({})",
+            self.table_name,
+            self.key_columns.join(", "),
+            placeholders(self.key_columns.len(), num_rows)
+        )
+    }
+
+    pub fn truncate(&self) -> String {
+        format!("TRUNCATE TABLE {}", self.table_name)
+    }
+
+    /// Parameters are all fields of the record, followed by its key fields.
+    pub fn update(&self) -> String {
+        let assignments = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| format!("{column} = ${}", index + 1))
+            .collect::<Vec<_>>();
+        let conditions = self
+            .key_columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| format!("{column} = ${}", self.columns.len() + index + 1))
+            .collect::<Vec<_>>();
+        format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.table_name,
+            assignments.join(", "),
+            conditions.join(" AND ")
+        )
+    }
+}
+
+fn placeholders(num_columns: usize, num_rows: usize) -> String {
+    (0..num_rows)
+        .map(|row| {
+            let row = (0..num_columns)
+                .map(|column| format!("${}", row * num_columns + column + 1))
+                .collect::<Vec<_>>();
+            format!("({})", row.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
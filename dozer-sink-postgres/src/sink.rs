@@ -0,0 +1,510 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_ingestion_postgres::connection::client::Client;
+use dozer_ingestion_postgres::connection::helper::{connect, map_connection_config};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::connection::{ConnectionConfig, PostgresConfig};
+use dozer_types::models::sink::{
+    ConflictResolution, OnDeleteResolutionTypes, OnInsertResolutionTypes, OnUpdateResolutionTypes,
+    PostgresSinkConfig,
+};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio_postgres::Transaction;
+
+use crate::batch::{Batch, PendingOperation};
+use crate::ddl::{get_create_table_query, get_qualified_table_name};
+use crate::errors::PostgresSinkError;
+use crate::metadata::{ReplicationMetadata, REPLICA_METADATA_TABLE};
+use crate::query::{OnConflict, TableQueries};
+use crate::types::{as_params, quote_literal, PostgresField};
+
+#[derive(Debug)]
+pub struct PostgresSinkFactory {
+    runtime: Arc<Runtime>,
+    config: PostgresSinkConfig,
+    connection: PostgresConfig,
+}
+
+impl PostgresSinkFactory {
+    pub fn new(
+        config: PostgresSinkConfig,
+        connection: PostgresConfig,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            runtime,
+            config,
+            connection,
+        }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for PostgresSinkFactory {
+    fn type_name(&self) -> String {
+        "postgres".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.source_table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+
+        let config = map_connection_config(&ConnectionConfig::Postgres(self.connection.clone()))?;
+        let mut client = connect(config).await?;
+
+        let pg_schema = self.connection.schema.as_deref();
+        let table_name = get_qualified_table_name(pg_schema, &self.config.sink_table_name);
+        let metadata_table_name = get_qualified_table_name(pg_schema, REPLICA_METADATA_TABLE);
+
+        // Create Sink Table
+        client
+            .batch_execute(&get_create_table_query(&table_name, &schema)?)
+            .await?;
+        // Create Metadata Table
+        client
+            .batch_execute(&ReplicationMetadata::get_create_table_query(
+                &metadata_table_name,
+            ))
+            .await?;
+
+        // Sink methods are called from within the runtime while the DAG is built, so restore the metadata here.
+        let metadata = ReplicationMetadata::load(
+            &mut client,
+            &metadata_table_name,
+            &self.config.sink_table_name,
+        )
+        .await?;
+        debug!(
+            "[Sink] Restored metadata for {}: {:?}",
+            self.config.sink_table_name, metadata.op_id
+        );
+
+        let writer = PostgresWriter {
+            client,
+            gid_prefix: format!("dozer:{table_name}:"),
+            queries: TableQueries::new(table_name.clone(), &schema),
+            primary_index: schema.primary_index.clone(),
+            conflict_resolution: self.config.conflict_resolution,
+            table_name: self.config.sink_table_name.clone(),
+            metadata_table_name,
+        };
+        let sink = PostgresSink {
+            writer,
+            runtime: self.runtime.clone(),
+            batch: Batch::new(
+                self.config.sink_table_name.clone(),
+                schema.primary_index,
+                self.config.conflict_resolution,
+            ),
+            committed_metadata: metadata.clone(),
+            metadata,
+            source: None,
+            preferred_batch_size: self.config.preferred_batch_size,
+            max_batch_duration_ms: self.config.max_batch_duration_ms,
+            two_phase_commit: self.config.two_phase_commit,
+        };
+
+        Ok(Box::new(sink))
+    }
+}
+
+#[derive(Debug)]
+struct PostgresWriter {
+    client: Client,
+    /// Prefix of the ids of the transactions prepared for the table, followed by the epoch id.
+    gid_prefix: String,
+    queries: TableQueries,
+    primary_index: Vec<usize>,
+    conflict_resolution: ConflictResolution,
+    table_name: String,
+    metadata_table_name: String,
+}
+
+impl PostgresWriter {
+    /// Applies a collapsed batch and the metadata in a single transaction.
+    /// With `prepared_epoch_id`, the transaction is prepared for that epoch instead of committed.
+    async fn write(
+        &mut self,
+        truncate: bool,
+        operations: Vec<PendingOperation>,
+        inserts_without_key: Vec<Record>,
+        metadata: &ReplicationMetadata,
+        prepared_epoch_id: Option<u64>,
+    ) -> Result<(), PostgresSinkError> {
+        let mut deletes = vec![];
+        let mut inserts = vec![];
+        let mut updates = vec![];
+        let mut upserts = vec![];
+        for operation in operations {
+            match operation {
+                PendingOperation::Insert(record) => inserts.push(record),
+                PendingOperation::Update(record) => {
+                    if self.conflict_resolution.on_update == OnUpdateResolutionTypes::Upsert {
+                        upserts.push(record);
+                    } else {
+                        updates.push(record);
+                    }
+                }
+                PendingOperation::Upsert(record) => upserts.push(record),
+                PendingOperation::Delete(record) => deletes.push(record),
+            }
+        }
+
+        let on_insert_conflict = match self.conflict_resolution.on_insert {
+            OnInsertResolutionTypes::Nothing => OnConflict::DoNothing,
+            OnInsertResolutionTypes::Update => OnConflict::DoUpdate,
+            OnInsertResolutionTypes::Panic => OnConflict::Error,
+        };
+
+        let transaction = self.client.transaction().await?;
+        if truncate {
+            transaction.batch_execute(&self.queries.truncate()).await?;
+        }
+        self.delete(&transaction, &deletes).await?;
+        self.insert(&transaction, &inserts, on_insert_conflict)
+            .await?;
+        self.insert(&transaction, &inserts_without_key, OnConflict::Error)
+            .await?;
+        self.insert(&transaction, &upserts, OnConflict::DoUpdate)
+            .await?;
+        self.update(&transaction, &updates).await?;
+        metadata
+            .store(&transaction, &self.metadata_table_name, &self.table_name)
+            .await?;
+        if let Some(epoch_id) = prepared_epoch_id {
+            transaction
+                .batch_execute(&format!(
+                    "PREPARE TRANSACTION {}",
+                    quote_literal(&self.gid(epoch_id))
+                ))
+                .await?;
+        }
+        // A prepared transaction is no longer open, so committing it only marks it as done.
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    fn gid(&self, epoch_id: u64) -> String {
+        format!("{}{epoch_id}", self.gid_prefix)
+    }
+
+    /// Returns the ids of the epochs whose transactions are prepared, but not committed or rolled back.
+    async fn prepared_epoch_ids(&mut self) -> Result<Vec<u64>, PostgresSinkError> {
+        let rows = self
+            .client
+            .query(
+                "SELECT gid FROM pg_prepared_xacts WHERE database = current_database() AND left(gid, length($1::text)) = $1",
+                &[&self.gid_prefix],
+            )
+            .await?;
+        let mut epoch_ids = vec![];
+        for row in rows {
+            let gid: String = row.try_get(0)?;
+            if let Some(Ok(epoch_id)) = gid.strip_prefix(&self.gid_prefix).map(str::parse) {
+                epoch_ids.push(epoch_id);
+            }
+        }
+        Ok(epoch_ids)
+    }
+
+    /// Commits or rolls back the transaction prepared for the epoch, returning if there was one.
+    async fn finish_prepared(
+        &mut self,
+        epoch_id: u64,
+        commit: bool,
+    ) -> Result<bool, PostgresSinkError> {
+        if !self.prepared_epoch_ids().await?.contains(&epoch_id) {
+            return Ok(false);
+        }
+        let command = if commit { "COMMIT" } else { "ROLLBACK" };
+        self.client
+            .batch_execute(&format!(
+                "{command} PREPARED {}",
+                quote_literal(&self.gid(epoch_id))
+            ))
+            .await?;
+        Ok(true)
+    }
+
+    async fn insert(
+        &self,
+        transaction: &Transaction<'_>,
+        records: &[Record],
+        on_conflict: OnConflict,
+    ) -> Result<(), PostgresSinkError> {
+        for chunk in records.chunks(self.queries.max_insert_rows()) {
+            let fields = chunk
+                .iter()
+                .flat_map(|record| record.values.iter().map(PostgresField))
+                .collect::<Vec<_>>();
+            let query = self.queries.insert(chunk.len(), on_conflict);
+            transaction
+                .execute(query.as_str(), &as_params(&fields))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        transaction: &Transaction<'_>,
+        records: &[Record],
+    ) -> Result<(), PostgresSinkError> {
+        for chunk in records.chunks(self.queries.max_delete_rows()) {
+            let fields = chunk
+                .iter()
+                .flat_map(|record| {
+                    self.primary_index
+                        .iter()
+                        .map(|index| PostgresField(&record.values[*index]))
+                })
+                .collect::<Vec<_>>();
+            let query = self.queries.delete(chunk.len());
+            let deleted = transaction
+                .execute(query.as_str(), &as_params(&fields))
+                .await?;
+            if self.conflict_resolution.on_delete == OnDeleteResolutionTypes::Panic
+                && deleted != chunk.len() as u64
+            {
+                return Err(PostgresSinkError::RecordNotFound {
+                    table: self.table_name.clone(),
+                    operation: "deleted",
+                    expected: chunk.len() as u64,
+                    actual: deleted,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        transaction: &Transaction<'_>,
+        records: &[Record],
+    ) -> Result<(), PostgresSinkError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let statement = transaction.prepare(&self.queries.update()).await?;
+        for record in records {
+            let fields = record
+                .values
+                .iter()
+                .chain(
+                    self.primary_index
+                        .iter()
+                        .map(|index| &record.values[*index]),
+                )
+                .map(PostgresField)
+                .collect::<Vec<_>>();
+            let updated = transaction.execute(&statement, &as_params(&fields)).await?;
+            if self.conflict_resolution.on_update == OnUpdateResolutionTypes::Panic && updated == 0
+            {
+                return Err(PostgresSinkError::RecordNotFound {
+                    table: self.table_name.clone(),
+                    operation: "updated",
+                    expected: 1,
+                    actual: 0,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PostgresSink {
+    writer: PostgresWriter,
+    runtime: Arc<Runtime>,
+    batch: Batch,
+    /// Metadata to be written on next flush.
+    metadata: ReplicationMetadata,
+    /// Metadata as it is in the database.
+    committed_metadata: ReplicationMetadata,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+    preferred_batch_size: Option<u64>,
+    max_batch_duration_ms: Option<u64>,
+    /// If epochs are written in prepared transactions, instead of flushed in batches.
+    two_phase_commit: bool,
+}
+
+impl Sink for PostgresSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.metadata.op_id = Some(*op_id);
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.batch.insert(new)?,
+            Operation::Delete { old } => self.batch.delete(old)?,
+            Operation::Update { old, new } => self.batch.update(old, new)?,
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.batch.insert(record)?;
+                }
+            }
+            Operation::Truncate => self.batch.truncate(),
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        // With two-phase commit, the batch is written when the epoch is prepared.
+        if self.two_phase_commit
+            || (self.batch.is_empty() && self.metadata == self.committed_metadata)
+        {
+            return Ok(());
+        }
+
+        let truncate = self.batch.take_truncate();
+        let (operations, inserts_without_key) = self.batch.take();
+        self.runtime.block_on(self.writer.write(
+            truncate,
+            operations,
+            inserts_without_key,
+            &self.metadata,
+            None,
+        ))?;
+        self.committed_metadata = self.metadata.clone();
+        Ok(())
+    }
+
+    fn supports_two_phase_commit(&self) -> bool {
+        self.two_phase_commit
+    }
+
+    fn prepare(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        self.commit(epoch_details)?;
+        if self.batch.is_empty() && self.metadata == self.committed_metadata {
+            return Ok(());
+        }
+
+        // The batch is only emptied once it's prepared, so a failed attempt can be retried.
+        let mut batch = self.batch.clone();
+        let truncate = batch.take_truncate();
+        let (operations, inserts_without_key) = batch.take();
+        self.runtime.block_on(self.writer.write(
+            truncate,
+            operations,
+            inserts_without_key,
+            &self.metadata,
+            Some(epoch_details.common_info.id),
+        ))?;
+        self.batch = batch;
+        Ok(())
+    }
+
+    fn commit_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        if self
+            .runtime
+            .block_on(self.writer.finish_prepared(epoch_id, true))?
+        {
+            // When committing what a previous run prepared, the metadata loaded on build is behind.
+            self.committed_metadata = self.runtime.block_on(ReplicationMetadata::load(
+                &mut self.writer.client,
+                &self.writer.metadata_table_name,
+                &self.writer.table_name,
+            ))?;
+            self.metadata = self.committed_metadata.clone();
+        }
+        Ok(())
+    }
+
+    fn abort_prepared(&mut self, epoch_id: u64) -> Result<(), BoxedError> {
+        self.batch = Batch::new(
+            self.writer.table_name.clone(),
+            self.writer.primary_index.clone(),
+            self.writer.conflict_resolution,
+        );
+        self.metadata = self.committed_metadata.clone();
+        self.runtime
+            .block_on(self.writer.finish_prepared(epoch_id, false))?;
+        Ok(())
+    }
+
+    fn prepared_epochs(&mut self) -> Result<Vec<u64>, BoxedError> {
+        Ok(self.runtime.block_on(self.writer.prepared_epoch_ids())?)
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.metadata.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.metadata.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.metadata.source_state.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.metadata.op_id)
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        self.preferred_batch_size
+    }
+
+    fn max_batch_duration_ms(&self) -> Option<u64> {
+        self.max_batch_duration_ms
+    }
+
+    fn supports_batching(&self) -> bool {
+        !self.two_phase_commit
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::batch::{Batch, PendingOperation};
+use crate::ddl::{get_create_table_query, get_qualified_table_name};
+use crate::metadata::{ReplicationMetadata, REPLICA_METADATA_TABLE};
+use crate::query::{OnConflict, TableQueries};
+use crate::PostgresSinkFactory;
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::{self, runtime::Runtime};
+use dozer_core::{resolve_prepared_epochs, DEFAULT_PORT_HANDLE};
+use dozer_ingestion_postgres::connection::helper::{connect, map_connection_config};
+use dozer_types::models::connection::{ConnectionConfig, PostgresConfig};
+use dozer_types::models::sink::{
+    ConflictResolution, OnDeleteResolutionTypes, OnInsertResolutionTypes, OnUpdateResolutionTypes,
+    PostgresSinkConfig,
+};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, TableOperation,
+};
+
+fn get_dozer_schema() -> Schema {
+    Schema {
+        fields: vec![
+            FieldDefinition {
+                name: "id".to_string(),
+                typ: FieldType::UInt,
+                nullable: false,
+                source: Default::default(),
+                description: None,
+            },
+            FieldDefinition {
+                name: "data".to_string(),
+                typ: FieldType::String,
+                nullable: true,
+                source: Default::default(),
+                description: None,
+            },
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn record(id: u64, data: &str) -> Record {
+    Record::new(vec![Field::UInt(id), Field::String(data.to_string())])
+}
+
+fn get_batch(conflict_resolution: ConflictResolution) -> Batch {
+    Batch::new("sink_table".to_string(), vec![0], conflict_resolution)
+}
+
+fn get_connection() -> PostgresConfig {
+    PostgresConfig {
+        user: Some("postgres".to_string()),
+        password: Some("postgres".to_string()),
+        host: Some("localhost".to_string()),
+        port: Some(5432),
+        database: Some("postgres".to_string()),
+        ..Default::default()
+    }
+}
+
+fn get_epoch(id: u64) -> Epoch {
+    let source_states = [(
+        NodeHandle::new(None, "source".to_string()),
+        SourceState::Restartable(OpIdentifier::new(id, 0)),
+    )]
+    .into_iter()
+    .collect();
+    Epoch::new(id, Arc::new(source_states), SystemTime::now())
+}
+
+#[test]
+fn test_create_table_query() {
+    let table_name = get_qualified_table_name(None, "sink_table");
+    assert_eq!(table_name, r#""public"."sink_table""#);
+    assert_eq!(
+        get_create_table_query(&table_name, &get_dozer_schema()).unwrap(),
+        r#"CREATE TABLE IF NOT EXISTS "public"."sink_table" (
+    "id" NUMERIC(20, 0) NOT NULL,
+    "data" TEXT,
+    PRIMARY KEY ("id")
+)"#
+    );
+}
+
+#[test]
+fn test_table_queries() {
+    let queries = TableQueries::new("t".to_string(), &get_dozer_schema());
+    assert_eq!(
+        queries.insert(2, OnConflict::Error),
+        r#"INSERT INTO t ("id", "data") VALUES ($1, $2), ($3, $4)"#
+    );
+    assert_eq!(
+        queries.insert(1, OnConflict::DoNothing),
+        r#"INSERT INTO t ("id", "data") VALUES ($1, $2) ON CONFLICT DO NOTHING"#
+    );
+    assert_eq!(
+        queries.insert(1, OnConflict::DoUpdate),
+        r#"INSERT INTO t ("id", "data") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "data" = EXCLUDED."data""#
+    );
+    assert_eq!(
+        queries.delete(2),
+        r#"DELETE FROM t WHERE ("id") IN (($1), ($2))"#
+    );
+    assert_eq!(
+        queries.update(),
+        r#"UPDATE t SET "id" = $1, "data" = $2 WHERE "id" = $3"#
+    );
+    assert_eq!(queries.truncate(), "TRUNCATE TABLE t");
+}
+
+#[test]
+fn test_batch_collapses_operations_by_key() {
+    let mut batch = get_batch(ConflictResolution::default());
+    batch.insert(record(1, "a")).unwrap();
+    batch.update(record(1, "a"), record(1, "b")).unwrap();
+    batch.insert(record(2, "a")).unwrap();
+    batch.delete(record(2, "a")).unwrap();
+    batch.delete(record(3, "a")).unwrap();
+    batch.insert(record(3, "b")).unwrap();
+    batch.update(record(4, "a"), record(5, "a")).unwrap();
+
+    let (operations, inserts_without_key) = batch.take();
+    assert!(batch.is_empty());
+    assert!(inserts_without_key.is_empty());
+    assert_eq!(
+        operations,
+        vec![
+            PendingOperation::Insert(record(1, "b")),
+            PendingOperation::Upsert(record(3, "b")),
+            PendingOperation::Delete(record(4, "a")),
+            PendingOperation::Upsert(record(5, "a")),
+        ]
+    );
+}
+
+#[test]
+fn test_batch_conflict_resolution() {
+    let mut batch = get_batch(ConflictResolution {
+        on_insert: OnInsertResolutionTypes::Panic,
+        on_update: OnUpdateResolutionTypes::Panic,
+        on_delete: OnDeleteResolutionTypes::Panic,
+    });
+    batch.insert(record(1, "a")).unwrap();
+    assert!(batch.insert(record(1, "b")).is_err());
+    batch.delete(record(2, "a")).unwrap();
+    assert!(batch.delete(record(2, "a")).is_err());
+    assert!(batch.update(record(2, "a"), record(2, "b")).is_err());
+
+    let mut batch = get_batch(ConflictResolution {
+        on_insert: OnInsertResolutionTypes::Update,
+        on_update: OnUpdateResolutionTypes::Upsert,
+        on_delete: OnDeleteResolutionTypes::Nothing,
+    });
+    batch.insert(record(1, "a")).unwrap();
+    batch.insert(record(1, "b")).unwrap();
+    batch.delete(record(2, "a")).unwrap();
+    batch.update(record(2, "a"), record(2, "b")).unwrap();
+    assert_eq!(
+        batch.take().0,
+        vec![
+            PendingOperation::Insert(record(1, "b")),
+            PendingOperation::Upsert(record(2, "b")),
+        ]
+    );
+
+    // A truncate drops the buffered operations.
+    batch.insert(record(1, "a")).unwrap();
+    batch.truncate();
+    assert!(!batch.is_empty());
+    assert!(batch.take_truncate());
+    assert!(batch.is_empty());
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_metadata_round_trip() {
+    let config = map_connection_config(&ConnectionConfig::Postgres(get_connection())).unwrap();
+    let mut client = connect(config).await.unwrap();
+
+    let metadata_table_name = get_qualified_table_name(None, REPLICA_METADATA_TABLE);
+    client
+        .batch_execute(&ReplicationMetadata::get_create_table_query(
+            &metadata_table_name,
+        ))
+        .await
+        .unwrap();
+
+    let metadata = ReplicationMetadata {
+        op_id: Some(OpIdentifier::new(u64::MAX, 2)),
+        source_state: Some(vec![1, 2, 3]),
+    };
+    let transaction = client.transaction().await.unwrap();
+    metadata
+        .store(&transaction, &metadata_table_name, "sink_table")
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    let loaded = ReplicationMetadata::load(&mut client, &metadata_table_name, "sink_table")
+        .await
+        .unwrap();
+    assert_eq!(loaded, metadata);
+}
+
+#[test]
+#[ignore]
+fn test_prepared_epochs_are_resolved() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    // Prepared transactions outlive failed runs, so every run writes its own table.
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let config = PostgresSinkConfig {
+        connection: "postgres".to_string(),
+        source_table_name: "source_table".to_string(),
+        sink_table_name: format!("two_phase_sink_table_{millis}"),
+        conflict_resolution: ConflictResolution::default(),
+        max_batch_duration_ms: None,
+        preferred_batch_size: None,
+        two_phase_commit: true,
+    };
+    let build_sink = || {
+        let factory = PostgresSinkFactory::new(config.clone(), get_connection(), runtime.clone());
+        let mut sink = runtime
+            .block_on(factory.build(
+                HashMap::from([(DEFAULT_PORT_HANDLE, get_dozer_schema())]),
+                EventHub::new(1),
+            ))
+            .unwrap();
+        sink.set_source(&NodeHandle::new(None, "source".to_string()))
+            .unwrap();
+        sink
+    };
+    let insert = |id: u64| {
+        TableOperation::without_id(
+            Operation::Insert {
+                new: record(id, "data"),
+            },
+            DEFAULT_PORT_HANDLE,
+        )
+    };
+    let handle = NodeHandle::new(None, "sink".to_string());
+
+    let mut sink = build_sink();
+    assert!(sink.supports_two_phase_commit());
+    sink.process(insert(1)).unwrap();
+    sink.prepare(&get_epoch(1)).unwrap();
+    assert_eq!(sink.prepared_epochs().unwrap(), vec![1]);
+    drop(sink);
+
+    // The epoch prepared before the restart isn't visible until it's committed.
+    let mut sink = build_sink();
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    resolve_prepared_epochs(&handle, sink.as_mut()).unwrap();
+    assert!(sink.prepared_epochs().unwrap().is_empty());
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+
+    sink.process(insert(2)).unwrap();
+    sink.prepare(&get_epoch(2)).unwrap();
+    sink.abort_prepared(2).unwrap();
+    assert!(sink.prepared_epochs().unwrap().is_empty());
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+    // Committing an aborted epoch does nothing.
+    sink.commit_prepared(2).unwrap();
+
+    let table_name = get_qualified_table_name(None, &config.sink_table_name);
+    let count: i64 = runtime.block_on(async {
+        let connection =
+            map_connection_config(&ConnectionConfig::Postgres(get_connection())).unwrap();
+        let mut client = connect(connection).await.unwrap();
+        client
+            .query_one(format!("SELECT count(*) FROM {table_name}").as_str(), &[])
+            .await
+            .unwrap()
+            .get(0)
+    });
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+
+use dozer_types::bytes::{BufMut, BytesMut};
+use dozer_types::json_types::json_to_string;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::types::{Field, FieldDefinition, FieldType};
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+
+use crate::errors::PostgresSinkError;
+
+pub fn map_field_to_type(field: &FieldDefinition) -> Result<String, PostgresSinkError> {
+    let typ = match field.typ {
+        FieldType::UInt => "NUMERIC(20, 0)",
+        FieldType::U128 => "NUMERIC(39, 0)",
+        FieldType::Int => "BIGINT",
+        FieldType::Int8 => "SMALLINT",
+        FieldType::I128 => "NUMERIC(39, 0)",
+        FieldType::Float => "DOUBLE PRECISION",
+        FieldType::Boolean => "BOOLEAN",
+        FieldType::String => "TEXT",
+        FieldType::Text => "TEXT",
+        FieldType::Binary => "BYTEA",
+        FieldType::Decimal => "NUMERIC",
+        FieldType::Timestamp => "TIMESTAMPTZ",
+        FieldType::Date => "DATE",
+        FieldType::Json => "JSONB",
+        FieldType::Point => "POINT",
+        FieldType::Duration => {
+            return Err(PostgresSinkError::UnsupportedFieldType(FieldType::Duration))
+        }
+    };
+
+    if field.nullable {
+        Ok(typ.to_string())
+    } else {
+        Ok(format!("{typ} NOT NULL"))
+    }
+}
+
+/// Quotes an identifier so it can be used verbatim in a postgres statement.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a string literal, for statements that don't take parameters.
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Binary encoding of a Dozer [`Field`] as a statement parameter.
+///
+/// The target column type is decided by the table, so this accepts any type and
+/// encodes the value the way the column created by [`map_field_to_type`] expects.
+#[derive(Debug)]
+pub struct PostgresField<'a>(pub &'a Field);
+
+impl ToSql for PostgresField<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+    where
+        Self: Sized,
+    {
+        match self.0 {
+            Field::UInt(value) => Decimal::from(*value).to_sql(ty, out),
+            Field::U128(value) => {
+                Decimal::try_from_i128_with_scale(i128::try_from(*value)?, 0)?.to_sql(ty, out)
+            }
+            Field::Int(value) => value.to_sql(ty, out),
+            Field::Int8(value) => i16::from(*value).to_sql(ty, out),
+            Field::I128(value) => Decimal::try_from_i128_with_scale(*value, 0)?.to_sql(ty, out),
+            Field::Float(value) => value.0.to_sql(ty, out),
+            Field::Boolean(value) => value.to_sql(ty, out),
+            Field::String(value) | Field::Text(value) => value.to_sql(ty, out),
+            Field::Binary(value) => value.to_sql(ty, out),
+            Field::Decimal(value) => value.to_sql(ty, out),
+            Field::Timestamp(value) => value.to_sql(ty, out),
+            Field::Date(value) => value.to_sql(ty, out),
+            Field::Json(value) => {
+                // JSONB binary format is a version byte followed by the text representation.
+                if *ty == Type::JSONB {
+                    out.put_u8(1);
+                }
+                out.put_slice(json_to_string(value).as_bytes());
+                Ok(IsNull::No)
+            }
+            Field::Point(value) => {
+                out.put_f64(value.0.x().0);
+                out.put_f64(value.0.y().0);
+                Ok(IsNull::No)
+            }
+            Field::Duration(_) => Err(Box::new(PostgresSinkError::UnsupportedFieldType(
+                FieldType::Duration,
+            ))),
+            Field::Null => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+pub fn as_params<'a>(fields: &'a [PostgresField<'a>]) -> Vec<&'a (dyn ToSql + Sync)> {
+    fields
+        .iter()
+        .map(|field| field as &(dyn ToSql + Sync))
+        .collect()
+}
@@ -0,0 +1,110 @@
+use dozer_types::models::sink_config::snowflake::Destination;
+use dozer_types::types::{FieldDefinition, FieldType, Schema};
+
+/// Operation of a staged row, `I`, `U` or `D`.
+pub const OP_COLUMN: &str = "__dozer_op";
+/// Order of a staged row within its batch, the last change of a key wins.
+pub const SEQ_COLUMN: &str = "__dozer_seq";
+
+/// Quotes an identifier so it can be used verbatim in a snowflake statement.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The fully qualified name of the destination table.
+pub fn destination_table_name(destination: &Destination) -> String {
+    format!(
+        "{}.{}.{}",
+        destination.database, destination.schema, destination.table
+    )
+}
+
+/// The fully qualified name of the table batches are staged in before they're merged.
+pub fn staging_table_name(destination: &Destination) -> String {
+    format!(
+        "{}.{}.{}_DOZER_STAGING",
+        destination.database, destination.schema, destination.table
+    )
+}
+
+/// The fully qualified name of the table the sinks writing to the destination's schema store their position in.
+pub fn metadata_table_name(destination: &Destination) -> String {
+    format!(
+        "{}.{}.DOZER_REPLICATION_METADATA",
+        destination.database, destination.schema
+    )
+}
+
+pub fn get_create_table_query(table_name: &str, schema: &Schema) -> String {
+    let mut parts = schema
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{} {}",
+                quote_identifier(&field.name),
+                get_column_type(field)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let primary_key = schema
+        .primary_index
+        .iter()
+        .map(|index| quote_identifier(&schema.fields[*index].name))
+        .collect::<Vec<_>>();
+    parts.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table_name} (\n    {}\n)",
+        parts.join(",\n    ")
+    )
+}
+
+/// The staging table has the columns of the destination, all nullable, plus the operation and its order.
+pub fn get_create_staging_table_query(table_name: &str, schema: &Schema) -> String {
+    let mut parts = schema
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{} {}",
+                quote_identifier(&field.name),
+                get_snowflake_type(field.typ)
+            )
+        })
+        .collect::<Vec<_>>();
+    parts.push(format!("{} VARCHAR NOT NULL", quote_identifier(OP_COLUMN)));
+    parts.push(format!("{} INTEGER NOT NULL", quote_identifier(SEQ_COLUMN)));
+
+    format!(
+        "CREATE TRANSIENT TABLE IF NOT EXISTS {table_name} (\n    {}\n)",
+        parts.join(",\n    ")
+    )
+}
+
+fn get_column_type(field: &FieldDefinition) -> String {
+    let typ = get_snowflake_type(field.typ);
+    if field.nullable {
+        typ.to_string()
+    } else {
+        format!("{typ} NOT NULL")
+    }
+}
+
+pub fn get_snowflake_type(typ: FieldType) -> &'static str {
+    match typ {
+        FieldType::UInt | FieldType::U128 | FieldType::I128 => "NUMBER(38, 0)",
+        FieldType::Int | FieldType::Int8 => "INTEGER",
+        FieldType::Float => "FLOAT",
+        FieldType::Boolean => "BOOLEAN",
+        FieldType::String | FieldType::Text | FieldType::Point => "VARCHAR",
+        FieldType::Binary => "BINARY",
+        FieldType::Decimal => "NUMBER(38, 10)",
+        FieldType::Timestamp => "TIMESTAMP_TZ",
+        FieldType::Date => "DATE",
+        FieldType::Json => "VARIANT",
+        // Durations are stored as nanoseconds.
+        FieldType::Duration => "NUMBER(38, 0)",
+    }
+}
@@ -0,0 +1,27 @@
+use dozer_ingestion_snowflake::SnowflakeError;
+use dozer_types::thiserror::{self, Error};
+use odbc::DiagnosticRecord;
+
+#[derive(Error, Debug)]
+pub enum SnowflakeSinkError {
+    #[error("Snowflake error: {0}")]
+    Snowflake(#[from] SnowflakeError),
+
+    #[error("Failed to create ODBC environment: {0:?}")]
+    Environment(Option<DiagnosticRecord>),
+
+    #[error("Table {0} has no primary key, rows can't be merged")]
+    PrimaryKeyNotFound(String),
+
+    #[error("Failed to spawn the snowflake sink thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Invalid source state in the sink metadata: {0}")]
+    InvalidMetadata(#[source] base64::DecodeError),
+
+    #[error("The snowflake sink thread stopped")]
+    WorkerStopped,
+
+    #[error("The snowflake sink thread panicked")]
+    WorkerPanicked,
+}
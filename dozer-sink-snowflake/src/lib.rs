@@ -0,0 +1,8 @@
+pub mod ddl;
+pub mod errors;
+mod metadata;
+mod query;
+mod sink;
+pub use sink::SnowflakeSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,118 @@
+use base64::Engine;
+use dozer_ingestion_snowflake::connection::client::Client;
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::Field;
+
+use crate::ddl::quote_identifier;
+use crate::errors::SnowflakeSinkError;
+use crate::query::text_literal;
+
+pub const META_TABLE_COL: &str = "table";
+pub const META_TXN_ID_COL: &str = "txn_id";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
+
+/// What the sink has merged into one destination table.
+///
+/// It's merged in the same transaction as the batch, so it never runs ahead of the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationMetadata {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+impl ReplicationMetadata {
+    pub fn get_create_table_query(metadata_table_name: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {metadata_table_name} (\n    \
+                {} VARCHAR NOT NULL,\n    \
+                {} INTEGER,\n    \
+                {} INTEGER,\n    \
+                {} VARCHAR,\n    \
+                PRIMARY KEY ({})\n)",
+            quote_identifier(META_TABLE_COL),
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+            quote_identifier(META_TABLE_COL),
+        )
+    }
+
+    pub fn get_select_query(metadata_table_name: &str, table_name: &str) -> String {
+        format!(
+            "SELECT {}, {}, {} FROM {metadata_table_name} WHERE {} = {}",
+            quote_identifier(META_TXN_ID_COL),
+            quote_identifier(META_SEQ_IN_TX_COL),
+            quote_identifier(META_SOURCE_STATE_COL),
+            quote_identifier(META_TABLE_COL),
+            text_literal(table_name),
+        )
+    }
+
+    /// Upserts the metadata of `table_name`. `u64`s are stored as `INTEGER`, reinterpreting the bits.
+    pub fn get_merge_query(&self, metadata_table_name: &str, table_name: &str) -> String {
+        let (txid, seq_in_tx) = match self.op_id {
+            Some(op_id) => (
+                (op_id.txid as i64).to_string(),
+                (op_id.seq_in_tx as i64).to_string(),
+            ),
+            None => ("NULL".to_string(), "NULL".to_string()),
+        };
+        let source_state = match &self.source_state {
+            Some(source_state) => format!(
+                "'{}'",
+                base64::engine::general_purpose::STANDARD.encode(source_state)
+            ),
+            None => "NULL".to_string(),
+        };
+        format!(
+            "MERGE INTO {metadata_table_name} AS t USING (\
+                SELECT {} AS {table}, {txid}::INTEGER AS {txid_col}, {seq_in_tx}::INTEGER AS {seq_col}, \
+                {source_state}::VARCHAR AS {state}\
+            ) AS s ON t.{table} = s.{table} \
+            WHEN MATCHED THEN UPDATE SET t.{txid_col} = s.{txid_col}, t.{seq_col} = s.{seq_col}, t.{state} = s.{state} \
+            WHEN NOT MATCHED THEN INSERT ({table}, {txid_col}, {seq_col}, {state}) \
+            VALUES (s.{table}, s.{txid_col}, s.{seq_col}, s.{state})",
+            text_literal(table_name),
+            table = quote_identifier(META_TABLE_COL),
+            txid_col = quote_identifier(META_TXN_ID_COL),
+            seq_col = quote_identifier(META_SEQ_IN_TX_COL),
+            state = quote_identifier(META_SOURCE_STATE_COL),
+        )
+    }
+
+    pub fn load(
+        client: &Client,
+        metadata_table_name: &str,
+        table_name: &str,
+    ) -> Result<Self, SnowflakeSinkError> {
+        let query = Self::get_select_query(metadata_table_name, table_name);
+        // The connection goes back to the pool once all rows are read.
+        let rows = client.fetch(query)?.collect::<Result<Vec<_>, _>>()?;
+        match rows.first() {
+            Some(row) => Self::from_row(row),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn from_row(row: &[Field]) -> Result<Self, SnowflakeSinkError> {
+        let int = |field: &Field| match field {
+            Field::Null => None,
+            field => field.to_int(),
+        };
+        let op_id = int(&row[0])
+            .map(|txid| OpIdentifier::new(txid as u64, int(&row[1]).unwrap_or(0) as u64));
+        let source_state = match row[2].as_string() {
+            Some(source_state) => Some(
+                base64::engine::general_purpose::STANDARD
+                    .decode(source_state)
+                    .map_err(SnowflakeSinkError::InvalidMetadata)?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            op_id,
+            source_state,
+        })
+    }
+}
@@ -0,0 +1,198 @@
+use base64::Engine;
+use dozer_types::json_types::json_to_string;
+use dozer_types::types::{Field, FieldType, Schema};
+
+use crate::ddl::{get_snowflake_type, quote_identifier, OP_COLUMN, SEQ_COLUMN};
+
+/// Snowflake doesn't accept more rows than this in one `VALUES` clause.
+const MAX_VALUES_ROWS: usize = 16384;
+/// Statements are kept well below the size Snowflake accepts.
+const MAX_STATEMENT_SIZE: usize = 1 << 20;
+
+/// A change staged to be merged into the destination table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedRow {
+    pub values: Vec<Field>,
+    /// `I`, `U` or `D`.
+    pub op: &'static str,
+}
+
+/// Builds the statements that stage a batch and merge it into the destination table.
+#[derive(Debug, Clone)]
+pub struct TableQueries {
+    destination: String,
+    staging: String,
+    types: Vec<FieldType>,
+    columns: Vec<String>,
+    key_columns: Vec<String>,
+    non_key_columns: Vec<String>,
+}
+
+impl TableQueries {
+    pub fn new(destination: String, staging: String, schema: &Schema) -> Self {
+        let columns = schema
+            .fields
+            .iter()
+            .map(|field| quote_identifier(&field.name))
+            .collect::<Vec<_>>();
+        let key_columns = schema
+            .primary_index
+            .iter()
+            .map(|index| columns[*index].clone())
+            .collect();
+        let non_key_columns = columns
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !schema.primary_index.contains(index))
+            .map(|(_, column)| column.clone())
+            .collect();
+        Self {
+            destination,
+            staging,
+            types: schema.fields.iter().map(|field| field.typ).collect(),
+            columns,
+            key_columns,
+            non_key_columns,
+        }
+    }
+
+    /// `INSERT` statements that stage the rows, in order.
+    ///
+    /// Values are passed as string literals and converted by the `SELECT`,
+    /// as `VALUES` only accepts constants.
+    pub fn insert_staging(&self, rows: &[StagedRow]) -> Vec<String> {
+        let expressions = self
+            .types
+            .iter()
+            .enumerate()
+            .map(|(index, typ)| column_expression(&format!("column{}", index + 1), *typ))
+            .chain([
+                format!("column{}", self.types.len() + 1),
+                format!("column{}", self.types.len() + 2),
+            ])
+            .collect::<Vec<_>>()
+            .join(", ");
+        let prefix = format!(
+            "INSERT INTO {} ({}, {}, {}) SELECT {expressions} FROM VALUES ",
+            self.staging,
+            self.columns.join(", "),
+            quote_identifier(OP_COLUMN),
+            quote_identifier(SEQ_COLUMN),
+        );
+
+        let mut statements = vec![];
+        let mut statement = prefix.clone();
+        let mut num_rows = 0;
+        for (seq, row) in rows.iter().enumerate() {
+            let values = row
+                .values
+                .iter()
+                .map(literal)
+                .chain([format!("'{}'", row.op), seq.to_string()])
+                .collect::<Vec<_>>()
+                .join(", ");
+            if num_rows > 0
+                && (num_rows == MAX_VALUES_ROWS
+                    || statement.len() + values.len() + 4 > MAX_STATEMENT_SIZE)
+            {
+                statements.push(std::mem::replace(&mut statement, prefix.clone()));
+                num_rows = 0;
+            }
+            if num_rows > 0 {
+                statement.push_str(", ");
+            }
+            statement.push('(');
+            statement.push_str(&values);
+            statement.push(')');
+            num_rows += 1;
+        }
+        if num_rows > 0 {
+            statements.push(statement);
+        }
+        statements
+    }
+
+    /// Applies the last staged change of every key to the destination table.
+    pub fn merge(&self) -> String {
+        let on = self
+            .key_columns
+            .iter()
+            .map(|column| format!("t.{column} = s.{column}"))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let op = quote_identifier(OP_COLUMN);
+
+        let mut query = format!(
+            "MERGE INTO {} AS t USING (\
+                SELECT * FROM {} QUALIFY ROW_NUMBER() OVER (PARTITION BY {} ORDER BY {} DESC) = 1\
+            ) AS s ON {on} \
+            WHEN MATCHED AND s.{op} = 'D' THEN DELETE ",
+            self.destination,
+            self.staging,
+            self.key_columns.join(", "),
+            quote_identifier(SEQ_COLUMN),
+        );
+        if !self.non_key_columns.is_empty() {
+            let set = self
+                .non_key_columns
+                .iter()
+                .map(|column| format!("t.{column} = s.{column}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            query.push_str(&format!("WHEN MATCHED THEN UPDATE SET {set} "));
+        }
+        let values = self
+            .columns
+            .iter()
+            .map(|column| format!("s.{column}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        query.push_str(&format!(
+            "WHEN NOT MATCHED AND s.{op} <> 'D' THEN INSERT ({}) VALUES ({values})",
+            self.columns.join(", ")
+        ));
+        query
+    }
+
+    pub fn truncate(&self) -> String {
+        format!("TRUNCATE TABLE {}", self.destination)
+    }
+
+    pub fn truncate_staging(&self) -> String {
+        format!("TRUNCATE TABLE {}", self.staging)
+    }
+}
+
+/// A text expression that needs no escaping.
+pub fn text_literal(value: &str) -> String {
+    column_expression(
+        &literal(&Field::String(value.to_string())),
+        FieldType::String,
+    )
+}
+
+/// Converts a string literal produced by [`literal`] to the column's type.
+fn column_expression(column: &str, typ: FieldType) -> String {
+    let text = format!("TO_VARCHAR(TO_BINARY({column}, 'BASE64'), 'UTF-8')");
+    match typ {
+        FieldType::String | FieldType::Text | FieldType::Point => text,
+        FieldType::Binary => format!("TO_BINARY({column}, 'BASE64')"),
+        FieldType::Json => format!("PARSE_JSON({text})"),
+        typ => format!("{column}::{}", get_snowflake_type(typ)),
+    }
+}
+
+/// Renders a field as a string literal, base64 encoded if it may contain quotes.
+pub fn literal(field: &Field) -> String {
+    let base64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+    let value = match field {
+        Field::Null => return "NULL".to_string(),
+        Field::String(value) | Field::Text(value) => base64(value.as_bytes()),
+        Field::Binary(value) => base64(value),
+        Field::Json(value) => base64(json_to_string(value).as_bytes()),
+        Field::Point(value) => base64(value.to_string().as_bytes()),
+        Field::Duration(value) => value.0.as_nanos().to_string(),
+        field => field.to_string(),
+    };
+    format!("'{value}'")
+}
@@ -0,0 +1,378 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::sync::oneshot;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_ingestion_snowflake::connection::client::Client;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::{debug, info, warn};
+use dozer_types::models::sink_config::snowflake::{
+    default_batch_interval, default_batch_size, default_suspend_warehouse,
+};
+use dozer_types::models::sink_config::Snowflake;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
+use odbc::create_environment_v3;
+use std::collections::HashMap;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::ddl::{
+    destination_table_name, get_create_staging_table_query, get_create_table_query,
+    metadata_table_name, staging_table_name,
+};
+use crate::errors::SnowflakeSinkError;
+use crate::metadata::ReplicationMetadata;
+use crate::query::{StagedRow, TableQueries};
+
+/// Rows that can be queued for the sink thread before `process` blocks.
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+pub struct SnowflakeSinkFactory {
+    config: Snowflake,
+}
+
+impl SnowflakeSinkFactory {
+    pub fn new(config: Snowflake) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for SnowflakeSinkFactory {
+    fn type_name(&self) -> String {
+        "snowflake".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.endpoint.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        if schema.primary_index.is_empty() {
+            return Err(
+                SnowflakeSinkError::PrimaryKeyNotFound(self.config.endpoint.clone()).into(),
+            );
+        }
+
+        let options = self.config.options.clone().unwrap_or_default();
+        let worker = Worker {
+            config: self.config.clone(),
+            schema: schema.clone(),
+            batch_size: options.batch_size.unwrap_or_else(default_batch_size),
+            batch_interval: options
+                .batch_interval_seconds
+                .unwrap_or_else(default_batch_interval),
+            suspend_warehouse: options
+                .suspend_warehouse_after_each_batch
+                .unwrap_or_else(default_suspend_warehouse),
+        };
+
+        // The ODBC client can't leave the thread it was created on, so it lives on a thread of its own.
+        let (sender, receiver) = sync_channel(CHANNEL_CAPACITY);
+        let (metadata_sender, metadata_receiver) = oneshot::channel();
+        let handle = std::thread::Builder::new()
+            .name(format!("snowflake-sink-{}", self.config.destination.table))
+            .spawn(move || worker.run(receiver, metadata_sender))
+            .map_err(SnowflakeSinkError::Spawn)?;
+        let Ok(metadata) = metadata_receiver.await else {
+            // The thread only stops before restoring the metadata on error.
+            return Err(worker_error(handle).into());
+        };
+        debug!(
+            "[Sink] Restored metadata for {}: {:?}",
+            self.config.destination.table, metadata.op_id
+        );
+
+        Ok(Box::new(SnowflakeSink {
+            sender,
+            handle: Some(handle),
+            primary_index: schema.primary_index,
+            sent_metadata: metadata.clone(),
+            metadata,
+            source: None,
+        }))
+    }
+}
+
+/// What the sink sends to its thread.
+#[derive(Debug)]
+enum WorkerMessage {
+    Row(StagedRow),
+    Truncate,
+    /// The metadata of a committed epoch, merged with the rows received before it.
+    Commit(ReplicationMetadata),
+}
+
+#[derive(Debug)]
+struct Worker {
+    config: Snowflake,
+    schema: Schema,
+    batch_size: usize,
+    batch_interval: Duration,
+    suspend_warehouse: bool,
+}
+
+impl Worker {
+    /// Stages and merges the received rows whenever the batch is full or the batch interval is over.
+    ///
+    /// Sends the metadata restored from the metadata table once the tables are created.
+    fn run(
+        self,
+        receiver: Receiver<WorkerMessage>,
+        metadata_sender: oneshot::Sender<ReplicationMetadata>,
+    ) -> Result<(), SnowflakeSinkError> {
+        let env = create_environment_v3().map_err(SnowflakeSinkError::Environment)?;
+        let client = Client::new(self.config.connection.clone().into(), &env);
+
+        let destination = destination_table_name(&self.config.destination);
+        let staging = staging_table_name(&self.config.destination);
+        let metadata_table = metadata_table_name(&self.config.destination);
+        client.exec(&get_create_table_query(&destination, &self.schema))?;
+        client.exec(&get_create_staging_table_query(&staging, &self.schema))?;
+        client.exec(&ReplicationMetadata::get_create_table_query(
+            &metadata_table,
+        ))?;
+        let metadata = ReplicationMetadata::load(&client, &metadata_table, &destination)?;
+        let queries = TableQueries::new(destination, staging, &self.schema);
+        // Rows left over by an interrupted batch will be sent again.
+        client.exec(&queries.truncate_staging())?;
+        // The sink is dropped if building fails.
+        let _ = metadata_sender.send(metadata);
+
+        let mut batch = vec![];
+        let mut metadata = None;
+        let mut deadline = None;
+        loop {
+            let message = match deadline {
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+            };
+            match message {
+                Ok(WorkerMessage::Row(row)) => {
+                    deadline.get_or_insert_with(|| Instant::now() + self.batch_interval);
+                    batch.push(row);
+                    if batch.len() < self.batch_size {
+                        continue;
+                    }
+                }
+                Ok(WorkerMessage::Commit(committed)) => {
+                    deadline.get_or_insert_with(|| Instant::now() + self.batch_interval);
+                    metadata = Some(committed);
+                    continue;
+                }
+                Ok(WorkerMessage::Truncate) => {
+                    // Rows not merged yet would be removed by the truncate anyway.
+                    batch.clear();
+                    // Pending metadata is still merged when the batch interval is over.
+                    if metadata.is_none() {
+                        deadline = None;
+                    }
+                    client.exec(&queries.truncate())?;
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush(
+                        &client,
+                        &queries,
+                        std::mem::take(&mut batch),
+                        metadata.take(),
+                    )?;
+                    return Ok(());
+                }
+            }
+            self.flush(
+                &client,
+                &queries,
+                std::mem::take(&mut batch),
+                metadata.take(),
+            )?;
+            deadline = None;
+        }
+    }
+
+    fn flush(
+        &self,
+        client: &Client,
+        queries: &TableQueries,
+        batch: Vec<StagedRow>,
+        metadata: Option<ReplicationMetadata>,
+    ) -> Result<(), SnowflakeSinkError> {
+        if batch.is_empty() && metadata.is_none() {
+            return Ok(());
+        }
+        debug!(
+            "[Sink] Merging {} rows into {}",
+            batch.len(),
+            self.config.destination.table
+        );
+
+        for statement in queries.insert_staging(&batch) {
+            client.exec(&statement)?;
+        }
+        // The pool hands this thread the same connection for every statement, so they share the transaction.
+        // Rows received after the last commit are merged too, and merged again when the sink resumes,
+        // which leaves the same result as the merge applies the last change of every key.
+        client.exec("BEGIN TRANSACTION")?;
+        if !batch.is_empty() {
+            client.exec(&queries.merge())?;
+        }
+        if let Some(metadata) = metadata {
+            client.exec(&metadata.get_merge_query(
+                &metadata_table_name(&self.config.destination),
+                &destination_table_name(&self.config.destination),
+            ))?;
+        }
+        client.exec("COMMIT")?;
+        client.exec(&queries.truncate_staging())?;
+
+        if self.suspend_warehouse {
+            let warehouse = &self.config.connection.warehouse;
+            // Suspending fails if the warehouse is already suspended, which is fine.
+            if let Err(e) = client.exec(&format!("ALTER WAREHOUSE {warehouse} SUSPEND")) {
+                warn!("[Sink] Failed to suspend warehouse {warehouse}: {e}");
+            } else {
+                info!("[Sink] Suspended warehouse {warehouse}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct SnowflakeSink {
+    sender: SyncSender<WorkerMessage>,
+    handle: Option<JoinHandle<Result<(), SnowflakeSinkError>>>,
+    primary_index: Vec<usize>,
+    /// Metadata to be sent with the next commit.
+    metadata: ReplicationMetadata,
+    /// Metadata last sent to the thread.
+    sent_metadata: ReplicationMetadata,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+}
+
+/// Returns why the sink thread stopped. It only stops on error.
+fn worker_error(handle: JoinHandle<Result<(), SnowflakeSinkError>>) -> SnowflakeSinkError {
+    match handle.join() {
+        Ok(Err(e)) => e,
+        Err(_) => SnowflakeSinkError::WorkerPanicked,
+        Ok(Ok(())) => SnowflakeSinkError::WorkerStopped,
+    }
+}
+
+impl SnowflakeSink {
+    fn stage(&mut self, values: Vec<Field>, op: &'static str) -> Result<(), SnowflakeSinkError> {
+        self.send(WorkerMessage::Row(StagedRow { values, op }))
+    }
+
+    fn send(&mut self, message: WorkerMessage) -> Result<(), SnowflakeSinkError> {
+        if self.sender.send(message).is_ok() {
+            return Ok(());
+        }
+        match self.handle.take() {
+            Some(handle) => Err(worker_error(handle)),
+            None => Err(SnowflakeSinkError::WorkerStopped),
+        }
+    }
+}
+
+impl Sink for SnowflakeSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.metadata.op_id = Some(*op_id);
+        }
+        if self.metadata != self.sent_metadata {
+            self.send(WorkerMessage::Commit(self.metadata.clone()))?;
+            self.sent_metadata = self.metadata.clone();
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.stage(new.values, "I")?,
+            Operation::Delete { old } => self.stage(old.values, "D")?,
+            Operation::Update { old, new } => {
+                let key_changed = self
+                    .primary_index
+                    .iter()
+                    .any(|index| old.values[*index] != new.values[*index]);
+                if key_changed {
+                    self.stage(old.values, "D")?;
+                    self.stage(new.values, "I")?;
+                } else {
+                    self.stage(new.values, "U")?;
+                }
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.stage(record.values, "I")?;
+                }
+            }
+            Operation::Truncate => self.send(WorkerMessage::Truncate)?,
+        }
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.metadata.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.metadata.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.metadata.source_state.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.metadata.op_id)
+    }
+}
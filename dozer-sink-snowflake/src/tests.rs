@@ -0,0 +1,160 @@
+use dozer_types::models::sink_config::snowflake::Destination;
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{Field, FieldDefinition, FieldType, Schema, SourceDefinition};
+
+use crate::ddl::{
+    destination_table_name, get_create_staging_table_query, get_create_table_query,
+    metadata_table_name, staging_table_name,
+};
+use crate::metadata::ReplicationMetadata;
+use crate::query::{literal, StagedRow, TableQueries};
+
+fn get_schema() -> Schema {
+    let field = |name: &str, typ, nullable| FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable,
+        source: SourceDefinition::Dynamic,
+        description: None,
+    };
+    Schema {
+        fields: vec![
+            field("id", FieldType::Int, false),
+            field("name", FieldType::String, true),
+            field("data", FieldType::Json, true),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_queries() -> TableQueries {
+    TableQueries::new(
+        "DB.PUBLIC.USERS".to_string(),
+        "DB.PUBLIC.USERS_DOZER_STAGING".to_string(),
+        &get_schema(),
+    )
+}
+
+#[test]
+fn table_names_are_qualified() {
+    let destination = Destination {
+        database: "DB".to_string(),
+        schema: "PUBLIC".to_string(),
+        table: "USERS".to_string(),
+    };
+    assert_eq!(destination_table_name(&destination), "DB.PUBLIC.USERS");
+    assert_eq!(
+        staging_table_name(&destination),
+        "DB.PUBLIC.USERS_DOZER_STAGING"
+    );
+    assert_eq!(
+        metadata_table_name(&destination),
+        "DB.PUBLIC.DOZER_REPLICATION_METADATA"
+    );
+}
+
+#[test]
+fn create_table_queries() {
+    assert_eq!(
+        get_create_table_query("DB.PUBLIC.USERS", &get_schema()),
+        "CREATE TABLE IF NOT EXISTS DB.PUBLIC.USERS (\n    \
+            \"id\" INTEGER NOT NULL,\n    \
+            \"name\" VARCHAR,\n    \
+            \"data\" VARIANT,\n    \
+            PRIMARY KEY (\"id\")\n)"
+    );
+    assert_eq!(
+        get_create_staging_table_query("DB.PUBLIC.USERS_DOZER_STAGING", &get_schema()),
+        "CREATE TRANSIENT TABLE IF NOT EXISTS DB.PUBLIC.USERS_DOZER_STAGING (\n    \
+            \"id\" INTEGER,\n    \
+            \"name\" VARCHAR,\n    \
+            \"data\" VARIANT,\n    \
+            \"__dozer_op\" VARCHAR NOT NULL,\n    \
+            \"__dozer_seq\" INTEGER NOT NULL\n)"
+    );
+}
+
+#[test]
+fn literals_are_quoted() {
+    assert_eq!(literal(&Field::Null), "NULL");
+    assert_eq!(literal(&Field::Int(-1)), "'-1'");
+    // Base64 can't contain a quote.
+    assert_eq!(literal(&Field::String("it's".to_string())), "'aXQncw=='");
+}
+
+#[test]
+fn rows_are_staged_in_order() {
+    let rows = vec![
+        StagedRow {
+            values: vec![Field::Int(1), Field::String("a".to_string()), Field::Null],
+            op: "I",
+        },
+        StagedRow {
+            values: vec![Field::Int(1), Field::Null, Field::Null],
+            op: "D",
+        },
+    ];
+    let statements = get_queries().insert_staging(&rows);
+    assert_eq!(
+        statements,
+        vec![
+            "INSERT INTO DB.PUBLIC.USERS_DOZER_STAGING (\"id\", \"name\", \"data\", \"__dozer_op\", \"__dozer_seq\") \
+            SELECT column1::INTEGER, TO_VARCHAR(TO_BINARY(column2, 'BASE64'), 'UTF-8'), \
+            PARSE_JSON(TO_VARCHAR(TO_BINARY(column3, 'BASE64'), 'UTF-8')), column4, column5 \
+            FROM VALUES ('1', 'YQ==', NULL, 'I', 0), ('1', NULL, NULL, 'D', 1)"
+        ]
+    );
+    assert!(get_queries().insert_staging(&[]).is_empty());
+}
+
+#[test]
+fn merge_applies_last_change_per_key() {
+    assert_eq!(
+        get_queries().merge(),
+        "MERGE INTO DB.PUBLIC.USERS AS t USING (\
+            SELECT * FROM DB.PUBLIC.USERS_DOZER_STAGING \
+            QUALIFY ROW_NUMBER() OVER (PARTITION BY \"id\" ORDER BY \"__dozer_seq\" DESC) = 1\
+        ) AS s ON t.\"id\" = s.\"id\" \
+        WHEN MATCHED AND s.\"__dozer_op\" = 'D' THEN DELETE \
+        WHEN MATCHED THEN UPDATE SET t.\"name\" = s.\"name\", t.\"data\" = s.\"data\" \
+        WHEN NOT MATCHED AND s.\"__dozer_op\" <> 'D' THEN INSERT (\"id\", \"name\", \"data\") \
+        VALUES (s.\"id\", s.\"name\", s.\"data\")"
+    );
+}
+
+#[test]
+fn truncate_queries() {
+    assert_eq!(get_queries().truncate(), "TRUNCATE TABLE DB.PUBLIC.USERS");
+    assert_eq!(
+        get_queries().truncate_staging(),
+        "TRUNCATE TABLE DB.PUBLIC.USERS_DOZER_STAGING"
+    );
+}
+
+#[test]
+fn metadata_is_merged_by_table() {
+    let metadata = ReplicationMetadata {
+        op_id: Some(OpIdentifier::new(u64::MAX, 2)),
+        source_state: Some(vec![1, 2, 3]),
+    };
+    assert_eq!(
+        metadata.get_merge_query("DB.PUBLIC.DOZER_REPLICATION_METADATA", "DB.PUBLIC.USERS"),
+        "MERGE INTO DB.PUBLIC.DOZER_REPLICATION_METADATA AS t USING (\
+            SELECT TO_VARCHAR(TO_BINARY('REIuUFVCTElDLlVTRVJT', 'BASE64'), 'UTF-8') AS \"table\", \
+            -1::INTEGER AS \"txn_id\", 2::INTEGER AS \"seq_in_tx\", 'AQID'::VARCHAR AS \"source_state\"\
+        ) AS s ON t.\"table\" = s.\"table\" \
+        WHEN MATCHED THEN UPDATE SET t.\"txn_id\" = s.\"txn_id\", t.\"seq_in_tx\" = s.\"seq_in_tx\", \
+        t.\"source_state\" = s.\"source_state\" \
+        WHEN NOT MATCHED THEN INSERT (\"table\", \"txn_id\", \"seq_in_tx\", \"source_state\") \
+        VALUES (s.\"table\", s.\"txn_id\", s.\"seq_in_tx\", s.\"source_state\")"
+    );
+    assert_eq!(
+        ReplicationMetadata::get_select_query(
+            "DB.PUBLIC.DOZER_REPLICATION_METADATA",
+            "DB.PUBLIC.USERS"
+        ),
+        "SELECT \"txn_id\", \"seq_in_tx\", \"source_state\" \
+        FROM DB.PUBLIC.DOZER_REPLICATION_METADATA \
+        WHERE \"table\" = TO_VARCHAR(TO_BINARY('REIuUFVCTElDLlVTRVJT', 'BASE64'), 'UTF-8')"
+    );
+}
@@ -0,0 +1,114 @@
+use dozer_core::tokio;
+use dozer_types::log::warn;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::WebhookSinkError;
+
+pub const TIMESTAMP_HEADER: &str = "X-Dozer-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Dozer-Signature";
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+    secret: Option<String>,
+    max_retries: u32,
+    initial_backoff: Duration,
+}
+
+impl WebhookClient {
+    pub fn new(
+        secret: Option<String>,
+        max_retries: u32,
+        initial_backoff: Duration,
+    ) -> Result<Self, WebhookSinkError> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .build()
+                .map_err(WebhookSinkError::Client)?,
+            secret,
+            max_retries,
+            initial_backoff,
+        })
+    }
+
+    /// Posts the body until the endpoint returns 2xx, backing off exponentially between attempts.
+    pub async fn post(&self, url: &str, body: String) -> Result<(), WebhookSinkError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.try_post(url, body.clone()).await;
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.max_retries && is_retryable(&e) => {
+                    let backoff = self
+                        .initial_backoff
+                        .checked_mul(2u32.saturating_pow(attempt))
+                        .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF));
+                    attempt += 1;
+                    warn!(
+                        "[Sink] {e}. Retrying in {backoff:?} ({attempt}/{})",
+                        self.max_retries
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn try_post(&self, url: &str, body: String) -> Result<(), WebhookSinkError> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| WebhookSinkError::Request(url.to_string(), e))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        Err(WebhookSinkError::Status {
+            url: url.to_string(),
+            status,
+            body: response.text().await.unwrap_or_default(),
+        })
+    }
+}
+
+/// The `sha256=` prefixed hex HMAC-SHA256 of `{timestamp}.{body}`.
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Client errors other than timeouts and rate limiting won't succeed on retry.
+pub fn is_retryable(error: &WebhookSinkError) -> bool {
+    match error {
+        WebhookSinkError::Status { status, .. } => {
+            !status.is_client_error()
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => true,
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use dozer_types::serde_json;
+use dozer_types::thiserror::{self, Error};
+
+#[derive(Error, Debug)]
+pub enum WebhookSinkError {
+    #[error("Failed to create HTTP client: {0}")]
+    Client(#[source] reqwest::Error),
+
+    #[error("Request to {0} failed: {1}")]
+    Request(String, #[source] reqwest::Error),
+
+    #[error("Request to {url} returned {status}: {body}")]
+    Status {
+        url: String,
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    #[error("Failed to access sink metadata at {0:?}: {1}")]
+    Metadata(PathBuf, #[source] std::io::Error),
+
+    #[error("Invalid sink metadata at {0:?}: {1}")]
+    InvalidMetadata(PathBuf, #[source] serde_json::Error),
+}
@@ -0,0 +1,8 @@
+mod client;
+pub mod errors;
+mod metadata;
+mod payload;
+mod sink;
+pub use sink::WebhookSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::serde_json;
+
+use crate::errors::WebhookSinkError;
+
+/// The position the endpoint has accepted operations up to.
+///
+/// Endpoints have nowhere to store it, so it's kept in a file of its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct WebhookSinkMetadata {
+    pub op_id: Option<OpIdentifier>,
+}
+
+impl WebhookSinkMetadata {
+    pub fn load(path: &Path) -> Result<Self, WebhookSinkError> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(WebhookSinkError::Metadata(path.to_path_buf(), e)),
+        };
+        serde_json::from_slice(&data)
+            .map_err(|e| WebhookSinkError::InvalidMetadata(path.to_path_buf(), e))
+    }
+
+    /// Writes the file aside, syncs and renames it, so a crash leaves either the old or the new position behind.
+    pub fn store(&self, path: &Path) -> Result<(), WebhookSinkError> {
+        let temp_path = path.with_extension("json.tmp");
+        let write = || {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = File::create(&temp_path)?;
+            file.write_all(&serde_json::to_vec(self)?)?;
+            file.sync_all()
+        };
+        write().map_err(|e| WebhookSinkError::Metadata(temp_path.clone(), e))?;
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| WebhookSinkError::Metadata(path.to_path_buf(), e))
+    }
+}
@@ -0,0 +1,94 @@
+use dozer_types::json_types::{
+    field_to_json_value, json_to_string, JsonArray, JsonObject, JsonValue,
+};
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{Operation, Record, Schema};
+
+/// The operations of one table, encoded as they're received.
+#[derive(Debug)]
+pub struct TablePayload {
+    table_name: String,
+    field_names: Vec<String>,
+    operations: Vec<JsonValue>,
+}
+
+impl TablePayload {
+    pub fn new(table_name: String, schema: &Schema) -> Self {
+        Self {
+            table_name,
+            field_names: schema
+                .fields
+                .iter()
+                .map(|field| field.name.clone())
+                .collect(),
+            operations: vec![],
+        }
+    }
+
+    pub fn push(&mut self, op: Operation, op_id: Option<OpIdentifier>) {
+        match op {
+            Operation::Insert { new } => self.push_operation("insert", None, Some(new), op_id),
+            Operation::Delete { old } => self.push_operation("delete", Some(old), None, op_id),
+            Operation::Update { old, new } => {
+                self.push_operation("update", Some(old), Some(new), op_id)
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.push_operation("insert", None, Some(record), op_id);
+                }
+            }
+            Operation::Truncate => self.push_operation("truncate", None, None, op_id),
+        }
+    }
+
+    fn push_operation(
+        &mut self,
+        op: &str,
+        old: Option<Record>,
+        new: Option<Record>,
+        op_id: Option<OpIdentifier>,
+    ) {
+        let mut operation = JsonObject::new();
+        operation.insert("op", op);
+        operation.insert("op_id", op_id.map_or(JsonValue::NULL, op_id_to_json));
+        operation.insert("old", self.record_to_json(old));
+        operation.insert("new", self.record_to_json(new));
+        self.operations.push(operation.into());
+    }
+
+    fn record_to_json(&self, record: Option<Record>) -> JsonValue {
+        let Some(record) = record else {
+            return JsonValue::NULL;
+        };
+        let mut object = JsonObject::new();
+        for (name, field) in self.field_names.iter().zip(record.values) {
+            object.insert(name.as_str(), field_to_json_value(field));
+        }
+        object.into()
+    }
+
+    /// The buffered operations as request bodies of at most `batch_size` operations each.
+    pub fn bodies(&self, batch_size: Option<usize>) -> Vec<String> {
+        let batch_size = batch_size.unwrap_or(self.operations.len()).max(1);
+        self.operations
+            .chunks(batch_size)
+            .map(|chunk| {
+                let mut body = JsonObject::new();
+                body.insert("table", self.table_name.as_str());
+                body.insert("operations", chunk.iter().cloned().collect::<JsonArray>());
+                json_to_string(&body.into())
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.operations.clear();
+    }
+}
+
+fn op_id_to_json(op_id: OpIdentifier) -> JsonValue {
+    let mut object = JsonObject::new();
+    object.insert("txid", op_id.txid);
+    object.insert("seq_in_tx", op_id.seq_in_tx);
+    object.into()
+}
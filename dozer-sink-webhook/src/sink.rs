@@ -0,0 +1,227 @@
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::sink::WebhookSinkConfig;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Schema, TableOperation};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::{self, WebhookClient};
+use crate::errors::WebhookSinkError;
+use crate::metadata::WebhookSinkMetadata;
+use crate::payload::TablePayload;
+
+#[derive(Debug)]
+pub struct WebhookSinkFactory {
+    runtime: Arc<Runtime>,
+    config: WebhookSinkConfig,
+    /// File the acknowledged op id is kept in. Without it, the sink starts over on every run.
+    metadata_path: Option<PathBuf>,
+}
+
+impl WebhookSinkFactory {
+    pub fn new(
+        config: WebhookSinkConfig,
+        metadata_path: Option<PathBuf>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            runtime,
+            config,
+            metadata_path,
+        }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for WebhookSinkFactory {
+    fn type_name(&self) -> String {
+        "webhook".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        (0..self.config.tables.len() as PortHandle).collect()
+    }
+
+    fn get_input_port_name(&self, port: &PortHandle) -> String {
+        self.config.tables[*port as usize].source_table_name.clone()
+    }
+
+    fn prepare(&self, _input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let client = WebhookClient::new(
+            self.config.secret.clone(),
+            self.config.max_retries,
+            Duration::from_millis(self.config.initial_backoff_ms),
+        )?;
+
+        let tables = self
+            .config
+            .tables
+            .iter()
+            .enumerate()
+            .map(|(port, table)| {
+                let schema = input_schemas.remove(&(port as PortHandle)).unwrap();
+                SinkTable {
+                    url: self.config.url(table),
+                    payload: TablePayload::new(table.source_table_name.clone(), &schema),
+                }
+            })
+            .collect();
+
+        let committed_op_id = match &self.metadata_path {
+            Some(path) => WebhookSinkMetadata::load(path)?.op_id,
+            None => None,
+        };
+        debug!("[Sink] Restored op id {committed_op_id:?}");
+
+        Ok(Box::new(WebhookSink {
+            client,
+            runtime: self.runtime.clone(),
+            tables,
+            batch_size: self.config.batch_size,
+            flush_interval_ms: self.config.flush_interval_ms,
+            metadata_path: self.metadata_path.clone(),
+            op_id: committed_op_id,
+            committed_op_id,
+            source: None,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct SinkTable {
+    url: String,
+    payload: TablePayload,
+}
+
+#[derive(Debug)]
+struct WebhookSink {
+    client: WebhookClient,
+    runtime: Arc<Runtime>,
+    /// Indexed by input port.
+    tables: Vec<SinkTable>,
+    batch_size: Option<u64>,
+    flush_interval_ms: Option<u64>,
+    metadata_path: Option<PathBuf>,
+    /// The op id of the last committed epoch.
+    op_id: Option<OpIdentifier>,
+    /// The op id the endpoint has accepted every operation up to.
+    committed_op_id: Option<OpIdentifier>,
+    /// The source the stored op id belongs to.
+    source: Option<NodeHandle>,
+}
+
+impl WebhookSink {
+    /// Operations stay buffered until they're accepted, so a failed flush is retried as a whole.
+    async fn post_all(&mut self) -> Result<(), WebhookSinkError> {
+        let batch_size = self.batch_size.map(|batch_size| batch_size as usize);
+        for table in &mut self.tables {
+            for body in table.payload.bodies(batch_size) {
+                debug!("[Sink] Posting {} bytes to {}", body.len(), table.url);
+                self.client.post(&table.url, body).await?;
+            }
+            table.payload.clear();
+        }
+        Ok(())
+    }
+}
+
+impl Sink for WebhookSink {
+    fn commit(&mut self, epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if let Some(op_id) = self
+            .source
+            .as_ref()
+            .and_then(|source| epoch_details.common_info.source_states.get(source))
+            .and_then(SourceState::op_id)
+        {
+            self.op_id = Some(*op_id);
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        self.tables[op.port as usize].payload.push(op.op, op.id);
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: &NodeHandle) -> Result<(), BoxedError> {
+        self.source = Some(source.clone());
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.committed_op_id)
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        self.batch_size
+    }
+
+    fn max_batch_duration_ms(&self) -> Option<u64> {
+        self.flush_interval_ms
+    }
+
+    /// Commits are only acknowledged once the endpoint has accepted every operation up to them.
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.post_all())?;
+        if self.op_id != self.committed_op_id {
+            if let Some(path) = &self.metadata_path {
+                WebhookSinkMetadata { op_id: self.op_id }.store(path)?;
+            }
+            self.committed_op_id = self.op_id;
+        }
+        Ok(())
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+
+    fn is_retryable(&self, error: &BoxedError) -> bool {
+        error
+            .downcast_ref::<WebhookSinkError>()
+            .map_or(true, client::is_retryable)
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_types::models::sink::{WebhookSinkConfig, WebhookSinkTable};
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use dozer_types::serde_json::{self, json, Value};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::client::{sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::payload::TablePayload;
+use crate::WebhookSinkFactory;
+
+fn get_schema() -> Schema {
+    let field = |name: &str, typ| FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable: true,
+        source: SourceDefinition::Dynamic,
+        description: None,
+    };
+    Schema {
+        fields: vec![
+            field("id", FieldType::Int),
+            field("name", FieldType::String),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_record(id: i64, name: &str) -> Record {
+    Record::new(vec![Field::Int(id), Field::String(name.to_string())])
+}
+
+fn get_config(url: String) -> WebhookSinkConfig {
+    WebhookSinkConfig {
+        url,
+        tables: vec![WebhookSinkTable {
+            source_table_name: "users".to_string(),
+            url: None,
+        }],
+        batch_size: None,
+        flush_interval_ms: None,
+        max_retries: 2,
+        initial_backoff_ms: 1,
+        secret: Some("secret".to_string()),
+    }
+}
+
+#[test]
+fn operations_are_encoded_with_records_and_op_id() {
+    let mut payload = TablePayload::new("users".to_string(), &get_schema());
+    payload.push(
+        Operation::Update {
+            old: get_record(1, "a"),
+            new: get_record(1, "b"),
+        },
+        Some(OpIdentifier::new(2, 3)),
+    );
+    payload.push(
+        Operation::Delete {
+            old: get_record(1, "b"),
+        },
+        None,
+    );
+
+    let bodies = payload.bodies(None);
+    assert_eq!(bodies.len(), 1);
+    let body: Value = serde_json::from_str(&bodies[0]).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "table": "users",
+            "operations": [
+                {
+                    "op": "update",
+                    "op_id": { "txid": 2, "seq_in_tx": 3 },
+                    "old": { "id": 1, "name": "a" },
+                    "new": { "id": 1, "name": "b" },
+                },
+                {
+                    "op": "delete",
+                    "op_id": null,
+                    "old": { "id": 1, "name": "b" },
+                    "new": null,
+                },
+            ],
+        })
+    );
+
+    // Bodies are split by batch size.
+    assert_eq!(payload.bodies(Some(1)).len(), 2);
+    payload.clear();
+    assert!(payload.bodies(None).is_empty());
+}
+
+#[test]
+fn urls_are_templated_per_table() {
+    let mut config = get_config("http://localhost/{table}/changes".to_string());
+    config.tables.push(WebhookSinkTable {
+        source_table_name: "orders".to_string(),
+        url: Some("http://orders.local/{table}".to_string()),
+    });
+    assert_eq!(
+        config.url(&config.tables[0]),
+        "http://localhost/users/changes"
+    );
+    assert_eq!(config.url(&config.tables[1]), "http://orders.local/orders");
+}
+
+#[test]
+fn signature_is_hmac_sha256_of_timestamp_and_body() {
+    assert_eq!(
+        sign("secret", 1700000000, r#"{"a":1}"#),
+        "sha256=49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686"
+    );
+}
+
+/// Accepts one request per connection, answering with the given statuses in turn.
+/// Returns the headers and body of every request.
+fn serve(listener: TcpListener, statuses: Vec<u16>) -> Vec<(HashMap<String, String>, String)> {
+    let mut requests = vec![];
+    for status in statuses {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(": ") {
+                headers.insert(name.to_lowercase(), value.to_string());
+            }
+        }
+        let length = headers["content-length"].parse().unwrap();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        requests.push((headers, String::from_utf8(body).unwrap()));
+
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+    }
+    requests
+}
+
+fn build_sink(runtime: &Runtime, factory: &WebhookSinkFactory) -> Box<dyn Sink> {
+    let mut sink = runtime
+        .block_on(factory.build(HashMap::from([(0, get_schema())]), EventHub::new(1)))
+        .unwrap();
+    sink.set_source(&NodeHandle::new(None, "source".to_string()))
+        .unwrap();
+    sink
+}
+
+fn get_epoch(op_id: OpIdentifier) -> Epoch {
+    let source_states = [
+        (
+            NodeHandle::new(None, "other".to_string()),
+            SourceState::Restartable(OpIdentifier::new(100, 0)),
+        ),
+        (
+            NodeHandle::new(None, "source".to_string()),
+            SourceState::Restartable(op_id),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    Epoch::new(op_id.txid, Arc::new(source_states), SystemTime::now())
+}
+
+#[test]
+fn flush_retries_until_accepted() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/{{table}}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || serve(listener, vec![503, 200]));
+
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let factory = WebhookSinkFactory::new(get_config(url), None, runtime.clone());
+    let mut sink = build_sink(&runtime, &factory);
+
+    let op_id = OpIdentifier::new(1, 0);
+    sink.process(TableOperation {
+        id: Some(op_id),
+        op: Operation::Insert {
+            new: get_record(1, "a"),
+        },
+        port: 0,
+    })
+    .unwrap();
+    sink.commit(&get_epoch(op_id)).unwrap();
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+
+    sink.flush_batch().unwrap();
+    assert_eq!(sink.get_latest_op_id().unwrap(), Some(op_id));
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    let (headers, body) = &requests[1];
+    let timestamp = headers[&TIMESTAMP_HEADER.to_lowercase()].parse().unwrap();
+    assert_eq!(
+        headers[&SIGNATURE_HEADER.to_lowercase()],
+        sign("secret", timestamp, body)
+    );
+    let body: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(
+        body["operations"][0]["new"],
+        json!({ "id": 1, "name": "a" })
+    );
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || serve(listener, vec![400]));
+
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let factory = WebhookSinkFactory::new(get_config(url), None, runtime.clone());
+    let mut sink = build_sink(&runtime, &factory);
+    sink.process(TableOperation::without_id(
+        Operation::Insert {
+            new: get_record(1, "a"),
+        },
+        0,
+    ))
+    .unwrap();
+
+    let error = sink.flush_batch().unwrap_err();
+    assert!(!sink.is_retryable(&error));
+    assert_eq!(server.join().unwrap().len(), 1);
+}
+
+#[test]
+fn accepted_op_id_is_restored() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || serve(listener, vec![200]));
+
+    let dir = tempfile::tempdir().unwrap();
+    let metadata_path = dir.path().join("webhook.json");
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let factory = WebhookSinkFactory::new(
+        get_config(url),
+        Some(metadata_path.clone()),
+        runtime.clone(),
+    );
+    let mut sink = build_sink(&runtime, &factory);
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+
+    let op_id = OpIdentifier::new(1, 0);
+    sink.process(TableOperation::without_id(
+        Operation::Insert {
+            new: get_record(1, "a"),
+        },
+        0,
+    ))
+    .unwrap();
+    sink.commit(&get_epoch(op_id)).unwrap();
+    sink.flush_batch().unwrap();
+    server.join().unwrap();
+    assert!(metadata_path.exists());
+
+    let mut sink = build_sink(&runtime, &factory);
+    assert_eq!(sink.get_latest_op_id().unwrap(), Some(op_id));
+}
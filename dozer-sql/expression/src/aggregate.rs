@@ -14,7 +14,7 @@ pub enum AggregateFunctionType {
 }
 
 impl AggregateFunctionType {
-    pub(crate) fn new(name: &str) -> Option<AggregateFunctionType> {
+    pub fn new(name: &str) -> Option<AggregateFunctionType> {
         match name {
             "avg" => Some(AggregateFunctionType::Avg),
             "count" => Some(AggregateFunctionType::Count),
@@ -35,7 +35,7 @@ pub trait Aggregator: Send + Sync + bincode::Encode + bincode::Decode {
 }
 
 #[enum_dispatch(Aggregator)]
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub enum AggregatorEnum {
     AvgAggregator,
     MinAggregator,
@@ -61,13 +61,13 @@ pub enum AggregatorType {
     Sum,
 }
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub(crate) struct OrderedAggregatorState {
     function_type: AggregateFunctionType,
     inner: OrderedAggregatorStateInner,
 }
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 enum OrderedAggregatorStateInner {
     UInt(BTreeMap<u64, u64>),
     U128(BTreeMap<u128, u64>),
@@ -12,7 +12,7 @@ use dozer_types::types::{DozerDuration, Field, FieldType, TimeUnit};
 
 use std::ops::Div;
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct AvgAggregator {
     current_state: SumState,
     current_count: u64,
@@ -1,17 +1,20 @@
 use crate::planner::projection::CommonPlanner;
-use crate::projection::processor::ProjectionProcessor;
+use crate::projection::processor::{ProjectionProcessor, ProjectionReplanner};
+use crate::utils::record_hashtable_key::get_record_hash;
 use crate::{aggregation::processor::AggregationProcessor, errors::PipelineError};
 use dozer_core::event::EventHub;
+use dozer_core::state_store::StateStore;
 use dozer_core::{
-    node::{PortHandle, Processor, ProcessorFactory},
+    node::{Partitioner, PortHandle, Processor, ProcessorFactory},
     DEFAULT_PORT_HANDLE,
 };
+use dozer_sql_expression::execution::Expression;
 use dozer_sql_expression::sqlparser::ast::{Expr, SelectItem};
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::models::udf_config::UdfConfig;
 use dozer_types::parking_lot::Mutex;
 use dozer_types::tonic::async_trait;
-use dozer_types::types::Schema;
+use dozer_types::types::{Field, Record, Schema};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
@@ -111,6 +114,8 @@ impl ProcessorFactory for AggregationProcessorFactory {
         input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         let input_schema = input_schemas
             .get(&DEFAULT_PORT_HANDLE)
@@ -122,6 +127,11 @@ impl ProcessorFactory for AggregationProcessorFactory {
             Box::new(ProjectionProcessor::new(
                 input_schema.clone(),
                 planner.projection_output,
+                Some(ProjectionReplanner::new(
+                    self.projection.clone(),
+                    self.udfs.clone(),
+                    self.runtime.clone(),
+                )),
             )?)
         } else {
             Box::new(AggregationProcessor::new(
@@ -133,6 +143,8 @@ impl ProcessorFactory for AggregationProcessorFactory {
                 input_schema.clone(),
                 planner.post_aggregation_schema,
                 self.enable_probabilistic_optimizations,
+                checkpoint_data,
+                &state_store,
             )?)
         };
         Ok(processor)
@@ -141,6 +153,45 @@ impl ProcessorFactory for AggregationProcessorFactory {
     fn id(&self) -> String {
         self.id.clone()
     }
+
+    async fn partitioner(
+        &self,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        let input_schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+
+        let planner = self.get_planner(input_schema.clone()).await?;
+        if planner.groupby.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(GroupByPartitioner {
+            dimensions: planner.groupby,
+            input_schema: input_schema.clone(),
+        })))
+    }
+}
+
+/// Shards an aggregation by its group by key, so every group is aggregated by one instance.
+#[derive(Debug)]
+struct GroupByPartitioner {
+    dimensions: Vec<Expression>,
+    input_schema: Schema,
+}
+
+impl Partitioner for GroupByPartitioner {
+    fn partition_hash(&mut self, _port: PortHandle, record: &Record) -> Result<u64, BoxedError> {
+        let mut key = Vec::<Field>::with_capacity(self.dimensions.len());
+        for dimension in self.dimensions.iter_mut() {
+            key.push(
+                dimension
+                    .evaluate(record, &self.input_schema)
+                    .map_err(PipelineError::from)?,
+            );
+        }
+        Ok(get_record_hash(key.iter()))
+    }
 }
 
 fn is_projection(planner: &CommonPlanner) -> bool {
@@ -5,7 +5,7 @@ use dozer_types::types::{Field, FieldType};
 
 use super::aggregator::OrderedAggregatorState;
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct MaxAggregator {
     current_state: Option<OrderedAggregatorState>,
     return_type: Option<FieldType>,
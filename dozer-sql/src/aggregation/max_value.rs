@@ -7,7 +7,7 @@ use dozer_sql_expression::aggregate::AggregateFunctionType::MaxValue;
 use dozer_types::types::{Field, FieldType};
 use std::collections::BTreeMap;
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct MaxValueAggregator {
     current_state: BTreeMap<Field, u64>,
     return_state: BTreeMap<Field, Vec<Field>>,
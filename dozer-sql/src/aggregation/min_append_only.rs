@@ -10,7 +10,7 @@ use dozer_types::rust_decimal::Decimal;
 
 use dozer_types::types::{DozerDuration, Field, FieldType, TimeUnit};
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct MinAppendOnlyAggregator {
     current_state: Field,
     return_type: Option<FieldType>,
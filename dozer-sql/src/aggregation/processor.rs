@@ -4,13 +4,14 @@ use crate::aggregation::aggregator::Aggregator;
 use crate::errors::PipelineError;
 use crate::utils::record_hashtable_key::{get_record_hash, RecordKey};
 use dozer_core::channels::ProcessorChannelForwarder;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
+use dozer_core::state_store::{StateMap, StateStore};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_sql_expression::execution::Expression;
 use dozer_types::bincode;
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::types::{Field, FieldType, Operation, Record, Schema, TableOperation};
-use std::collections::HashMap;
+use dozer_types::types::{Field, FieldType, Operation, Record, Schema, SchemaDiff, TableOperation};
 
 use crate::aggregation::aggregator::{
     get_aggregator_from_aggregator_type, get_aggregator_type_from_aggregation_expression,
@@ -55,7 +56,9 @@ pub struct AggregationProcessor {
     having: Option<Expression>,
     input_schema: Schema,
     aggregation_schema: Schema,
-    states: HashMap<RecordKey, AggregationState>,
+    states: StateMap<RecordKey, AggregationState>,
+    /// Number of segments in `states`, kept as they come and go, as disk backed maps are counted by scanning.
+    segment_count: usize,
     default_segment_key: RecordKey,
     having_eval_schema: Schema,
     accurate_keys: bool,
@@ -77,6 +80,8 @@ impl AggregationProcessor {
         input_schema: Schema,
         aggregation_schema: Schema,
         enable_probabilistic_optimizations: bool,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: &StateStore,
     ) -> Result<Self, BoxedError> {
         let mut aggr_types = Vec::new();
         let mut aggr_measures = Vec::new();
@@ -95,13 +100,27 @@ impl AggregationProcessor {
 
         let accurate_keys = !enable_probabilistic_optimizations;
 
+        let states = match checkpoint_data {
+            Some(data) => {
+                let (states, _): (Vec<(RecordKey, AggregationState)>, _) =
+                    bincode::decode_from_slice(&data, bincode::config::legacy())
+                        .map_err(|e| PipelineError::RestoreState(e.into()))?;
+                state_store
+                    .create_map_from(states)
+                    .map_err(PipelineError::StateStore)?
+            }
+            None => state_store.create_map(),
+        };
+        let segment_count = states.count().map_err(PipelineError::StateStore)?;
+
         Ok(Self {
             _id: id,
             dimensions,
             projections,
             input_schema,
             aggregation_schema,
-            states: Default::default(),
+            states,
+            segment_count,
             measures: aggr_measures,
             having,
             measures_types: aggr_types,
@@ -181,22 +200,36 @@ impl AggregationProcessor {
     }
 
     fn agg_delete(&mut self, old: &mut Record) -> Result<Vec<Operation>, PipelineError> {
-        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
-        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
-
         let key = if !self.dimensions.is_empty() {
-            Some(self.get_key(old)?)
+            self.get_key(old)?
         } else {
-            None
+            self.default_segment_key.clone()
         };
-        let key = key.as_ref().unwrap_or(&self.default_segment_key);
 
-        let curr_state_opt = self.states.get_mut(key);
+        let curr_state_opt = self.states.remove(&key)?;
         assert!(
             curr_state_opt.is_some(),
             "Unable to find aggregator state during DELETE operation"
         );
-        let curr_state = curr_state_opt.unwrap();
+        let mut curr_state = curr_state_opt.unwrap();
+
+        let res = self.agg_delete_from_state(&mut curr_state, old);
+        // The last record of the segment is deleted when its count drops to 0.
+        if curr_state.count > 0 {
+            self.states.insert(key, curr_state)?;
+        } else {
+            self.segment_count -= 1;
+        }
+        res
+    }
+
+    fn agg_delete_from_state(
+        &mut self,
+        curr_state: &mut AggregationState,
+        old: &mut Record,
+    ) -> Result<Vec<Operation>, PipelineError> {
+        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
+        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
 
         let new_values = Self::calc_and_fill_measures(
             curr_state,
@@ -229,7 +262,7 @@ impl AggregationProcessor {
             };
 
         let res = if curr_state.count == 1 {
-            self.states.remove(key);
+            curr_state.count = 0;
             if out_rec_delete_having_satisfied {
                 vec![Operation::Delete {
                     old: Self::build_projection(
@@ -261,19 +294,32 @@ impl AggregationProcessor {
     }
 
     fn agg_insert(&mut self, new: &mut Record) -> Result<Vec<Operation>, PipelineError> {
-        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
-        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
-
         let key = if !self.dimensions.is_empty() {
             self.get_key(new)?
         } else {
             self.default_segment_key.clone()
         };
 
-        let curr_state = self.states.entry(key).or_insert(AggregationState::new(
-            &self.measures_types,
-            &self.measures_return_types,
-        ));
+        let mut curr_state = match self.states.remove(&key)? {
+            Some(state) => state,
+            None => {
+                self.segment_count += 1;
+                AggregationState::new(&self.measures_types, &self.measures_return_types)
+            }
+        };
+
+        let res = self.agg_insert_into_state(&mut curr_state, new);
+        self.states.insert(key, curr_state)?;
+        res
+    }
+
+    fn agg_insert_into_state(
+        &mut self,
+        curr_state: &mut AggregationState,
+        new: &mut Record,
+    ) -> Result<Vec<Operation>, PipelineError> {
+        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
+        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
 
         let new_values = Self::calc_and_fill_measures(
             curr_state,
@@ -417,15 +463,26 @@ impl AggregationProcessor {
         new: &mut Record,
         key: RecordKey,
     ) -> Result<Vec<Operation>, PipelineError> {
-        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
-        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
-
-        let curr_state_opt = self.states.get_mut(&key);
+        let curr_state_opt = self.states.remove(&key)?;
         assert!(
             curr_state_opt.is_some(),
             "Unable to find aggregator state during UPDATE operation"
         );
-        let curr_state = curr_state_opt.unwrap();
+        let mut curr_state = curr_state_opt.unwrap();
+
+        let res = self.agg_update_state(&mut curr_state, old, new);
+        self.states.insert(key, curr_state)?;
+        res
+    }
+
+    fn agg_update_state(
+        &mut self,
+        curr_state: &mut AggregationState,
+        old: &mut Record,
+        new: &mut Record,
+    ) -> Result<Vec<Operation>, PipelineError> {
+        let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
+        let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
 
         let new_values = Self::calc_and_fill_measures(
             curr_state,
@@ -551,6 +608,12 @@ impl AggregationProcessor {
                 }
                 Ok(result)
             }
+            Operation::Truncate => {
+                // Without input there are no groups, so the whole output goes away.
+                self.states.clear()?;
+                self.segment_count = 0;
+                Ok(vec![Operation::Truncate])
+            }
         }
     }
 
@@ -574,13 +637,54 @@ impl Processor for AggregationProcessor {
 
     fn process(
         &mut self,
-        op: TableOperation,
+        mut op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError> {
+        // Fields appended by schema changes are not part of the aggregation. Dropping them keeps
+        // the HAVING clause, which refers to the aggregates after the input fields, valid.
+        let num_fields = self.input_schema.fields.len();
+        match &mut op.op {
+            Operation::Delete { old } => old.values.truncate(num_fields),
+            Operation::Insert { new } => new.values.truncate(num_fields),
+            Operation::Update { old, new } => {
+                old.values.truncate(num_fields);
+                new.values.truncate(num_fields);
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    record.values.truncate(num_fields);
+                }
+            }
+            Operation::Truncate => (),
+        }
+
         let ops = self.aggregate(op.op)?;
         for output_op in ops {
             fw.send(TableOperation::without_id(output_op, DEFAULT_PORT_HANDLE));
         }
         Ok(())
     }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data = bincode::encode_to_vec(&self.states, bincode::config::legacy())
+            .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
+
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(Some(self.segment_count))
+    }
+
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        if !SchemaDiff::new(old, new).is_additive() {
+            return Err(SchemaChangeError::NotAdditive.into());
+        }
+        // The output only depends on the fields the aggregation was planned with.
+        Ok(vec![])
+    }
 }
@@ -8,13 +8,13 @@ use dozer_types::rust_decimal::Decimal;
 
 use dozer_types::types::{DozerDuration, Field, FieldType, TimeUnit};
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct SumAggregator {
     current_state: SumState,
     return_type: Option<FieldType>,
 }
 
-#[derive(Debug, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct SumState {
     pub(crate) int_state: i64,
     pub(crate) int8_state: i8,
@@ -0,0 +1,95 @@
+use crate::aggregation::tests::aggregation_tests_utils::{
+    delete_exp, delete_field, init_input_schema, init_processor_from_checkpoint, insert_exp,
+    insert_field, update_exp, FIELD_100_FLOAT, FIELD_150_FLOAT, FIELD_200_FLOAT, FIELD_250_FLOAT,
+    FIELD_50_FLOAT, ITALY, SINGAPORE,
+};
+use crate::output;
+use crate::tests::utils::open_node_state_store;
+use dozer_core::node::Processor;
+use dozer_core::state_store::StateStore;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::types::FieldType::Float;
+use std::collections::HashMap;
+
+const SQL: &str = "SELECT Country, SUM(Salary) \
+    FROM Users \
+    WHERE Salary >= 1 GROUP BY Country";
+
+/// `open_state_store` opens the processor's store, restored from the given database checkpoint.
+fn test_aggregation_restored_from_checkpoint(open_state_store: impl Fn(Option<u64>) -> StateStore) {
+    let schema = init_input_schema(Float, "SUM");
+    let state_store = open_state_store(None);
+    let mut processor = init_processor_from_checkpoint(
+        SQL,
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema.clone())]),
+        None,
+        &state_store,
+    )
+    .unwrap();
+
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    output!(processor, insert_field(SINGAPORE, FIELD_50_FLOAT));
+    assert_eq!(processor.state_size().unwrap(), Some(2));
+
+    let checkpoint_data = processor.serialize_state().unwrap();
+    assert!(checkpoint_data.is_some());
+    let state_store_checkpoint = state_store.checkpoint(0).unwrap();
+    drop((processor, state_store));
+    let mut processor = init_processor_from_checkpoint(
+        SQL,
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
+        checkpoint_data,
+        &open_state_store(state_store_checkpoint),
+    )
+    .unwrap();
+    assert_eq!(processor.state_size().unwrap(), Some(2));
+
+    // The restored processor continues from the checkpointed sums.
+    let out = output!(processor, insert_field(ITALY, FIELD_50_FLOAT));
+    assert_eq!(
+        out,
+        vec![update_exp(ITALY, ITALY, FIELD_200_FLOAT, FIELD_250_FLOAT)]
+    );
+    let out = output!(processor, insert_field(SINGAPORE, FIELD_100_FLOAT));
+    assert_eq!(
+        out,
+        vec![update_exp(
+            SINGAPORE,
+            SINGAPORE,
+            FIELD_50_FLOAT,
+            FIELD_150_FLOAT
+        )]
+    );
+    let out = output!(processor, delete_field(SINGAPORE, FIELD_50_FLOAT));
+    assert_eq!(
+        out,
+        vec![update_exp(
+            SINGAPORE,
+            SINGAPORE,
+            FIELD_150_FLOAT,
+            FIELD_100_FLOAT
+        )]
+    );
+    let out = output!(processor, delete_field(SINGAPORE, FIELD_100_FLOAT));
+    assert_eq!(out, vec![delete_exp(SINGAPORE, FIELD_100_FLOAT)]);
+    assert_eq!(processor.state_size().unwrap(), Some(1));
+
+    // Groups that were not in the checkpoint start from scratch.
+    let out = output!(processor, insert_field("Japan", FIELD_100_FLOAT));
+    assert_eq!(out, vec![insert_exp("Japan", FIELD_100_FLOAT)]);
+    assert_eq!(processor.state_size().unwrap(), Some(2));
+}
+
+#[test]
+fn test_aggregation_restored_from_checkpoint_in_memory() {
+    test_aggregation_restored_from_checkpoint(|_| StateStore::default());
+}
+
+#[test]
+fn test_aggregation_restored_from_checkpoint_on_disk() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    test_aggregation_restored_from_checkpoint(|checkpoint| {
+        open_node_state_store(temp_dir.path(), checkpoint)
+    });
+}
@@ -9,6 +9,7 @@ use crate::aggregation::tests::aggregation_tests_utils::{
 use crate::output;
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::types::FieldType::{Decimal, Duration, Float, Int, UInt};
+use dozer_types::types::Operation;
 use std::collections::HashMap;
 
 #[test]
@@ -947,3 +948,26 @@ fn test_sum_aggregation_duration_null() {
     exp = vec![delete_exp(ITALY, &get_duration_field(0))];
     assert_eq!(out, exp);
 }
+
+#[test]
+fn test_sum_aggregation_truncate() {
+    let schema = init_input_schema(Float, "SUM");
+    let mut processor = init_processor(
+        "SELECT Country, SUM(Salary) \
+        FROM Users \
+        WHERE Salary >= 1 GROUP BY Country",
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
+    )
+    .unwrap();
+
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    output!(processor, insert_field(SINGAPORE, FIELD_50_FLOAT));
+
+    // Truncating the input removes every group.
+    let out = output!(processor, Operation::Truncate);
+    assert_eq!(out, vec![Operation::Truncate]);
+
+    // Groups start from scratch after the truncate.
+    let out = output!(processor, insert_field(ITALY, FIELD_50_FLOAT));
+    assert_eq!(out, vec![insert_exp(ITALY, FIELD_50_FLOAT)]);
+}
@@ -1,6 +1,7 @@
 use crate::planner::projection::CommonPlanner;
 use crate::tests::utils::get_select;
 use crate::{aggregation::processor::AggregationProcessor, tests::utils::create_test_runtime};
+use dozer_core::state_store::StateStore;
 use dozer_types::types::{
     Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition,
 };
@@ -92,6 +93,8 @@ fn test_planner_with_aggregator() {
         schema,
         projection_planner.post_aggregation_schema,
         false,
+        None,
+        &StateStore::default(),
     )
     .unwrap();
 
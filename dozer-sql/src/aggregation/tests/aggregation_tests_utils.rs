@@ -1,4 +1,4 @@
-use dozer_core::{node::PortHandle, DEFAULT_PORT_HANDLE};
+use dozer_core::{node::PortHandle, state_store::StateStore, DEFAULT_PORT_HANDLE};
 use dozer_types::types::{
     DozerDuration, Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition,
     TimeUnit, DATE_FORMAT,
@@ -18,6 +18,15 @@ use std::ops::Div;
 pub(crate) fn init_processor(
     sql: &str,
     input_schemas: HashMap<PortHandle, Schema>,
+) -> Result<AggregationProcessor, PipelineError> {
+    init_processor_from_checkpoint(sql, input_schemas, None, &StateStore::default())
+}
+
+pub(crate) fn init_processor_from_checkpoint(
+    sql: &str,
+    input_schemas: HashMap<PortHandle, Schema>,
+    checkpoint_data: Option<Vec<u8>>,
+    state_store: &StateStore,
 ) -> Result<AggregationProcessor, PipelineError> {
     let input_schema = input_schemas
         .get(&DEFAULT_PORT_HANDLE)
@@ -44,6 +53,8 @@ pub(crate) fn init_processor(
         input_schema.clone(),
         projection_planner.post_aggregation_schema,
         false,
+        checkpoint_data,
+        state_store,
     )
     .unwrap_or_else(|e| panic!("{}", e.to_string()));
 
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod aggregation_avg_tests;
 #[cfg(test)]
+mod aggregation_checkpoint_tests;
+#[cfg(test)]
 mod aggregation_count_tests;
 #[cfg(test)]
 mod aggregation_having_tests;
@@ -1,6 +1,9 @@
 use dozer_sql_expression::{
+    aggregate::AggregateFunctionType,
     builder::{ExpressionBuilder, NameOrAlias},
-    sqlparser::ast::{ObjectName, TableFactor},
+    sqlparser::ast::{
+        Expr, FunctionArg, FunctionArgExpr, Interval, ObjectName, Select, SelectItem, TableFactor,
+    },
 };
 
 use crate::errors::{PipelineError, ProductError};
@@ -77,3 +80,73 @@ pub fn string_from_sql_object_name(name: &ObjectName) -> String {
         .collect::<Vec<String>>()
         .join(".")
 }
+
+/// Whether the projection or HAVING clause of `select` calls an aggregate function.
+pub fn has_aggregate_functions(select: &Select) -> bool {
+    select.projection.iter().any(|item| match item {
+        SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+            calls_aggregate_function(expr)
+        }
+        SelectItem::QualifiedWildcard(..) | SelectItem::Wildcard(_) => false,
+    }) || select.having.as_ref().is_some_and(calls_aggregate_function)
+}
+
+/// Whether `expr` calls an aggregate function. Aggregate functions with OVER are window functions instead.
+fn calls_aggregate_function(expr: &Expr) -> bool {
+    match expr {
+        Expr::Function(function) => {
+            let name = function.name.to_string().to_lowercase();
+            (function.over.is_none() && AggregateFunctionType::new(&name).is_some())
+                || function.args.iter().any(|arg| match arg {
+                    FunctionArg::Named {
+                        arg: FunctionArgExpr::Expr(arg),
+                        ..
+                    }
+                    | FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) => {
+                        calls_aggregate_function(arg)
+                    }
+                    _ => false,
+                })
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            calls_aggregate_function(left) || calls_aggregate_function(right)
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Extract { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Interval(Interval { value: expr, .. }) => calls_aggregate_function(expr),
+        Expr::Like { expr, pattern, .. } => {
+            calls_aggregate_function(expr) || calls_aggregate_function(pattern)
+        }
+        Expr::InList { expr, list, .. } => {
+            calls_aggregate_function(expr) || list.iter().any(calls_aggregate_function)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => [expr, low, high]
+            .into_iter()
+            .any(|expr| calls_aggregate_function(expr)),
+        Expr::Trim {
+            expr, trim_what, ..
+        } => {
+            calls_aggregate_function(expr)
+                || trim_what.as_deref().is_some_and(calls_aggregate_function)
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => operand
+            .iter()
+            .chain(else_result.iter())
+            .map(|expr| &**expr)
+            .chain(conditions.iter())
+            .chain(results.iter())
+            .any(calls_aggregate_function),
+        _ => false,
+    }
+}
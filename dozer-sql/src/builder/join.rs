@@ -3,12 +3,17 @@ use dozer_core::{
     node::PortHandle,
     DEFAULT_PORT_HANDLE,
 };
-use dozer_sql_expression::sqlparser::ast::{TableFactor, TableWithJoins};
+use dozer_sql_expression::sqlparser::ast::{
+    JoinOperator as SqlJoinOperator, TableFactor, TableVersion, TableWithJoins,
+};
 
 use crate::{
     builder::{get_from_source, QueryContext},
     errors::PipelineError,
-    product::join::factory::{JoinProcessorFactory, LEFT_JOIN_PORT, RIGHT_JOIN_PORT},
+    product::join::{
+        as_of::AsOfClause,
+        factory::{JoinProcessorFactory, LEFT_JOIN_PORT, RIGHT_JOIN_PORT},
+    },
 };
 
 use super::{
@@ -33,6 +38,11 @@ pub fn insert_join_to_pipeline(
     let mut input_nodes = vec![];
 
     let left_table = from.relation;
+    if get_as_of(&left_table)?.is_some() {
+        return Err(PipelineError::InvalidJoin(
+            "FOR SYSTEM_TIME AS OF is only supported on the right table of a JOIN".to_string(),
+        ));
+    }
     let mut left_name_or_alias = Some(get_name_or_alias(&left_table)?);
     let mut left_join_source =
         insert_join_source_to_pipeline(left_table, pipeline, pipeline_idx, query_context)?;
@@ -40,6 +50,7 @@ pub fn insert_join_to_pipeline(
     for join in from.joins {
         let right_table = join.relation;
         let right_name_or_alias = Some(get_name_or_alias(&right_table)?);
+        let as_of = get_as_of(&right_table)?;
         let right_join_source = insert_join_source_to_pipeline(
             right_table.clone(),
             pipeline,
@@ -54,6 +65,8 @@ pub fn insert_join_to_pipeline(
         {
             return Err(PipelineError::ProcessorAlreadyExists(join_processor_name));
         }
+        // Every record of a cross join matches all records of the other side, so it can't be sharded.
+        let partitionable = !matches!(join.join_operator, SqlJoinOperator::CrossJoin);
         let join_processor_factory = JoinProcessorFactory::new(
             join_processor_name.clone(),
             left_name_or_alias,
@@ -64,11 +77,20 @@ pub fn insert_join_to_pipeline(
                 .enable_probabilistic_optimizations
                 .in_joins
                 .unwrap_or(false),
+            pipeline.flags().max_cross_join_records,
+            as_of,
+            query_context.udfs.clone(),
+            query_context.runtime.clone(),
         );
         pipeline.add_processor(
             Box::new(join_processor_factory),
             join_processor_name.clone(),
         );
+        if partitionable {
+            query_context
+                .partitionable_processors
+                .push(join_processor_name.clone());
+        }
 
         input_nodes.extend(modify_pipeline_graph(
             left_join_source,
@@ -129,6 +151,27 @@ fn insert_join_source_to_pipeline(
     Ok(join_source)
 }
 
+/// Returns the `FOR SYSTEM_TIME AS OF` clause of a table, with the version column it declares as `WITH (column)`.
+fn get_as_of(table: &TableFactor) -> Result<Option<AsOfClause>, PipelineError> {
+    let TableFactor::Table {
+        version: Some(TableVersion::ForSystemTimeAsOf(time)),
+        with_hints,
+        ..
+    } = table
+    else {
+        return Ok(None);
+    };
+    if with_hints.len() > 1 {
+        return Err(PipelineError::InvalidJoin(
+            "A table joined FOR SYSTEM_TIME AS OF declares one version column".to_string(),
+        ));
+    }
+    Ok(Some(AsOfClause {
+        time: time.clone(),
+        version_column: with_hints.first().cloned(),
+    }))
+}
+
 fn is_nested_join(left_table: &TableFactor) -> bool {
     matches!(left_table, TableFactor::NestedJoin { .. })
 }
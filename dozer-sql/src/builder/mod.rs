@@ -2,6 +2,8 @@ use crate::aggregation::factory::AggregationProcessorFactory;
 use crate::builder::PipelineError::InvalidQuery;
 use crate::errors::PipelineError;
 use crate::selection::factory::SelectionProcessorFactory;
+use crate::top_n::factory::TopNProcessorFactory;
+use crate::window_function::factory::WindowFunctionProcessorFactory;
 use dozer_core::app::AppPipeline;
 use dozer_core::node::PortHandle;
 use dozer_core::DEFAULT_PORT_HANDLE;
@@ -50,6 +52,9 @@ pub struct QueryContext {
     // Processors counter
     processor_counter: usize,
 
+    // Processors of the current statement that can be sharded by key
+    partitionable_processors: Vec<String>,
+
     // Udf related configs
     udfs: Vec<UdfConfig>,
 
@@ -70,6 +75,7 @@ impl QueryContext {
             used_sources: Default::default(),
             processors_list: Default::default(),
             processor_counter: Default::default(),
+            partitionable_processors: Default::default(),
             udfs,
             runtime,
         }
@@ -93,6 +99,8 @@ pub fn statement_to_pipeline(
     for (idx, statement) in ast.into_iter().enumerate() {
         match statement {
             Statement::Query(query) => {
+                let output_tables: HashSet<String> =
+                    ctx.output_tables_map.keys().cloned().collect();
                 query_to_pipeline(
                     TableInfo {
                         name: query_name.clone(),
@@ -104,6 +112,19 @@ pub fn statement_to_pipeline(
                     idx,
                     is_top_select,
                 )?;
+
+                // Each statement outputs one table, which its parallelism is configured by.
+                let parallelism = match ctx
+                    .output_tables_map
+                    .keys()
+                    .find(|table_name| !output_tables.contains(*table_name))
+                {
+                    Some(table_name) => pipeline.flags().parallelism_of(table_name),
+                    None => pipeline.flags().parallelism,
+                };
+                for processor in std::mem::take(&mut ctx.partitionable_processors) {
+                    pipeline.set_parallelism(&processor, parallelism);
+                }
             }
             s => {
                 return Err(PipelineError::UnsupportedSqlError(
@@ -123,24 +144,14 @@ struct TableInfo {
 
 fn query_to_pipeline(
     table_info: TableInfo,
-    query: Query,
+    mut query: Query,
     pipeline: &mut AppPipeline,
     query_ctx: &mut QueryContext,
     pipeline_idx: usize,
     is_top_select: bool,
 ) -> Result<(), PipelineError> {
-    // return error if there is unsupported syntax
-    if !query.order_by.is_empty() {
-        return Err(PipelineError::UnsupportedSqlError(
-            UnsupportedSqlError::OrderByError,
-        ));
-    }
-
-    if query.limit.is_some() || query.offset.is_some() {
-        return Err(PipelineError::UnsupportedSqlError(
-            UnsupportedSqlError::LimitOffsetError,
-        ));
-    }
+    let top_n = top_n::take_top_n(&mut query)?;
+    let table_name = table_info.name.0.clone();
 
     // Attach the first pipeline if there is with clause
     if let Some(with) = query.with {
@@ -203,7 +214,10 @@ fn query_to_pipeline(
                 &mut ctx,
                 pipeline_idx,
                 false, //Inside a subquery, so not top select
-            )?
+            )?;
+            query_ctx
+                .partitionable_processors
+                .extend(ctx.partitionable_processors);
         }
         SetExpr::SetOperation {
             op,
@@ -231,17 +245,82 @@ fn query_to_pipeline(
             ))
         }
     };
+
+    if let Some(top_n) = top_n {
+        top_n_to_pipeline(top_n, &table_name, pipeline, query_ctx, pipeline_idx)?;
+    }
+    Ok(())
+}
+
+/// Keeps the top rows of the query output named `table_name`, which then comes from the Top-N processor.
+fn top_n_to_pipeline(
+    top_n: top_n::TopNClause,
+    table_name: &str,
+    pipeline: &mut AppPipeline,
+    query_ctx: &mut QueryContext,
+    pipeline_idx: usize,
+) -> Result<(), PipelineError> {
+    let Some(input) = query_ctx
+        .pipeline_map
+        .get(&(pipeline_idx, table_name.to_string()))
+        .cloned()
+    else {
+        return Err(PipelineError::UnsupportedSqlError(
+            UnsupportedSqlError::GenericError(
+                "ORDER BY and LIMIT are not supported on this query".to_string(),
+            ),
+        ));
+    };
+
+    let gen_top_n_name = format!("top_n--{}", query_ctx.get_next_processor_id());
+    // Partitions are ranked independently, so the Top-N can be sharded by them.
+    if !top_n.partition_by.is_empty() {
+        query_ctx
+            .partitionable_processors
+            .push(gen_top_n_name.clone());
+    }
+    let top_n_factory = TopNProcessorFactory::new(
+        gen_top_n_name.clone(),
+        top_n.partition_by,
+        top_n.order_by,
+        top_n.offset,
+        top_n.limit,
+        query_ctx.udfs.clone(),
+        query_ctx.runtime.clone(),
+    );
+    pipeline.add_processor(Box::new(top_n_factory), gen_top_n_name.clone());
+    pipeline.connect_nodes(
+        input.node.clone(),
+        input.port,
+        gen_top_n_name.clone(),
+        DEFAULT_PORT_HANDLE,
+    );
+
+    let output = OutputNodeInfo {
+        node: gen_top_n_name,
+        port: DEFAULT_PORT_HANDLE,
+    };
+    for output_table in query_ctx.output_tables_map.values_mut() {
+        if output_table.node == input.node && output_table.port == input.port {
+            *output_table = output.clone();
+        }
+    }
+    query_ctx
+        .pipeline_map
+        .insert((pipeline_idx, table_name.to_string()), output);
     Ok(())
 }
 
 fn select_to_pipeline(
     table_info: TableInfo,
-    select: Select,
+    mut select: Select,
     pipeline: &mut AppPipeline,
     query_ctx: &mut QueryContext,
     pipeline_idx: usize,
     is_top_select: bool,
 ) -> Result<String, PipelineError> {
+    let windows = window_function::take_window_functions(&mut select)?;
+
     // FROM clause
     let Some(from) = select.from.into_iter().next() else {
         return Err(PipelineError::UnsupportedSqlError(
@@ -257,7 +336,7 @@ fn select_to_pipeline(
     let gen_agg_name = format!("agg--{}", query_ctx.get_next_processor_id());
 
     let gen_selection_name = format!("select--{}", query_ctx.get_next_processor_id());
-    let (gen_product_name, product_output_port) = output_node;
+    let (mut gen_product_name, mut product_output_port) = output_node;
 
     for (source_name, processor_name, processor_port) in input_nodes {
         if let Some(table_info) = query_ctx
@@ -276,6 +355,13 @@ fn select_to_pipeline(
         }
     }
 
+    // Aggregations without GROUP BY keep a single group, so they can't be sharded.
+    if !select.group_by.is_empty() {
+        query_ctx
+            .partitionable_processors
+            .push(gen_agg_name.clone());
+    }
+
     let aggregation = AggregationProcessorFactory::new(
         gen_agg_name.clone(),
         select.projection,
@@ -310,21 +396,43 @@ fn select_to_pipeline(
             DEFAULT_PORT_HANDLE,
         );
 
-        pipeline.connect_nodes(
-            gen_selection_name,
-            DEFAULT_PORT_HANDLE,
-            gen_agg_name.clone(),
-            DEFAULT_PORT_HANDLE,
+        (gen_product_name, product_output_port) = (gen_selection_name, DEFAULT_PORT_HANDLE);
+    }
+
+    // Window functions see the rows after the WHERE clause, and the projection sees their values.
+    for window in windows {
+        let gen_window_name = format!("window_function--{}", query_ctx.get_next_processor_id());
+        // Partitions are computed independently, so the window functions can be sharded by them.
+        if !window.window.partition_by.is_empty() {
+            query_ctx
+                .partitionable_processors
+                .push(gen_window_name.clone());
+        }
+        let window_factory = WindowFunctionProcessorFactory::new(
+            gen_window_name.clone(),
+            window.window,
+            window.functions,
+            query_ctx.udfs.clone(),
+            query_ctx.runtime.clone(),
         );
-    } else {
+        pipeline.add_processor(Box::new(window_factory), gen_window_name.clone());
         pipeline.connect_nodes(
             gen_product_name,
             product_output_port,
-            gen_agg_name.clone(),
+            gen_window_name.clone(),
             DEFAULT_PORT_HANDLE,
         );
+
+        (gen_product_name, product_output_port) = (gen_window_name, DEFAULT_PORT_HANDLE);
     }
 
+    pipeline.connect_nodes(
+        gen_product_name,
+        product_output_port,
+        gen_agg_name.clone(),
+        DEFAULT_PORT_HANDLE,
+    );
+
     query_ctx.pipeline_map.insert(
         (pipeline_idx, table_info.name.0.to_string()),
         OutputNodeInfo {
@@ -562,6 +670,8 @@ mod common;
 mod from;
 mod join;
 mod table_operator;
+mod top_n;
+mod window_function;
 
 pub use common::string_from_sql_object_name;
 pub use table_operator::{TableOperatorArg, TableOperatorDescriptor};
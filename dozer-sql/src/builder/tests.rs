@@ -1,6 +1,15 @@
+use super::common::has_aggregate_functions;
 use super::statement_to_pipeline;
-use crate::{errors::PipelineError, tests::utils::create_test_runtime};
+use crate::{
+    errors::{PipelineError, UnsupportedSqlError},
+    tests::utils::create_test_runtime,
+};
 use dozer_core::app::AppPipeline;
+use dozer_sql_expression::sqlparser::{
+    ast::{SetExpr, Statement},
+    dialect::DozerDialect,
+    parser::Parser,
+};
 #[test]
 #[should_panic]
 fn disallow_zero_outgoing_ndes() {
@@ -209,3 +218,124 @@ from tbl;"#;
     //check if the result is ok
     assert!(result.is_ok());
 }
+
+#[test]
+fn test_top_n() {
+    let sql = r#"
+            SELECT category, SUM(revenue) AS revenue
+            INTO top_categories
+            FROM sales
+            GROUP BY category
+            ORDER BY revenue DESC
+            LIMIT 10;
+
+            SELECT category, product, revenue
+            INTO top_products
+            FROM products
+            GROUP BY category
+            ORDER BY revenue DESC
+            LIMIT 3 OFFSET 1;
+        "#;
+    let runtime = create_test_runtime();
+    let context = statement_to_pipeline(
+        sql,
+        &mut AppPipeline::new_with_default_flags(),
+        None,
+        vec![],
+        runtime,
+    )
+    .unwrap();
+
+    // Both outputs come from a Top-N processor.
+    for table_name in ["top_categories", "top_products"] {
+        assert!(context.output_tables_map[table_name]
+            .node
+            .starts_with("top_n--"));
+    }
+}
+
+#[test]
+fn test_order_by_requires_limit() {
+    let sql = r#"SELECT a INTO c FROM b ORDER BY a"#;
+    let runtime = create_test_runtime();
+    let result = statement_to_pipeline(
+        sql,
+        &mut AppPipeline::new_with_default_flags(),
+        None,
+        vec![],
+        runtime,
+    );
+    assert!(matches!(
+        result,
+        Err(PipelineError::UnsupportedSqlError(
+            UnsupportedSqlError::OrderByError
+        ))
+    ));
+}
+
+#[test]
+fn test_window_functions() {
+    let sql = r#"
+            SELECT category, product,
+                ROW_NUMBER() OVER (PARTITION BY category ORDER BY revenue DESC) AS position,
+                SUM(revenue) OVER (PARTITION BY category ORDER BY revenue DESC) + 1,
+                LAG(product) OVER (ORDER BY revenue) AS previous_product
+            INTO ranked_products
+            FROM products
+            WHERE revenue > 0;
+        "#;
+    let runtime = create_test_runtime();
+    let result = statement_to_pipeline(
+        sql,
+        &mut AppPipeline::new_with_default_flags(),
+        None,
+        vec![],
+        runtime,
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_window_functions_with_aggregation() {
+    for sql in [
+        r#"SELECT category, SUM(revenue), RANK() OVER (ORDER BY category) INTO c FROM b GROUP BY category"#,
+        r#"SELECT *, RANK() OVER (ORDER BY category) INTO c FROM b"#,
+    ] {
+        let runtime = create_test_runtime();
+        let result = statement_to_pipeline(
+            sql,
+            &mut AppPipeline::new_with_default_flags(),
+            None,
+            vec![],
+            runtime,
+        );
+        assert!(matches!(result, Err(PipelineError::WindowFunctionError(_))));
+    }
+}
+
+#[test]
+fn test_has_aggregate_functions() {
+    for (sql, expected) in [
+        ("SELECT category, ROUND(SUM(revenue) / 2) FROM b", true),
+        (
+            "SELECT CASE WHEN COUNT(product) > 1 THEN 'many' ELSE 'one' END FROM b",
+            true,
+        ),
+        (
+            "SELECT category FROM b GROUP BY category HAVING MAX(revenue) > 1",
+            true,
+        ),
+        ("SELECT category, \"sum\", UPPER(product) FROM b", false),
+        ("SELECT category, 'count(product)' FROM b", false),
+        ("SELECT SUM(revenue) OVER (ORDER BY revenue) FROM b", false),
+    ] {
+        let statement = Parser::parse_sql(&DozerDialect {}, sql).unwrap().remove(0);
+        let Statement::Query(query) = statement else {
+            panic!("not a query");
+        };
+        let SetExpr::Select(select) = *query.body else {
+            panic!("not a select");
+        };
+        assert_eq!(has_aggregate_functions(&select), expected, "{sql}");
+    }
+}
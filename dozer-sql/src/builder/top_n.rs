@@ -0,0 +1,68 @@
+use dozer_sql_expression::sqlparser::ast::{Expr, OrderByExpr, Query, SetExpr, Value};
+
+use crate::errors::{PipelineError, UnsupportedSqlError};
+
+use super::common::has_aggregate_functions;
+
+/// The ORDER BY, LIMIT and OFFSET of a query, which keep the top rows of its output.
+#[derive(Debug)]
+pub struct TopNClause {
+    /// Splits the rows into partitions that each keep their top rows.
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<OrderByExpr>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Takes the ORDER BY, LIMIT and OFFSET out of `query`, if it has them.
+///
+/// If the query is a SELECT with GROUP BY but no aggregate functions, its GROUP BY is taken too,
+/// and partitions the top rows instead of grouping them.
+pub fn take_top_n(query: &mut Query) -> Result<Option<TopNClause>, PipelineError> {
+    if query.order_by.is_empty() && query.limit.is_none() && query.offset.is_none() {
+        return Ok(None);
+    }
+    let Some(limit) = query.limit.take() else {
+        return Err(PipelineError::UnsupportedSqlError(
+            if query.order_by.is_empty() {
+                UnsupportedSqlError::LimitOffsetError
+            } else {
+                UnsupportedSqlError::OrderByError
+            },
+        ));
+    };
+    if query.order_by.is_empty() {
+        return Err(PipelineError::UnsupportedSqlError(
+            UnsupportedSqlError::LimitOffsetError,
+        ));
+    }
+    let limit = parse_count(&limit, "LIMIT")?;
+    let offset = match query.offset.take() {
+        Some(offset) => parse_count(&offset.value, "OFFSET")?,
+        None => 0,
+    };
+
+    let partition_by = match query.body.as_mut() {
+        SetExpr::Select(select)
+            if !select.group_by.is_empty() && !has_aggregate_functions(select) =>
+        {
+            std::mem::take(&mut select.group_by)
+        }
+        _ => vec![],
+    };
+
+    Ok(Some(TopNClause {
+        partition_by,
+        order_by: std::mem::take(&mut query.order_by),
+        offset,
+        limit,
+    }))
+}
+
+fn parse_count(expr: &Expr, clause: &str) -> Result<usize, PipelineError> {
+    match expr {
+        Expr::Value(Value::Number(number, _)) => number.to_string().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| PipelineError::InvalidQuery(format!("{clause} must be a non-negative integer")))
+}
@@ -0,0 +1,124 @@
+use dozer_sql_expression::sqlparser::ast::{
+    Expr, Function, FunctionArg, FunctionArgExpr, Ident, Select, SelectItem, WindowSpec, WindowType,
+};
+
+use crate::errors::{PipelineError, WindowFunctionError};
+
+use super::common::has_aggregate_functions;
+
+/// The window functions over one window, which one processor computes.
+#[derive(Debug)]
+pub struct WindowClause {
+    pub window: WindowSpec,
+    /// The window functions, with the names of the columns that replace them in the projection.
+    pub functions: Vec<(String, Function)>,
+}
+
+/// Takes the window functions out of the projection of `select`, replacing them with the columns
+/// their values are appended as. Unnamed items keep the name of the expression they had.
+pub fn take_window_functions(select: &mut Select) -> Result<Vec<WindowClause>, PipelineError> {
+    let mut windows = vec![];
+    for item in select.projection.iter_mut() {
+        match item {
+            SelectItem::UnnamedExpr(expr) => {
+                let name = expr.to_string();
+                if take_from_expr(expr, &mut windows)? {
+                    let expr = expr.clone();
+                    *item = SelectItem::ExprWithAlias {
+                        expr,
+                        alias: Ident::with_quote('"', name),
+                    };
+                }
+            }
+            SelectItem::ExprWithAlias { expr, .. } => {
+                take_from_expr(expr, &mut windows)?;
+            }
+            SelectItem::QualifiedWildcard(..) | SelectItem::Wildcard(_) => (),
+        }
+    }
+    if windows.is_empty() {
+        return Ok(windows);
+    }
+
+    // `*` would select the columns of the window functions too.
+    if select
+        .projection
+        .iter()
+        .any(|item| matches!(item, SelectItem::Wildcard(_)))
+    {
+        return Err(WindowFunctionError::WithWildcard.into());
+    }
+    if !select.group_by.is_empty() || select.having.is_some() || has_aggregate_functions(select) {
+        return Err(WindowFunctionError::WithAggregation.into());
+    }
+    Ok(windows)
+}
+
+/// Replaces the window functions in `expr` with their columns, returning whether there were any.
+fn take_from_expr(expr: &mut Expr, windows: &mut Vec<WindowClause>) -> Result<bool, PipelineError> {
+    match expr {
+        Expr::Function(function) => match function.over.take() {
+            Some(WindowType::WindowSpec(window)) => {
+                let count = windows
+                    .iter()
+                    .map(|window| window.functions.len())
+                    .sum::<usize>();
+                let name = format!("__window_function_{count}");
+                let function = (name.clone(), function.clone());
+                match windows.iter_mut().find(|clause| clause.window == window) {
+                    Some(clause) => clause.functions.push(function),
+                    None => windows.push(WindowClause {
+                        window,
+                        functions: vec![function],
+                    }),
+                }
+                *expr = Expr::Identifier(Ident::new(name));
+                Ok(true)
+            }
+            Some(WindowType::NamedWindow(name)) => {
+                Err(WindowFunctionError::NamedWindow(name.value).into())
+            }
+            None => {
+                let mut found = false;
+                for arg in function.args.iter_mut() {
+                    if let FunctionArg::Named {
+                        arg: FunctionArgExpr::Expr(arg),
+                        ..
+                    }
+                    | FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) = arg
+                    {
+                        found |= take_from_expr(arg, windows)?;
+                    }
+                }
+                Ok(found)
+            }
+        },
+        Expr::BinaryOp { left, right, .. } => {
+            let left = take_from_expr(left, windows)?;
+            let right = take_from_expr(right, windows)?;
+            Ok(left || right)
+        }
+        Expr::UnaryOp { expr, .. } | Expr::Nested(expr) | Expr::Cast { expr, .. } => {
+            take_from_expr(expr, windows)
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            let mut found = false;
+            for expr in operand
+                .iter_mut()
+                .chain(else_result.iter_mut())
+                .map(|expr| &mut **expr)
+                .chain(conditions.iter_mut())
+                .chain(results.iter_mut())
+            {
+                found |= take_from_expr(expr, windows)?;
+            }
+            Ok(found)
+        }
+        _ => Ok(false),
+    }
+}
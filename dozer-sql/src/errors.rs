@@ -1,9 +1,10 @@
 #![allow(clippy::enum_variant_names)]
 
+use dozer_core::errors::StateStoreError;
 use dozer_core::node::PortHandle;
 use dozer_types::chrono::RoundingError;
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::errors::types::{DeserializationError, TypeError};
+use dozer_types::errors::types::{DeserializationError, SerializationError, TypeError};
 
 use dozer_types::thiserror;
 use dozer_types::thiserror::Error;
@@ -75,6 +76,9 @@ pub enum PipelineError {
     #[error("Window: {0}")]
     WindowError(#[from] WindowError),
 
+    #[error("Window function: {0}")]
+    WindowFunctionError(#[from] WindowFunctionError),
+
     #[error("Table Function is not supported")]
     UnsupportedTableFunction,
 
@@ -93,7 +97,7 @@ pub enum PipelineError {
     #[error("Invalid JOIN: {0}")]
     InvalidJoin(String),
 
-    #[error("The JOIN clause is not supported. In this version only INNER, LEFT and RIGHT OUTER JOINs are supported")]
+    #[error("The JOIN clause is not supported. In this version only INNER, LEFT OUTER, RIGHT OUTER, FULL OUTER and CROSS JOINs are supported")]
     UnsupportedJoinType,
 
     #[error(
@@ -123,6 +127,15 @@ pub enum PipelineError {
 
     #[error("Duplicated Processor name: {0}")]
     ProcessorAlreadyExists(String),
+
+    #[error("Failed to serialize processor state: {0}")]
+    SerializeState(#[source] SerializationError),
+
+    #[error("Failed to restore processor state: {0}")]
+    RestoreState(#[source] DeserializationError),
+
+    #[error("State store error: {0}")]
+    StateStore(#[from] StateStoreError),
 }
 
 #[derive(Error, Debug)]
@@ -138,9 +151,11 @@ pub enum UnsupportedSqlError {
 
     #[error("FROM clause doesn't support \"Comma Syntax\"")]
     FromCommaSyntax,
-    #[error("ORDER BY is not supported in SQL. You could achieve the same by using the ORDER BY operator in the cache and APIs")]
+    #[error(
+        "ORDER BY is only supported together with LIMIT, as the output of a query has no order"
+    )]
     OrderByError,
-    #[error("Limit and Offset are not supported in SQL. You could achieve the same by using the LIMIT and OFFSET operators in the cache and APIs")]
+    #[error("LIMIT and OFFSET are only supported together with ORDER BY, and OFFSET only together with LIMIT")]
     LimitOffsetError,
     #[error("Select statements should specify INTO for creating output tables")]
     IntoError,
@@ -171,18 +186,26 @@ pub enum JoinError {
     AmbiguousField(String),
     #[error("Invalid Field specified in join : {0}")]
     InvalidFieldSpecified(String),
-    #[error("Unsupported Join constraint {0} only comparison of fields with \'=\' and \'AND\' operators are allowed in the JOIN ON constraint")]
-    UnsupportedJoinConstraint(String),
-    #[error(
-        "Unsupported Join constraint operator {0}, only \'=\' and \'AND\' operators are allowed in the JOIN ON constraint"
-    )]
-    UnsupportedJoinConstraintOperator(String),
-    #[error(
-        "Unsupported Join constraint, only ON is allowed as the JOIN constraint using \'=\' and \'AND\' operators"
-    )]
+    #[error("Unsupported Join constraint, only ON is allowed as the JOIN constraint")]
     UnsupportedJoinConstraintType,
     #[error("Unsupported Join type")]
     UnsupportedJoinType,
+    #[error("A side of the CROSS JOIN, or of a JOIN without an equality or range condition, would keep more than {0} records")]
+    CrossJoinTooLarge(usize),
+    #[error("FOR SYSTEM_TIME AS OF is only supported in INNER and LEFT OUTER joins")]
+    UnsupportedAsOfJoinType,
+    #[error(
+        "Joins FOR SYSTEM_TIME AS OF only support equality of fields in the JOIN ON constraint"
+    )]
+    UnsupportedAsOfJoinConstraint,
+    #[error("FOR SYSTEM_TIME AS OF must evaluate to timestamp, but it evaluates to {0}")]
+    InvalidAsOfTime(Field),
+    #[error("A table joined FOR SYSTEM_TIME AS OF must declare the column its versions are valid from, as in `FOR SYSTEM_TIME AS OF <time> WITH (<column>)`")]
+    MissingAsOfVersionColumn,
+    #[error("The version column {0} of a table joined FOR SYSTEM_TIME AS OF must be a timestamp column of the table")]
+    InvalidAsOfVersionColumn(String),
+    #[error("The version column of a table joined FOR SYSTEM_TIME AS OF must be a timestamp, but it is {0}")]
+    InvalidAsOfVersionTime(Field),
 
     #[error("Overflow error computing the eviction time in the TTL reference field")]
     EvictionTimeOverflow,
@@ -192,6 +215,12 @@ pub enum JoinError {
 
     #[error("Deserialization error: {0}")]
     Deserialization(#[from] DeserializationError),
+
+    #[error("State store error: {0}")]
+    StateStore(#[from] StateStoreError),
+
+    #[error("Join condition: {0}")]
+    Expression(#[from] dozer_sql_expression::error::Error),
 }
 
 #[derive(Error, Debug)]
@@ -289,6 +318,29 @@ pub enum WindowError {
     NoAlias,
 }
 
+#[derive(Error, Debug)]
+pub enum WindowFunctionError {
+    #[error("{0}() is not a window function")]
+    UnknownFunction(String),
+
+    #[error("Named window {0} is not supported, the window must be specified in OVER (...)")]
+    NamedWindow(String),
+
+    #[error("Window functions are not supported together with GROUP BY or aggregate functions")]
+    WithAggregation,
+
+    #[error(
+        "Window functions are not supported together with SELECT *, the columns must be listed"
+    )]
+    WithWildcard,
+
+    #[error("Invalid arguments for window function {0}()")]
+    InvalidArguments(String),
+
+    #[error("Unsupported window frame {0}. ROWS frames support UNBOUNDED, <n> PRECEDING, CURRENT ROW and <n> FOLLOWING bounds, RANGE frames only UNBOUNDED and CURRENT ROW bounds")]
+    UnsupportedFrame(String),
+}
+
 #[derive(Error, Debug)]
 pub enum TableOperatorError {
     #[error("Internal error: {0}")]
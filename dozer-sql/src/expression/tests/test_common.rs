@@ -3,6 +3,7 @@ use crate::{projection::factory::ProjectionProcessorFactory, tests::utils::get_s
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::event::EventHub;
 use dozer_core::node::ProcessorFactory;
+use dozer_core::state_store::StateStore;
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::types::{Field, Schema, TableOperation};
 use dozer_types::types::{Operation, Record};
@@ -43,6 +44,8 @@ pub(crate) fn run_fct(sql: &str, schema: Schema, input: Vec<Field>) -> Field {
             HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
             HashMap::new(),
             EventHub::new(1),
+            None,
+            StateStore::default(),
         ))
         .unwrap();
 
@@ -7,8 +7,10 @@ mod product;
 mod projection;
 mod selection;
 mod table_operator;
+mod top_n;
 mod utils;
 mod window;
+mod window_function;
 
 pub use dozer_sql_expression::sqlparser;
 
@@ -0,0 +1,850 @@
+use std::collections::BTreeMap;
+
+use dozer_core::channels::ProcessorChannelForwarder;
+use dozer_core::epoch::Epoch;
+use dozer_core::node::Processor;
+use dozer_core::state_store::{SortKey, SortedStateMap, StateMap, StateStore};
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::Expr as SqlExpr;
+use dozer_types::bincode::{self, serde::Compat};
+use dozer_types::chrono;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::types::{Field, Lifetime, Operation, Record, Schema, TableOperation, Timestamp};
+
+use crate::errors::{JoinError, PipelineError};
+use crate::utils::record_hashtable_key::get_record_hash;
+
+use super::factory::{LEFT_JOIN_PORT, RIGHT_JOIN_PORT};
+use super::JoinResult;
+
+/// The `FOR SYSTEM_TIME AS OF` clause of the right table of a join.
+#[derive(Debug, Clone)]
+pub struct AsOfClause {
+    /// The time to join the right table as of, evaluated on left records.
+    pub time: SqlExpr,
+    /// The timestamp column of the right table that its versions are valid from, declared as `WITH (column)`.
+    pub version_column: Option<SqlExpr>,
+}
+
+/// A version of a right record: the primary key of the record, and the record, or `None` if the record ended.
+type Version = (u64, Option<Record>);
+
+/// The state of an [AsOfJoinProcessor] that isn't kept in the state store.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+struct AsOfJoinIndexes {
+    next_sequence: u64,
+    /// The latest version time of the right records, which deletes end their record at.
+    latest_version_time: Option<Timestamp>,
+    /// When right versions expire, from their lifetime.
+    version_expiry: BTreeMap<Timestamp, Vec<SortKey>>,
+    /// When left records expire, from their lifetime.
+    join_record_expiry: BTreeMap<Timestamp, Vec<u64>>,
+}
+
+/// What an [AsOfJoinProcessor] checkpoints: its versions, its join records and its indexes.
+type AsOfJoinState = (
+    Vec<(SortKey, Version)>,
+    Vec<(u64, Vec<Vec<Record>>)>,
+    Compat<AsOfJoinIndexes>,
+);
+
+/// Joins every left record with the versions of its matching right records that were valid at the left record's
+/// `FOR SYSTEM_TIME AS OF` time.
+///
+/// A right record's versions are valid from the time in its version column, until its next version. Versions are
+/// kept for every primary key of the right table, so a record that changes its join key ends at its old key, and a
+/// deleted record ends at the latest version time of the right table. Versions expire with their lifetime.
+/// Only left changes emit join records, so right changes never rewrite the join records emitted before them.
+#[derive(Debug)]
+pub struct AsOfJoinProcessor {
+    left_outer: bool,
+    left_join_key_indexes: Vec<usize>,
+    right_join_key_indexes: Vec<usize>,
+    left_primary_key_indexes: Vec<usize>,
+    right_primary_key_indexes: Vec<usize>,
+    accurate_keys: bool,
+    /// Evaluates to the time of the left record to find the right versions at.
+    as_of: Expression,
+    version_column: usize,
+    left_schema: Schema,
+    right_default_record: Record,
+    /// The versions of the right records, keyed by their join key, primary key, version time and arrival.
+    versions: SortedStateMap<Version>,
+    /// The join records of every insert of a left record, by the primary key of the left record.
+    join_records: StateMap<u64, Vec<Vec<Record>>>,
+    /// Numbers of entries in `versions` and `join_records`, kept as they come and go, as disk backed maps are
+    /// counted by scanning.
+    version_count: usize,
+    join_record_count: usize,
+    indexes: AsOfJoinIndexes,
+}
+
+impl AsOfJoinProcessor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        left_outer: bool,
+        (left_join_key_indexes, right_join_key_indexes): (Vec<usize>, Vec<usize>),
+        (as_of, version_column): (Expression, usize),
+        (left_schema, right_schema): (Schema, &Schema),
+        enable_probabilistic_optimizations: bool,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: &StateStore,
+    ) -> Result<Self, JoinError> {
+        let primary_key_indexes = |schema: &Schema| {
+            if schema.primary_index.is_empty() {
+                (0..schema.fields.len()).collect()
+            } else {
+                schema.primary_index.clone()
+            }
+        };
+        let (versions, join_records, indexes) = match checkpoint_data {
+            Some(data) => {
+                let ((versions, join_records, Compat(indexes)), _): (AsOfJoinState, _) =
+                    bincode::decode_from_slice(&data, bincode::config::legacy())
+                        .map_err(|e| JoinError::Deserialization(e.into()))?;
+                (
+                    state_store.create_sorted_map_from(versions)?,
+                    state_store.create_map_from(join_records)?,
+                    indexes,
+                )
+            }
+            None => (
+                state_store.create_sorted_map(),
+                state_store.create_map(),
+                AsOfJoinIndexes::default(),
+            ),
+        };
+        let version_count = versions.count()?;
+        let join_record_count = join_records.count()?;
+        Ok(Self {
+            left_outer,
+            left_join_key_indexes,
+            right_join_key_indexes,
+            left_primary_key_indexes: primary_key_indexes(&left_schema),
+            right_primary_key_indexes: primary_key_indexes(right_schema),
+            accurate_keys: !enable_probabilistic_optimizations,
+            as_of,
+            version_column,
+            left_schema,
+            right_default_record: Record::nulls_from_schema(right_schema),
+            versions,
+            join_records,
+            version_count,
+            join_record_count,
+            indexes,
+        })
+    }
+
+    fn join_key(&self, record: &Record, join_key_indexes: &[usize]) -> SortKey {
+        let fields = join_key_indexes.iter().map(|index| &record.values[*index]);
+        let mut key = SortKey::new();
+        if self.accurate_keys {
+            for field in fields {
+                key.push(field);
+            }
+        } else {
+            key.push(&Field::UInt(get_record_hash(fields)));
+        }
+        key
+    }
+
+    fn left_primary_key(&self, record: &Record) -> u64 {
+        primary_key(record, &self.left_primary_key_indexes)
+    }
+
+    /// Returns the prefix of the keys of the versions of a right record, and its primary key.
+    fn right_record_key(&self, record: &Record) -> (SortKey, u64) {
+        let mut key = self.join_key(record, &self.right_join_key_indexes);
+        let primary_key = primary_key(record, &self.right_primary_key_indexes);
+        key.push(&Field::UInt(primary_key));
+        (key, primary_key)
+    }
+
+    fn version_time(&self, record: &Record) -> JoinResult<Timestamp> {
+        match &record.values[self.version_column] {
+            Field::Timestamp(time) => Ok(*time),
+            other => Err(JoinError::InvalidAsOfVersionTime(other.clone())),
+        }
+    }
+
+    /// Adds a version of a right record valid from `time`, which ends the record if `record` is `None`.
+    fn add_version(
+        &mut self,
+        (mut key, primary_key): (SortKey, u64),
+        time: Timestamp,
+        lifetime: Option<Lifetime>,
+        record: Option<Record>,
+    ) -> JoinResult<()> {
+        key.push(&Field::Timestamp(time));
+        key.push(&Field::UInt(self.indexes.next_sequence));
+        self.indexes.next_sequence += 1;
+        if self
+            .indexes
+            .latest_version_time
+            .map_or(true, |latest| latest < time)
+        {
+            self.indexes.latest_version_time = Some(time);
+        }
+        if let Some(expiry) = lifetime.as_ref().and_then(expiry) {
+            self.indexes
+                .version_expiry
+                .entry(expiry)
+                .or_default()
+                .push(key.clone());
+        }
+        self.versions.insert(key, (primary_key, record))?;
+        self.version_count += 1;
+        Ok(())
+    }
+
+    fn insert_right(&mut self, record: Record) -> JoinResult<()> {
+        let time = self.version_time(&record)?;
+        self.add_version(
+            self.right_record_key(&record),
+            time,
+            record.get_lifetime(),
+            Some(record),
+        )
+    }
+
+    /// Returns the versions of the right records matching `record` that were valid at its AS OF time.
+    fn find_versions(&self, record: &Record) -> JoinResult<Vec<Record>> {
+        let as_of = match self.as_of.evaluate(record, &self.left_schema)? {
+            Field::Timestamp(timestamp) => timestamp,
+            Field::Null => return Ok(vec![]),
+            other => return Err(JoinError::InvalidAsOfTime(other)),
+        };
+        let join_key = self.join_key(record, &self.left_join_key_indexes);
+
+        // Skips from one primary key of the join key to the next, taking the last version of each up to `as_of`.
+        let mut records = vec![];
+        let mut start = join_key.clone();
+        loop {
+            let Some(entry) = self.versions.iter_from(&start).next() else {
+                break;
+            };
+            let (key, (primary_key, _)) = entry?;
+            if !key.starts_with(&join_key) {
+                break;
+            }
+            let mut record_key = join_key.clone();
+            record_key.push(&Field::UInt(primary_key));
+            let mut end = record_key.clone();
+            end.push(&Field::Timestamp(as_of));
+            if let Some(entry) = self.versions.iter_rev_before(&end.prefix_end()).next() {
+                let (key, (_, version)) = entry?;
+                if key.starts_with(&record_key) {
+                    records.extend(version);
+                }
+            }
+            start = record_key.prefix_end();
+        }
+        Ok(records)
+    }
+
+    fn insert_left(&mut self, record: &Record) -> JoinResult<Vec<Record>> {
+        let mut right_records = self.find_versions(record)?;
+        if right_records.is_empty() && self.left_outer {
+            right_records.push(self.right_default_record.clone());
+        }
+        let join_records = right_records
+            .into_iter()
+            .map(|right_record| {
+                let mut values = record.values.clone();
+                values.extend(right_record.values);
+                let mut join_record = Record::new(values);
+                join_record.set_lifetime(record.get_lifetime());
+                join_record
+            })
+            .collect::<Vec<_>>();
+
+        let primary_key = self.left_primary_key(record);
+        if let Some(expiry) = record.get_lifetime().as_ref().and_then(expiry) {
+            self.indexes
+                .join_record_expiry
+                .entry(expiry)
+                .or_default()
+                .push(primary_key);
+        }
+        // Inserts without join records are kept too, so every delete takes back the join records of its own insert.
+        let added = self.join_records.update(primary_key, |inserts| {
+            let added = inserts.is_none();
+            inserts
+                .get_or_insert_with(Default::default)
+                .push(join_records.clone());
+            added
+        })?;
+        if added {
+            self.join_record_count += 1;
+        }
+        Ok(join_records)
+    }
+
+    fn delete_left(&mut self, record: &Record) -> JoinResult<Vec<Record>> {
+        let primary_key = self.left_primary_key(record);
+        Ok(self.pop_join_records(primary_key)?.unwrap_or_default())
+    }
+
+    /// Removes the join records of the last insert of the left record with `primary_key`.
+    fn pop_join_records(&mut self, primary_key: u64) -> JoinResult<Option<Vec<Record>>> {
+        let (join_records, removed) = self.join_records.update(primary_key, |inserts| {
+            let existed = inserts.is_some();
+            let join_records = pop_insert(inserts);
+            (join_records, existed && inserts.is_none())
+        })?;
+        if removed {
+            self.join_record_count -= 1;
+        }
+        Ok(join_records)
+    }
+
+    fn process_left(&mut self, op: Operation) -> JoinResult<Vec<Operation>> {
+        let mut output = vec![];
+        let deletes =
+            |records: Vec<Record>| records.into_iter().map(|old| Operation::Delete { old });
+        let inserts =
+            |records: Vec<Record>| records.into_iter().map(|new| Operation::Insert { new });
+        match op {
+            Operation::Insert { new } => {
+                output.extend(inserts(self.insert_left(&new)?));
+            }
+            Operation::Delete { old } => {
+                output.extend(deletes(self.delete_left(&old)?));
+            }
+            Operation::Update { old, new } => {
+                output.extend(deletes(self.delete_left(&old)?));
+                output.extend(inserts(self.insert_left(&new)?));
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    output.extend(inserts(self.insert_left(&record)?));
+                }
+            }
+            Operation::Truncate => {
+                self.indexes.join_record_expiry.clear();
+                for (_, join_records) in self.join_records.entries()? {
+                    output.extend(deletes(join_records.into_iter().flatten().collect()));
+                }
+                self.join_records.clear()?;
+                self.join_record_count = 0;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Right changes only add versions, which later left records join with.
+    fn process_right(&mut self, op: Operation) -> JoinResult<()> {
+        match op {
+            Operation::Insert { new } => self.insert_right(new)?,
+            Operation::Update { old, new } => {
+                // A record that moves to another join key or primary key ends at its old key.
+                let old_key = self.right_record_key(&old);
+                if old_key != self.right_record_key(&new) {
+                    let time = self.version_time(&new)?;
+                    self.add_version(old_key, time, new.get_lifetime(), None)?;
+                }
+                self.insert_right(new)?;
+            }
+            Operation::Delete { old } => {
+                let time = self.version_time(&old)?;
+                let time = self
+                    .indexes
+                    .latest_version_time
+                    .map_or(time, |latest| latest.max(time));
+                self.add_version(self.right_record_key(&old), time, old.get_lifetime(), None)?;
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.insert_right(record)?;
+                }
+            }
+            Operation::Truncate => {
+                self.versions.clear()?;
+                self.version_count = 0;
+                self.indexes.version_expiry.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the right versions and left join records that expired by `now`.
+    fn evict(&mut self, now: &Timestamp) -> JoinResult<()> {
+        while let Some(entry) = self.indexes.version_expiry.first_entry() {
+            if entry.key() > now {
+                break;
+            }
+            for key in entry.remove() {
+                if self.versions.remove(&key)?.is_some() {
+                    self.version_count -= 1;
+                }
+            }
+        }
+        while let Some(entry) = self.indexes.join_record_expiry.first_entry() {
+            if entry.key() > now {
+                break;
+            }
+            for primary_key in entry.remove() {
+                self.pop_join_records(primary_key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn primary_key(record: &Record, primary_key_indexes: &[usize]) -> u64 {
+    get_record_hash(
+        primary_key_indexes
+            .iter()
+            .map(|index| &record.values[*index]),
+    )
+}
+
+/// Removes the join records of the last insert of a left record.
+fn pop_insert(inserts: &mut Option<Vec<Vec<Record>>>) -> Option<Vec<Record>> {
+    let join_records = inserts.as_mut()?.pop();
+    if inserts.as_ref().is_some_and(Vec::is_empty) {
+        *inserts = None;
+    }
+    join_records
+}
+
+fn expiry(lifetime: &Lifetime) -> Option<Timestamp> {
+    lifetime
+        .reference
+        .checked_add_signed(chrono::Duration::nanoseconds(
+            lifetime.duration.as_nanos() as i64
+        ))
+}
+
+fn lifetime_of(op: &Operation) -> Option<Lifetime> {
+    match op {
+        Operation::Insert { new } | Operation::Update { new, .. } => new.get_lifetime(),
+        Operation::Delete { old } => old.get_lifetime(),
+        Operation::BatchInsert { new } => new.iter().find_map(Record::get_lifetime),
+        Operation::Truncate => None,
+    }
+}
+
+impl Processor for AsOfJoinProcessor {
+    fn commit(&self, _epoch: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        if let Some(lifetime) = lifetime_of(&op.op) {
+            self.evict(&lifetime.reference)
+                .map_err(PipelineError::JoinError)?;
+        }
+        let output = match op.port {
+            LEFT_JOIN_PORT => self.process_left(op.op).map_err(PipelineError::JoinError)?,
+            RIGHT_JOIN_PORT => {
+                self.process_right(op.op)
+                    .map_err(PipelineError::JoinError)?;
+                vec![]
+            }
+            port => return Err(PipelineError::InvalidPortHandle(port).into()),
+        };
+        for op in output {
+            fw.send(TableOperation::without_id(op, DEFAULT_PORT_HANDLE));
+        }
+        Ok(())
+    }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data = bincode::encode_to_vec(
+            (&self.versions, &self.join_records, Compat(&self.indexes)),
+            bincode::config::legacy(),
+        )
+        .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
+
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(Some(self.version_count + self.join_record_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use dozer_core::event::EventHub;
+    use dozer_core::node::{PortHandle, ProcessorFactory};
+    use dozer_sql_expression::builder::NameOrAlias;
+    use dozer_sql_expression::sqlparser::ast::Ident;
+    use dozer_types::chrono::DateTime;
+    use dozer_types::types::{FieldDefinition, FieldType, SourceDefinition};
+
+    use crate::product::join::factory::JoinProcessorFactory;
+    use crate::tests::utils::{create_test_runtime, get_select, open_node_state_store};
+
+    use super::*;
+
+    struct TestChannelForwarder {
+        operations: Vec<Operation>,
+    }
+
+    impl ProcessorChannelForwarder for TestChannelForwarder {
+        fn send(&mut self, op: TableOperation) {
+            self.operations.push(op.op);
+        }
+    }
+
+    fn create_schema(table_name: &str, fields: &[(&str, FieldType)], primary_key: bool) -> Schema {
+        let mut schema = Schema::new();
+        for (index, (name, typ)) in fields.iter().enumerate() {
+            schema.field(
+                FieldDefinition::new(
+                    name.to_string(),
+                    *typ,
+                    true,
+                    SourceDefinition::Table {
+                        connection: "test".to_string(),
+                        name: table_name.to_string(),
+                    },
+                ),
+                primary_key && index == 0,
+            );
+        }
+        schema
+    }
+
+    fn left_schema() -> Schema {
+        create_schema(
+            "l",
+            &[("category", FieldType::UInt), ("at", FieldType::Timestamp)],
+            false,
+        )
+    }
+
+    /// Right records have the primary key `id` and are valid from `valid_from`.
+    fn right_schema() -> Schema {
+        create_schema(
+            "r",
+            &[
+                ("id", FieldType::UInt),
+                ("category", FieldType::UInt),
+                ("value", FieldType::UInt),
+                ("valid_from", FieldType::Timestamp),
+            ],
+            true,
+        )
+    }
+
+    fn time(time: &str) -> Timestamp {
+        DateTime::parse_from_rfc3339(&format!("2020-01-01T{time}Z")).unwrap()
+    }
+
+    fn create_processor(
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: &StateStore,
+    ) -> AsOfJoinProcessor {
+        AsOfJoinProcessor::new(
+            false,
+            (vec![0], vec![1]),
+            (Expression::Column { index: 1 }, 3),
+            (left_schema(), &right_schema()),
+            false,
+            checkpoint_data,
+            state_store,
+        )
+        .unwrap()
+    }
+
+    fn process(
+        processor: &mut AsOfJoinProcessor,
+        port: PortHandle,
+        op: Operation,
+    ) -> Vec<Operation> {
+        let mut forwarder = TestChannelForwarder { operations: vec![] };
+        processor
+            .process(TableOperation::without_id(op, port), &mut forwarder)
+            .unwrap();
+        forwarder.operations
+    }
+
+    fn right(id: u64, category: u64, value: u64, valid_from: &str) -> Record {
+        Record::new(vec![
+            Field::UInt(id),
+            Field::UInt(category),
+            Field::UInt(value),
+            Field::Timestamp(time(valid_from)),
+        ])
+    }
+
+    fn left(category: u64, at: &str) -> Record {
+        Record::new(vec![Field::UInt(category), Field::Timestamp(time(at))])
+    }
+
+    fn join_record(left: &Record, right: &Record) -> Record {
+        let mut values = left.values.clone();
+        values.extend(right.values.clone());
+        Record::new(values)
+    }
+
+    /// Inserts a left record and returns the right records it joined with, in the order of their values.
+    fn join(processor: &mut AsOfJoinProcessor, left: Record) -> Vec<Record> {
+        let mut right_records = process(processor, LEFT_JOIN_PORT, Operation::Insert { new: left })
+            .into_iter()
+            .map(|op| match op {
+                Operation::Insert { new } => Record::new(new.values[2..].to_vec()),
+                op => panic!("unexpected operation {op:?}"),
+            })
+            .collect::<Vec<_>>();
+        right_records.sort_by(|a, b| a.values.cmp(&b.values));
+        right_records
+    }
+
+    #[test]
+    fn test_as_of_join() {
+        let state_store = StateStore::default();
+        let mut processor = create_processor(None, &state_store);
+
+        let first = right(1, 1, 10, "01:00:00");
+        let ops = process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Insert { new: first.clone() },
+        );
+        assert_eq!(ops, vec![]);
+        let second = right(1, 1, 20, "02:00:00");
+        let ops = process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Update {
+                old: first.clone(),
+                new: second.clone(),
+            },
+        );
+        assert_eq!(ops, vec![]);
+
+        // Left records join with the version valid at their time, and with nothing before the first version.
+        let early = left(1, "01:30:00");
+        let ops = process(
+            &mut processor,
+            LEFT_JOIN_PORT,
+            Operation::Insert { new: early.clone() },
+        );
+        assert_eq!(
+            ops,
+            vec![Operation::Insert {
+                new: join_record(&early, &first)
+            }]
+        );
+        assert_eq!(
+            join(&mut processor, left(1, "02:00:00")),
+            vec![second.clone()]
+        );
+        assert_eq!(join(&mut processor, left(1, "02:30:00")), vec![second]);
+        assert_eq!(join(&mut processor, left(1, "00:30:00")), vec![]);
+        assert_eq!(join(&mut processor, left(2, "01:30:00")), vec![]);
+
+        // Deleting a left record deletes its join record.
+        let ops = process(
+            &mut processor,
+            LEFT_JOIN_PORT,
+            Operation::Delete { old: early.clone() },
+        );
+        assert_eq!(
+            ops,
+            vec![Operation::Delete {
+                old: join_record(&early, &first)
+            }]
+        );
+        // Both versions, and the join records of the four left records left.
+        assert_eq!(processor.state_size().unwrap(), Some(6));
+
+        // The versions survive a checkpoint.
+        let data = processor.serialize_state().unwrap();
+        let mut processor = create_processor(data, &state_store);
+        assert_eq!(processor.state_size().unwrap(), Some(6));
+        assert_eq!(join(&mut processor, early), vec![first]);
+    }
+
+    #[test]
+    fn test_as_of_join_key_change() {
+        let mut processor = create_processor(None, &StateStore::default());
+
+        let old = right(1, 1, 10, "01:00:00");
+        let new = right(1, 2, 10, "02:00:00");
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Insert { new: old.clone() },
+        );
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Update {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        );
+
+        // The record ends at its old join key when it moves to the new one.
+        assert_eq!(join(&mut processor, left(1, "01:30:00")), vec![old]);
+        assert_eq!(join(&mut processor, left(1, "02:30:00")), vec![]);
+        assert_eq!(join(&mut processor, left(2, "01:30:00")), vec![]);
+        assert_eq!(join(&mut processor, left(2, "02:30:00")), vec![new]);
+    }
+
+    #[test]
+    fn test_as_of_join_delete() {
+        let mut processor = create_processor(None, &StateStore::default());
+
+        // Versions are kept for every primary key of a join key.
+        let first = right(1, 1, 10, "01:00:00");
+        let second = right(2, 1, 20, "02:00:00");
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::BatchInsert {
+                new: vec![first.clone(), second.clone()],
+            },
+        );
+        assert_eq!(
+            join(&mut processor, left(1, "02:30:00")),
+            vec![first.clone(), second.clone()]
+        );
+
+        // A deleted record ends at the latest version time.
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Delete { old: first.clone() },
+        );
+        assert_eq!(join(&mut processor, left(1, "01:30:00")), vec![first]);
+        assert_eq!(join(&mut processor, left(1, "02:30:00")), vec![second]);
+
+        // Right truncates drop all versions.
+        process(&mut processor, RIGHT_JOIN_PORT, Operation::Truncate);
+        assert_eq!(join(&mut processor, left(1, "02:30:00")), vec![]);
+    }
+
+    #[test]
+    fn test_as_of_join_versions_expire() {
+        let mut processor = create_processor(None, &StateStore::default());
+        let with_lifetime = |mut record: Record, reference: &str| {
+            record.set_lifetime(Some(Lifetime {
+                reference: time(reference),
+                duration: Duration::from_secs(3600),
+            }));
+            record
+        };
+
+        let first = with_lifetime(right(1, 1, 10, "01:00:00"), "01:00:00");
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Insert { new: first.clone() },
+        );
+        assert_eq!(
+            join(&mut processor, left(1, "01:30:00")),
+            vec![Record::new(first.values)]
+        );
+
+        // Versions expire with their lifetime.
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Insert {
+                new: with_lifetime(right(2, 2, 20, "03:00:00"), "03:00:00"),
+            },
+        );
+        assert_eq!(join(&mut processor, left(1, "01:30:00")), vec![]);
+    }
+
+    #[test]
+    fn test_as_of_join_with_state_on_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state_store = open_node_state_store(temp_dir.path(), None);
+        let mut processor = create_processor(None, &state_store);
+
+        let first = right(1, 1, 10, "01:00:00");
+        let second = right(1, 1, 20, "02:00:00");
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Insert { new: first.clone() },
+        );
+        process(
+            &mut processor,
+            RIGHT_JOIN_PORT,
+            Operation::Update {
+                old: first.clone(),
+                new: second.clone(),
+            },
+        );
+        assert_eq!(
+            join(&mut processor, left(1, "01:30:00")),
+            vec![first.clone()]
+        );
+        assert_eq!(join(&mut processor, left(1, "02:30:00")), vec![second]);
+
+        let data = processor.serialize_state().unwrap();
+        let state_store_checkpoint = state_store.checkpoint(0).unwrap();
+        drop((processor, state_store));
+        let state_store = open_node_state_store(temp_dir.path(), state_store_checkpoint);
+        let mut processor = create_processor(data, &state_store);
+        assert_eq!(join(&mut processor, left(1, "01:30:00")), vec![first]);
+    }
+
+    #[test]
+    fn test_as_of_join_requires_version_column() {
+        let runtime = create_test_runtime();
+        let build = |version_column: Option<&str>| {
+            let select =
+                get_select("SELECT l.category FROM l INNER JOIN r ON l.category = r.category")
+                    .unwrap();
+            let join_operator = select.from[0].joins[0].join_operator.clone();
+            let factory = JoinProcessorFactory::new(
+                "test".into(),
+                Some(NameOrAlias("l".into(), None)),
+                Some(NameOrAlias("r".into(), None)),
+                join_operator,
+                false,
+                100,
+                Some(AsOfClause {
+                    time: SqlExpr::Identifier(Ident::new("at")),
+                    version_column: version_column
+                        .map(|name| SqlExpr::Identifier(Ident::new(name))),
+                }),
+                vec![],
+                runtime.clone(),
+            );
+            let schemas = HashMap::from([
+                (LEFT_JOIN_PORT, left_schema()),
+                (RIGHT_JOIN_PORT, right_schema()),
+            ]);
+            runtime.block_on(factory.build(
+                schemas,
+                HashMap::new(),
+                EventHub::new(1),
+                None,
+                StateStore::default(),
+            ))
+        };
+        let error = |result: Result<Box<dyn Processor>, BoxedError>| match result {
+            Ok(_) => panic!("the join was built"),
+            Err(e) => e.downcast::<PipelineError>().unwrap(),
+        };
+
+        assert!(matches!(
+            *error(build(None)),
+            PipelineError::JoinError(JoinError::MissingAsOfVersionColumn)
+        ));
+        assert!(matches!(
+            *error(build(Some("value"))),
+            PipelineError::JoinError(JoinError::InvalidAsOfVersionColumn(_))
+        ));
+        assert!(build(Some("valid_from")).is_ok());
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use dozer_core::{
     event::EventHub,
-    node::{PortHandle, Processor, ProcessorFactory},
+    node::{Partitioner, PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 use dozer_sql_expression::{
@@ -15,16 +17,23 @@ use dozer_sql_expression::{
 
 use dozer_types::{
     errors::internal::BoxedError,
+    models::udf_config::UdfConfig,
     tonic::async_trait,
-    types::{FieldDefinition, Schema},
+    types::{FieldDefinition, FieldType, Record, Schema},
 };
+use tokio::runtime::Runtime;
 
 use crate::errors::JoinError;
 use crate::errors::PipelineError;
+use crate::utils::record_hashtable_key::get_record_hash;
 use dozer_sql_expression::builder::extend_schema_source_def;
 
 use super::{
-    operator::{JoinOperator, JoinType},
+    as_of::{AsOfClause, AsOfJoinProcessor},
+    operator::{
+        range::{Comparison, RangeCondition},
+        JoinCondition, JoinOperator, JoinType, ResidualPredicate,
+    },
     processor::ProductProcessor,
 };
 
@@ -38,15 +47,25 @@ pub struct JoinProcessorFactory {
     right: Option<NameOrAlias>,
     join_operator: SqlJoinOperator,
     enable_probabilistic_optimizations: bool,
+    max_cross_join_records: usize,
+    /// The `FOR SYSTEM_TIME AS OF` clause of the right table.
+    as_of: Option<AsOfClause>,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
 }
 
 impl JoinProcessorFactory {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: String,
         left: Option<NameOrAlias>,
         right: Option<NameOrAlias>,
         join_operator: SqlJoinOperator,
         enable_probabilistic_optimizations: bool,
+        max_cross_join_records: usize,
+        as_of: Option<AsOfClause>,
+        udfs: Vec<UdfConfig>,
+        runtime: Arc<Runtime>,
     ) -> Self {
         Self {
             id,
@@ -54,10 +73,72 @@ impl JoinProcessorFactory {
             right,
             join_operator,
             enable_probabilistic_optimizations,
+            max_cross_join_records,
+            as_of,
+            udfs,
+            runtime,
+        }
+    }
+
+    fn plan(&self, input_schemas: &HashMap<PortHandle, Schema>) -> Result<JoinPlan, PipelineError> {
+        let (join_type, join_constraint) = match &self.join_operator {
+            SqlJoinOperator::Inner(constraint) => (JoinType::Inner, Some(constraint)),
+            SqlJoinOperator::LeftOuter(constraint) => (JoinType::LeftOuter, Some(constraint)),
+            SqlJoinOperator::RightOuter(constraint) => (JoinType::RightOuter, Some(constraint)),
+            SqlJoinOperator::FullOuter(constraint) => (JoinType::FullOuter, Some(constraint)),
+            SqlJoinOperator::CrossJoin => (JoinType::Cross, None),
+            _ => return Err(PipelineError::JoinError(JoinError::UnsupportedJoinType)),
+        };
+
+        let mut left_schema = input_schemas
+            .get(&LEFT_JOIN_PORT)
+            .ok_or(PipelineError::InternalError(
+                "Invalid Product".to_string().into(),
+            ))?
+            .clone();
+        if let Some(left_table_name) = &self.left {
+            left_schema = extend_schema_source_def(&left_schema, left_table_name);
+        }
+
+        let mut right_schema = input_schemas
+            .get(&RIGHT_JOIN_PORT)
+            .ok_or(PipelineError::InternalError(
+                "Invalid Product".to_string().into(),
+            ))?
+            .clone();
+        if let Some(right_table_name) = &self.right {
+            right_schema = extend_schema_source_def(&right_schema, right_table_name);
+        }
+
+        let mut constraint = ParsedJoinConstraint::default();
+        match join_constraint {
+            Some(SqlJoinConstraint::On(expression)) => {
+                parse_join_constraint(expression, &left_schema, &right_schema, &mut constraint)?
+            }
+            Some(_) => {
+                return Err(PipelineError::JoinError(
+                    JoinError::UnsupportedJoinConstraintType,
+                ))
+            }
+            None => (),
         }
+
+        Ok(JoinPlan {
+            join_type,
+            left_schema,
+            right_schema,
+            constraint,
+        })
     }
 }
 
+struct JoinPlan {
+    join_type: JoinType,
+    left_schema: Schema,
+    right_schema: Schema,
+    constraint: ParsedJoinConstraint,
+}
+
 #[async_trait]
 impl ProcessorFactory for JoinProcessorFactory {
     fn id(&self) -> String {
@@ -112,58 +193,141 @@ impl ProcessorFactory for JoinProcessorFactory {
         input_schemas: HashMap<PortHandle, dozer_types::types::Schema>,
         _output_schemas: HashMap<PortHandle, dozer_types::types::Schema>,
         _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
-        let (join_type, join_constraint) = match &self.join_operator {
-            SqlJoinOperator::Inner(constraint) => (JoinType::Inner, constraint),
-            SqlJoinOperator::LeftOuter(constraint) => (JoinType::LeftOuter, constraint),
-            SqlJoinOperator::RightOuter(constraint) => (JoinType::RightOuter, constraint),
-            _ => return Err(PipelineError::JoinError(JoinError::UnsupportedJoinType).into()),
-        };
-
-        let expression = match join_constraint {
-            SqlJoinConstraint::On(expression) => expression,
-            _ => {
+        let JoinPlan {
+            join_type,
+            left_schema,
+            right_schema,
+            constraint,
+        } = self.plan(&input_schemas)?;
+
+        if let Some(as_of) = &self.as_of {
+            let left_outer = match join_type {
+                JoinType::Inner => false,
+                JoinType::LeftOuter => true,
+                _ => {
+                    return Err(PipelineError::JoinError(JoinError::UnsupportedAsOfJoinType).into())
+                }
+            };
+            if !constraint.range_conditions.is_empty() || !constraint.residual.is_empty() {
                 return Err(
-                    PipelineError::JoinError(JoinError::UnsupportedJoinConstraintType).into(),
-                )
+                    PipelineError::JoinError(JoinError::UnsupportedAsOfJoinConstraint).into(),
+                );
             }
-        };
-
-        let mut left_schema = input_schemas
-            .get(&LEFT_JOIN_PORT)
-            .ok_or(PipelineError::InternalError(
-                "Invalid Product".to_string().into(),
-            ))?
-            .clone();
-        if let Some(left_table_name) = &self.left {
-            left_schema = extend_schema_source_def(&left_schema, left_table_name);
-        }
-
-        let mut right_schema = input_schemas
-            .get(&RIGHT_JOIN_PORT)
-            .ok_or(PipelineError::InternalError(
-                "Invalid Product".to_string().into(),
-            ))?
-            .clone();
-        if let Some(right_table_name) = &self.right {
-            right_schema = extend_schema_source_def(&right_schema, right_table_name);
+            let version_column = get_version_column(&as_of.version_column, &right_schema)
+                .map_err(PipelineError::JoinError)?;
+            let time = ExpressionBuilder::new(left_schema.fields.len(), self.runtime.clone())
+                .build(false, &as_of.time, &left_schema, &self.udfs)
+                .await?;
+            return Ok(Box::new(AsOfJoinProcessor::new(
+                left_outer,
+                (
+                    constraint.left_join_key_indexes,
+                    constraint.right_join_key_indexes,
+                ),
+                (time, version_column),
+                (left_schema, &right_schema),
+                self.enable_probabilistic_optimizations,
+                checkpoint_data,
+                &state_store,
+            )?));
         }
 
-        let (left_join_key_indexes, right_join_key_indexes) =
-            parse_join_constraint(expression, &left_schema, &right_schema)?;
+        let residual = match constraint.residual_expression() {
+            Some(expression) => {
+                let schema = append_schema(&left_schema, &right_schema);
+                let expression = ExpressionBuilder::new(schema.fields.len(), self.runtime.clone())
+                    .build(false, &expression, &schema, &self.udfs)
+                    .await?;
+                Some(ResidualPredicate { expression, schema })
+            }
+            None => None,
+        };
+        let condition = JoinCondition {
+            left_join_key_indexes: constraint.left_join_key_indexes,
+            right_join_key_indexes: constraint.right_join_key_indexes,
+            range_conditions: constraint.range_conditions,
+            residual,
+        };
 
-        let join_operator = JoinOperator::new(
+        let mut join_operator = JoinOperator::new(
             join_type,
-            (left_join_key_indexes, right_join_key_indexes),
+            condition,
             (&left_schema, &right_schema),
             self.enable_probabilistic_optimizations,
+            self.max_cross_join_records,
+            &state_store,
         )?;
+        if let Some(data) = checkpoint_data {
+            join_operator.restore_state(&data)?;
+        }
 
         Ok(Box::new(ProductProcessor::new(
             self.id.clone(),
             join_operator,
         )))
     }
+
+    async fn partitioner(
+        &self,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        let plan = self.plan(input_schemas)?;
+        // Cross joins and joins on non-equi conditions only have an empty join key,
+        // which would send all records to one instance.
+        if plan.constraint.left_join_key_indexes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(JoinKeyPartitioner {
+            left_join_key_indexes: plan.constraint.left_join_key_indexes,
+            right_join_key_indexes: plan.constraint.right_join_key_indexes,
+        })))
+    }
+}
+
+/// Shards a join by its key, so matching records of both sides meet in the same instance.
+#[derive(Debug)]
+struct JoinKeyPartitioner {
+    left_join_key_indexes: Vec<usize>,
+    right_join_key_indexes: Vec<usize>,
+}
+
+impl Partitioner for JoinKeyPartitioner {
+    fn partition_hash(&mut self, port: PortHandle, record: &Record) -> Result<u64, BoxedError> {
+        let join_key_indexes = if port == LEFT_JOIN_PORT {
+            &self.left_join_key_indexes
+        } else {
+            &self.right_join_key_indexes
+        };
+        Ok(get_record_hash(
+            join_key_indexes.iter().map(|index| &record.values[*index]),
+        ))
+    }
+}
+
+/// Returns the index of the timestamp column a table joined `FOR SYSTEM_TIME AS OF` declares its versions valid from.
+fn get_version_column(
+    version_column: &Option<SqlExpr>,
+    right_schema: &Schema,
+) -> Result<usize, JoinError> {
+    let (index, name) = match version_column {
+        None => return Err(JoinError::MissingAsOfVersionColumn),
+        Some(SqlExpr::Identifier(ident)) => (
+            get_field_index(&[ident.clone()], right_schema)?,
+            ident.value.clone(),
+        ),
+        Some(SqlExpr::CompoundIdentifier(ident)) => (
+            get_field_index(ident, right_schema)?,
+            ExpressionBuilder::fullname_from_ident(ident),
+        ),
+        Some(expr) => return Err(JoinError::InvalidAsOfVersionColumn(expr.to_string())),
+    };
+    match index {
+        Some(index) if right_schema.fields[index].typ == FieldType::Timestamp => Ok(index),
+        _ => Err(JoinError::InvalidAsOfVersionColumn(name)),
+    }
 }
 
 fn append_schema(left_schema: &Schema, right_schema: &Schema) -> Schema {
@@ -190,76 +354,189 @@ fn append_schema(left_schema: &Schema, right_schema: &Schema) -> Schema {
     output_schema
 }
 
+/// The conditions of a JOIN ON constraint, split into the join key equalities and the rest.
+#[derive(Debug, Default)]
+struct ParsedJoinConstraint {
+    left_join_key_indexes: Vec<usize>,
+    right_join_key_indexes: Vec<usize>,
+    range_conditions: Vec<RangeCondition>,
+    /// The conditions that aren't equalities of a left and a right field, including the range conditions.
+    residual: Vec<SqlExpr>,
+}
+
+impl ParsedJoinConstraint {
+    fn residual_expression(&self) -> Option<SqlExpr> {
+        self.residual
+            .iter()
+            .cloned()
+            .reduce(|left, right| SqlExpr::BinaryOp {
+                left: Box::new(left),
+                op: BinaryOperator::And,
+                right: Box::new(right),
+            })
+    }
+}
+
 fn parse_join_constraint(
-    expression: &dozer_sql_expression::sqlparser::ast::Expr,
+    expression: &SqlExpr,
     left_join_table: &Schema,
     right_join_table: &Schema,
-) -> Result<(Vec<usize>, Vec<usize>), JoinError> {
+    parsed: &mut ParsedJoinConstraint,
+) -> Result<(), JoinError> {
     match expression {
+        SqlExpr::Nested(expression) => {
+            parse_join_constraint(expression, left_join_table, right_join_table, parsed)
+        }
         SqlExpr::BinaryOp {
-            ref left,
-            op,
-            ref right,
-        } => match op {
-            BinaryOperator::And => {
-                let (mut left_keys, mut right_keys) =
-                    parse_join_constraint(left, left_join_table, right_join_table)?;
-
-                let (mut left_keys_from_right, mut right_keys_from_right) =
-                    parse_join_constraint(right, left_join_table, right_join_table)?;
-                left_keys.append(&mut left_keys_from_right);
-                right_keys.append(&mut right_keys_from_right);
-
-                Ok((left_keys, right_keys))
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            parse_join_constraint(left, left_join_table, right_join_table, parsed)?;
+            parse_join_constraint(right, left_join_table, right_join_table, parsed)
+        }
+        SqlExpr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => {
+            match parse_field_pair(left, right, left_join_table, right_join_table)? {
+                Some((left_index, right_index, _)) => {
+                    parsed.left_join_key_indexes.push(left_index);
+                    parsed.right_join_key_indexes.push(right_index);
+                }
+                None => parsed.residual.push(expression.clone()),
             }
-            BinaryOperator::Eq => {
-                let mut left_key_indexes = vec![];
-                let mut right_key_indexes = vec![];
-
-                let (left_arr, right_arr) =
-                    parse_join_eq_expression(left, left_join_table, right_join_table)?;
-                left_key_indexes.extend(left_arr);
-                right_key_indexes.extend(right_arr);
+            Ok(())
+        }
+        SqlExpr::BinaryOp { left, op, right } => {
+            if let Some(comparison) = parse_comparison(op) {
+                add_range_condition(
+                    left,
+                    comparison,
+                    right,
+                    left_join_table,
+                    right_join_table,
+                    parsed,
+                )?;
+            }
+            parsed.residual.push(expression.clone());
+            Ok(())
+        }
+        SqlExpr::Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        } => {
+            add_range_condition(
+                low,
+                Comparison::LtEq,
+                expr,
+                left_join_table,
+                right_join_table,
+                parsed,
+            )?;
+            add_range_condition(
+                expr,
+                Comparison::LtEq,
+                high,
+                left_join_table,
+                right_join_table,
+                parsed,
+            )?;
+            parsed.residual.push(expression.clone());
+            Ok(())
+        }
+        _ => {
+            parsed.residual.push(expression.clone());
+            Ok(())
+        }
+    }
+}
 
-                let (left_arr, right_arr) =
-                    parse_join_eq_expression(right, left_join_table, right_join_table)?;
-                left_key_indexes.extend(left_arr);
-                right_key_indexes.extend(right_arr);
+fn parse_comparison(op: &BinaryOperator) -> Option<Comparison> {
+    match op {
+        BinaryOperator::Lt => Some(Comparison::Lt),
+        BinaryOperator::LtEq => Some(Comparison::LtEq),
+        BinaryOperator::Gt => Some(Comparison::Gt),
+        BinaryOperator::GtEq => Some(Comparison::GtEq),
+        _ => None,
+    }
+}
 
-                Ok((left_key_indexes, right_key_indexes))
-            }
-            _ => Err(JoinError::UnsupportedJoinConstraintOperator(op.to_string())),
-        },
-        _ => Err(JoinError::UnsupportedJoinConstraint(expression.to_string())),
+/// Adds `left <comparison> right` as a range condition, if it compares a left and a right field of the same type.
+fn add_range_condition(
+    left: &SqlExpr,
+    comparison: Comparison,
+    right: &SqlExpr,
+    left_join_table: &Schema,
+    right_join_table: &Schema,
+    parsed: &mut ParsedJoinConstraint,
+) -> Result<(), JoinError> {
+    let Some((left_index, right_index, swapped)) =
+        parse_field_pair(left, right, left_join_table, right_join_table)?
+    else {
+        return Ok(());
+    };
+    if left_join_table.fields[left_index].typ != right_join_table.fields[right_index].typ {
+        return Ok(());
     }
+    parsed.range_conditions.push(RangeCondition {
+        left_index,
+        comparison: if swapped {
+            comparison.flip()
+        } else {
+            comparison
+        },
+        right_index,
+    });
+    Ok(())
 }
 
-fn parse_join_eq_expression(
-    expr: &SqlExpr,
+/// Returns the indexes of the left and the right field if `first` and `second` are fields of opposite sides,
+/// and whether `first` is the right field.
+fn parse_field_pair(
+    first: &SqlExpr,
+    second: &SqlExpr,
     left_join_table: &Schema,
     right_join_table: &Schema,
-) -> Result<(Vec<usize>, Vec<usize>), JoinError> {
-    let mut left_key_indexes = vec![];
-    let mut right_key_indexes = vec![];
-    let (left_keys, right_keys) = match expr.clone() {
-        SqlExpr::Identifier(ident) => parse_identifier(&[ident], left_join_table, right_join_table),
-        SqlExpr::CompoundIdentifier(ident) => {
-            parse_identifier(&ident, left_join_table, right_join_table)
+) -> Result<Option<(usize, usize, bool)>, JoinError> {
+    let (Some(first), Some(second)) = (
+        parse_field(first, left_join_table, right_join_table)?,
+        parse_field(second, left_join_table, right_join_table)?,
+    ) else {
+        return Ok(None);
+    };
+    Ok(match (first, second) {
+        ((Some(left_index), None), (None, Some(right_index))) => {
+            Some((left_index, right_index, false))
         }
-        _ => {
-            return Err(JoinError::UnsupportedJoinConstraint(
-                expr.clone().to_string(),
-            ))
+        ((None, Some(right_index)), (Some(left_index), None)) => {
+            Some((left_index, right_index, true))
         }
-    }?;
+        _ => None,
+    })
+}
 
-    match (left_keys, right_keys) {
-        (Some(left_key), None) => left_key_indexes.push(left_key),
-        (None, Some(right_key)) => right_key_indexes.push(right_key),
-        _ => return Err(JoinError::UnsupportedJoinConstraint("".to_string())),
+fn parse_field(
+    expr: &SqlExpr,
+    left_join_table: &Schema,
+    right_join_table: &Schema,
+) -> Result<Option<(Option<usize>, Option<usize>)>, JoinError> {
+    match expr {
+        SqlExpr::Identifier(ident) => Ok(Some(parse_identifier(
+            &[ident.clone()],
+            left_join_table,
+            right_join_table,
+        )?)),
+        SqlExpr::CompoundIdentifier(ident) => Ok(Some(parse_identifier(
+            ident,
+            left_join_table,
+            right_join_table,
+        )?)),
+        _ => Ok(None),
     }
-
-    Ok((left_key_indexes, right_key_indexes))
 }
 
 fn parse_identifier(
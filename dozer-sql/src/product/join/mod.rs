@@ -1,5 +1,6 @@
 use crate::errors::JoinError;
 
+mod as_of;
 pub mod factory;
 
 pub(crate) mod operator;
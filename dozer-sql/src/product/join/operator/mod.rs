@@ -1,8 +1,12 @@
-use dozer_types::types::{Record, Schema, Timestamp};
+use bincode::error::EncodeError;
+use dozer_core::state_store::StateStore;
+use dozer_sql_expression::execution::Expression;
+use dozer_types::types::{Field, Record, Schema, Timestamp};
 
 use crate::errors::JoinError;
 
-use self::table::{JoinKey, JoinTable};
+use self::range::{index_field, lookup_range, RangeCondition};
+use self::table::{JoinKey, JoinTable, JoinTableState, MatchingRecords};
 
 use super::JoinResult;
 
@@ -17,6 +21,18 @@ pub enum JoinType {
     Inner,
     LeftOuter,
     RightOuter,
+    FullOuter,
+    /// Joins every record with all records of the other side, on an empty join key.
+    Cross,
+}
+
+impl JoinBranch {
+    fn other(self) -> Self {
+        match self {
+            JoinBranch::Left => JoinBranch::Right,
+            JoinBranch::Right => JoinBranch::Left,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,60 +41,143 @@ pub enum JoinAction {
     Delete,
 }
 
+/// When a left and a right record join.
+#[derive(Debug)]
+pub struct JoinCondition {
+    pub left_join_key_indexes: Vec<usize>,
+    pub right_join_key_indexes: Vec<usize>,
+    /// Comparisons of a left and a right field, which both sides keep a sorted index for.
+    pub range_conditions: Vec<RangeCondition>,
+    /// The conditions other than the join key equalities, checked on the joined record.
+    pub residual: Option<ResidualPredicate>,
+}
+
+#[derive(Debug)]
+pub struct ResidualPredicate {
+    pub expression: Expression,
+    /// The schema of the joined records.
+    pub schema: Schema,
+}
+
+impl ResidualPredicate {
+    fn evaluate(&mut self, join_record: &Record) -> Result<bool, JoinError> {
+        Ok(self.expression.evaluate(join_record, &self.schema)? == Field::Boolean(true))
+    }
+}
+
+pub(crate) mod range;
 mod table;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct JoinOperator {
     join_type: JoinType,
 
     left: JoinTable,
     right: JoinTable,
+
+    range_conditions: Vec<RangeCondition>,
+    residual: Option<ResidualPredicate>,
+
+    /// How many records either side of a cross join may keep.
+    max_cross_join_records: usize,
+    /// Whether the join has no equality or range condition to look records up by, so every record is checked
+    /// against all records of the other side, like in a cross join.
+    unkeyed: bool,
 }
 
 impl JoinOperator {
     pub fn new(
         join_type: JoinType,
-        (left_join_key_indexes, right_join_key_indexes): (Vec<usize>, Vec<usize>),
+        condition: JoinCondition,
         (left_schema, right_schema): (&Schema, &Schema),
         enable_probabilistic_optimizations: bool,
+        max_cross_join_records: usize,
+        state_store: &StateStore,
     ) -> Result<Self, JoinError> {
         let accurate_keys = !enable_probabilistic_optimizations;
-        let left = JoinTable::new(left_schema, left_join_key_indexes, accurate_keys)?;
-        let right = JoinTable::new(right_schema, right_join_key_indexes, accurate_keys)?;
+        let unkeyed =
+            condition.left_join_key_indexes.is_empty() && condition.range_conditions.is_empty();
+        let left = JoinTable::new(
+            left_schema,
+            condition.left_join_key_indexes,
+            index_field(&condition.range_conditions, JoinBranch::Left),
+            accurate_keys,
+            state_store,
+        )?;
+        let right = JoinTable::new(
+            right_schema,
+            condition.right_join_key_indexes,
+            index_field(&condition.range_conditions, JoinBranch::Right),
+            accurate_keys,
+            state_store,
+        )?;
         Ok(Self {
             join_type,
             left,
             right,
+            range_conditions: condition.range_conditions,
+            residual: condition.residual,
+            max_cross_join_records,
+            unkeyed,
         })
     }
 
+    /// Encodes the records of both sides, so the operator can be restored with [JoinOperator::restore_state].
+    pub fn encode_state(&self) -> Result<Vec<u8>, EncodeError> {
+        bincode::encode_to_vec(
+            (self.left.state(), self.right.state()),
+            bincode::config::legacy(),
+        )
+    }
+
+    /// Returns the number of records both sides keep.
+    pub fn state_size(&self) -> usize {
+        self.left.record_count() + self.right.record_count()
+    }
+
+    pub fn restore_state(&mut self, data: &[u8]) -> Result<(), JoinError> {
+        let ((left, right), _): ((JoinTableState, JoinTableState), _) =
+            bincode::decode_from_slice(data, bincode::config::legacy())
+                .map_err(|e| JoinError::Deserialization(e.into()))?;
+        self.left.restore_state(left)?;
+        self.right.restore_state(right)
+    }
+
     fn inner_join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
         default_if_no_match: bool,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> Result<Vec<(JoinAction, Record)>, JoinError> {
         let table = match record_branch {
             JoinBranch::Left => &self.right,
             JoinBranch::Right => &self.left,
         };
         let join_records = create_join_records_fn(record, record_branch);
 
-        table
-            .get_matching_records(join_key, default_if_no_match)
-            .map(|matching_record| (action, join_records(matching_record)))
-            .collect()
+        Ok(get_matching_records(
+            table,
+            &self.range_conditions,
+            self.residual.as_mut(),
+            join_key,
+            record,
+            record_branch,
+            default_if_no_match,
+        )?
+        .iter()
+        .map(|matching_record| (action, join_records(matching_record)))
+        .collect())
     }
 
     fn outer_join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> Result<Vec<(JoinAction, Record)>, JoinError> {
         let (table_to_match, table_of_record) = match record_branch {
             JoinBranch::Left => (&self.right, &self.left),
             JoinBranch::Right => (&self.left, &self.right),
@@ -88,30 +187,50 @@ impl JoinOperator {
             create_join_records_fn(table_of_record.default_record(), record_branch);
 
         // We need to query from the table where this record is from:
-        // - For JoinAction::Insert, did this join key exist before this insert? If not, we need to remove the default record.
-        // - For JoinAction::Delete, does this join key exist after this delete? If not, we need to insert the default record.
-        let need_to_act_on_default_record = match action {
-            JoinAction::Insert => {
-                // Because this record is already inserted, the join key didn't exist before this insert iif the matching count is now 1.
-                table_of_record
-                    .get_matching_records(join_key, false)
-                    .take(2)
-                    .count()
-                    == 1
-            }
-            JoinAction::Delete => {
-                table_of_record
-                    .get_matching_records(join_key, false)
-                    .take(1)
-                    .count()
-                    == 0
-            }
+        // - For JoinAction::Insert, did the matching record join with any record before this insert? If not, we need to remove the default record.
+        // - For JoinAction::Delete, does the matching record join with any record after this delete? If not, we need to insert the default record.
+        // Without conditions other than the join key, all matching records join with the records of this join key.
+        let has_conditions = !self.range_conditions.is_empty() || self.residual.is_some();
+        let key_need_to_act_on_default_record = if has_conditions {
+            false
+        } else {
+            need_to_act_on_default_record(
+                action,
+                &table_of_record.get_matching_records(join_key, None, false)?,
+            )
         };
 
         let mut output_records = vec![];
-        for matching_record in table_to_match.get_matching_records(join_key, false) {
+        for matching_record in get_matching_records(
+            table_to_match,
+            &self.range_conditions,
+            self.residual.as_mut(),
+            join_key,
+            record,
+            record_branch,
+            false,
+        )?
+        .iter()
+        {
             let join_record = join_records(matching_record);
 
+            let need_to_act_on_default_record = if has_conditions {
+                need_to_act_on_default_record(
+                    action,
+                    &get_matching_records(
+                        table_of_record,
+                        &self.range_conditions,
+                        self.residual.as_mut(),
+                        join_key,
+                        matching_record,
+                        record_branch.other(),
+                        false,
+                    )?,
+                )
+            } else {
+                key_need_to_act_on_default_record
+            };
+
             if need_to_act_on_default_record {
                 let default_join_record = default_join_records(matching_record);
                 match action {
@@ -131,16 +250,32 @@ impl JoinOperator {
             }
         }
 
-        output_records
+        Ok(output_records)
+    }
+
+    /// Like [JoinOperator::outer_join], and also pads the record with the default record of the other side
+    /// if the other side has no matching records.
+    fn full_outer_join(
+        &mut self,
+        action: JoinAction,
+        join_key: &JoinKey,
+        record: &Record,
+        record_branch: JoinBranch,
+    ) -> Result<Vec<(JoinAction, Record)>, JoinError> {
+        let mut output_records = self.outer_join(action, join_key, record, record_branch)?;
+        if output_records.is_empty() {
+            output_records = self.inner_join(action, join_key, record, record_branch, true)?;
+        }
+        Ok(output_records)
     }
 
     fn join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> Result<Vec<(JoinAction, Record)>, JoinError> {
         match (&self.join_type, record_branch) {
             (JoinType::Inner, _) => self.inner_join(action, join_key, record, record_branch, false),
             (JoinType::LeftOuter, JoinBranch::Left) => {
@@ -155,6 +290,10 @@ impl JoinOperator {
             (JoinType::RightOuter, JoinBranch::Right) => {
                 self.inner_join(action, join_key, record, JoinBranch::Right, true)
             }
+            (JoinType::FullOuter, _) => {
+                self.full_outer_join(action, join_key, record, record_branch)
+            }
+            (JoinType::Cross, _) => self.inner_join(action, join_key, record, record_branch, false),
         }
     }
 
@@ -163,10 +302,10 @@ impl JoinOperator {
         from: JoinBranch,
         old: &Record,
         old_decoded: &Record,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
         let join_key = match from {
-            JoinBranch::Left => self.left.remove(old_decoded),
-            JoinBranch::Right => self.right.remove(old_decoded),
+            JoinBranch::Left => self.left.remove(old_decoded)?,
+            JoinBranch::Right => self.right.remove(old_decoded)?,
         };
 
         self.join(JoinAction::Delete, &join_key, old, from)
@@ -178,17 +317,89 @@ impl JoinOperator {
         new: &Record,
         new_decoded: &Record,
     ) -> JoinResult<Vec<(JoinAction, Record)>> {
+        if self.unkeyed {
+            let table = match from {
+                JoinBranch::Left => &self.left,
+                JoinBranch::Right => &self.right,
+            };
+            if table.record_count() >= self.max_cross_join_records {
+                return Err(JoinError::CrossJoinTooLarge(self.max_cross_join_records));
+            }
+        }
+
         let join_key = match from {
             JoinBranch::Left => self.left.insert(new.clone(), new_decoded)?,
             JoinBranch::Right => self.right.insert(new.clone(), new_decoded)?,
         };
 
-        Ok(self.join(JoinAction::Insert, &join_key, new, from))
+        self.join(JoinAction::Insert, &join_key, new, from)
     }
 
-    pub fn evict_index(&mut self, now: &Timestamp) {
-        self.left.evict_index(now);
-        self.right.evict_index(now);
+    /// Removes all records of `from`, retracting the join records they are part of.
+    pub fn truncate(&mut self, from: JoinBranch) -> JoinResult<Vec<(JoinAction, Record)>> {
+        let records = match from {
+            JoinBranch::Left => self.left.records()?,
+            JoinBranch::Right => self.right.records()?,
+        };
+        let mut output_records = vec![];
+        for record in records {
+            output_records.extend(self.delete(from, &record, &record)?);
+        }
+        match from {
+            JoinBranch::Left => self.left.clear()?,
+            JoinBranch::Right => self.right.clear()?,
+        }
+        Ok(output_records)
+    }
+
+    pub fn evict_index(&mut self, now: &Timestamp) -> JoinResult<()> {
+        self.left.evict_index(now)?;
+        self.right.evict_index(now)
+    }
+}
+
+/// Returns the records of `table` that `record` of the other side joins with: those with `join_key`
+/// that satisfy the range conditions and the residual predicate.
+fn get_matching_records<'a>(
+    table: &'a JoinTable,
+    range_conditions: &[RangeCondition],
+    residual: Option<&mut ResidualPredicate>,
+    join_key: &JoinKey,
+    record: &Record,
+    record_branch: JoinBranch,
+    default_if_no_match: bool,
+) -> Result<MatchingRecords<'a>, JoinError> {
+    let range = table
+        .range_index_field()
+        .map(|field| lookup_range(range_conditions, field, record_branch.other(), record));
+    let Some(residual) = residual else {
+        return table.get_matching_records(join_key, range.as_ref(), default_if_no_match);
+    };
+
+    let join_records = create_join_records_fn(record, record_branch);
+    let mut records = vec![];
+    for matching_record in table
+        .get_matching_records(join_key, range.as_ref(), false)?
+        .iter()
+    {
+        if residual.evaluate(&join_records(matching_record))? {
+            records.push(matching_record.clone());
+        }
+    }
+    Ok(if !records.is_empty() {
+        MatchingRecords::Records(records)
+    } else if default_if_no_match {
+        MatchingRecords::Default(table.default_record())
+    } else {
+        MatchingRecords::Empty
+    })
+}
+
+fn need_to_act_on_default_record(action: JoinAction, matching_records: &MatchingRecords) -> bool {
+    match action {
+        // Because this record is already inserted, the matching record didn't join with any record before this insert iif it now joins with one.
+        JoinAction::Insert => matching_records.iter().take(2).count() == 1,
+        JoinAction::Delete => matching_records.iter().take(1).count() == 0,
     }
 }
 
@@ -0,0 +1,190 @@
+use std::ops::Bound;
+
+use dozer_types::types::{Field, Record};
+
+use super::JoinBranch;
+
+/// A range of values of a field. Bounds compare with the order of [Field].
+pub type FieldRange = (Bound<Field>, Bound<Field>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Comparison {
+    /// The comparison with its operands swapped.
+    pub fn flip(self) -> Self {
+        match self {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::LtEq => Comparison::GtEq,
+            Comparison::Gt => Comparison::Lt,
+            Comparison::GtEq => Comparison::LtEq,
+        }
+    }
+}
+
+/// `left[left_index] <comparison> right[right_index]`, a join condition a sorted index can look up.
+///
+/// Both fields must have the same type, as fields of different types don't order by value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeCondition {
+    pub left_index: usize,
+    pub comparison: Comparison,
+    pub right_index: usize,
+}
+
+impl RangeCondition {
+    /// Returns the field of `branch`, how it compares to the field of the other side, and the field of the other side.
+    fn on(&self, branch: JoinBranch) -> (usize, Comparison, usize) {
+        match branch {
+            JoinBranch::Left => (self.left_index, self.comparison, self.right_index),
+            JoinBranch::Right => (self.right_index, self.comparison.flip(), self.left_index),
+        }
+    }
+}
+
+/// The field the table of `branch` sorts its records by, which is its field in the first range condition.
+pub fn index_field(conditions: &[RangeCondition], branch: JoinBranch) -> Option<usize> {
+    conditions.first().map(|condition| condition.on(branch).0)
+}
+
+/// Returns the values of field `index_field` of the table of `table_branch` that can satisfy the range conditions
+/// with `record`, a record of the other side.
+pub fn lookup_range(
+    conditions: &[RangeCondition],
+    index_field: usize,
+    table_branch: JoinBranch,
+    record: &Record,
+) -> FieldRange {
+    let (mut start, mut end) = (Bound::Unbounded, Bound::Unbounded);
+    for condition in conditions {
+        let (field, comparison, other_field) = condition.on(table_branch);
+        if field != index_field {
+            continue;
+        }
+        let value = record.values[other_field].clone();
+        match comparison {
+            Comparison::Lt => end = min_end(end, Bound::Excluded(value)),
+            Comparison::LtEq => end = min_end(end, Bound::Included(value)),
+            Comparison::Gt => start = max_start(start, Bound::Excluded(value)),
+            Comparison::GtEq => start = max_start(start, Bound::Included(value)),
+        }
+    }
+    (start, end)
+}
+
+/// Whether no value is in `range`.
+pub fn is_empty_range((start, end): &FieldRange) -> bool {
+    match (start, end) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
+
+fn max_start(left: Bound<Field>, right: Bound<Field>) -> Bound<Field> {
+    match (&left, &right) {
+        (Bound::Unbounded, _) => right,
+        (_, Bound::Unbounded) => left,
+        (
+            Bound::Included(left_value) | Bound::Excluded(left_value),
+            Bound::Included(right_value) | Bound::Excluded(right_value),
+        ) => {
+            if left_value > right_value || (left_value == right_value && is_excluded(&left)) {
+                left
+            } else {
+                right
+            }
+        }
+    }
+}
+
+fn min_end(left: Bound<Field>, right: Bound<Field>) -> Bound<Field> {
+    match (&left, &right) {
+        (Bound::Unbounded, _) => right,
+        (_, Bound::Unbounded) => left,
+        (
+            Bound::Included(left_value) | Bound::Excluded(left_value),
+            Bound::Included(right_value) | Bound::Excluded(right_value),
+        ) => {
+            if left_value < right_value || (left_value == right_value && is_excluded(&left)) {
+                left
+            } else {
+                right
+            }
+        }
+    }
+}
+
+fn is_excluded(bound: &Bound<Field>) -> bool {
+    matches!(bound, Bound::Excluded(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_range() {
+        // left.0 BETWEEN right.0 AND right.1
+        let conditions = [
+            RangeCondition {
+                left_index: 0,
+                comparison: Comparison::GtEq,
+                right_index: 0,
+            },
+            RangeCondition {
+                left_index: 0,
+                comparison: Comparison::LtEq,
+                right_index: 1,
+            },
+        ];
+        assert_eq!(index_field(&conditions, JoinBranch::Left), Some(0));
+        assert_eq!(index_field(&conditions, JoinBranch::Right), Some(0));
+
+        // A right record looks up the left records between its fields.
+        let right_record = Record::new(vec![Field::Int(10), Field::Int(20)]);
+        assert_eq!(
+            lookup_range(&conditions, 0, JoinBranch::Left, &right_record),
+            (
+                Bound::Included(Field::Int(10)),
+                Bound::Included(Field::Int(20))
+            )
+        );
+        // A left record looks up the right records starting before it, and the residual checks where they end.
+        let left_record = Record::new(vec![Field::Int(15)]);
+        assert_eq!(
+            lookup_range(&conditions, 0, JoinBranch::Right, &left_record),
+            (Bound::Unbounded, Bound::Included(Field::Int(15)))
+        );
+    }
+
+    #[test]
+    fn test_tightest_bounds() {
+        assert_eq!(
+            max_start(
+                Bound::Included(Field::Int(1)),
+                Bound::Excluded(Field::Int(1))
+            ),
+            Bound::Excluded(Field::Int(1))
+        );
+        assert_eq!(
+            min_end(Bound::Included(Field::Int(1)), Bound::Unbounded),
+            Bound::Included(Field::Int(1))
+        );
+        assert!(is_empty_range(&(
+            Bound::Excluded(Field::Int(1)),
+            Bound::Excluded(Field::Int(1))
+        )));
+        assert!(!is_empty_range(&(
+            Bound::Included(Field::Int(1)),
+            Bound::Included(Field::Int(1))
+        )));
+    }
+}
@@ -1,17 +1,18 @@
 use std::{
-    collections::{
-        hash_map::{self, Values},
-        HashMap,
-    },
-    iter::{once, Flatten, Once},
+    borrow::Cow,
+    collections::{hash_map, HashMap, HashSet},
+    ops::{Bound, RangeBounds},
 };
 
+use dozer_core::state_store::{SortKey, SortedStateMap, StateMap, StateStore};
 use dozer_types::{
+    bincode::{self, serde::Compat},
     chrono,
     types::{Field, Record, Schema, Timestamp},
 };
 use linked_hash_map::LinkedHashMap;
 
+use super::range::{is_empty_range, FieldRange};
 use crate::{
     errors::JoinError,
     utils::record_hashtable_key::{get_record_hash, RecordKey},
@@ -19,22 +20,36 @@ use crate::{
 
 pub type JoinKey = RecordKey;
 type IndexKey = (JoinKey, u64); // (join_key, primary_key)
+type RecordMap = HashMap<u64, Vec<Record>>; // primary_key -> records
 
-#[derive(Debug, Clone)]
+/// What a [JoinTable] checkpoints: its records, its eviction index and its record count.
+/// Disk backed records are left to the database checkpoint, so the count is checkpointed on its own.
+pub type JoinTableState = (
+    Vec<(JoinKey, RecordMap)>,
+    Compat<LinkedHashMap<Timestamp, Vec<IndexKey>>>,
+    u64,
+);
+
+#[derive(Debug)]
 pub struct JoinTable {
     join_key_indexes: Vec<usize>,
     primary_key_indexes: Vec<usize>,
     default_record: Record,
-    map: HashMap<JoinKey, HashMap<u64, Vec<Record>>>,
+    map: StateMap<JoinKey, RecordMap>,
     lifetime_map: LinkedHashMap<Timestamp, Vec<IndexKey>>,
+    range_index: Option<RangeIndex>,
     accurate_keys: bool,
+    /// The number of records in `map`, kept as records come and go so it needn't be counted.
+    record_count: usize,
 }
 
 impl JoinTable {
     pub fn new(
         schema: &Schema,
         join_key_indexes: Vec<usize>,
+        range_index_field: Option<usize>,
         accurate_keys: bool,
+        state_store: &StateStore,
     ) -> Result<Self, JoinError> {
         let primary_key_indexes = if schema.primary_index.is_empty() {
             (0..schema.fields.len()).collect()
@@ -46,24 +61,85 @@ impl JoinTable {
             join_key_indexes,
             primary_key_indexes,
             default_record: Record::nulls_from_schema(schema),
-            map: Default::default(),
+            map: state_store.create_map(),
             lifetime_map: Default::default(),
+            range_index: range_index_field
+                .map(|field_index| RangeIndex::new(field_index, state_store)),
             accurate_keys,
+            record_count: 0,
         })
     }
 
-    pub fn get_matching_records<'a>(
-        &'a self,
+    /// The state to checkpoint, which decodes as a [JoinTableState].
+    pub fn state(&self) -> impl bincode::Encode + '_ {
+        (
+            &self.map,
+            Compat(&self.lifetime_map),
+            self.record_count as u64,
+        )
+    }
+
+    pub fn restore_state(
+        &mut self,
+        (records, lifetime_map, record_count): JoinTableState,
+    ) -> Result<(), JoinError> {
+        for (join_key, record_map) in records {
+            if let Some(range_index) = &mut self.range_index {
+                for (primary_key, records) in &record_map {
+                    for record in records {
+                        range_index.insert(&join_key, record, *primary_key)?;
+                    }
+                }
+            }
+            self.map.insert(join_key, record_map)?;
+        }
+        self.lifetime_map = lifetime_map.0;
+        self.record_count = record_count as usize;
+        Ok(())
+    }
+
+    /// The field the table sorts its records by, if any.
+    pub fn range_index_field(&self) -> Option<usize> {
+        self.range_index
+            .as_ref()
+            .map(|range_index| range_index.field_index)
+    }
+
+    /// Returns the records with `join_key`, and if `range` is given, with the value of the range index field in it.
+    pub fn get_matching_records(
+        &self,
         join_key: &JoinKey,
+        range: Option<&FieldRange>,
         default_if_no_match: bool,
-    ) -> MatchingRecords<'a> {
-        if let Some(records_map) = self.map.get(join_key) {
-            MatchingRecords::Values(records_map.values().flatten())
+    ) -> Result<MatchingRecords<'_>, JoinError> {
+        let matching_records = match (range, &self.range_index) {
+            (Some(range), Some(range_index)) => {
+                let primary_keys = range_index.primary_keys(join_key, range)?;
+                match self.map.get(join_key)? {
+                    Some(records_map) if !primary_keys.is_empty() => {
+                        let records = primary_keys
+                            .iter()
+                            .filter_map(|primary_key| records_map.get(primary_key))
+                            .flatten()
+                            .filter(|record| {
+                                range.contains(&record.values[range_index.field_index])
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        (!records.is_empty()).then_some(MatchingRecords::Records(records))
+                    }
+                    _ => None,
+                }
+            }
+            _ => self.map.get(join_key)?.map(MatchingRecords::Values),
+        };
+        Ok(if let Some(matching_records) = matching_records {
+            matching_records
         } else if default_if_no_match {
-            MatchingRecords::Default(once(&self.default_record))
+            MatchingRecords::Default(&self.default_record)
         } else {
             MatchingRecords::Empty
-        }
+        })
     }
 
     pub fn default_record(&self) -> &Record {
@@ -95,34 +171,74 @@ impl JoinTable {
                 .push((join_key.clone(), primary_key));
         }
 
-        self.map
-            .entry(join_key.clone())
-            .or_default()
-            .entry(primary_key)
-            .or_default()
-            .push(record);
+        if let Some(range_index) = &mut self.range_index {
+            range_index.insert(&join_key, &record, primary_key)?;
+        }
+        self.map.update(join_key.clone(), |record_map| {
+            record_map
+                .get_or_insert_with(Default::default)
+                .entry(primary_key)
+                .or_default()
+                .push(record)
+        })?;
+        self.record_count += 1;
 
         Ok(join_key)
     }
 
-    pub fn remove(&mut self, record: &Record) -> JoinKey {
+    pub fn remove(&mut self, record: &Record) -> Result<JoinKey, JoinError> {
         let join_key = self.get_join_key(record);
-        if let hash_map::Entry::Occupied(record_map) = self.map.entry(join_key.clone()) {
-            let primary_key = get_record_key_hash(record, &self.primary_key_indexes);
-            remove_record_using_primary_key(record_map, primary_key);
+        let primary_key = get_record_key_hash(record, &self.primary_key_indexes);
+        let removed = self.map.update(join_key.clone(), |record_map| {
+            remove_record_using_primary_key(record_map, primary_key)
+        })?;
+        if let Some(removed) = removed {
+            self.record_count -= 1;
+            if let Some(range_index) = &mut self.range_index {
+                range_index.remove(&join_key, &removed, primary_key)?;
+            }
+        }
+        Ok(join_key)
+    }
+
+    pub fn record_count(&self) -> usize {
+        self.record_count
+    }
+
+    /// Returns all records of the table.
+    pub fn records(&self) -> Result<Vec<Record>, JoinError> {
+        Ok(self
+            .map
+            .entries()?
+            .into_iter()
+            .flat_map(|(_, record_map)| record_map.into_values().flatten())
+            .collect())
+    }
+
+    pub fn clear(&mut self) -> Result<(), JoinError> {
+        self.map.clear()?;
+        self.lifetime_map.clear();
+        self.record_count = 0;
+        if let Some(range_index) = &mut self.range_index {
+            range_index.keys.clear()?;
         }
-        join_key
+        Ok(())
     }
 
-    pub fn evict_index(&mut self, now: &Timestamp) {
+    pub fn evict_index(&mut self, now: &Timestamp) -> Result<(), JoinError> {
         let mut keys_to_remove = vec![];
         for (eviction_instant, join_index_keys) in self.lifetime_map.iter() {
             if eviction_instant <= now {
                 keys_to_remove.push(*eviction_instant);
                 for (join_key, primary_key) in join_index_keys {
-                    if let hash_map::Entry::Occupied(record_map) = self.map.entry(join_key.clone())
-                    {
-                        remove_record_using_primary_key(record_map, *primary_key);
+                    let removed = self.map.update(join_key.clone(), |record_map| {
+                        remove_record_using_primary_key(record_map, *primary_key)
+                    })?;
+                    if let Some(removed) = removed {
+                        self.record_count -= 1;
+                        if let Some(range_index) = &mut self.range_index {
+                            range_index.remove(join_key, &removed, *primary_key)?;
+                        }
                     }
                 }
             } else {
@@ -133,6 +249,7 @@ impl JoinTable {
         for key in keys_to_remove {
             self.lifetime_map.remove(&key);
         }
+        Ok(())
     }
 
     fn get_join_key(&self, record: &Record) -> JoinKey {
@@ -144,22 +261,134 @@ impl JoinTable {
     }
 }
 
+/// Primary keys of the records of every join key, sorted by the value of a field.
 #[derive(Debug)]
-pub enum MatchingRecords<'a> {
-    Values(Flatten<Values<'a, u64, Vec<Record>>>),
-    Default(Once<&'a Record>),
-    Empty,
+struct RangeIndex {
+    field_index: usize,
+    /// Keyed by the join key, the value and the primary key, with the primary key and the number of records.
+    keys: SortedStateMap<(u64, u64)>,
 }
 
-impl<'a> Iterator for MatchingRecords<'a> {
-    type Item = &'a Record;
+impl RangeIndex {
+    fn new(field_index: usize, state_store: &StateStore) -> Self {
+        Self {
+            field_index,
+            keys: state_store.create_sorted_map(),
+        }
+    }
+
+    fn key(&self, join_key: &JoinKey, record: &Record, primary_key: u64) -> SortKey {
+        let mut key = value_key(&join_key_prefix(join_key), &record.values[self.field_index]);
+        key.push(&Field::UInt(primary_key));
+        key
+    }
+
+    fn insert(
+        &mut self,
+        join_key: &JoinKey,
+        record: &Record,
+        primary_key: u64,
+    ) -> Result<(), JoinError> {
+        let key = self.key(join_key, record, primary_key);
+        let count = self.keys.get(&key)?.map_or(0, |entry| entry.1);
+        self.keys.insert(key, (primary_key, count + 1))?;
+        Ok(())
+    }
+
+    fn remove(
+        &mut self,
+        join_key: &JoinKey,
+        record: &Record,
+        primary_key: u64,
+    ) -> Result<(), JoinError> {
+        let key = self.key(join_key, record, primary_key);
+        match self.keys.get(&key)?.map_or(0, |entry| entry.1) {
+            0 => (),
+            1 => {
+                self.keys.remove(&key)?;
+            }
+            count => self.keys.insert(key, (primary_key, count - 1))?,
+        }
+        Ok(())
+    }
+
+    fn primary_keys(
+        &self,
+        join_key: &JoinKey,
+        range: &FieldRange,
+    ) -> Result<HashSet<u64>, JoinError> {
+        if is_empty_range(range) {
+            return Ok(HashSet::new());
+        }
+        let prefix = join_key_prefix(join_key);
+        let start = match &range.0 {
+            Bound::Included(value) => value_key(&prefix, value),
+            Bound::Excluded(value) => value_key(&prefix, value).prefix_end(),
+            Bound::Unbounded => prefix.clone(),
+        };
+        let end = match &range.1 {
+            Bound::Included(value) => value_key(&prefix, value).prefix_end(),
+            Bound::Excluded(value) => value_key(&prefix, value),
+            Bound::Unbounded => prefix.prefix_end(),
+        };
+        let mut primary_keys = HashSet::new();
+        for entry in self.keys.iter_from(&start) {
+            let (key, (primary_key, _)) = entry?;
+            if key >= end {
+                break;
+            }
+            primary_keys.insert(primary_key);
+        }
+        Ok(primary_keys)
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            MatchingRecords::Values(values) => values.next(),
-            MatchingRecords::Default(default) => default.next(),
-            MatchingRecords::Empty => None,
+fn join_key_prefix(join_key: &JoinKey) -> SortKey {
+    let mut key = SortKey::new();
+    match join_key {
+        JoinKey::Accurate(fields) => {
+            for field in fields {
+                key.push(field);
+            }
         }
+        JoinKey::Hash(hash) => key.push(&Field::UInt(*hash)),
+    }
+    key
+}
+
+fn value_key(prefix: &SortKey, value: &Field) -> SortKey {
+    let mut key = prefix.clone();
+    key.push(value);
+    key
+}
+
+#[derive(Debug)]
+pub enum MatchingRecords<'a> {
+    Values(Cow<'a, RecordMap>),
+    Records(Vec<Record>),
+    Default(&'a Record),
+    Empty,
+}
+
+impl MatchingRecords<'_> {
+    pub fn iter(&self) -> impl Iterator<Item = &Record> {
+        let values = match self {
+            MatchingRecords::Values(values) => Some(values.values().flatten()),
+            _ => None,
+        };
+        let records = match self {
+            MatchingRecords::Records(records) => Some(records.iter()),
+            _ => None,
+        };
+        let default = match self {
+            MatchingRecords::Default(default) => Some(*default),
+            _ => None,
+        };
+        values
+            .into_iter()
+            .flatten()
+            .chain(records.into_iter().flatten())
+            .chain(default)
     }
 }
 
@@ -175,30 +404,34 @@ fn get_record_key_fields(record: &Record, key_indexes: &[usize]) -> Vec<Field> {
         .collect()
 }
 
+/// Removes a record with `primary_key`, returning it.
 fn remove_record_using_primary_key(
-    mut record_map: hash_map::OccupiedEntry<JoinKey, HashMap<u64, Vec<Record>>>,
+    record_map: &mut Option<RecordMap>,
     primary_key: u64,
-) {
-    if let hash_map::Entry::Occupied(mut record_vec) = record_map.get_mut().entry(primary_key) {
-        record_vec.get_mut().pop();
+) -> Option<Record> {
+    let records = record_map.as_mut()?;
+    let mut removed = None;
+    if let hash_map::Entry::Occupied(mut record_vec) = records.entry(primary_key) {
+        removed = record_vec.get_mut().pop();
         if record_vec.get().is_empty() {
             record_vec.remove();
         }
     }
 
-    if record_map.get().is_empty() {
-        record_map.remove();
+    if records.is_empty() {
+        *record_map = None;
     }
+    removed
 }
 
 #[cfg(test)]
 mod tests {
+    use dozer_core::state_store::StateStoreOptions;
     use dozer_types::types::{FieldDefinition, FieldType};
 
     use super::*;
 
-    #[test]
-    fn test_match_insert_remove() {
+    fn test_match_insert_remove(state_store: &StateStore) {
         let schema = Schema {
             fields: vec![FieldDefinition {
                 name: "a".to_string(),
@@ -209,19 +442,48 @@ mod tests {
             }],
             primary_index: vec![0],
         };
-        let mut table = JoinTable::new(&schema, vec![0], true).unwrap();
+        let mut table = JoinTable::new(&schema, vec![0], None, true, state_store).unwrap();
+        let count = |table: &JoinTable, join_key: &JoinKey, default_if_no_match: bool| {
+            table
+                .get_matching_records(join_key, None, default_if_no_match)
+                .unwrap()
+                .iter()
+                .count()
+        };
 
         let record = Record::new(vec![Field::Int(1)]);
         let join_key = table.get_join_key(&record);
-        assert_eq!(table.get_matching_records(&join_key, true).count(), 1);
-        assert_eq!(table.get_matching_records(&join_key, false).count(), 0);
+        assert_eq!(count(&table, &join_key, true), 1);
+        assert_eq!(count(&table, &join_key, false), 0);
 
         let join_key = table.insert(record.clone(), &record).unwrap();
-        assert_eq!(table.get_matching_records(&join_key, true).count(), 1);
-        assert_eq!(table.get_matching_records(&join_key, false).count(), 1);
+        assert_eq!(count(&table, &join_key, true), 1);
+        assert_eq!(count(&table, &join_key, false), 1);
+        assert_eq!(table.record_count(), 1);
+
+        let join_key = table.remove(&record).unwrap();
+        assert_eq!(count(&table, &join_key, true), 1);
+        assert_eq!(count(&table, &join_key, false), 0);
+        assert_eq!(table.record_count(), 0);
+
+        // Removing a record that isn't there leaves the count alone.
+        table.remove(&record).unwrap();
+        assert_eq!(table.record_count(), 0);
+    }
 
-        let join_key = table.remove(&record);
-        assert_eq!(table.get_matching_records(&join_key, true).count(), 1);
-        assert_eq!(table.get_matching_records(&join_key, false).count(), 0);
+    #[test]
+    fn test_match_insert_remove_in_memory() {
+        test_match_insert_remove(&StateStore::default());
+    }
+
+    #[test]
+    fn test_match_insert_remove_on_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state_store = StateStore::open(&StateStoreOptions::OnDisk {
+            path: temp_dir.path().join("state"),
+            cache_size: 1024 * 1024,
+        })
+        .unwrap();
+        test_match_insert_remove(&state_store);
     }
 }
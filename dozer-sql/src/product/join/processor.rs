@@ -19,8 +19,10 @@ impl ProductProcessor {
         Self { join_operator }
     }
 
-    fn update_eviction_index(&mut self, lifetime: Lifetime) {
-        self.join_operator.evict_index(&lifetime.reference);
+    fn update_eviction_index(&mut self, lifetime: Lifetime) -> Result<(), PipelineError> {
+        self.join_operator
+            .evict_index(&lifetime.reference)
+            .map_err(PipelineError::JoinError)
     }
 }
 
@@ -42,14 +44,16 @@ impl Processor for ProductProcessor {
         let records = match op.op {
             Operation::Delete { old } => {
                 if let Some(lifetime) = old.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
-                self.join_operator.delete(from_branch, &old, &old)
+                self.join_operator
+                    .delete(from_branch, &old, &old)
+                    .map_err(PipelineError::JoinError)?
             }
             Operation::Insert { new } => {
                 if let Some(lifetime) = new.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
                 self.join_operator
@@ -58,10 +62,13 @@ impl Processor for ProductProcessor {
             }
             Operation::Update { old, new } => {
                 if let Some(lifetime) = old.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
-                let mut old_records = self.join_operator.delete(from_branch, &old, &old);
+                let mut old_records = self
+                    .join_operator
+                    .delete(from_branch, &old, &old)
+                    .map_err(PipelineError::JoinError)?;
 
                 let new_records = self
                     .join_operator
@@ -85,6 +92,10 @@ impl Processor for ProductProcessor {
                 }
                 return Ok(());
             }
+            Operation::Truncate => self
+                .join_operator
+                .truncate(from_branch)
+                .map_err(PipelineError::JoinError)?,
         };
 
         for (action, record) in records {
@@ -106,22 +117,39 @@ impl Processor for ProductProcessor {
 
         Ok(())
     }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data = self
+            .join_operator
+            .encode_state()
+            .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
+
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(Some(self.join_operator.state_size()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::{Arc, OnceLock};
 
-    use dozer_core::{event::EventHub, node::ProcessorFactory};
+    use dozer_core::event::EventHub;
+    use dozer_core::node::{PortHandle, ProcessorFactory};
+    use dozer_core::state_store::StateStore;
     use dozer_sql_expression::builder::NameOrAlias;
     use dozer_sql_expression::sqlparser::ast::JoinOperator as SqlJoinOperator;
     use dozer_types::types::{Field, FieldDefinition, Record, Schema};
+    use tokio::runtime::Runtime;
 
+    use crate::product::join::factory::JoinProcessorFactory;
     use crate::product::join::{
         factory::{LEFT_JOIN_PORT, RIGHT_JOIN_PORT},
         operator::JoinType,
     };
-    use crate::{product::join::factory::JoinProcessorFactory, tests::utils::get_select};
+    use crate::tests::utils::{create_test_runtime, get_select, open_node_state_store};
 
     use super::*;
 
@@ -167,6 +195,52 @@ mod tests {
         schema
     }
 
+    const MAX_CROSS_JOIN_RECORDS: usize = 2;
+
+    /// The runtime the factories build expressions with. It's never dropped, as dropping a runtime
+    /// within the runtime of a test panics.
+    fn expression_runtime() -> Arc<Runtime> {
+        static RUNTIME: OnceLock<Arc<Runtime>> = OnceLock::new();
+        RUNTIME.get_or_init(create_test_runtime).clone()
+    }
+
+    fn create_factory(kind: JoinType, condition: &str) -> JoinProcessorFactory {
+        let stmt = get_select(&format!(
+            "SELECT left.joinkey FROM left INNER JOIN right ON {condition}"
+        ))
+        .unwrap();
+        let join = &stmt.from[0].joins[0];
+        let join_op = join.join_operator.clone();
+        let SqlJoinOperator::Inner(constraint) = join_op else {
+            unreachable!()
+        };
+        let join_op = match kind {
+            JoinType::Inner => SqlJoinOperator::Inner(constraint),
+            JoinType::LeftOuter => SqlJoinOperator::LeftOuter(constraint),
+            JoinType::RightOuter => SqlJoinOperator::RightOuter(constraint),
+            JoinType::FullOuter => SqlJoinOperator::FullOuter(constraint),
+            JoinType::Cross => SqlJoinOperator::CrossJoin,
+        };
+        JoinProcessorFactory::new(
+            "test".into(),
+            Some(NameOrAlias("left".into(), None)),
+            Some(NameOrAlias("right".into(), None)),
+            join_op,
+            false,
+            MAX_CROSS_JOIN_RECORDS,
+            None,
+            vec![],
+            expression_runtime(),
+        )
+    }
+
+    fn schemas() -> HashMap<PortHandle, Schema> {
+        HashMap::from([
+            (LEFT_JOIN_PORT, create_schema("left")),
+            (RIGHT_JOIN_PORT, create_schema("right")),
+        ])
+    }
+
     enum JoinSide {
         Left,
         Right,
@@ -179,39 +253,38 @@ mod tests {
 
     impl Executor {
         async fn new(kind: JoinType) -> Self {
-            let left_schema = create_schema("left");
-            let right_schema = create_schema("right");
+            Self::from_checkpoint(kind, None, StateStore::default()).await
+        }
 
-            let stmt = get_select(
-                "SELECT left.joinkey FROM left INNER JOIN right ON left.joinkey = right.joinkey",
+        async fn from_checkpoint(
+            kind: JoinType,
+            checkpoint_data: Option<Vec<u8>>,
+            state_store: StateStore,
+        ) -> Self {
+            Self::with_condition(
+                kind,
+                "left.joinkey = right.joinkey",
+                checkpoint_data,
+                state_store,
             )
-            .unwrap();
-            let join = &stmt.from[0].joins[0];
-            let join_op = join.join_operator.clone();
-            let SqlJoinOperator::Inner(constraint) = join_op else {
-                unreachable!()
-            };
-            let join_op = match kind {
-                JoinType::Inner => SqlJoinOperator::Inner(constraint),
-                JoinType::LeftOuter => SqlJoinOperator::LeftOuter(constraint),
-                JoinType::RightOuter => SqlJoinOperator::RightOuter(constraint),
-            };
-            let factory = JoinProcessorFactory::new(
-                "test".into(),
-                Some(NameOrAlias("left".into(), None)),
-                Some(NameOrAlias("right".into(), None)),
-                join_op,
-                false,
-            );
+            .await
+        }
 
-            let schemas = [
-                (LEFT_JOIN_PORT, left_schema),
-                (RIGHT_JOIN_PORT, right_schema),
-            ]
-            .into_iter()
-            .collect();
+        async fn with_condition(
+            kind: JoinType,
+            condition: &str,
+            checkpoint_data: Option<Vec<u8>>,
+            state_store: StateStore,
+        ) -> Self {
+            let factory = create_factory(kind, condition);
             let processor = factory
-                .build(schemas, HashMap::new(), EventHub::new(1))
+                .build(
+                    schemas(),
+                    HashMap::new(),
+                    EventHub::new(1),
+                    checkpoint_data,
+                    state_store,
+                )
                 .await
                 .unwrap();
 
@@ -311,6 +384,175 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_inner_join_with_range_condition() {
+        let mut exec = Executor::with_condition(
+            JoinType::Inner,
+            "left.data = right.data AND left.joinkey > right.joinkey",
+            None,
+            StateStore::default(),
+        )
+        .await;
+
+        let (right_record, _) = exec.insert(JoinSide::Right, &[Field::UInt(5), Field::UInt(0)]);
+        let (_, ops) = exec.insert(JoinSide::Left, &[Field::UInt(3), Field::UInt(1)]);
+        assert_eq!(ops, &[]);
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(7), Field::UInt(0)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), right_record.clone())
+            }]
+        );
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(6), Field::UInt(0)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), other_right_record.clone())
+            }]
+        );
+
+        // An update moving the right record out of the range retracts its join record.
+        let (_, ops) = exec.update(
+            JoinSide::Right,
+            right_record.clone(),
+            &[Field::UInt(8), Field::UInt(0)],
+        );
+        assert_eq!(
+            ops,
+            &[Operation::Delete {
+                old: join_record(left_record.clone(), right_record)
+            }]
+        );
+        assert_eq!(
+            exec.delete(JoinSide::Left, left_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record, other_right_record)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_restored_from_checkpoint() {
+        let mut exec = Executor::new(JoinType::Inner).await;
+        let (left_record, _) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        let (right_record, _) = exec.insert(JoinSide::Right, &[Field::UInt(1), Field::UInt(2)]);
+
+        let checkpoint_data = exec.processor.serialize_state().unwrap();
+        assert!(checkpoint_data.is_some());
+        let mut exec =
+            Executor::from_checkpoint(JoinType::Inner, checkpoint_data, StateStore::default())
+                .await;
+
+        // Both sides' records survive the restore.
+        let (new_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record, new_right_record)
+            }]
+        );
+        let (new_left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(1), Field::UInt(4)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(new_left_record, right_record)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_with_state_on_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state_store = open_node_state_store(temp_dir.path(), None);
+        let mut exec = Executor::from_checkpoint(JoinType::Inner, None, state_store.clone()).await;
+
+        let (left_record, _) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), right_record.clone())
+            }]
+        );
+        let (other_right_record, _) =
+            exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record.clone(), right_record)
+            }]
+        );
+        assert_eq!(exec.processor.state_size().unwrap(), Some(2));
+
+        // A disk backed join restores from the database checkpoint taken along.
+        let checkpoint_data = exec.processor.serialize_state().unwrap();
+        let state_store_checkpoint = state_store.checkpoint(0).unwrap();
+        drop((exec, state_store));
+        let mut exec = Executor::from_checkpoint(
+            JoinType::Inner,
+            checkpoint_data,
+            open_node_state_store(temp_dir.path(), state_store_checkpoint),
+        )
+        .await;
+        assert_eq!(exec.processor.state_size().unwrap(), Some(2));
+        let (new_left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(4)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(new_left_record, other_right_record)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_join_with_state_on_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let condition = "left.joinkey < right.joinkey";
+        let state_store = open_node_state_store(temp_dir.path(), None);
+        let mut exec =
+            Executor::with_condition(JoinType::Inner, condition, None, state_store.clone()).await;
+
+        let (left_record, _) = exec.insert(JoinSide::Left, &[Field::UInt(3), Field::UInt(0)]);
+        let (_, ops) = exec.insert(JoinSide::Left, &[Field::UInt(10), Field::UInt(0)]);
+        assert_eq!(ops, &[]);
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(5), Field::UInt(0)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), right_record.clone())
+            }]
+        );
+
+        // The sorted index is restored from the database checkpoint too.
+        let checkpoint_data = exec.processor.serialize_state().unwrap();
+        let state_store_checkpoint = state_store.checkpoint(0).unwrap();
+        drop((exec, state_store));
+        let mut exec = Executor::with_condition(
+            JoinType::Inner,
+            condition,
+            checkpoint_data,
+            open_node_state_store(temp_dir.path(), state_store_checkpoint),
+        )
+        .await;
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(4), Field::UInt(0)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), other_right_record)
+            }]
+        );
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record, right_record)
+            }]
+        );
+    }
+
     #[tokio::test]
     async fn test_left_outer_join() {
         let mut exec = Executor::new(JoinType::LeftOuter).await;
@@ -375,6 +617,115 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_left_outer_join_with_between_condition() {
+        // The right records are the ranges [joinkey, data].
+        let condition = "left.data BETWEEN right.joinkey AND right.data";
+        let mut exec =
+            Executor::with_condition(JoinType::LeftOuter, condition, None, StateStore::default())
+                .await;
+
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(15)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), null_record.clone())
+            }]
+        );
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(10), Field::UInt(20)]);
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), null_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), right_record.clone())
+                }
+            ]
+        );
+        // The left record already has a match, so only the new join record is inserted.
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(12), Field::UInt(30)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), other_right_record.clone())
+            }]
+        );
+        // A range the left record isn't in doesn't join with it.
+        let (_, ops) = exec.insert(JoinSide::Right, &[Field::UInt(16), Field::UInt(18)]);
+        assert_eq!(ops, &[]);
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record.clone(), right_record)
+            }]
+        );
+
+        // The sorted index is rebuilt from a checkpoint.
+        let checkpoint_data = exec.processor.serialize_state().unwrap();
+        let mut exec = Executor::with_condition(
+            JoinType::LeftOuter,
+            condition,
+            checkpoint_data,
+            StateStore::default(),
+        )
+        .await;
+        let (new_left_record, ops) =
+            exec.insert(JoinSide::Left, &[Field::UInt(1), Field::UInt(25)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(new_left_record.clone(), other_right_record.clone())
+            }]
+        );
+        let ops = exec.delete(JoinSide::Right, other_right_record.clone());
+        assert_eq!(ops.len(), 4);
+        for left_record in [left_record, new_left_record] {
+            assert!(ops.contains(&Operation::Delete {
+                old: join_record(left_record.clone(), other_right_record.clone())
+            }));
+            assert!(ops.contains(&Operation::Insert {
+                new: join_record(left_record, null_record.clone())
+            }));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_left_outer_join_truncate() {
+        let mut exec = Executor::new(JoinType::LeftOuter).await;
+
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+        let (left_record, _) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        let (right_record, _) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+
+        // Truncating the right side brings back the left record's default join record.
+        assert_eq!(
+            exec.do_op(Operation::Truncate, JoinSide::Right),
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), right_record)
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), null_record.clone())
+                },
+            ]
+        );
+        assert_eq!(
+            exec.do_op(Operation::Truncate, JoinSide::Left),
+            &[Operation::Delete {
+                old: join_record(left_record, null_record)
+            }]
+        );
+
+        // Both sides are empty afterwards.
+        let (_, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(ops, &[]);
+    }
+
     #[tokio::test]
     async fn test_right_outer_join() {
         let mut exec = Executor::new(JoinType::RightOuter).await;
@@ -434,4 +785,199 @@ mod tests {
             },]
         );
     }
+
+    #[tokio::test]
+    async fn test_full_outer_join() {
+        let mut exec = Executor::new(JoinType::FullOuter).await;
+
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), null_record.clone())
+            }]
+        );
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(1), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(null_record.clone(), other_right_record.clone())
+            }]
+        );
+
+        // A match retracts the left record's default join record.
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), null_record.clone()),
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), right_record.clone())
+                }
+            ]
+        );
+
+        // Moving the left record to the other key swaps which right record is unmatched.
+        let (new_left_record, ops) = exec.update(
+            JoinSide::Left,
+            left_record.clone(),
+            &[Field::UInt(1), Field::UInt(1)],
+        );
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), right_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(null_record.clone(), right_record.clone())
+                },
+                Operation::Delete {
+                    old: join_record(null_record.clone(), other_right_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(new_left_record.clone(), other_right_record.clone())
+                }
+            ]
+        );
+
+        // Unmatched records retract their own default join records.
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[Operation::Delete {
+                old: join_record(null_record.clone(), right_record)
+            }]
+        );
+        assert_eq!(
+            exec.delete(JoinSide::Right, other_right_record.clone()),
+            &[
+                Operation::Delete {
+                    old: join_record(new_left_record.clone(), other_right_record)
+                },
+                Operation::Insert {
+                    new: join_record(new_left_record.clone(), null_record.clone())
+                },
+            ]
+        );
+        assert_eq!(
+            exec.delete(JoinSide::Left, new_left_record.clone()),
+            &[Operation::Delete {
+                old: join_record(new_left_record, null_record)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cross_join() {
+        let mut exec = Executor::new(JoinType::Cross).await;
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        assert_eq!(ops, &[]);
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(1), Field::UInt(2)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), right_record.clone())
+            }]
+        );
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(2), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), other_right_record.clone())
+            }]
+        );
+
+        // Every right record joins with a new left record.
+        let (new_left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(3), Field::UInt(4)]);
+        assert_eq!(ops.len(), 2);
+        for right_record in [&right_record, &other_right_record] {
+            assert!(ops.contains(&Operation::Insert {
+                new: join_record(new_left_record.clone(), right_record.clone())
+            }));
+        }
+
+        let ops = exec.delete(JoinSide::Right, right_record.clone());
+        assert_eq!(ops.len(), 2);
+        for left_record in [&left_record, &new_left_record] {
+            assert!(ops.contains(&Operation::Delete {
+                old: join_record(left_record.clone(), right_record.clone())
+            }));
+        }
+
+        // A side can't keep more than the maximum number of records.
+        let result = exec.processor.process(
+            TableOperation::without_id(
+                Operation::Insert {
+                    new: Record::new(vec![Field::UInt(4), Field::UInt(5)]),
+                },
+                LEFT_JOIN_PORT,
+            ),
+            &mut exec.forwarder,
+        );
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_join_without_key_is_capped() {
+        // Without an equality or a range condition, every record is checked against all records of the other side.
+        let mut exec = Executor::with_condition(
+            JoinType::Inner,
+            "left.joinkey <> right.joinkey",
+            None,
+            StateStore::default(),
+        )
+        .await;
+
+        let (right_record, _) = exec.insert(JoinSide::Right, &[Field::UInt(1), Field::UInt(0)]);
+        let (_, ops) = exec.insert(JoinSide::Left, &[Field::UInt(1), Field::UInt(0)]);
+        assert_eq!(ops, &[]);
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(2), Field::UInt(0)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record, right_record)
+            }]
+        );
+
+        // So a side can't keep more than the maximum number of records of a cross join.
+        let result = exec.processor.process(
+            TableOperation::without_id(
+                Operation::Insert {
+                    new: Record::new(vec![Field::UInt(3), Field::UInt(0)]),
+                },
+                LEFT_JOIN_PORT,
+            ),
+            &mut exec.forwarder,
+        );
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_join_partitioned_only_by_a_join_key() {
+        async fn partitioned(kind: JoinType, condition: &str) -> bool {
+            create_factory(kind, condition)
+                .partitioner(&schemas())
+                .await
+                .unwrap()
+                .is_some()
+        }
+        assert!(partitioned(JoinType::Inner, "left.joinkey = right.joinkey").await);
+        assert!(
+            partitioned(
+                JoinType::LeftOuter,
+                "left.joinkey = right.joinkey AND left.data < right.data"
+            )
+            .await
+        );
+        // Without an equi condition, all records would go to one instance.
+        assert!(!partitioned(JoinType::Inner, "left.joinkey < right.joinkey").await);
+        assert!(!partitioned(JoinType::Cross, "left.joinkey = right.joinkey").await);
+    }
 }
@@ -7,6 +7,7 @@ use enum_dispatch::enum_dispatch;
 use std::collections::HashMap;
 
 #[enum_dispatch(CountingRecordMap)]
+#[derive(bincode::Encode, bincode::Decode)]
 pub enum CountingRecordMapEnum {
     AccurateCountingRecordMap,
     ProbabilisticCountingRecordMap,
@@ -28,7 +29,7 @@ pub trait CountingRecordMap {
     fn clear(&mut self);
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, bincode::Encode, bincode::Decode)]
 pub struct AccurateCountingRecordMap {
     map: HashMap<Record, u64>,
 }
@@ -39,6 +40,11 @@ impl AccurateCountingRecordMap {
             map: Default::default(),
         })
     }
+
+    /// Removes all records, returning them with their insertion counts.
+    pub fn drain(&mut self) -> Vec<(Record, u64)> {
+        self.map.drain().collect()
+    }
 }
 
 impl CountingRecordMap for AccurateCountingRecordMap {
@@ -67,9 +73,10 @@ impl CountingRecordMap for AccurateCountingRecordMap {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
 #[serde(crate = "dozer_types::serde")]
 pub struct ProbabilisticCountingRecordMap {
+    #[bincode(with_serde)]
     map: bloom::CountingBloomFilter,
 }
 
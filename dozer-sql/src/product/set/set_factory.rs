@@ -4,6 +4,7 @@ use crate::errors::PipelineError;
 use crate::errors::SetError;
 
 use dozer_core::event::EventHub;
+use dozer_core::state_store::StateStore;
 use dozer_core::{
     node::{PortHandle, Processor, ProcessorFactory},
     DEFAULT_PORT_HANDLE,
@@ -75,6 +76,8 @@ impl ProcessorFactory for SetProcessorFactory {
         _input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         Ok(Box::new(SetProcessor::new(
             self.id.clone(),
@@ -83,6 +86,7 @@ impl ProcessorFactory for SetProcessorFactory {
                 quantifier: self.set_quantifier,
             },
             self.enable_probabilistic_optimizations,
+            checkpoint_data,
         )?))
     }
 }
@@ -1,14 +1,16 @@
 use super::operator::{SetAction, SetOperation};
 use super::record_map::{
-    AccurateCountingRecordMap, CountingRecordMapEnum, ProbabilisticCountingRecordMap,
+    AccurateCountingRecordMap, CountingRecordMap, CountingRecordMapEnum,
+    ProbabilisticCountingRecordMap,
 };
 use crate::errors::{PipelineError, ProductError, SetError};
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::types::{Operation, Record, TableOperation};
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 
 pub struct SetProcessor {
@@ -17,6 +19,8 @@ pub struct SetProcessor {
     operator: SetOperation,
     /// Hashmap containing records with its occurrence
     record_map: CountingRecordMapEnum,
+    /// The records received on each input port, so truncating a port can delete them
+    inputs: HashMap<PortHandle, AccurateCountingRecordMap>,
 }
 
 impl SetProcessor {
@@ -25,15 +29,25 @@ impl SetProcessor {
         id: String,
         operator: SetOperation,
         enable_probabilistic_optimizations: bool,
+        checkpoint_data: Option<Vec<u8>>,
     ) -> Result<Self, SetError> {
+        let (record_map, inputs) = if let Some(data) = checkpoint_data {
+            bincode::decode_from_slice(&data, bincode::config::legacy())
+                .map_err(|e| SetError::Deserialization(e.into()))?
+                .0
+        } else if enable_probabilistic_optimizations {
+            (
+                ProbabilisticCountingRecordMap::new()?.into(),
+                HashMap::new(),
+            )
+        } else {
+            (AccurateCountingRecordMap::new()?.into(), HashMap::new())
+        };
         Ok(Self {
             _id: id,
             operator,
-            record_map: if enable_probabilistic_optimizations {
-                ProbabilisticCountingRecordMap::new()?.into()
-            } else {
-                AccurateCountingRecordMap::new()?.into()
-            },
+            record_map,
+            inputs,
         })
     }
 
@@ -75,6 +89,22 @@ impl SetProcessor {
 
         Ok((old_records, new_records))
     }
+
+    /// Deletes all records received on `port`.
+    fn truncate(&mut self, port: PortHandle) -> Result<Vec<(SetAction, Record)>, ProductError> {
+        let records = self
+            .inputs
+            .get_mut(&port)
+            .map(AccurateCountingRecordMap::drain)
+            .unwrap_or_default();
+        let mut output_records = vec![];
+        for (record, count) in records {
+            for _ in 0..count {
+                output_records.extend(self.delete(record.clone())?);
+            }
+        }
+        Ok(output_records)
+    }
 }
 
 impl Debug for SetProcessor {
@@ -93,6 +123,18 @@ impl Processor for SetProcessor {
         op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError> {
+        let input = self.inputs.entry(op.port).or_default();
+        match &op.op {
+            Operation::Delete { old } => input.remove(old),
+            Operation::Insert { new } => input.insert(new),
+            Operation::Update { old, new } => {
+                input.remove(old);
+                input.insert(new);
+            }
+            // Batches are processed as single inserts.
+            Operation::BatchInsert { .. } | Operation::Truncate => (),
+        }
+
         match op.op {
             Operation::Delete { old } => {
                 let records = self.delete(old).map_err(PipelineError::ProductError)?;
@@ -180,7 +222,36 @@ impl Processor for SetProcessor {
                     )?;
                 }
             }
+            Operation::Truncate => {
+                let records = self
+                    .truncate(op.port)
+                    .map_err(PipelineError::ProductError)?;
+
+                for (action, record) in records.into_iter() {
+                    match action {
+                        SetAction::Insert => {
+                            fw.send(TableOperation::without_id(
+                                Operation::Insert { new: record },
+                                DEFAULT_PORT_HANDLE,
+                            ));
+                        }
+                        SetAction::Delete => {
+                            fw.send(TableOperation::without_id(
+                                Operation::Delete { old: record },
+                                DEFAULT_PORT_HANDLE,
+                            ));
+                        }
+                    }
+                }
+            }
         }
         Ok(())
     }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data =
+            bincode::encode_to_vec((&self.record_map, &self.inputs), bincode::config::legacy())
+                .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
 }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use dozer_core::{
     event::EventHub,
     node::{PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 use dozer_sql_expression::builder::{extend_schema_source_def, NameOrAlias};
@@ -60,7 +61,12 @@ impl ProcessorFactory for TableProcessorFactory {
         _input_schemas: HashMap<PortHandle, dozer_types::types::Schema>,
         _output_schemas: HashMap<PortHandle, dozer_types::types::Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
-        Ok(Box::new(TableProcessor::new(self.id.clone())))
+        Ok(Box::new(TableProcessor::new(
+            self.id.clone(),
+            self.table.clone(),
+        )))
     }
 }
@@ -3,6 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 use dozer_core::{
     event::EventHub,
     node::{PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 use dozer_sql_expression::{
@@ -129,6 +130,8 @@ impl ProcessorFactory for ProjectionProcessorFactory {
         input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         let schema = match input_schemas.get(&DEFAULT_PORT_HANDLE) {
             Some(schema) => Ok(schema),
@@ -144,6 +147,7 @@ impl ProcessorFactory for ProjectionProcessorFactory {
         Ok(Box::new(ProjectionProcessor::new(
             schema.clone(),
             expressions.into_iter().map(|e| e.1).collect(),
+            None,
         )?))
     }
 }
@@ -1,24 +1,63 @@
+use std::sync::Arc;
+
 use crate::errors::PipelineError;
+use crate::planner::projection::CommonPlanner;
 use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::SelectItem;
 
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use dozer_types::models::udf_config::UdfConfig;
+use dozer_types::types::{Operation, Record, Schema, SchemaDiff, TableOperation};
+use tokio::runtime::Runtime;
+
+/// Plans the projection again when the input schema changes, so wildcards pick up added fields.
+#[derive(Debug)]
+pub struct ProjectionReplanner {
+    projection: Vec<SelectItem>,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
+}
+
+impl ProjectionReplanner {
+    pub fn new(projection: Vec<SelectItem>, udfs: Vec<UdfConfig>, runtime: Arc<Runtime>) -> Self {
+        Self {
+            projection,
+            udfs,
+            runtime,
+        }
+    }
+
+    fn plan(&self, input_schema: &Schema) -> Result<(Vec<Expression>, Schema), PipelineError> {
+        let mut planner =
+            CommonPlanner::new(input_schema.clone(), &self.udfs, self.runtime.clone());
+        self.runtime
+            .block_on(planner.plan(self.projection.clone(), vec![], None))?;
+        Ok((planner.projection_output, planner.post_projection_schema))
+    }
+}
 
 #[derive(Debug)]
 pub struct ProjectionProcessor {
     expressions: Vec<Expression>,
     input_schema: Schema,
+    replanner: Option<ProjectionReplanner>,
 }
 
 impl ProjectionProcessor {
-    pub fn new(input_schema: Schema, expressions: Vec<Expression>) -> Result<Self, PipelineError> {
+    pub fn new(
+        input_schema: Schema,
+        expressions: Vec<Expression>,
+        replanner: Option<ProjectionReplanner>,
+    ) -> Result<Self, PipelineError> {
         Ok(Self {
             input_schema,
             expressions,
+            replanner,
         })
     }
 
@@ -86,6 +125,7 @@ impl Processor for ProjectionProcessor {
                     .collect::<Result<Vec<_>, _>>()?;
                 Operation::BatchInsert { new: records }
             }
+            Operation::Truncate => Operation::Truncate,
         };
         fw.send(TableOperation {
             id: op.id,
@@ -98,4 +138,23 @@ impl Processor for ProjectionProcessor {
     fn commit(&self, _epoch: &Epoch) -> Result<(), BoxedError> {
         Ok(())
     }
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // Expressions refer to fields by index, which only stay valid if fields are appended.
+        if !SchemaDiff::new(old, new).is_additive() {
+            return Err(SchemaChangeError::NotAdditive.into());
+        }
+        self.input_schema = new.clone();
+
+        let Some(replanner) = &self.replanner else {
+            return Ok(vec![]);
+        };
+        let (expressions, output_schema) = replanner.plan(new)?;
+        self.expressions = expressions;
+        Ok(vec![(DEFAULT_PORT_HANDLE, output_schema)])
+    }
 }
@@ -4,6 +4,7 @@ use crate::errors::PipelineError;
 use dozer_core::{
     event::EventHub,
     node::{PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 use dozer_sql_expression::builder::ExpressionBuilder;
@@ -71,6 +72,8 @@ impl ProcessorFactory for SelectionProcessorFactory {
         input_schemas: HashMap<PortHandle, Schema>,
         _output_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
+        _checkpoint_data: Option<Vec<u8>>,
+        _state_store: StateStore,
     ) -> Result<Box<dyn Processor>, BoxedError> {
         let schema = input_schemas
             .get(&DEFAULT_PORT_HANDLE)
@@ -1,10 +1,11 @@
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_sql_expression::execution::Expression;
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::types::{Field, Operation, Record, Schema, TableOperation};
+use dozer_types::types::{Field, Operation, Record, Schema, SchemaDiff, TableOperation};
 
 use crate::errors::PipelineError;
 
@@ -100,7 +101,24 @@ impl Processor for SelectionProcessor {
                     });
                 }
             }
+            Operation::Truncate => {
+                op.port = DEFAULT_PORT_HANDLE;
+                fw.send(op);
+            }
         }
         Ok(())
     }
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // The filter refers to fields by index, which only stay valid if fields are appended.
+        if !SchemaDiff::new(old, new).is_additive() {
+            return Err(SchemaChangeError::NotAdditive.into());
+        }
+        self.input_schema = new.clone();
+        Ok(vec![(DEFAULT_PORT_HANDLE, new.clone())])
+    }
 }
@@ -98,6 +98,12 @@ impl Processor for TableOperatorProcessor {
                     DEFAULT_PORT_HANDLE,
                 ));
             }
+            Operation::Truncate => {
+                fw.send(TableOperation::without_id(
+                    Operation::Truncate,
+                    DEFAULT_PORT_HANDLE,
+                ));
+            }
         }
         Ok(())
     }
@@ -1,4 +1,4 @@
-use dozer_core::app::{App, AppPipeline};
+use dozer_core::app::{App, AppPipeline, PipelineFlags};
 use dozer_core::appsource::{AppSourceManager, AppSourceMappings};
 use dozer_core::epoch::Epoch;
 use dozer_core::event::EventHub;
@@ -280,3 +280,71 @@ fn test_pipeline_builder() {
     let elapsed = now.elapsed();
     debug!("Elapsed: {:.2?}", elapsed);
 }
+
+#[test]
+fn test_pipeline_builder_with_parallelism() {
+    let mut pipeline = AppPipeline::new(PipelineFlags {
+        parallelism: 2,
+        query_parallelism: HashMap::from([("results".to_string(), 3)]),
+        ..Default::default()
+    });
+    let runtime = create_test_runtime();
+    let context = statement_to_pipeline(
+        "SELECT u.Country, SUM(t.Spending) \
+        FROM users t JOIN users u ON t.CustomerID = u.CustomerID \
+        GROUP BY u.Country",
+        &mut pipeline,
+        Some("results".to_string()),
+        vec![],
+        runtime.clone(),
+    )
+    .unwrap();
+
+    let table_info = context.output_tables_map.get("results").unwrap();
+
+    let mut asm = AppSourceManager::new();
+    asm.add(
+        Box::new(TestSourceFactory::new(vec![DEFAULT_PORT_HANDLE])),
+        AppSourceMappings::new(
+            "mem".to_string(),
+            vec![("users".to_string(), DEFAULT_PORT_HANDLE)]
+                .into_iter()
+                .collect(),
+        ),
+    )
+    .unwrap();
+
+    pipeline.add_sink(
+        Box::new(TestSinkFactory::new(vec![DEFAULT_PORT_HANDLE])),
+        "sink".to_string(),
+    );
+    pipeline.connect_nodes(
+        table_info.node.clone(),
+        table_info.port,
+        "sink".to_string(),
+        DEFAULT_PORT_HANDLE,
+    );
+
+    let mut app = App::new(asm);
+    app.add_pipeline(pipeline);
+
+    let dag = app.into_dag().unwrap();
+
+    // The join and the aggregation both run as the query's 3 instances.
+    let instances = dag
+        .node_handles()
+        .filter(|handle| handle.id.ends_with("-of-3"))
+        .count();
+    assert_eq!(instances, 6);
+
+    let runtime_clone = runtime.clone();
+    let handle = runtime.block_on(async move {
+        DagExecutor::new(dag, Default::default())
+            .await
+            .unwrap()
+            .start(pending::<()>(), Default::default(), runtime_clone)
+            .await
+            .unwrap()
+    });
+    handle.join().unwrap();
+}
@@ -1,11 +1,14 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::errors::PipelineError;
+use dozer_core::state_store::{StateStore, StateStoreOptions};
 use dozer_sql_expression::sqlparser::{
     ast::{Query, Select, SetExpr, Statement},
     dialect::DozerDialect,
     parser::Parser,
 };
+use dozer_types::node::NodeHandle;
 use tokio::runtime::Runtime;
 
 pub fn get_select(sql: &str) -> Result<Box<Select>, PipelineError> {
@@ -37,3 +40,14 @@ pub fn create_test_runtime() -> Arc<Runtime> {
             .unwrap(),
     )
 }
+
+/// Opens the disk backed store of a processor under `dir`, restored from its database checkpoint `checkpoint`.
+pub fn open_node_state_store(dir: &Path, checkpoint: Option<u64>) -> StateStore {
+    StateStore::open(&StateStoreOptions::OnDisk {
+        path: dir.to_path_buf(),
+        cache_size: 1024 * 1024,
+    })
+    .unwrap()
+    .for_node(&NodeHandle::new(None, "processor".to_string()), checkpoint)
+    .unwrap()
+}
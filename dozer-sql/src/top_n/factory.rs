@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_core::event::EventHub;
+use dozer_core::node::{Partitioner, PortHandle, Processor, ProcessorFactory};
+use dozer_core::state_store::StateStore;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::builder::ExpressionBuilder;
+use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::{Expr, OrderByExpr};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::models::udf_config::UdfConfig;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Record, Schema};
+use tokio::runtime::Runtime;
+
+use crate::errors::PipelineError;
+use crate::utils::record_hashtable_key::get_record_hash;
+
+use super::processor::{SortOrder, TopNProcessor};
+
+#[derive(Debug)]
+pub struct TopNProcessorFactory {
+    id: String,
+    /// Expressions that split the rows into partitions, each with its own top rows.
+    partition_by: Vec<Expr>,
+    order_by: Vec<OrderByExpr>,
+    offset: usize,
+    limit: usize,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
+}
+
+impl TopNProcessorFactory {
+    pub fn new(
+        id: String,
+        partition_by: Vec<Expr>,
+        order_by: Vec<OrderByExpr>,
+        offset: usize,
+        limit: usize,
+        udfs: Vec<UdfConfig>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            id,
+            partition_by,
+            order_by,
+            offset,
+            limit,
+            udfs,
+            runtime,
+        }
+    }
+
+    async fn build_expression(
+        &self,
+        expr: &Expr,
+        schema: &Schema,
+    ) -> Result<Expression, BoxedError> {
+        let expression = ExpressionBuilder::new(schema.fields.len(), self.runtime.clone())
+            .build(false, expr, schema, &self.udfs)
+            .await?;
+        Ok(expression)
+    }
+
+    async fn build_partition_by(&self, schema: &Schema) -> Result<Vec<Expression>, BoxedError> {
+        let mut partition_by = Vec::with_capacity(self.partition_by.len());
+        for expr in &self.partition_by {
+            partition_by.push(self.build_expression(expr, schema).await?);
+        }
+        Ok(partition_by)
+    }
+}
+
+#[async_trait]
+impl ProcessorFactory for TopNProcessorFactory {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn type_name(&self) -> String {
+        "TopN".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    async fn get_output_schema(
+        &self,
+        _output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+        Ok(schema.clone())
+    }
+
+    async fn build(
+        &self,
+        input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+
+        let partition_by = self.build_partition_by(schema).await?;
+        let mut order_by = Vec::with_capacity(self.order_by.len());
+        for expr in &self.order_by {
+            order_by.push((
+                self.build_expression(&expr.expr, schema).await?,
+                SortOrder::new(expr.asc, expr.nulls_first),
+            ));
+        }
+
+        Ok(Box::new(TopNProcessor::new(
+            schema.clone(),
+            partition_by,
+            order_by,
+            self.offset,
+            self.limit,
+            checkpoint_data,
+            &state_store,
+        )?))
+    }
+
+    async fn partitioner(
+        &self,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        if self.partition_by.is_empty() {
+            return Ok(None);
+        }
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+        Ok(Some(Box::new(PartitionByPartitioner {
+            partition_by: self.build_partition_by(schema).await?,
+            input_schema: schema.clone(),
+        })))
+    }
+}
+
+/// Shards rows by their partition key, so the rows of every partition meet in one instance.
+#[derive(Debug)]
+pub(crate) struct PartitionByPartitioner {
+    pub partition_by: Vec<Expression>,
+    pub input_schema: Schema,
+}
+
+impl Partitioner for PartitionByPartitioner {
+    fn partition_hash(&mut self, _port: PortHandle, record: &Record) -> Result<u64, BoxedError> {
+        let mut key = Vec::<Field>::with_capacity(self.partition_by.len());
+        for expression in self.partition_by.iter_mut() {
+            key.push(
+                expression
+                    .evaluate(record, &self.input_schema)
+                    .map_err(PipelineError::from)?,
+            );
+        }
+        Ok(get_record_hash(key.iter()))
+    }
+}
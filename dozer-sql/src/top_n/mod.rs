@@ -0,0 +1,7 @@
+pub mod factory;
+mod processor;
+
+pub(crate) use processor::{RowKey, SortOrder, SortValue};
+
+#[cfg(test)]
+mod tests;
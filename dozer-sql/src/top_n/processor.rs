@@ -0,0 +1,354 @@
+use std::cmp::Ordering;
+
+use dozer_core::channels::ProcessorChannelForwarder;
+use dozer_core::epoch::Epoch;
+use dozer_core::node::Processor;
+use dozer_core::state_store::{SortKey, SortedStateMap, StateStore};
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::execution::Expression;
+use dozer_types::bincode;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::types::{Field, Operation, Record, Schema, TableOperation};
+
+use crate::errors::PipelineError;
+
+/// How the rows are ordered by an ORDER BY expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct SortOrder {
+    pub descending: bool,
+    pub nulls_first: bool,
+}
+
+impl SortOrder {
+    /// The order of `ASC`/`DESC` and `NULLS FIRST`/`NULLS LAST`. Nulls come last in ascending order by default.
+    pub fn new(asc: Option<bool>, nulls_first: Option<bool>) -> Self {
+        let descending = asc == Some(false);
+        Self {
+            descending,
+            nulls_first: nulls_first.unwrap_or(descending),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) struct SortValue {
+    pub value: Field,
+    pub order: SortOrder,
+}
+
+impl Ord for SortValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let nulls_first = self.order.nulls_first;
+        match (&self.value, &other.value) {
+            (Field::Null, Field::Null) => Ordering::Equal,
+            (Field::Null, _) if nulls_first => Ordering::Less,
+            (Field::Null, _) => Ordering::Greater,
+            (_, Field::Null) if nulls_first => Ordering::Greater,
+            (_, Field::Null) => Ordering::Less,
+            (left, right) if self.order.descending => right.cmp(left),
+            (left, right) => left.cmp(right),
+        }
+    }
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders a row by its ORDER BY values, then by its values, so equal keys are equal rows.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, bincode::Encode, bincode::Decode)]
+pub(crate) struct RowKey {
+    pub sort_values: Vec<SortValue>,
+    pub values: Vec<Field>,
+}
+
+impl RowKey {
+    /// Appends the row to `key` so that keys sort like rows.
+    pub fn push_to(&self, key: &mut SortKey) {
+        for sort_value in &self.sort_values {
+            key.push_ordered(
+                &sort_value.value,
+                sort_value.order.descending,
+                sort_value.order.nulls_first,
+            );
+        }
+        for value in &self.values {
+            key.push(value);
+        }
+    }
+}
+
+/// A row of a partition, with the number of copies of it.
+type Row = (Vec<Field>, u64);
+
+/// Keeps the rows of every partition in order, and emits the changes to the rows ranked
+/// from `offset` to `offset + limit` as rows come and go.
+#[derive(Debug)]
+pub struct TopNProcessor {
+    input_schema: Schema,
+    partition_by: Vec<Expression>,
+    order_by: Vec<(Expression, SortOrder)>,
+    offset: usize,
+    limit: usize,
+    /// The rows keyed by their partition key followed by their [RowKey].
+    rows: SortedStateMap<Row>,
+    /// Number of entries in `rows`, kept as they come and go, as disk backed maps are counted by scanning.
+    row_count: usize,
+}
+
+impl TopNProcessor {
+    pub fn new(
+        input_schema: Schema,
+        partition_by: Vec<Expression>,
+        order_by: Vec<(Expression, SortOrder)>,
+        offset: usize,
+        limit: usize,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: &StateStore,
+    ) -> Result<Self, PipelineError> {
+        let rows = match checkpoint_data {
+            Some(data) => {
+                let (rows, _): (Vec<(SortKey, Row)>, _) =
+                    bincode::decode_from_slice(&data, bincode::config::legacy())
+                        .map_err(|e| PipelineError::RestoreState(e.into()))?;
+                state_store.create_sorted_map_from(rows)?
+            }
+            None => state_store.create_sorted_map(),
+        };
+        let row_count = rows.count()?;
+        Ok(Self {
+            input_schema,
+            partition_by,
+            order_by,
+            offset,
+            limit,
+            rows,
+            row_count,
+        })
+    }
+
+    fn partition_key(&mut self, record: &Record) -> Result<SortKey, PipelineError> {
+        let mut key = SortKey::new();
+        for expression in self.partition_by.iter_mut() {
+            key.push(&expression.evaluate(record, &self.input_schema)?);
+        }
+        Ok(key)
+    }
+
+    fn row_key(
+        &mut self,
+        partition_key: &SortKey,
+        record: &Record,
+    ) -> Result<SortKey, PipelineError> {
+        let mut sort_values = Vec::with_capacity(self.order_by.len());
+        for (expression, order) in self.order_by.iter_mut() {
+            sort_values.push(SortValue {
+                value: expression.evaluate(record, &self.input_schema)?,
+                order: *order,
+            });
+        }
+        let row = RowKey {
+            sort_values,
+            values: record.values.clone(),
+        };
+        let mut key = partition_key.clone();
+        row.push_to(&mut key);
+        Ok(key)
+    }
+
+    /// Applies the inserted and deleted rows of one partition, returning the changes to its top rows.
+    fn apply(
+        &mut self,
+        partition_key: &SortKey,
+        changes: Vec<(SortKey, Vec<Field>, bool)>,
+    ) -> Result<Vec<Operation>, PipelineError> {
+        let bound = self.offset + self.limit;
+
+        // Rows ranked after the last top row don't move the top rows.
+        let last_top_row = match bound.checked_sub(1) {
+            Some(n) => nth_row(&self.rows, partition_key, n)?,
+            None => None,
+        };
+        let affects_top = bound > 0
+            && changes.iter().any(|(row, _, _)| {
+                last_top_row
+                    .as_ref()
+                    .map_or(true, |last_top_row| row <= last_top_row)
+            });
+        let before = if affects_top {
+            top_rows(&self.rows, partition_key, self.offset, self.limit)?
+        } else {
+            vec![]
+        };
+
+        for (row, values, insert) in changes {
+            let count = self.rows.get(&row)?.map_or(0, |row| row.1);
+            if insert {
+                self.rows.insert(row, (values, count + 1))?;
+                if count == 0 {
+                    self.row_count += 1;
+                }
+            } else if count > 1 {
+                self.rows.insert(row, (values, count - 1))?;
+            } else if count == 1 {
+                self.rows.remove(&row)?;
+                self.row_count -= 1;
+            }
+        }
+
+        Ok(if affects_top {
+            diff(
+                before,
+                top_rows(&self.rows, partition_key, self.offset, self.limit)?,
+            )
+        } else {
+            vec![]
+        })
+    }
+
+    fn top_n(&mut self, op: Operation) -> Result<Vec<Operation>, PipelineError> {
+        match op {
+            Operation::Insert { new } => {
+                let partition_key = self.partition_key(&new)?;
+                let row = self.row_key(&partition_key, &new)?;
+                self.apply(&partition_key, vec![(row, new.values, true)])
+            }
+            Operation::Delete { old } => {
+                let partition_key = self.partition_key(&old)?;
+                let row = self.row_key(&partition_key, &old)?;
+                self.apply(&partition_key, vec![(row, old.values, false)])
+            }
+            Operation::Update { old, new } => {
+                let old_partition_key = self.partition_key(&old)?;
+                let old_row = self.row_key(&old_partition_key, &old)?;
+                let new_partition_key = self.partition_key(&new)?;
+                let new_row = self.row_key(&new_partition_key, &new)?;
+                if old_partition_key == new_partition_key {
+                    self.apply(
+                        &new_partition_key,
+                        vec![(old_row, old.values, false), (new_row, new.values, true)],
+                    )
+                } else {
+                    let mut ops =
+                        self.apply(&old_partition_key, vec![(old_row, old.values, false)])?;
+                    ops.extend(self.apply(&new_partition_key, vec![(new_row, new.values, true)])?);
+                    Ok(ops)
+                }
+            }
+            Operation::BatchInsert { new } => {
+                let mut ops = vec![];
+                for record in new {
+                    ops.extend(self.top_n(Operation::Insert { new: record })?);
+                }
+                Ok(ops)
+            }
+            Operation::Truncate => {
+                self.rows.clear()?;
+                self.row_count = 0;
+                Ok(vec![Operation::Truncate])
+            }
+        }
+    }
+}
+
+/// Returns the key of the row of the partition ranked `n`th, counting from 0.
+fn nth_row(
+    rows: &SortedStateMap<Row>,
+    partition_key: &SortKey,
+    n: usize,
+) -> Result<Option<SortKey>, PipelineError> {
+    let mut rank = 0;
+    for entry in rows.iter_prefix(partition_key) {
+        let (key, (_, count)) = entry?;
+        rank += count as usize;
+        if rank > n {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
+
+fn top_rows(
+    rows: &SortedStateMap<Row>,
+    partition_key: &SortKey,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<(SortKey, Vec<Field>)>, PipelineError> {
+    let mut result = Vec::with_capacity(limit);
+    let mut rank = 0;
+    for entry in rows.iter_prefix(partition_key) {
+        if rank >= offset + limit {
+            break;
+        }
+        let (key, (values, count)) = entry?;
+        let count = count as usize;
+        let start = rank.max(offset);
+        rank += count;
+        for _ in start..rank.min(offset + limit) {
+            result.push((key.clone(), values.clone()));
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the deletes and inserts that turn the ordered rows `before` into `after`.
+fn diff(before: Vec<(SortKey, Vec<Field>)>, after: Vec<(SortKey, Vec<Field>)>) -> Vec<Operation> {
+    let mut deletes = vec![];
+    let mut inserts = vec![];
+    let mut before = before.into_iter().peekable();
+    let mut after = after.into_iter().peekable();
+    loop {
+        match (before.peek(), after.peek()) {
+            (Some(old), Some(new)) => match old.0.cmp(&new.0) {
+                Ordering::Less => deletes.push(before.next().unwrap().1),
+                Ordering::Greater => inserts.push(after.next().unwrap().1),
+                Ordering::Equal => {
+                    before.next();
+                    after.next();
+                }
+            },
+            (Some(_), None) => deletes.push(before.next().unwrap().1),
+            (None, Some(_)) => inserts.push(after.next().unwrap().1),
+            (None, None) => break,
+        }
+    }
+
+    deletes
+        .into_iter()
+        .map(|values| Operation::Delete {
+            old: Record::new(values),
+        })
+        .chain(inserts.into_iter().map(|values| Operation::Insert {
+            new: Record::new(values),
+        }))
+        .collect()
+}
+
+impl Processor for TopNProcessor {
+    fn commit(&self, _epoch: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        for output_op in self.top_n(op.op)? {
+            fw.send(TableOperation::without_id(output_op, DEFAULT_PORT_HANDLE));
+        }
+        Ok(())
+    }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data = bincode::encode_to_vec(&self.rows, bincode::config::legacy())
+            .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
+
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(Some(self.row_count))
+    }
+}
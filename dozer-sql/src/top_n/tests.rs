@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use dozer_core::channels::ProcessorChannelForwarder;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Processor, ProcessorFactory};
+use dozer_core::state_store::StateStore;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::sqlparser::ast::{Expr, Ident, Statement};
+use dozer_sql_expression::sqlparser::{dialect::DozerDialect, parser::Parser};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::tests::utils::create_test_runtime;
+
+use super::factory::TopNProcessorFactory;
+
+struct TestChannelForwarder {
+    operations: Vec<Operation>,
+}
+
+impl ProcessorChannelForwarder for TestChannelForwarder {
+    fn send(&mut self, op: TableOperation) {
+        self.operations.push(op.op);
+    }
+}
+
+fn get_schema() -> Schema {
+    let mut schema = Schema::new();
+    for (name, typ) in [
+        ("category", FieldType::String),
+        ("product", FieldType::String),
+        ("revenue", FieldType::Int),
+    ] {
+        schema.field(
+            FieldDefinition::new(name.to_string(), typ, true, SourceDefinition::Dynamic),
+            false,
+        );
+    }
+    schema
+}
+
+fn row(category: &str, product: &str, revenue: i64) -> Record {
+    Record::new(vec![
+        Field::String(category.to_string()),
+        Field::String(product.to_string()),
+        Field::Int(revenue),
+    ])
+}
+
+/// Top 2 products by revenue per category.
+fn build(checkpoint_data: Option<Vec<u8>>) -> Box<dyn Processor> {
+    let sql = "SELECT category, product, revenue FROM products ORDER BY revenue DESC LIMIT 2";
+    let statement = Parser::parse_sql(&DozerDialect {}, sql).unwrap().remove(0);
+    let Statement::Query(query) = statement else {
+        panic!("not a query");
+    };
+    let runtime = create_test_runtime();
+    let factory = TopNProcessorFactory::new(
+        "top_n".to_string(),
+        vec![Expr::Identifier(Ident::new("category"))],
+        query.order_by,
+        0,
+        2,
+        vec![],
+        runtime.clone(),
+    );
+    runtime
+        .block_on(factory.build(
+            HashMap::from([(DEFAULT_PORT_HANDLE, get_schema())]),
+            HashMap::new(),
+            EventHub::new(1),
+            checkpoint_data,
+            StateStore::default(),
+        ))
+        .unwrap()
+}
+
+fn process(processor: &mut Box<dyn Processor>, op: Operation) -> Vec<Operation> {
+    let mut fw = TestChannelForwarder { operations: vec![] };
+    processor
+        .process(TableOperation::without_id(op, DEFAULT_PORT_HANDLE), &mut fw)
+        .unwrap();
+    fw.operations
+}
+
+fn insert(new: Record) -> Operation {
+    Operation::Insert { new }
+}
+
+fn delete(old: Record) -> Operation {
+    Operation::Delete { old }
+}
+
+#[test]
+fn test_top_n() {
+    let mut processor = build(None);
+
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p1", 10))),
+        vec![insert(row("a", "p1", 10))]
+    );
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p2", 20))),
+        vec![insert(row("a", "p2", 20))]
+    );
+    // Rows below the top 2 are kept, but not emitted.
+    assert_eq!(process(&mut processor, insert(row("a", "p3", 5))), vec![]);
+    // Other categories are ranked on their own.
+    assert_eq!(
+        process(&mut processor, insert(row("b", "p4", 1))),
+        vec![insert(row("b", "p4", 1))]
+    );
+
+    // A row entering the top 2 pushes the last one out.
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p5", 15))),
+        vec![delete(row("a", "p1", 10)), insert(row("a", "p5", 15))]
+    );
+    // A row leaving the top 2 lets the next one in.
+    assert_eq!(
+        process(&mut processor, delete(row("a", "p2", 20))),
+        vec![delete(row("a", "p2", 20)), insert(row("a", "p1", 10))]
+    );
+    assert_eq!(
+        process(
+            &mut processor,
+            Operation::Update {
+                old: row("a", "p3", 5),
+                new: row("a", "p3", 30),
+            }
+        ),
+        vec![delete(row("a", "p1", 10)), insert(row("a", "p3", 30))]
+    );
+
+    // The ranked rows are restored from a checkpoint.
+    let checkpoint_data = processor.serialize_state().unwrap();
+    let mut processor = build(checkpoint_data);
+    assert_eq!(processor.state_size().unwrap(), Some(4));
+    assert_eq!(
+        process(&mut processor, delete(row("a", "p3", 30))),
+        vec![delete(row("a", "p3", 30)), insert(row("a", "p1", 10))]
+    );
+
+    assert_eq!(
+        process(&mut processor, Operation::Truncate),
+        vec![Operation::Truncate]
+    );
+    assert_eq!(processor.state_size().unwrap(), Some(0));
+}
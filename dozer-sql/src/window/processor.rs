@@ -80,6 +80,12 @@ impl Processor for WindowProcessor {
                     DEFAULT_PORT_HANDLE,
                 ));
             }
+            Operation::Truncate => {
+                fw.send(TableOperation::without_id(
+                    Operation::Truncate,
+                    DEFAULT_PORT_HANDLE,
+                ));
+            }
         }
         Ok(())
     }
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_core::event::EventHub;
+use dozer_core::node::{Partitioner, PortHandle, Processor, ProcessorFactory};
+use dozer_core::state_store::StateStore;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::aggregate::AggregateFunctionType;
+use dozer_sql_expression::builder::ExpressionBuilder;
+use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::{
+    Expr, Function, FunctionArg, FunctionArgExpr, Value, WindowFrame as SqlWindowFrame,
+    WindowFrameBound, WindowFrameUnits, WindowSpec,
+};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::models::udf_config::UdfConfig;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, FieldDefinition, FieldType, Schema, SourceDefinition};
+use tokio::runtime::Runtime;
+
+use crate::aggregation::aggregator::get_aggregator_type_from_aggregation_expression;
+use crate::errors::{PipelineError, WindowFunctionError};
+use crate::top_n::factory::PartitionByPartitioner;
+use crate::top_n::SortOrder;
+
+use super::processor::{
+    FrameBound, FrameUnits, WindowFrame, WindowFunction, WindowFunctionProcessor,
+    WindowFunctionType,
+};
+
+#[derive(Debug)]
+pub struct WindowFunctionProcessorFactory {
+    id: String,
+    /// The PARTITION BY, ORDER BY and frame that all `functions` are computed over.
+    window: WindowSpec,
+    /// The window functions, with the names of the columns they are appended as.
+    functions: Vec<(String, Function)>,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
+}
+
+impl WindowFunctionProcessorFactory {
+    pub fn new(
+        id: String,
+        window: WindowSpec,
+        functions: Vec<(String, Function)>,
+        udfs: Vec<UdfConfig>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            id,
+            window,
+            functions,
+            udfs,
+            runtime,
+        }
+    }
+
+    async fn build_expression(
+        &self,
+        expr: &Expr,
+        schema: &Schema,
+    ) -> Result<Expression, PipelineError> {
+        let expression = ExpressionBuilder::new(schema.fields.len(), self.runtime.clone())
+            .build(false, expr, schema, &self.udfs)
+            .await?;
+        Ok(expression)
+    }
+
+    async fn build_partition_by(&self, schema: &Schema) -> Result<Vec<Expression>, PipelineError> {
+        let mut partition_by = Vec::with_capacity(self.window.partition_by.len());
+        for expr in &self.window.partition_by {
+            partition_by.push(self.build_expression(expr, schema).await?);
+        }
+        Ok(partition_by)
+    }
+
+    async fn build_function(
+        &self,
+        function: &Function,
+        schema: &Schema,
+    ) -> Result<WindowFunction, PipelineError> {
+        let name = function.name.to_string().to_lowercase();
+        let invalid_arguments = || WindowFunctionError::InvalidArguments(name.clone());
+
+        if AggregateFunctionType::new(&name).is_some() {
+            let mut builder = ExpressionBuilder::new(schema.fields.len(), self.runtime.clone());
+            builder
+                .build(true, &Expr::Function(function.clone()), schema, &self.udfs)
+                .await?;
+            let measure = builder.aggregations.pop().ok_or_else(invalid_arguments)?;
+            let (args, typ) = get_aggregator_type_from_aggregation_expression(&measure, schema)?;
+            return Ok(WindowFunction {
+                typ: WindowFunctionType::Aggregate(typ),
+                args,
+                return_type: measure.get_type(schema)?.return_type,
+            });
+        }
+
+        let mut args = Vec::with_capacity(function.args.len());
+        for arg in &function.args {
+            let FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) = arg else {
+                return Err(invalid_arguments().into());
+            };
+            args.push(self.build_expression(expr, schema).await?);
+        }
+        let literal = |index: usize, default: Field| match args.get(index) {
+            None => Ok(default),
+            Some(Expression::Literal(value)) => Ok(value.clone()),
+            Some(_) => Err(invalid_arguments()),
+        };
+        let offset = || -> Result<usize, WindowFunctionError> {
+            match literal(1, Field::UInt(1))? {
+                Field::UInt(offset) => Ok(offset as usize),
+                Field::Int(offset) if offset >= 0 => Ok(offset as usize),
+                _ => Err(invalid_arguments()),
+            }
+        };
+
+        let (typ, arg_count) = match name.as_str() {
+            "row_number" => (WindowFunctionType::RowNumber, 0..=0),
+            "rank" => (WindowFunctionType::Rank, 0..=0),
+            "dense_rank" => (WindowFunctionType::DenseRank, 0..=0),
+            "lag" => (
+                WindowFunctionType::Lag {
+                    offset: offset()?,
+                    default: literal(2, Field::Null)?,
+                },
+                1..=3,
+            ),
+            "lead" => (
+                WindowFunctionType::Lead {
+                    offset: offset()?,
+                    default: literal(2, Field::Null)?,
+                },
+                1..=3,
+            ),
+            "first_value" => (WindowFunctionType::FirstValue, 1..=1),
+            "last_value" => (WindowFunctionType::LastValue, 1..=1),
+            _ => return Err(WindowFunctionError::UnknownFunction(name).into()),
+        };
+        if !arg_count.contains(&args.len()) {
+            return Err(invalid_arguments().into());
+        }
+
+        let return_type = match args.first() {
+            Some(arg) => arg.get_type(schema)?.return_type,
+            None => FieldType::UInt,
+        };
+        // Only the value argument is evaluated for every row.
+        args.truncate(1);
+        Ok(WindowFunction {
+            typ,
+            args,
+            return_type,
+        })
+    }
+
+    async fn build_functions(&self, schema: &Schema) -> Result<Vec<WindowFunction>, PipelineError> {
+        let mut functions = Vec::with_capacity(self.functions.len());
+        for (_, function) in &self.functions {
+            functions.push(self.build_function(function, schema).await?);
+        }
+        Ok(functions)
+    }
+}
+
+fn parse_frame(frame: &Option<SqlWindowFrame>) -> Result<WindowFrame, WindowFunctionError> {
+    let Some(sql_frame) = frame else {
+        return Ok(WindowFrame::DEFAULT);
+    };
+    let unsupported = || WindowFunctionError::UnsupportedFrame(sql_frame.to_string());
+    let units = match sql_frame.units {
+        WindowFrameUnits::Rows => FrameUnits::Rows,
+        WindowFrameUnits::Range => FrameUnits::Range,
+        WindowFrameUnits::Groups => return Err(unsupported()),
+    };
+    let parse_bound = |bound: &WindowFrameBound| {
+        let offset = |offset: &Expr| match (units, offset) {
+            (FrameUnits::Rows, Expr::Value(Value::Number(number, _))) => number.parse().ok(),
+            _ => None,
+        };
+        match bound {
+            WindowFrameBound::CurrentRow => Some(FrameBound::CurrentRow),
+            WindowFrameBound::Preceding(None) => Some(FrameBound::UnboundedPreceding),
+            WindowFrameBound::Preceding(Some(expr)) => offset(expr).map(FrameBound::Preceding),
+            WindowFrameBound::Following(None) => Some(FrameBound::UnboundedFollowing),
+            WindowFrameBound::Following(Some(expr)) => offset(expr).map(FrameBound::Following),
+        }
+    };
+    let start = parse_bound(&sql_frame.start_bound).ok_or_else(unsupported)?;
+    let end = match &sql_frame.end_bound {
+        Some(bound) => parse_bound(bound).ok_or_else(unsupported)?,
+        None => FrameBound::CurrentRow,
+    };
+    if start == FrameBound::UnboundedFollowing || end == FrameBound::UnboundedPreceding {
+        return Err(unsupported());
+    }
+    Ok(WindowFrame { units, start, end })
+}
+
+#[async_trait]
+impl ProcessorFactory for WindowFunctionProcessorFactory {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn type_name(&self) -> String {
+        "WindowFunction".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    async fn get_output_schema(
+        &self,
+        _output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+
+        let mut output_schema = schema.clone();
+        let functions = self.build_functions(schema).await?;
+        for ((name, _), function) in self.functions.iter().zip(functions) {
+            let nullable = !matches!(
+                function.typ,
+                WindowFunctionType::RowNumber
+                    | WindowFunctionType::Rank
+                    | WindowFunctionType::DenseRank
+            );
+            output_schema.fields.push(FieldDefinition::new(
+                name.clone(),
+                function.return_type,
+                nullable,
+                SourceDefinition::Dynamic,
+            ));
+        }
+        Ok(output_schema)
+    }
+
+    async fn build(
+        &self,
+        input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: StateStore,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+
+        let partition_by = self.build_partition_by(schema).await?;
+        let mut order_by = Vec::with_capacity(self.window.order_by.len());
+        for expr in &self.window.order_by {
+            order_by.push((
+                self.build_expression(&expr.expr, schema).await?,
+                SortOrder::new(expr.asc, expr.nulls_first),
+            ));
+        }
+        let frame = parse_frame(&self.window.window_frame).map_err(PipelineError::from)?;
+        let functions = self.build_functions(schema).await?;
+
+        Ok(Box::new(WindowFunctionProcessor::new(
+            schema.clone(),
+            partition_by,
+            order_by,
+            frame,
+            functions,
+            checkpoint_data,
+            &state_store,
+        )?))
+    }
+
+    async fn partitioner(
+        &self,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Option<Box<dyn Partitioner>>, BoxedError> {
+        if self.window.partition_by.is_empty() {
+            return Ok(None);
+        }
+        let schema = input_schemas
+            .get(&DEFAULT_PORT_HANDLE)
+            .ok_or(PipelineError::InvalidPortHandle(DEFAULT_PORT_HANDLE))?;
+        Ok(Some(Box::new(PartitionByPartitioner {
+            partition_by: self.build_partition_by(schema).await?,
+            input_schema: schema.clone(),
+        })))
+    }
+}
@@ -0,0 +1,5 @@
+pub mod factory;
+mod processor;
+
+#[cfg(test)]
+mod tests;
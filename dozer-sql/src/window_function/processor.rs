@@ -0,0 +1,945 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Bound, Range, RangeBounds};
+
+use dozer_core::channels::ProcessorChannelForwarder;
+use dozer_core::epoch::Epoch;
+use dozer_core::errors::StateStoreError;
+use dozer_core::node::Processor;
+use dozer_core::state_store::{SortKey, SortedStateMap, SortedStateMapIter, StateStore};
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::execution::Expression;
+use dozer_types::bincode;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::types::{Field, FieldType, Operation, Record, Schema, TableOperation};
+
+use crate::aggregation::aggregator::{
+    get_aggregator_from_aggregator_type, Aggregator, AggregatorEnum, AggregatorType,
+};
+use crate::errors::PipelineError;
+use crate::top_n::{RowKey, SortOrder, SortValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowFunctionType {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// The argument of the row `offset` rows before, or `default` if there is none.
+    Lag {
+        offset: usize,
+        default: Field,
+    },
+    /// The argument of the row `offset` rows after, or `default` if there is none.
+    Lead {
+        offset: usize,
+        default: Field,
+    },
+    FirstValue,
+    LastValue,
+    /// An aggregate function over the frame of the row.
+    Aggregate(AggregatorType),
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowFunction {
+    pub typ: WindowFunctionType,
+    pub args: Vec<Expression>,
+    pub return_type: FieldType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameUnits {
+    /// Bounds count rows.
+    Rows,
+    /// `CURRENT ROW` bounds include the rows with the same ORDER BY values.
+    Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+/// The rows of the partition that FIRST_VALUE, LAST_VALUE and aggregate functions see from a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub units: FrameUnits,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl WindowFrame {
+    /// The frame without a frame clause: the rows up to the last row with the same ORDER BY values.
+    pub const DEFAULT: Self = Self {
+        units: FrameUnits::Range,
+        start: FrameBound::UnboundedPreceding,
+        end: FrameBound::CurrentRow,
+    };
+
+    /// Returns the start index of the frame of row `index`.
+    fn start(&self, rows: &[PartitionRow], index: usize) -> usize {
+        match (self.start, self.units) {
+            (FrameBound::UnboundedPreceding, _) => 0,
+            (FrameBound::Preceding(offset), _) => index.saturating_sub(offset),
+            (FrameBound::CurrentRow, FrameUnits::Rows) => index,
+            (FrameBound::CurrentRow, FrameUnits::Range) => first_peer(rows, index),
+            (FrameBound::Following(offset), _) => (index + offset).min(rows.len()),
+            (FrameBound::UnboundedFollowing, _) => rows.len(),
+        }
+    }
+
+    /// Returns the end index of the frame of row `index`, exclusive.
+    fn end(&self, rows: &[PartitionRow], index: usize) -> usize {
+        match (self.end, self.units) {
+            (FrameBound::UnboundedPreceding, _) => 0,
+            (FrameBound::Preceding(offset), _) => (index + 1).saturating_sub(offset),
+            (FrameBound::CurrentRow, FrameUnits::Rows) => index + 1,
+            (FrameBound::CurrentRow, FrameUnits::Range) => last_peer(rows, index) + 1,
+            (FrameBound::Following(offset), _) => (index + offset + 1).min(rows.len()),
+            (FrameBound::UnboundedFollowing, _) => rows.len(),
+        }
+    }
+}
+
+/// How many rows before and after some rows a window function reaches. `None` is all of them.
+#[derive(Debug, Clone, Copy)]
+struct Reach {
+    before: Option<usize>,
+    after: Option<usize>,
+}
+
+impl Reach {
+    /// Reaches no rows but the row itself.
+    const NONE: Self = Self {
+        before: Some(0),
+        after: Some(0),
+    };
+
+    /// The rows before and after the changed rows whose values can change.
+    fn of(function: &WindowFunctionType, frame: &WindowFrame) -> Self {
+        match function {
+            WindowFunctionType::RowNumber
+            | WindowFunctionType::Rank
+            | WindowFunctionType::DenseRank => Self {
+                before: Some(0),
+                after: None,
+            },
+            WindowFunctionType::Lag { offset, .. } => Self {
+                before: Some(0),
+                after: Some(*offset),
+            },
+            WindowFunctionType::Lead { offset, .. } => Self {
+                before: Some(*offset),
+                after: Some(0),
+            },
+            WindowFunctionType::FirstValue
+            | WindowFunctionType::LastValue
+            | WindowFunctionType::Aggregate(_) => Self {
+                // Rows see a change if their frame reaches it.
+                before: match frame.end {
+                    FrameBound::Following(offset) => Some(offset),
+                    FrameBound::UnboundedFollowing => None,
+                    _ => Some(0),
+                },
+                after: match frame.start {
+                    FrameBound::Preceding(offset) => Some(offset),
+                    FrameBound::UnboundedPreceding => None,
+                    _ => Some(0),
+                },
+            },
+        }
+    }
+
+    /// The rows before and after a row that its value reads, beyond what the row before it carries over.
+    fn context(function: &WindowFunctionType, frame: &WindowFrame) -> Self {
+        let preceding = |bound: FrameBound| match bound {
+            FrameBound::Preceding(offset) => offset,
+            _ => 0,
+        };
+        match function {
+            WindowFunctionType::RowNumber
+            | WindowFunctionType::Rank
+            | WindowFunctionType::DenseRank => Self::NONE,
+            WindowFunctionType::Lag { offset, .. } => Self {
+                before: Some(*offset),
+                after: Some(0),
+            },
+            WindowFunctionType::Lead { offset, .. } => Self {
+                before: Some(0),
+                after: Some(*offset),
+            },
+            WindowFunctionType::FirstValue
+            | WindowFunctionType::LastValue
+            | WindowFunctionType::Aggregate(_) => Self {
+                before: match (function, frame.start) {
+                    // Aggregates over frames that start at the start of the partition read every row before,
+                    // while FIRST_VALUE reads the first row of the partition on its own.
+                    (WindowFunctionType::Aggregate(_), FrameBound::UnboundedPreceding) => None,
+                    _ => Some(preceding(frame.start).max(preceding(frame.end))),
+                },
+                after: match frame.end {
+                    FrameBound::Following(offset) => Some(offset),
+                    FrameBound::UnboundedFollowing => None,
+                    _ => Some(0),
+                },
+            },
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        let max = |left: Option<usize>, right: Option<usize>| Some(left?.max(right?));
+        Self {
+            before: max(self.before, other.before),
+            after: max(self.after, other.after),
+        }
+    }
+}
+
+/// The position of a row in its partition.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+struct Position {
+    row_number: u64,
+    rank: u64,
+    dense_rank: u64,
+}
+
+/// The copies of a row, the arguments of every window function for it, and what was computed for it.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+struct RowState {
+    row: RowKey,
+    count: usize,
+    arguments: Vec<Vec<Field>>,
+    /// The values of the window functions for every copy, as last emitted.
+    window_values: Vec<Vec<Field>>,
+    /// The position of the last copy of the row, which the row after it continues from.
+    ///
+    /// Only the functions that see every row before a row read it, and those recompute every row after a change,
+    /// so it's up to date wherever it's read.
+    position: Position,
+}
+
+/// A row of a partition, once for every copy.
+type PartitionRow<'a> = &'a RowState;
+
+/// A row of a partition, with its key.
+type Entry = (SortKey, RowState);
+
+/// The keys of the rows from one row of a partition to another.
+type RowBounds = (Bound<SortKey>, Bound<SortKey>);
+
+/// Keeps the rows of every partition in order, and appends the values of the window functions to them.
+///
+/// A change only recomputes the rows whose values it can change, continuing from the row before them,
+/// and emits updates for those whose values changed.
+#[derive(Debug)]
+pub struct WindowFunctionProcessor {
+    input_schema: Schema,
+    partition_by: Vec<Expression>,
+    order_by: Vec<(Expression, SortOrder)>,
+    frame: WindowFrame,
+    functions: Vec<WindowFunction>,
+    reach: Reach,
+    context: Reach,
+    /// The rows keyed by their partition key followed by their [RowKey].
+    rows: SortedStateMap<RowState>,
+    /// Number of entries in `rows`, kept as they come and go, as disk backed maps are counted by scanning.
+    row_count: usize,
+}
+
+impl WindowFunctionProcessor {
+    pub fn new(
+        input_schema: Schema,
+        partition_by: Vec<Expression>,
+        order_by: Vec<(Expression, SortOrder)>,
+        frame: WindowFrame,
+        functions: Vec<WindowFunction>,
+        checkpoint_data: Option<Vec<u8>>,
+        state_store: &StateStore,
+    ) -> Result<Self, PipelineError> {
+        let rows = match checkpoint_data {
+            Some(data) => {
+                let (rows, _): (Vec<Entry>, _) =
+                    bincode::decode_from_slice(&data, bincode::config::legacy())
+                        .map_err(|e| PipelineError::RestoreState(e.into()))?;
+                state_store.create_sorted_map_from(rows)?
+            }
+            None => state_store.create_sorted_map(),
+        };
+        let row_count = rows.count()?;
+        let reach = functions
+            .iter()
+            .map(|function| Reach::of(&function.typ, &frame))
+            .fold(Reach::NONE, Reach::max);
+        let context = functions
+            .iter()
+            .map(|function| Reach::context(&function.typ, &frame))
+            .fold(Reach::NONE, Reach::max);
+        Ok(Self {
+            input_schema,
+            partition_by,
+            order_by,
+            frame,
+            functions,
+            reach,
+            context,
+            rows,
+            row_count,
+        })
+    }
+
+    fn partition_key(&mut self, record: &Record) -> Result<SortKey, PipelineError> {
+        let mut key = SortKey::new();
+        for expression in self.partition_by.iter_mut() {
+            key.push(&expression.evaluate(record, &self.input_schema)?);
+        }
+        Ok(key)
+    }
+
+    fn row(&mut self, record: &Record) -> Result<(RowKey, Vec<Vec<Field>>), PipelineError> {
+        let mut sort_values = Vec::with_capacity(self.order_by.len());
+        for (expression, order) in self.order_by.iter_mut() {
+            sort_values.push(SortValue {
+                value: expression.evaluate(record, &self.input_schema)?,
+                order: *order,
+            });
+        }
+        let mut arguments = Vec::with_capacity(self.functions.len());
+        for function in self.functions.iter_mut() {
+            let mut values = Vec::with_capacity(function.args.len());
+            for arg in function.args.iter_mut() {
+                values.push(arg.evaluate(record, &self.input_schema)?);
+            }
+            arguments.push(values);
+        }
+        let key = RowKey {
+            sort_values,
+            values: record.values.clone(),
+        };
+        Ok((key, arguments))
+    }
+
+    /// Applies the inserted and deleted rows of one partition, returning the changes to the rows with their window values.
+    fn apply(
+        &mut self,
+        partition_key: &SortKey,
+        changes: Vec<(RowKey, Vec<Vec<Field>>, bool)>,
+    ) -> Result<Vec<Operation>, PipelineError> {
+        let (Some(first_change), Some(last_change)) = (
+            changes.iter().map(|(row, _, _)| &row.sort_values).min(),
+            changes.iter().map(|(row, _, _)| &row.sort_values).max(),
+        ) else {
+            return Ok(vec![]);
+        };
+        let first_change = peers_key(partition_key, first_change);
+        let last_change = peers_key(partition_key, last_change);
+
+        let bounds = recomputed_bounds(
+            &self.rows,
+            partition_key,
+            &self.reach,
+            &first_change,
+            &last_change,
+        )?;
+        let mut before = vec![];
+        for entry in range(&self.rows, partition_key, &bounds) {
+            let (_, state) = entry?;
+            before.extend(
+                state
+                    .window_values
+                    .into_iter()
+                    .map(|window_values| (state.row.values.clone(), window_values)),
+            );
+        }
+
+        for (row, arguments, insert) in changes {
+            let mut key = partition_key.clone();
+            row.push_to(&mut key);
+            if insert {
+                let mut state = match self.rows.get(&key)? {
+                    Some(state) => state.into_owned(),
+                    None => {
+                        self.row_count += 1;
+                        RowState {
+                            row,
+                            count: 0,
+                            arguments,
+                            window_values: vec![],
+                            position: Position::default(),
+                        }
+                    }
+                };
+                state.count += 1;
+                self.rows.insert(key, state)?;
+            } else if let Some(mut state) = self.rows.remove(&key)? {
+                state.count -= 1;
+                if state.count > 0 {
+                    self.rows.insert(key, state)?;
+                } else {
+                    self.row_count -= 1;
+                }
+            }
+        }
+
+        let mut after = vec![];
+        if let Some(window) = WindowRows::read(&self.rows, partition_key, &bounds, &self.context)? {
+            let recomputed = window.window().recompute(&self.functions, &self.frame)?;
+            for ((key, mut state), (window_values, position)) in window.recomputed().zip(recomputed)
+            {
+                after.extend(
+                    window_values
+                        .iter()
+                        .map(|window_values| (state.row.values.clone(), window_values.clone())),
+                );
+                // Rows the change didn't touch are only written back if their values moved.
+                if state.window_values != window_values || state.position != position {
+                    state.window_values = window_values;
+                    state.position = position;
+                    self.rows.insert(key, state)?;
+                }
+            }
+        }
+        Ok(diff(before, after))
+    }
+
+    fn window_functions(&mut self, op: Operation) -> Result<Vec<Operation>, PipelineError> {
+        match op {
+            Operation::Insert { new } => {
+                let partition_key = self.partition_key(&new)?;
+                let (row, arguments) = self.row(&new)?;
+                self.apply(&partition_key, vec![(row, arguments, true)])
+            }
+            Operation::Delete { old } => {
+                let partition_key = self.partition_key(&old)?;
+                let (row, arguments) = self.row(&old)?;
+                self.apply(&partition_key, vec![(row, arguments, false)])
+            }
+            Operation::Update { old, new } => {
+                let old_partition_key = self.partition_key(&old)?;
+                let (old_row, old_arguments) = self.row(&old)?;
+                let new_partition_key = self.partition_key(&new)?;
+                let (new_row, new_arguments) = self.row(&new)?;
+                if old_partition_key == new_partition_key {
+                    self.apply(
+                        &new_partition_key,
+                        vec![
+                            (old_row, old_arguments, false),
+                            (new_row, new_arguments, true),
+                        ],
+                    )
+                } else {
+                    let mut ops =
+                        self.apply(&old_partition_key, vec![(old_row, old_arguments, false)])?;
+                    ops.extend(
+                        self.apply(&new_partition_key, vec![(new_row, new_arguments, true)])?,
+                    );
+                    Ok(ops)
+                }
+            }
+            Operation::BatchInsert { new } => {
+                let mut ops = vec![];
+                for record in new {
+                    ops.extend(self.window_functions(Operation::Insert { new: record })?);
+                }
+                Ok(ops)
+            }
+            Operation::Truncate => {
+                self.rows.clear()?;
+                self.row_count = 0;
+                Ok(vec![Operation::Truncate])
+            }
+        }
+    }
+}
+
+/// Returns the key of the rows of the partition with the ORDER BY values `sort_values`, which starts the keys
+/// of all of them, and of no other row.
+fn peers_key(partition_key: &SortKey, sort_values: &[SortValue]) -> SortKey {
+    let mut key = partition_key.clone();
+    RowKey {
+        sort_values: sort_values.to_vec(),
+        values: vec![],
+    }
+    .push_to(&mut key);
+    key
+}
+
+/// Stops `entries` at the first entry outside the partition.
+fn within<'a>(
+    entries: SortedStateMapIter<'a, RowState>,
+    partition_key: &'a SortKey,
+) -> impl Iterator<Item = Result<Entry, StateStoreError>> + 'a {
+    entries.take_while(move |entry| {
+        entry
+            .as_ref()
+            .map_or(true, |(key, _)| key.starts_with(partition_key))
+    })
+}
+
+/// Iterates over the rows of the partition within `bounds`.
+fn range<'a>(
+    rows: &'a SortedStateMap<RowState>,
+    partition_key: &'a SortKey,
+    bounds: &'a RowBounds,
+) -> impl Iterator<Item = Result<Entry, StateStoreError>> + 'a {
+    let entries = match &bounds.0 {
+        Bound::Included(start) | Bound::Excluded(start) => rows.iter_from(start),
+        Bound::Unbounded => rows.iter_from(partition_key),
+    };
+    within(entries, partition_key)
+        .skip_while(move |entry| {
+            matches!((entry, &bounds.0), (Ok((key, _)), Bound::Excluded(start)) if key == start)
+        })
+        .take_while(move |entry| entry.as_ref().map_or(true, |(key, _)| bounds.contains(key)))
+}
+
+/// Returns the bounds of the rows whose values can change when the rows from the peers of `first_change`
+/// to the peers of `last_change` change, given the keys of the peers as returned by [peers_key].
+///
+/// The bounds are rows ordered before or after the changed rows and their peers, which stay where they are,
+/// so they bound the same rows before and after the change.
+fn recomputed_bounds(
+    rows: &SortedStateMap<RowState>,
+    partition_key: &SortKey,
+    reach: &Reach,
+    first_change: &SortKey,
+    last_change: &SortKey,
+) -> Result<RowBounds, PipelineError> {
+    let start = match reach.before {
+        Some(0) => Bound::Included(first_change.clone()),
+        Some(before) => nth_copy(
+            within(rows.iter_rev_before(first_change), partition_key),
+            before,
+        )?
+        .map_or(Bound::Unbounded, Bound::Included),
+        None => Bound::Unbounded,
+    };
+
+    let mut following = within(rows.iter_from(last_change), partition_key).skip_while(|entry| {
+        entry
+            .as_ref()
+            .map_or(false, |(key, _)| key.starts_with(last_change))
+    });
+    let end = match reach.after {
+        Some(0) => following
+            .next()
+            .transpose()?
+            .map_or(Bound::Unbounded, |(key, _)| Bound::Excluded(key)),
+        Some(after) => nth_copy(following, after)?.map_or(Bound::Unbounded, Bound::Included),
+        None => Bound::Unbounded,
+    };
+    Ok((start, end))
+}
+
+fn copies(rows: &[Entry]) -> usize {
+    rows.iter().map(|(_, state)| state.count).sum()
+}
+
+/// Returns the key of the row of the `n`th copy along `rows`, or `None` if there are fewer.
+fn nth_copy(
+    rows: impl Iterator<Item = Result<Entry, StateStoreError>>,
+    n: usize,
+) -> Result<Option<SortKey>, PipelineError> {
+    let mut copies = 0;
+    for entry in rows {
+        let (key, state) = entry?;
+        copies += state.count;
+        if copies >= n {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
+
+/// Takes rows from `rows` until it has `copies` copies, then the rest of the peers of the last row it took,
+/// returning them and the row after them.
+fn take_context(
+    rows: impl Iterator<Item = Result<Entry, StateStoreError>>,
+    peer: &RowKey,
+    copies: Option<usize>,
+) -> Result<(Vec<Entry>, Option<RowState>), PipelineError> {
+    let mut taken: Vec<Entry> = vec![];
+    let mut count = 0;
+    for entry in rows {
+        let (key, state) = entry?;
+        let peer = taken.last().map_or(peer, |(_, last)| &last.row);
+        let enough = copies.map_or(false, |copies| count >= copies);
+        if enough && state.row.sort_values != peer.sort_values {
+            return Ok((taken, Some(state)));
+        }
+        count += state.count;
+        taken.push((key, state));
+    }
+    Ok((taken, None))
+}
+
+/// The rows a change recomputes, with the rows around them that their values read, read from the partition.
+struct WindowRows {
+    rows: Vec<Entry>,
+    /// The indexes of the rows to recompute.
+    recomputed: Range<usize>,
+    /// The row before `rows`, which they continue from.
+    previous: Option<RowState>,
+    /// The first row of the partition, if it's before `rows`.
+    first: Option<RowState>,
+}
+
+impl WindowRows {
+    /// Reads the rows in `bounds`, and the rows `context` reaches around them, extended to whole peer groups.
+    fn read(
+        rows: &SortedStateMap<RowState>,
+        partition_key: &SortKey,
+        bounds: &RowBounds,
+        context: &Reach,
+    ) -> Result<Option<Self>, PipelineError> {
+        let recomputed = range(rows, partition_key, bounds).collect::<Result<Vec<_>, _>>()?;
+        let (Some((first_key, first)), Some((last_key, last))) =
+            (recomputed.first(), recomputed.last())
+        else {
+            return Ok(None);
+        };
+        let (mut before, previous) = take_context(
+            within(rows.iter_rev_before(first_key), partition_key),
+            &first.row,
+            context.before,
+        )?;
+        before.reverse();
+        let (after, _) = take_context(
+            within(rows.iter_from(last_key), partition_key).skip(1),
+            &last.row,
+            context.after,
+        )?;
+        let first = match previous {
+            Some(_) => within(rows.iter_from(partition_key), partition_key)
+                .next()
+                .transpose()?
+                .map(|(_, state)| state),
+            None => None,
+        };
+
+        let start = before.len();
+        let end = start + recomputed.len();
+        Ok(Some(Self {
+            rows: before.into_iter().chain(recomputed).chain(after).collect(),
+            recomputed: start..end,
+            previous,
+            first,
+        }))
+    }
+
+    /// Returns the rows, once for every copy.
+    fn window(&self) -> Window<'_> {
+        let start = copies(&self.rows[..self.recomputed.start]);
+        let end = start + copies(&self.rows[self.recomputed.clone()]);
+        Window {
+            rows: self
+                .rows
+                .iter()
+                .flat_map(|(_, state)| std::iter::repeat(state).take(state.count))
+                .collect(),
+            range: start..end,
+            previous: self.previous.as_ref(),
+            first: self.first.as_ref(),
+        }
+    }
+
+    /// Returns the rows to recompute.
+    fn recomputed(self) -> impl Iterator<Item = Entry> {
+        let recomputed = self.recomputed;
+        self.rows
+            .into_iter()
+            .skip(recomputed.start)
+            .take(recomputed.len())
+    }
+}
+
+/// The rows a change recomputes, with the rows around them that their values read, once for every copy.
+struct Window<'a> {
+    rows: Vec<PartitionRow<'a>>,
+    /// The indexes of the rows to recompute.
+    range: Range<usize>,
+    /// The row before `rows`, which they continue from.
+    previous: Option<PartitionRow<'a>>,
+    /// The first row of the partition, if it's before `rows`.
+    first: Option<PartitionRow<'a>>,
+}
+
+impl Window<'_> {
+    /// Returns the values of every copy of the recomputed rows, and the position of the last copy of every
+    /// recomputed row.
+    fn recompute(
+        &self,
+        functions: &[WindowFunction],
+        frame: &WindowFrame,
+    ) -> Result<Vec<(Vec<Vec<Field>>, Position)>, PipelineError> {
+        let positions = self.positions();
+        let mut window_values = vec![Vec::with_capacity(functions.len()); self.range.len()];
+        for (function_index, function) in functions.iter().enumerate() {
+            let column = self.function_values(function, function_index, frame, &positions)?;
+            for (row_values, value) in window_values.iter_mut().zip(column) {
+                row_values.push(value);
+            }
+        }
+
+        let mut window_values = window_values.into_iter();
+        Ok(self
+            .last_positions(&positions)
+            .into_iter()
+            .map(|(count, position)| (window_values.by_ref().take(count).collect(), position))
+            .collect())
+    }
+
+    /// Returns the positions of the rows, continuing from the position of the row before them.
+    fn positions(&self) -> Vec<Position> {
+        let mut position = self
+            .previous
+            .map(|state| state.position)
+            .unwrap_or_default();
+        let mut previous = self.previous.map(|state| &state.row);
+        let mut positions = Vec::with_capacity(self.range.end);
+        for state in &self.rows[..self.range.end] {
+            position.row_number += 1;
+            if previous.map_or(true, |previous| {
+                previous.sort_values != state.row.sort_values
+            }) {
+                position.rank = position.row_number;
+                position.dense_rank += 1;
+            }
+            previous = Some(&state.row);
+            positions.push(position);
+        }
+        positions
+    }
+
+    /// Returns the number of copies of every recomputed row and the position of its last copy.
+    fn last_positions(&self, positions: &[Position]) -> Vec<(usize, Position)> {
+        let mut last_positions = vec![];
+        for index in self.range.clone() {
+            let state = self.rows[index];
+            let last_copy = self
+                .rows
+                .get(index + 1)
+                .map_or(true, |next| !std::ptr::eq(*next, state));
+            if last_copy {
+                last_positions.push((state.count, positions[index]));
+            }
+        }
+        last_positions
+    }
+
+    /// Returns the values of window function `function_index` for the recomputed rows.
+    fn function_values(
+        &self,
+        function: &WindowFunction,
+        function_index: usize,
+        frame: &WindowFrame,
+        positions: &[Position],
+    ) -> Result<Vec<Field>, PipelineError> {
+        let rows = self.rows.as_slice();
+        let argument = |state: PartitionRow| {
+            state.arguments[function_index]
+                .first()
+                .cloned()
+                .unwrap_or(Field::Null)
+        };
+        let range = self.range.clone();
+        let values = match &function.typ {
+            WindowFunctionType::RowNumber => range
+                .map(|index| Field::UInt(positions[index].row_number))
+                .collect(),
+            WindowFunctionType::Rank => range
+                .map(|index| Field::UInt(positions[index].rank))
+                .collect(),
+            WindowFunctionType::DenseRank => range
+                .map(|index| Field::UInt(positions[index].dense_rank))
+                .collect(),
+            // The rows reach far enough before and after the recomputed rows, unless the partition ends first.
+            WindowFunctionType::Lag { offset, default } => range
+                .map(|index| {
+                    index
+                        .checked_sub(*offset)
+                        .map_or_else(|| default.clone(), |index| argument(rows[index]))
+                })
+                .collect(),
+            WindowFunctionType::Lead { offset, default } => range
+                .map(|index| {
+                    rows.get(index + offset)
+                        .map_or_else(|| default.clone(), |row| argument(*row))
+                })
+                .collect(),
+            WindowFunctionType::FirstValue => range
+                .map(|index| {
+                    let (start, end) = (frame.start(rows, index), frame.end(rows, index));
+                    if frame.start == FrameBound::UnboundedPreceding && self.previous.is_some() {
+                        // The frame starts before the rows.
+                        self.first.map_or(Field::Null, argument)
+                    } else if start < end {
+                        argument(rows[start])
+                    } else {
+                        Field::Null
+                    }
+                })
+                .collect(),
+            WindowFunctionType::LastValue => range
+                .map(|index| {
+                    let (start, end) = (frame.start(rows, index), frame.end(rows, index));
+                    if start < end {
+                        argument(rows[end - 1])
+                    } else {
+                        Field::Null
+                    }
+                })
+                .collect(),
+            WindowFunctionType::Aggregate(typ) => {
+                self.aggregate_values(*typ, function, function_index, frame)?
+            }
+        };
+        Ok(values)
+    }
+
+    /// Slides an aggregator over the frames of the recomputed rows, as the frames only move forward.
+    /// Frames that start at the start of the partition only ever insert, as the rows start there too.
+    fn aggregate_values(
+        &self,
+        typ: AggregatorType,
+        function: &WindowFunction,
+        function_index: usize,
+        frame: &WindowFrame,
+    ) -> Result<Vec<Field>, PipelineError> {
+        let rows = self.rows.as_slice();
+        let mut aggregator = aggregator(
+            typ,
+            function.return_type,
+            frame.start == FrameBound::UnboundedPreceding,
+        );
+        let empty_value = match typ {
+            AggregatorType::Count => Field::Int(0),
+            _ => Field::Null,
+        };
+
+        let mut values = Vec::with_capacity(self.range.len());
+        let (mut start, mut end) = (0, 0);
+        let mut value = empty_value.clone();
+        for index in self.range.clone() {
+            let frame_start = frame.start(rows, index);
+            let frame_end = frame.end(rows, index);
+            // Inserting before deleting keeps the aggregated rows from being empty in between.
+            if start == end {
+                (start, end) = (frame_start, frame_start);
+            }
+            while end < frame_end.max(frame_start) {
+                value = aggregator.insert(&rows[end].arguments[function_index])?;
+                end += 1;
+            }
+            while start < frame_start {
+                value = aggregator.delete(&rows[start].arguments[function_index])?;
+                start += 1;
+            }
+            values.push(if frame_start < frame_end {
+                value.clone()
+            } else {
+                empty_value.clone()
+            });
+        }
+        Ok(values)
+    }
+}
+
+/// Returns the index of the first row with the same ORDER BY values as row `index`.
+fn first_peer(rows: &[PartitionRow], index: usize) -> usize {
+    let sort_values = &rows[index].row.sort_values;
+    rows.partition_point(|state| state.row.sort_values < *sort_values)
+}
+
+/// Returns the index of the last row with the same ORDER BY values as row `index`.
+fn last_peer(rows: &[PartitionRow], index: usize) -> usize {
+    let sort_values = &rows[index].row.sort_values;
+    rows.partition_point(|state| state.row.sort_values <= *sort_values) - 1
+}
+
+/// Returns an aggregator for an aggregate function. Aggregators that only ever insert, as they do over frames that
+/// start at the start of the partition, keep only their value for MIN and MAX.
+fn aggregator(typ: AggregatorType, return_type: FieldType, insert_only: bool) -> AggregatorEnum {
+    let typ = match typ {
+        AggregatorType::Min if insert_only => AggregatorType::MinAppendOnly,
+        AggregatorType::Max if insert_only => AggregatorType::MaxAppendOnly,
+        typ => typ,
+    };
+    let mut aggregator = get_aggregator_from_aggregator_type(typ);
+    aggregator.init(return_type);
+    aggregator
+}
+
+/// Returns the changes that turn the rows `before` into the rows `after`, matching rows by their values.
+fn diff(
+    before: Vec<(Vec<Field>, Vec<Field>)>,
+    after: Vec<(Vec<Field>, Vec<Field>)>,
+) -> Vec<Operation> {
+    let mut before_indexes = HashMap::<&Vec<Field>, VecDeque<usize>>::new();
+    for (index, (values, _)) in before.iter().enumerate() {
+        before_indexes.entry(values).or_default().push_back(index);
+    }
+
+    let mut matched = vec![false; before.len()];
+    let mut changes = vec![];
+    for (values, window_values) in &after {
+        let new = || output_record(values, window_values);
+        match before_indexes.get_mut(values).and_then(VecDeque::pop_front) {
+            Some(index) => {
+                matched[index] = true;
+                if before[index].1 != *window_values {
+                    changes.push(Operation::Update {
+                        old: output_record(values, &before[index].1),
+                        new: new(),
+                    });
+                }
+            }
+            None => changes.push(Operation::Insert { new: new() }),
+        }
+    }
+
+    before
+        .iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|((values, window_values), _)| Operation::Delete {
+            old: output_record(values, window_values),
+        })
+        .chain(changes)
+        .collect()
+}
+
+fn output_record(values: &[Field], window_values: &[Field]) -> Record {
+    let mut values = values.to_vec();
+    values.extend_from_slice(window_values);
+    Record::new(values)
+}
+
+impl Processor for WindowFunctionProcessor {
+    fn commit(&self, _epoch: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        for output_op in self.window_functions(op.op)? {
+            fw.send(TableOperation::without_id(output_op, DEFAULT_PORT_HANDLE));
+        }
+        Ok(())
+    }
+
+    fn serialize_state(&self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let data = bincode::encode_to_vec(&self.rows, bincode::config::legacy())
+            .map_err(|e| PipelineError::SerializeState(e.into()))?;
+        Ok(Some(data))
+    }
+
+    fn state_size(&self) -> Result<Option<usize>, BoxedError> {
+        Ok(Some(self.row_count))
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+
+use dozer_core::channels::ProcessorChannelForwarder;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Processor, ProcessorFactory};
+use dozer_core::state_store::StateStore;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::sqlparser::ast::{Expr, SelectItem, SetExpr, Statement, WindowType};
+use dozer_sql_expression::sqlparser::{dialect::DozerDialect, parser::Parser};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::tests::utils::{create_test_runtime, open_node_state_store};
+
+use super::factory::WindowFunctionProcessorFactory;
+
+struct TestChannelForwarder {
+    operations: Vec<Operation>,
+}
+
+impl ProcessorChannelForwarder for TestChannelForwarder {
+    fn send(&mut self, op: TableOperation) {
+        self.operations.push(op.op);
+    }
+}
+
+fn get_schema() -> Schema {
+    let mut schema = Schema::new();
+    for (name, typ) in [
+        ("category", FieldType::String),
+        ("product", FieldType::String),
+        ("revenue", FieldType::Int),
+    ] {
+        schema.field(
+            FieldDefinition::new(name.to_string(), typ, true, SourceDefinition::Dynamic),
+            false,
+        );
+    }
+    schema
+}
+
+fn row(category: &str, product: &str, revenue: i64) -> Record {
+    Record::new(vec![
+        Field::String(category.to_string()),
+        Field::String(product.to_string()),
+        Field::Int(revenue),
+    ])
+}
+
+fn output(record: Record, window_values: &[Field]) -> Record {
+    let mut values = record.values;
+    values.extend_from_slice(window_values);
+    Record::new(values)
+}
+
+/// Builds a processor for the window functions of `sql`, which must all have the same window.
+fn build(
+    sql: &str,
+    checkpoint_data: Option<Vec<u8>>,
+    state_store: StateStore,
+) -> Box<dyn Processor> {
+    let statement = Parser::parse_sql(&DozerDialect {}, sql).unwrap().remove(0);
+    let Statement::Query(query) = statement else {
+        panic!("not a query");
+    };
+    let SetExpr::Select(select) = *query.body else {
+        panic!("not a select");
+    };
+    let mut window = None;
+    let mut functions = vec![];
+    for (index, item) in select.projection.into_iter().enumerate() {
+        let SelectItem::UnnamedExpr(Expr::Function(mut function)) = item else {
+            panic!("not a function");
+        };
+        let Some(WindowType::WindowSpec(spec)) = function.over.take() else {
+            panic!("not a window function");
+        };
+        window = Some(spec);
+        functions.push((format!("window_{index}"), function));
+    }
+
+    let runtime = create_test_runtime();
+    let factory = WindowFunctionProcessorFactory::new(
+        "window_function".to_string(),
+        window.unwrap(),
+        functions,
+        vec![],
+        runtime.clone(),
+    );
+    runtime
+        .block_on(factory.build(
+            HashMap::from([(DEFAULT_PORT_HANDLE, get_schema())]),
+            HashMap::new(),
+            EventHub::new(1),
+            checkpoint_data,
+            state_store,
+        ))
+        .unwrap()
+}
+
+fn process(processor: &mut Box<dyn Processor>, op: Operation) -> Vec<Operation> {
+    let mut fw = TestChannelForwarder { operations: vec![] };
+    processor
+        .process(TableOperation::without_id(op, DEFAULT_PORT_HANDLE), &mut fw)
+        .unwrap();
+    fw.operations
+}
+
+fn insert(new: Record) -> Operation {
+    Operation::Insert { new }
+}
+
+fn delete(old: Record) -> Operation {
+    Operation::Delete { old }
+}
+
+fn update(old: Record, new: Record) -> Operation {
+    Operation::Update { old, new }
+}
+
+#[test]
+fn test_ranking_and_running_sum() {
+    let sql = "SELECT \
+        ROW_NUMBER() OVER (PARTITION BY category ORDER BY revenue DESC), \
+        RANK() OVER (PARTITION BY category ORDER BY revenue DESC), \
+        LAG(revenue) OVER (PARTITION BY category ORDER BY revenue DESC), \
+        SUM(revenue) OVER (PARTITION BY category ORDER BY revenue DESC) \
+        FROM products";
+    let mut processor = build(sql, None, StateStore::default());
+    let values = |row_number: u64, rank: u64, lag: Field, sum: i64| {
+        [
+            Field::UInt(row_number),
+            Field::UInt(rank),
+            lag,
+            Field::Int(sum),
+        ]
+    };
+
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p1", 10))),
+        vec![insert(output(
+            row("a", "p1", 10),
+            &values(1, 1, Field::Null, 10)
+        ))]
+    );
+    // A row ordered first moves the others down.
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p2", 20))),
+        vec![
+            insert(output(row("a", "p2", 20), &values(1, 1, Field::Null, 20))),
+            update(
+                output(row("a", "p1", 10), &values(1, 1, Field::Null, 10)),
+                output(row("a", "p1", 10), &values(2, 2, Field::Int(20), 30))
+            ),
+        ]
+    );
+    // Other partitions are computed on their own.
+    assert_eq!(
+        process(&mut processor, insert(row("b", "p3", 5))),
+        vec![insert(output(
+            row("b", "p3", 5),
+            &values(1, 1, Field::Null, 5)
+        ))]
+    );
+    assert_eq!(
+        process(&mut processor, delete(row("a", "p2", 20))),
+        vec![
+            delete(output(row("a", "p2", 20), &values(1, 1, Field::Null, 20))),
+            update(
+                output(row("a", "p1", 10), &values(2, 2, Field::Int(20), 30)),
+                output(row("a", "p1", 10), &values(1, 1, Field::Null, 10))
+            ),
+        ]
+    );
+
+    // The rows survive a checkpoint. Rows with the same revenue share a rank and their running sum.
+    let checkpoint_data = processor.serialize_state().unwrap();
+    let mut processor = build(sql, checkpoint_data, StateStore::default());
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p4", 10))),
+        vec![
+            update(
+                output(row("a", "p1", 10), &values(1, 1, Field::Null, 10)),
+                output(row("a", "p1", 10), &values(1, 1, Field::Null, 20))
+            ),
+            insert(output(
+                row("a", "p4", 10),
+                &values(2, 1, Field::Int(10), 20)
+            )),
+        ]
+    );
+}
+
+#[test]
+fn test_rows_frame_and_lead() {
+    let sql = "SELECT \
+        SUM(revenue) OVER (ORDER BY revenue ROWS BETWEEN 1 PRECEDING AND CURRENT ROW), \
+        LEAD(revenue, 1, 0) OVER (ORDER BY revenue ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) \
+        FROM products";
+    let mut processor = build(sql, None, StateStore::default());
+    let values = |sum: i64, lead: i64| [Field::Int(sum), Field::Int(lead)];
+
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p1", 10))),
+        vec![insert(output(row("a", "p1", 10), &values(10, 0)))]
+    );
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p2", 30))),
+        vec![
+            update(
+                output(row("a", "p1", 10), &values(10, 0)),
+                output(row("a", "p1", 10), &values(10, 30))
+            ),
+            insert(output(row("a", "p2", 30), &values(40, 0))),
+        ]
+    );
+    // Only the rows whose frame or next row changed are updated.
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p3", 20))),
+        vec![
+            update(
+                output(row("a", "p1", 10), &values(10, 30)),
+                output(row("a", "p1", 10), &values(10, 20))
+            ),
+            insert(output(row("a", "p3", 20), &values(30, 30))),
+            update(
+                output(row("a", "p2", 30), &values(40, 0)),
+                output(row("a", "p2", 30), &values(50, 0))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_running_values_continue_from_previous_row() {
+    let sql = "SELECT \
+        DENSE_RANK() OVER (ORDER BY revenue DESC), \
+        MAX(revenue) OVER (ORDER BY revenue DESC), \
+        COUNT(revenue) OVER (ORDER BY revenue DESC) \
+        FROM products";
+    let mut processor = build(sql, None, StateStore::default());
+    let values = |dense_rank: u64, max: i64, count: i64| {
+        [Field::UInt(dense_rank), Field::Int(max), Field::Int(count)]
+    };
+
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p1", 30))),
+        vec![insert(output(row("a", "p1", 30), &values(1, 30, 1)))]
+    );
+    // A row ordered last changes no other row.
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p2", 10))),
+        vec![insert(output(row("a", "p2", 10), &values(2, 30, 2)))]
+    );
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p3", 20))),
+        vec![
+            insert(output(row("a", "p3", 20), &values(2, 30, 2))),
+            update(
+                output(row("a", "p2", 10), &values(2, 30, 2)),
+                output(row("a", "p2", 10), &values(3, 30, 3))
+            ),
+        ]
+    );
+    // Peers share their rank and their frame.
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p4", 20))),
+        vec![
+            update(
+                output(row("a", "p3", 20), &values(2, 30, 2)),
+                output(row("a", "p3", 20), &values(2, 30, 3))
+            ),
+            insert(output(row("a", "p4", 20), &values(2, 30, 3))),
+            update(
+                output(row("a", "p2", 10), &values(3, 30, 3)),
+                output(row("a", "p2", 10), &values(3, 30, 4))
+            ),
+        ]
+    );
+
+    // The running values survive a checkpoint.
+    let checkpoint_data = processor.serialize_state().unwrap();
+    let mut processor = build(sql, checkpoint_data, StateStore::default());
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p5", 5))),
+        vec![insert(output(row("a", "p5", 5), &values(4, 30, 5)))]
+    );
+    assert_eq!(
+        process(&mut processor, delete(row("a", "p1", 30))),
+        vec![
+            delete(output(row("a", "p1", 30), &values(1, 30, 1))),
+            update(
+                output(row("a", "p3", 20), &values(2, 30, 3)),
+                output(row("a", "p3", 20), &values(1, 20, 2))
+            ),
+            update(
+                output(row("a", "p4", 20), &values(2, 30, 3)),
+                output(row("a", "p4", 20), &values(1, 20, 2))
+            ),
+            update(
+                output(row("a", "p2", 10), &values(3, 30, 4)),
+                output(row("a", "p2", 10), &values(2, 20, 3))
+            ),
+            update(
+                output(row("a", "p5", 5), &values(4, 30, 5)),
+                output(row("a", "p5", 5), &values(3, 20, 4))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_rows_on_disk() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let sql = "SELECT \
+        DENSE_RANK() OVER (ORDER BY revenue DESC), \
+        MAX(revenue) OVER (ORDER BY revenue DESC), \
+        COUNT(revenue) OVER (ORDER BY revenue DESC) \
+        FROM products";
+    let state_store = open_node_state_store(temp_dir.path(), None);
+    let mut processor = build(sql, None, state_store.clone());
+    let values = |dense_rank: u64, max: i64, count: i64| {
+        [Field::UInt(dense_rank), Field::Int(max), Field::Int(count)]
+    };
+
+    process(&mut processor, insert(row("a", "p1", 30)));
+    process(&mut processor, insert(row("a", "p2", 10)));
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p3", 20))),
+        vec![
+            insert(output(row("a", "p3", 20), &values(2, 30, 2))),
+            update(
+                output(row("a", "p2", 10), &values(2, 30, 2)),
+                output(row("a", "p2", 10), &values(3, 30, 3))
+            ),
+        ]
+    );
+    assert_eq!(processor.state_size().unwrap(), Some(3));
+
+    // The rows are restored from the database checkpoint.
+    let checkpoint_data = processor.serialize_state().unwrap();
+    let state_store_checkpoint = state_store.checkpoint(0).unwrap();
+    drop((processor, state_store));
+    let mut processor = build(
+        sql,
+        checkpoint_data,
+        open_node_state_store(temp_dir.path(), state_store_checkpoint),
+    );
+    assert_eq!(processor.state_size().unwrap(), Some(3));
+    assert_eq!(
+        process(&mut processor, insert(row("a", "p5", 5))),
+        vec![insert(output(row("a", "p5", 5), &values(4, 30, 4)))]
+    );
+    assert_eq!(
+        process(&mut processor, delete(row("a", "p1", 30))),
+        vec![
+            delete(output(row("a", "p1", 30), &values(1, 30, 1))),
+            update(
+                output(row("a", "p3", 20), &values(2, 30, 2)),
+                output(row("a", "p3", 20), &values(1, 20, 1))
+            ),
+            update(
+                output(row("a", "p2", 10), &values(3, 30, 3)),
+                output(row("a", "p2", 10), &values(2, 20, 2))
+            ),
+            update(
+                output(row("a", "p5", 5), &values(4, 30, 4)),
+                output(row("a", "p5", 5), &values(3, 20, 3))
+            ),
+        ]
+    );
+    assert_eq!(processor.state_size().unwrap(), Some(3));
+}
@@ -247,6 +247,7 @@ impl TestSink {
                     self.update_result(Operation::Insert { new: record })?;
                 }
             }
+            Operation::Truncate => records_map.clear(),
         }
         Ok(())
     }
@@ -5,13 +5,25 @@ pub const DOZER_METER_NAME: &str = "dozer.meter";
 pub const SINK_OPERATION_COUNTER_NAME: &str = "sink_operation";
 pub const PIPELINE_LATENCY_GAUGE_NAME: &str = "pipeline_latency";
 pub const TOTAL_LATENCY_HISTOGRAM_NAME: &str = "total_latency";
+pub const SINK_RETRY_COUNTER_NAME: &str = "sink_retry";
+pub const SINK_CIRCUIT_OPEN_GAUGE_NAME: &str = "sink_circuit_open";
 
 pub const SOURCE_OPERATION_COUNTER_NAME: &str = "source_operation";
+pub const SOURCE_LAG_GAUGE_NAME: &str = "source_lag";
+
+pub const NODE_OPERATION_COUNTER_NAME: &str = "node_operation";
+pub const NODE_PROCESSING_TIME_HISTOGRAM_NAME: &str = "node_processing_time";
+pub const NODE_STATE_SIZE_GAUGE_NAME: &str = "node_state_size";
+pub const CHANNEL_QUEUE_DEPTH_GAUGE_NAME: &str = "channel_queue_depth";
+pub const CHANNEL_FILL_GAUGE_NAME: &str = "channel_fill";
 
 //  Labels
 pub const OPERATION_TYPE_LABEL: &str = "operation_type";
 pub const TABLE_LABEL: &str = "table";
 pub const CONNECTION_LABEL: &str = "connection";
+pub const SINK_CALL_LABEL: &str = "call";
+pub const NODE_LABEL: &str = "node";
+pub const TARGET_NODE_LABEL: &str = "target_node";
 
 // Traces
 pub const CONNECTOR_EVENTS: &str = "connector_events";
@@ -132,3 +132,21 @@ fn roundtrip_record_to_record_batch() {
 
     assert_eq!(original_schema, arrow_field_test_cases_schema());
 }
+
+#[test]
+fn roundtrip_records_to_record_batch() {
+    use super::super::arrow_types::from_arrow::map_record_batch_to_dozer_records;
+    use super::super::arrow_types::to_arrow::map_records_to_arrow;
+    use super::super::types::Record;
+    use crate::types::field::{arrow_field_test_cases, arrow_field_test_cases_schema};
+
+    let record: Record = Record::new(arrow_field_test_cases().collect());
+    let records = vec![record.clone(), record];
+    let record_batch =
+        map_records_to_arrow(records.clone(), &arrow_field_test_cases_schema()).unwrap();
+    assert_eq!(record_batch.num_rows(), 2);
+
+    let res: Vec<Record> =
+        map_record_batch_to_dozer_records(record_batch, &arrow_field_test_cases_schema()).unwrap();
+    assert_eq!(records, res);
+}
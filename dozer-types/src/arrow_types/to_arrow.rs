@@ -110,6 +110,11 @@ pub fn map_record_to_arrow(
             (Field::Binary(v), FieldType::Binary) => {
                 Arc::new(arrow_array::BinaryArray::from_iter_values([v])) as ArrayRef
             }
+            (Field::Null, FieldType::Binary) => {
+                Arc::new(arrow_array::BinaryArray::from_opt_vec(vec![
+                    None as Option<&[u8]>,
+                ])) as ArrayRef
+            }
             (Field::Json(v), FieldType::Json) => Arc::new(
                 arrow_array::StringArray::from_iter_values([format!("{v:?}")]),
             ) as ArrayRef,
@@ -130,8 +135,8 @@ pub fn map_record_to_arrow(
                 ])) as ArrayRef
             }
             (Field::Null, FieldType::Duration) => {
-                Arc::new(arrow_array::BinaryArray::from_opt_vec(vec![
-                    None as Option<&[u8]>,
+                Arc::new(arrow_array::DurationNanosecondArray::from(vec![
+                    None as Option<i64>,
                 ])) as ArrayRef
             }
             (a, b) => Err(arrow::error::ArrowError::InvalidArgumentError(format!(
@@ -145,6 +150,19 @@ pub fn map_record_to_arrow(
     RecordBatch::try_new(Arc::new(schema), columns)
 }
 
+// Maps Dozer Records to a single Arrow RecordBatch
+pub fn map_records_to_arrow(
+    records: Vec<Record>,
+    schema: &Schema,
+) -> Result<RecordBatch, arrow::error::ArrowError> {
+    let arrow_schema = Arc::new(map_to_arrow_schema(schema)?);
+    let batches = records
+        .into_iter()
+        .map(|rec| map_record_to_arrow(rec, schema))
+        .collect::<Result<Vec<_>, _>>()?;
+    arrow::compute::concat_batches(&arrow_schema, &batches)
+}
+
 // Maps the dozer field type to the arrow data type
 // Optionally takes a metadata map to add additional metadata to the field
 
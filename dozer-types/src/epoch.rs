@@ -13,7 +13,7 @@ pub struct EpochCommonInfo {
     pub source_states: Arc<SourceStates>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
 pub struct SourceTime {
     millis_since_epoch: u64,
     accuracy: u64,
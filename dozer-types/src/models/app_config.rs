@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -12,9 +14,141 @@ pub struct AppConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_threshold: Option<u32>,
 
+    /// What to do with an operation that a processor or sink fails on. Defaults to `Fail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_policy: Option<ErrorPolicy>,
+
+    /// Overrides `error_policy` for individual processors and sinks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub node_error_policies: Vec<NodeErrorPolicy>,
+
+    /// How failed sink calls are retried before the sink is paused.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sink_retry: Option<SinkRetryConfig>,
+
+    /// Logs what sources ingest to the home directory, so pipelines and new sinks can be rebuilt by replaying it. Disabled if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_log: Option<OperationLogConfig>,
+
     /// The event hub's queue capacity. Events that are not processed will be dropped.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub event_hub_capacity: Option<usize>,
+
+    /// Where joins, aggregations and primary key lookups keep their state. Defaults to memory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_store: Option<StateStoreConfig>,
+
+    /// How many threads each aggregation with GROUP BY and each join runs on, sharded by its group by or join key. Defaults to 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallelism: Option<NonZeroUsize>,
+
+    /// Overrides `parallelism` for individual queries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query_parallelism: Vec<QueryParallelism>,
+
+    /// How many records either side of a CROSS JOIN may keep, as every record joins with all records of the other side. Defaults to 100000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cross_join_records: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct QueryParallelism {
+    /// The table the query outputs `INTO`.
+    pub table_name: String,
+
+    pub parallelism: NonZeroUsize,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Counts the error towards `error_threshold`, and brings down the app once it's exceeded.
+    #[default]
+    Fail,
+
+    /// Logs the error and drops the operation.
+    Skip,
+
+    /// Logs the error and keeps the operation in the home directory, so it can be replayed with `dozer dead-letters replay`.
+    DeadLetter,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct NodeErrorPolicy {
+    /// The sink name, or the processor name as shown in logs.
+    pub node: String,
+
+    pub policy: ErrorPolicy,
+}
+
+/// Failed sink calls are retried with exponential backoff. Once retries are exhausted, the sink's circuit opens:
+/// the sink is paused and its input buffered, while other sinks keep running.
+///
+/// Failed operations are only retried if the sink's error policy is `Fail`. Errors the sink knows won't go away are never retried.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SinkRetryConfig {
+    /// Retries of a failed call before the circuit opens. 0 disables retries and the circuit breaker. Defaults to 5.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// Milliseconds before the first retry, doubled on every retry. Defaults to 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+
+    /// Maximum milliseconds between retries. Defaults to 10000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_backoff_ms: Option<u64>,
+
+    /// Milliseconds an open circuit pauses the sink before the failed call is tried again. Defaults to 30000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_duration_ms: Option<u64>,
+
+    /// Milliseconds failed calls may keep the circuit open before their errors are reported. Defaults to 600000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_open_duration_ms: Option<u64>,
+
+    /// Inputs buffered while the circuit is open. Once full, the sink back-pressures its inputs. Defaults to 100000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_buffered: Option<usize>,
+}
+
+/// An append-only log of the messages every source ingests, split into segments.
+/// The oldest segments are removed once the log exceeds its retention limits.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct OperationLogConfig {
+    /// Bytes a segment grows to before a new one is started. Defaults to 67108864.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_segment_bytes: Option<u64>,
+
+    /// Bytes of segments kept per source. Defaults to 10737418240.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_bytes: Option<u64>,
+
+    /// Seconds a segment is kept after it's last written to. Segments are kept regardless of age if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum StateStoreConfig {
+    /// In yaml, present as tag: `!InMemory`
+    InMemory,
+
+    /// In yaml, present as tag: `!OnDisk`
+    OnDisk(OnDiskStateStoreConfig),
+}
+
+/// Keeps operator state in an embedded database, so it can grow beyond memory.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct OnDiskStateStoreConfig {
+    /// Bytes of state cached in memory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_size: Option<usize>,
 }
 
 pub fn default_app_buffer_size() -> u32 {
@@ -28,3 +162,47 @@ pub fn default_error_threshold() -> u32 {
 pub fn default_event_hub_capacity() -> usize {
     100
 }
+
+pub fn default_parallelism() -> NonZeroUsize {
+    NonZeroUsize::MIN
+}
+
+pub fn default_max_cross_join_records() -> usize {
+    100_000
+}
+
+pub fn default_operation_log_max_segment_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+pub fn default_operation_log_retention_bytes() -> u64 {
+    10 * 1024 * 1024 * 1024
+}
+
+pub fn default_state_store_cache_size() -> usize {
+    256 * 1024 * 1024
+}
+
+pub fn default_sink_max_retries() -> u32 {
+    5
+}
+
+pub fn default_sink_initial_backoff_ms() -> u64 {
+    100
+}
+
+pub fn default_sink_max_backoff_ms() -> u64 {
+    10_000
+}
+
+pub fn default_sink_open_duration_ms() -> u64 {
+    30_000
+}
+
+pub fn default_sink_max_open_duration_ms() -> u64 {
+    600_000
+}
+
+pub fn default_sink_max_buffered() -> usize {
+    100_000
+}
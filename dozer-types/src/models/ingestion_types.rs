@@ -9,14 +9,14 @@ use crate::{
     helper::{deserialize_duration_secs_f64, f64_schema, serialize_duration_secs_f64},
     models::connection::SchemaExample,
     node::OpIdentifier,
-    types::Operation,
+    types::{Operation, Schema},
 };
 
 use super::equal_default;
 
 pub const SECRET: &str = "*********";
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
 /// All possible kinds of `IngestionMessage`.
 pub enum IngestionMessage {
     /// A CDC event.
@@ -28,10 +28,17 @@ pub enum IngestionMessage {
         /// If this connector supports restarting from a specific CDC event, it should provide a `OpIdentifier`.
         id: Option<OpIdentifier>,
     },
+    /// The schema of a table changed. Following `OperationEvent`s of the table use the new schema.
+    SchemaChanged {
+        /// Index of the table whose schema changed.
+        table_index: usize,
+        /// The table's new schema.
+        schema: Schema,
+    },
     TransactionInfo(TransactionInfo),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
 pub enum TransactionInfo {
     Commit {
         /// If this connector supports restarting from after this commit, it should provide a `OpIdentifier`.
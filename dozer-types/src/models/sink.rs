@@ -1,6 +1,7 @@
 use std::{num::NonZeroUsize, path::Display};
 
 use super::equal_default;
+use super::sink_config::Snowflake as SnowflakeSinkConfig;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -109,6 +110,12 @@ pub enum SinkConfig {
     Aerospike(AerospikeSinkConfig),
     Clickhouse(ClickhouseSinkConfig),
     Oracle(OracleSinkConfig),
+    Postgres(PostgresSinkConfig),
+    MySQL(MySQLSinkConfig),
+    Kafka(KafkaSinkConfig),
+    File(FileSinkConfig),
+    Snowflake(SnowflakeSinkConfig),
+    Webhook(WebhookSinkConfig),
 }
 impl SinkConfig {
     pub fn name(&self) -> String {
@@ -117,6 +124,12 @@ impl SinkConfig {
             SinkConfig::Aerospike(_) => "aerospike",
             SinkConfig::Clickhouse(_) => "clickhouse",
             SinkConfig::Oracle(_) => "oracle",
+            SinkConfig::Postgres(_) => "postgres",
+            SinkConfig::MySQL(_) => "mysql",
+            SinkConfig::Kafka(_) => "kafka",
+            SinkConfig::File(_) => "file",
+            SinkConfig::Snowflake(_) => "snowflake",
+            SinkConfig::Webhook(_) => "webhook",
         };
         return name.to_string();
     }
@@ -200,6 +213,15 @@ pub struct AerospikeSinkConfig {
     pub metadata_set: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ClickhouseSinkTable {
+    pub source_table_name: String,
+    pub sink_table_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_table_options: Option<ClickhouseTableOptions>,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone, Default)]
 #[serde(deny_unknown_fields)]
 pub struct ClickhouseSinkConfig {
@@ -216,12 +238,26 @@ pub struct ClickhouseSinkConfig {
     #[serde(default = "ClickhouseSinkConfig::default_database")]
     pub database: String,
     pub options: Vec<(String, String)>,
-    pub source_table_name: String,
-    pub sink_table_name: String,
-    pub create_table_options: Option<ClickhouseTableOptions>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<ClickhouseSinkTable>,
+    /// Maximum number of rows to buffer before inserting them
+    #[serde(default)]
+    pub batch_size: Option<u64>,
+    /// Maximum estimated size in bytes of the buffered rows before inserting them
+    #[serde(default)]
+    pub batch_bytes: Option<u64>,
+    /// Maximum time in milliseconds to buffer rows before inserting them
+    #[serde(default)]
+    pub max_batch_duration_ms: Option<u64>,
+    /// How many times a failed insert is retried
+    #[serde(default = "ClickhouseSinkConfig::default_insert_retries")]
+    pub insert_retries: u32,
 }
 
 impl ClickhouseSinkConfig {
+    fn default_insert_retries() -> u32 {
+        3
+    }
     fn default_database() -> String {
         "default".to_string()
     }
@@ -261,6 +297,180 @@ pub struct OracleSinkConfig {
     pub owner: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PostgresSinkConfig {
+    /// Name of the Postgres connection to write to
+    pub connection: String,
+    pub source_table_name: String,
+    pub sink_table_name: String,
+    #[serde(default, skip_serializing_if = "equal_default")]
+    pub conflict_resolution: ConflictResolution,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_batch_size: Option<u64>,
+    /// Commit every epoch in two phases with `PREPARE TRANSACTION`, which needs `max_prepared_transactions` set on the server
+    #[serde(default, skip_serializing_if = "equal_default")]
+    pub two_phase_commit: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MySQLSinkConfig {
+    /// Name of the MySQL connection to write to
+    pub connection: String,
+    pub source_table_name: String,
+    pub sink_table_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_batch_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSinkTable {
+    pub source_table_name: String,
+    /// Topic to publish to, defaults to the source table name prefixed with `topic_prefix`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSinkConfig {
+    /// Name of the Kafka connection to publish to
+    pub connection: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<KafkaSinkTable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_prefix: Option<String>,
+    /// Register JSON schemas with the connection's schema registry instead of embedding them in every message
+    #[serde(default)]
+    pub use_schema_registry: bool,
+    /// Produce in transactions committed with every epoch, otherwise the producer is only idempotent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactional_id: Option<String>,
+    /// Follow every delete with a tombstone, so that log compaction can remove the key
+    #[serde(default = "KafkaSinkConfig::default_tombstones_on_delete")]
+    pub tombstones_on_delete: bool,
+    /// Compacted topic the sink stores the position it has committed in, keyed by sink name. Defaults to `__dozer_sink_metadata`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_topic: Option<String>,
+}
+
+impl KafkaSinkConfig {
+    fn default_tombstones_on_delete() -> bool {
+        true
+    }
+
+    pub fn topic(&self, table: &KafkaSinkTable) -> String {
+        table.topic.clone().unwrap_or_else(|| {
+            format!(
+                "{}{}",
+                self.topic_prefix.as_deref().unwrap_or_default(),
+                table.source_table_name
+            )
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub enum FileFormat {
+    #[default]
+    Parquet,
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub enum FileSinkChangeHandling {
+    /// Fail on updates and deletes
+    #[default]
+    Reject,
+    /// Write every change as a row, with an `op` column of `I`, `U` or `D`
+    AppendOp,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FileSinkConfig {
+    /// Name of an S3 or local storage connection to write to, `path` is a local directory if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+    /// Directory, or prefix within the connection, the files are written under
+    pub path: String,
+    pub source_table_name: String,
+    #[serde(default, skip_serializing_if = "equal_default")]
+    pub format: FileFormat,
+    /// Finish the open files at the first commit after one of them has this many rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows_per_file: Option<u64>,
+    /// Finish the open files at the first commit after they have been open this long.
+    /// Without this or `max_rows_per_file`, files are finished at every commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_duration_ms: Option<u64>,
+    /// Write the rows of every value of this column to a Hive style `column=value` directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<String>,
+    #[serde(default, skip_serializing_if = "equal_default")]
+    pub on_change: FileSinkChangeHandling,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSinkTable {
+    pub source_table_name: String,
+    /// URL template to post the table's operations to, defaults to the sink's `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSinkConfig {
+    /// URL template to post operations to, `{table}` is replaced with the source table name
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<WebhookSinkTable>,
+    /// Maximum number of operations in one request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<u64>,
+    /// Maximum time in milliseconds to buffer operations before posting them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_interval_ms: Option<u64>,
+    /// How many times a failed request is retried
+    #[serde(default = "WebhookSinkConfig::default_max_retries")]
+    pub max_retries: u32,
+    /// Delay in milliseconds before the first retry, doubled with every further retry
+    #[serde(default = "WebhookSinkConfig::default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Secret to sign requests with, the HMAC-SHA256 of the timestamp and body is sent in the `X-Dozer-Signature` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl WebhookSinkConfig {
+    fn default_max_retries() -> u32 {
+        5
+    }
+
+    fn default_initial_backoff_ms() -> u64 {
+        500
+    }
+
+    pub fn url(&self, table: &WebhookSinkTable) -> String {
+        table
+            .url
+            .as_deref()
+            .unwrap_or(&self.url)
+            .replace("{table}", &table.source_table_name)
+    }
+}
+
 pub fn default_log_reader_batch_size() -> u32 {
     1000
 }
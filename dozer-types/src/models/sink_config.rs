@@ -11,6 +11,7 @@ pub enum SinkConfig {
 #[serde(deny_unknown_fields)]
 pub struct Snowflake {
     pub connection: snowflake::ConnectionParameters,
+    /// The table the sink reads from.
     pub endpoint: String,
     pub destination: snowflake::Destination,
 
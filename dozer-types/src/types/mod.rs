@@ -13,6 +13,7 @@ use prettytable::{Cell, Row, Table};
 use serde::{self, Deserialize, Serialize};
 
 pub mod field;
+mod schema_diff;
 
 #[cfg(test)]
 mod tests;
@@ -20,6 +21,7 @@ mod tests;
 use crate::errors::internal::BoxedError;
 use crate::errors::types::TypeError::InvalidFieldValue;
 pub use field::{field_test_cases, Field, FieldType, DATE_FORMAT};
+pub use schema_diff::SchemaDiff;
 
 #[derive(
     Clone,
@@ -46,7 +48,18 @@ pub enum SourceDefinition {
     Dynamic,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Clone,
+    Serialize,
+    Deserialize,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    bincode::Encode,
+    bincode::Decode,
+)]
 pub struct FieldDefinition {
     pub name: String,
     pub typ: FieldType,
@@ -76,7 +89,9 @@ impl FieldDefinition {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[derive(
+    Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default, bincode::Encode, bincode::Decode,
+)]
 pub struct Schema {
     /// fields contains a list of FieldDefinition for all the fields that appear in a record.
     /// Not necessarily all these fields will end up in the final object structure stored in
@@ -295,6 +310,7 @@ pub enum Operation {
     Insert { new: Record },
     Update { old: Record, new: Record },
     BatchInsert { new: Vec<Record> },
+    Truncate,
 }
 
 pub type PortHandle = u16;
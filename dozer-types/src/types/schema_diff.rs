@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+
+use super::{FieldDefinition, Schema};
+
+/// The difference between two versions of a schema. Fields are matched by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SchemaDiff {
+    /// Fields only in the new schema.
+    pub added: Vec<FieldDefinition>,
+    /// Fields only in the old schema.
+    pub removed: Vec<FieldDefinition>,
+    /// Fields whose type or nullability changed, as `(old, new)`.
+    pub changed: Vec<(FieldDefinition, FieldDefinition)>,
+    /// If the fields kept from the old schema are no longer the leading fields of the new schema, in the same order.
+    pub reordered: bool,
+    /// The old and new primary key field names, if the primary key changed.
+    pub primary_key: Option<(Vec<String>, Vec<String>)>,
+}
+
+impl SchemaDiff {
+    pub fn new(old: &Schema, new: &Schema) -> Self {
+        let find = |schema: &Schema, name: &str| {
+            schema
+                .fields
+                .iter()
+                .find(|field| field.name == name)
+                .cloned()
+        };
+
+        let mut diff = SchemaDiff::default();
+        let mut kept = vec![];
+        for old_field in &old.fields {
+            match find(new, &old_field.name) {
+                Some(new_field) => {
+                    if new_field.typ != old_field.typ || new_field.nullable != old_field.nullable {
+                        diff.changed.push((old_field.clone(), new_field));
+                    }
+                    kept.push(old_field.name.as_str());
+                }
+                None => diff.removed.push(old_field.clone()),
+            }
+        }
+        for new_field in &new.fields {
+            if find(old, &new_field.name).is_none() {
+                diff.added.push(new_field.clone());
+            }
+        }
+        diff.reordered = !new
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .take(kept.len())
+            .eq(kept);
+
+        let old_key = primary_key_names(old);
+        let new_key = primary_key_names(new);
+        if old_key != new_key {
+            diff.primary_key = Some((old_key, new_key));
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && !self.reordered
+            && self.primary_key.is_none()
+    }
+
+    /// Returns if records of the old schema are valid in the new schema once nulls are appended for the added fields.
+    ///
+    /// That's the case if the new schema only appends nullable fields and keeps the primary key.
+    pub fn is_additive(&self) -> bool {
+        self.removed.is_empty()
+            && self.changed.is_empty()
+            && !self.reordered
+            && self.primary_key.is_none()
+            && self.added.iter().all(|field| field.nullable)
+    }
+}
+
+fn primary_key_names(schema: &Schema) -> Vec<String> {
+    schema
+        .primary_index
+        .iter()
+        .map(|index| schema.fields[*index].name.clone())
+        .collect()
+}
+
+fn describe_field(field: &FieldDefinition) -> String {
+    if field.nullable {
+        format!("{} (nullable)", field.typ)
+    } else {
+        field.typ.to_string()
+    }
+}
+
+impl Display for SchemaDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut changes = vec![];
+        for field in &self.added {
+            changes.push(format!(
+                "added field `{}`: {}",
+                field.name,
+                describe_field(field)
+            ));
+        }
+        for field in &self.removed {
+            changes.push(format!("removed field `{}`", field.name));
+        }
+        for (old, new) in &self.changed {
+            changes.push(format!(
+                "changed field `{}` from {} to {}",
+                old.name,
+                describe_field(old),
+                describe_field(new)
+            ));
+        }
+        if self.reordered {
+            changes.push("reordered fields".to_string());
+        }
+        if let Some((old, new)) = &self.primary_key {
+            changes.push(format!("changed primary key from {old:?} to {new:?}"));
+        }
+
+        if changes.is_empty() {
+            f.write_str("no changes")
+        } else {
+            f.write_str(&changes.join("; "))
+        }
+    }
+}
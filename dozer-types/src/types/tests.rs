@@ -1,4 +1,7 @@
-use crate::types::{field_test_cases, DozerDuration, DozerPoint, Field, TimeUnit};
+use crate::types::{
+    field_test_cases, DozerDuration, DozerPoint, Field, FieldDefinition, FieldType, Schema,
+    SchemaDiff, SourceDefinition, TimeUnit,
+};
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use ordered_float::OrderedFloat;
 use rust_decimal::Decimal;
@@ -565,3 +568,56 @@ fn test_to_conversion() {
     assert!(field.to_duration().is_some());
     assert!(field.to_null().is_some());
 }
+
+#[test]
+fn test_schema_diff() {
+    let field = |name: &str, typ: FieldType, nullable: bool| {
+        FieldDefinition::new(name.to_string(), typ, nullable, SourceDefinition::Dynamic)
+    };
+    let mut old = Schema::new();
+    old.field(field("id", FieldType::Int, false), true)
+        .field(field("name", FieldType::String, false), false);
+
+    assert!(SchemaDiff::new(&old, &old).is_empty());
+
+    // Appending a nullable field is additive.
+    let mut new = old.clone();
+    new.field(field("email", FieldType::String, true), false);
+    let diff = SchemaDiff::new(&old, &new);
+    assert_eq!(diff.added, vec![field("email", FieldType::String, true)]);
+    assert!(diff.is_additive());
+
+    // Appending a non-nullable field, or inserting a field in between, is not.
+    let mut new = old.clone();
+    new.field(field("email", FieldType::String, false), false);
+    assert!(!SchemaDiff::new(&old, &new).is_additive());
+    let mut new = old.clone();
+    new.fields
+        .insert(1, field("email", FieldType::String, true));
+    let diff = SchemaDiff::new(&old, &new);
+    assert!(diff.reordered);
+    assert!(!diff.is_additive());
+
+    // Type changes and removals are reported by field.
+    let mut new = Schema::new();
+    new.field(field("id", FieldType::String, false), true);
+    let diff = SchemaDiff::new(&old, &new);
+    assert!(!diff.is_additive());
+    assert_eq!(
+        diff.to_string(),
+        "removed field `name`; changed field `id` from 64-bit int to string"
+    );
+
+    // So are primary key changes.
+    let mut new = old.clone();
+    new.primary_index = vec![0, 1];
+    let diff = SchemaDiff::new(&old, &new);
+    assert_eq!(
+        diff.primary_key,
+        Some((
+            vec!["id".to_string()],
+            vec!["id".to_string(), "name".to_string()]
+        ))
+    );
+    assert!(!diff.is_additive());
+}